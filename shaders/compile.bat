@@ -1,4 +1,13 @@
 D:\SDKs\Vulkan\Bin\glslc.exe shader.vert -o bin/vert.spv
 D:\SDKs\Vulkan\Bin\glslc.exe shader.frag -o bin/frag.spv
 D:\SDKs\Vulkan\Bin\glslc.exe text-shader.vert -o bin/text-vert.spv
-D:\SDKs\Vulkan\Bin\glslc.exe text-shader.frag -o bin/text-frag.spv
\ No newline at end of file
+D:\SDKs\Vulkan\Bin\glslc.exe text-shader.frag -o bin/text-frag.spv
+D:\SDKs\Vulkan\Bin\glslc.exe ui-shader.vert -o bin/ui-vert.spv
+D:\SDKs\Vulkan\Bin\glslc.exe ui-shader.frag -o bin/ui-frag.spv
+D:\SDKs\Vulkan\Bin\glslc.exe outline-shader.vert -o bin/outline-vert.spv
+D:\SDKs\Vulkan\Bin\glslc.exe outline-shader.frag -o bin/outline-frag.spv
+D:\SDKs\Vulkan\Bin\glslc.exe crack-shader.vert -o bin/crack-vert.spv
+D:\SDKs\Vulkan\Bin\glslc.exe crack-shader.frag -o bin/crack-frag.spv
+D:\SDKs\Vulkan\Bin\glslc.exe sky-shader.vert -o bin/sky-vert.spv
+D:\SDKs\Vulkan\Bin\glslc.exe sky-shader.frag -o bin/sky-frag.spv
+D:\SDKs\Vulkan\Bin\glslc.exe shadow-shader.vert -o bin/shadow-vert.spv
\ No newline at end of file
@@ -0,0 +1,41 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    vec4 voxel;
+} pcs;
+
+layout(location = 0) in vec2 fragUV;
+
+layout(location = 0) out vec4 outColor;
+
+const int MAX_CRACKS = 12;
+const float TAU = 6.2831853;
+
+float hash(float value) {
+    return fract(sin(value * 127.1) * 43758.5453);
+}
+
+// Cracks run out from the middle of every face, each stage adds one and lengthens them all
+void main() {
+    float stage = pcs.voxel.w;
+    vec2 offset = fragUV - vec2(0.5);
+    float distance = length(offset);
+    float angle = atan(offset.y, offset.x);
+    float reach = 0.2 + 0.05 * stage;
+    int cracks = 3 + int(stage);
+
+    for (int i = 0; i < MAX_CRACKS; i++) {
+        if (i >= cracks) {
+            break;
+        }
+        float crackAngle = hash(float(i) + 1.0) * TAU;
+        float difference = abs(mod(angle - crackAngle + TAU * 0.5, TAU) - TAU * 0.5);
+        // Thinner towards the tip
+        float width = 0.004 + 0.02 * (1.0 - distance / reach);
+        if (distance < reach && difference * distance < width) {
+            outColor = vec4(0.05, 0.05, 0.05, 0.75);
+            return;
+        }
+    }
+    discard;
+}
@@ -0,0 +1,44 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+// xyz is the lowest corner of the voxel being broken, w the crack stage
+layout(push_constant) uniform PushConstants {
+    vec4 voxel;
+} pcs;
+
+layout(location = 0) out vec2 fragUV;
+
+// Two triangles per face
+const vec2 QUAD[6] = vec2[6](
+    vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0),
+    vec2(0.0, 0.0), vec2(1.0, 1.0), vec2(0.0, 1.0)
+);
+
+// Every face as a corner and the directions its u and v run along
+const vec3 FACE_ORIGINS[6] = vec3[6](
+    vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0),
+    vec3(1.0, 1.0, 0.0), vec3(0.0, 0.0, 0.0),
+    vec3(0.0, 0.0, 1.0), vec3(0.0, 1.0, 0.0)
+);
+const vec3 FACE_U[6] = vec3[6](
+    vec3(0.0, 1.0, 0.0), vec3(0.0, -1.0, 0.0),
+    vec3(-1.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0),
+    vec3(1.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)
+);
+const vec3 FACE_V[6] = vec3[6](
+    vec3(0.0, 0.0, 1.0), vec3(0.0, 0.0, 1.0),
+    vec3(0.0, 0.0, 1.0), vec3(0.0, 0.0, 1.0),
+    vec3(0.0, 1.0, 0.0), vec3(0.0, -1.0, 0.0)
+);
+
+void main() {
+    int face = gl_VertexIndex / 6;
+    vec2 uv = QUAD[gl_VertexIndex % 6];
+    vec3 position = pcs.voxel.xyz + FACE_ORIGINS[face] + FACE_U[face] * uv.x + FACE_V[face] * uv.y;
+    gl_Position = ubo.proj * ubo.view * vec4(position, 1.0);
+    fragUV = uv;
+}
@@ -0,0 +1,28 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+// xyz is the lowest corner of the targeted voxel
+layout(push_constant) uniform PushConstants {
+    vec4 voxel;
+} pcs;
+
+// Vulkan only biases polygons, so the edges also sit a little outside the voxel
+const float INFLATE = 0.002;
+
+// The 12 edges as pairs of corners, bit 0 is x, bit 1 is y and bit 2 is z
+const int EDGES[24] = int[24](
+    0, 1, 2, 3, 4, 5, 6, 7,
+    0, 2, 1, 3, 4, 6, 5, 7,
+    0, 4, 1, 5, 2, 6, 3, 7
+);
+
+void main() {
+    int corner = EDGES[gl_VertexIndex];
+    vec3 offset = vec3(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+    vec3 position = pcs.voxel.xyz + mix(vec3(-INFLATE), vec3(1.0 + INFLATE), offset);
+    gl_Position = ubo.proj * ubo.view * vec4(position, 1.0);
+}
@@ -1,15 +1,59 @@
 #version 450
 
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+    vec4 skyZenith;
+    vec4 skyHorizon;
+    vec4 sunDirection;
+    // Start and end distance, light multiplier
+    vec4 fog;
+    mat4 lightMatrices[3];
+    // Far distance of each cascade, w is 1 when shadows are on
+    vec4 cascadeSplits;
+} ubo;
+
 layout(binding = 1) uniform sampler2D texSampler;
+layout(binding = 2) uniform sampler2DArrayShadow shadowMap;
 
 layout(push_constant) uniform PushConstants {
     layout(offset = 64) float opacity;
 } pcs;
 
 layout(location = 0) in vec2 fragUV;
+layout(location = 1) in float fragDistance;
+layout(location = 2) in vec3 fragWorldPosition;
+layout(location = 3) in float fragViewDepth;
 
 layout(location = 0) out vec4 outColor;
 
+// How much light is left in full shadow
+const float SHADOW_LIGHT = 0.45;
+
+// 1 when lit, 0 in full shadow, averaged over the texels around the position for soft edges
+float shadow() {
+    if (ubo.cascadeSplits.w < 0.5 || fragViewDepth > ubo.cascadeSplits.z) {
+        return 1.0;
+    }
+    int cascade = fragViewDepth < ubo.cascadeSplits.x ? 0 : (fragViewDepth < ubo.cascadeSplits.y ? 1 : 2);
+
+    vec4 light = ubo.lightMatrices[cascade] * vec4(fragWorldPosition, 1.0);
+    vec3 coords = light.xyz / light.w;
+    vec2 uv = coords.xy * 0.5 + 0.5;
+
+    vec2 texel = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    float lit = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            lit += texture(shadowMap, vec4(uv + vec2(x, y) * texel, cascade, coords.z));
+        }
+    }
+    return lit / 9.0;
+}
+
 void main() {
-    outColor = vec4(texture(texSampler, fragUV).rgb, pcs.opacity);
+    vec3 color = texture(texSampler, fragUV).rgb * ubo.fog.z * mix(SHADOW_LIGHT, 1.0, shadow());
+    // Terrain fades into the sky towards the view distance
+    float fog = smoothstep(ubo.fog.x, ubo.fog.y, fragDistance);
+    outColor = vec4(mix(color, ubo.skyHorizon.rgb, fog), pcs.opacity);
 }
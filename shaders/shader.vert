@@ -3,6 +3,12 @@
 layout(binding = 0) uniform UniformBufferObject {
     mat4 view;
     mat4 proj;
+    vec4 skyZenith;
+    vec4 skyHorizon;
+    vec4 sunDirection;
+    vec4 fog;
+    mat4 lightMatrices[3];
+    vec4 cascadeSplits;
 } ubo;
 
 layout(push_constant) uniform PushConstants {
@@ -13,8 +19,17 @@ layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec2 inTexCoord;
 
 layout(location = 0) out vec2 fragTexCoord;
+layout(location = 1) out float fragDistance;
+layout(location = 2) out vec3 fragWorldPosition;
+// Along the view direction, the cascades are split by it
+layout(location = 3) out float fragViewDepth;
 
 void main() {
-    gl_Position = ubo.proj * ubo.view * pcs.model * vec4(inPosition, 1.0);
+    vec4 worldPosition = pcs.model * vec4(inPosition, 1.0);
+    vec4 viewPosition = ubo.view * worldPosition;
+    gl_Position = ubo.proj * viewPosition;
     fragTexCoord = inTexCoord;
+    fragDistance = length(viewPosition.xyz);
+    fragWorldPosition = worldPosition.xyz;
+    fragViewDepth = -viewPosition.z;
 }
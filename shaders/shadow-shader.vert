@@ -0,0 +1,23 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+    vec4 skyZenith;
+    vec4 skyHorizon;
+    vec4 sunDirection;
+    vec4 fog;
+    mat4 lightMatrices[3];
+    vec4 cascadeSplits;
+} ubo;
+
+layout(push_constant) uniform PushConstants {
+    mat4 model;
+    uint cascade;
+} pcs;
+
+layout(location = 0) in vec3 inPosition;
+
+void main() {
+    gl_Position = ubo.lightMatrices[pcs.cascade] * pcs.model * vec4(inPosition, 1.0);
+}
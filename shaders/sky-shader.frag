@@ -0,0 +1,38 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+    vec4 skyZenith;
+    vec4 skyHorizon;
+    vec4 sunDirection;
+    vec4 fog;
+} ubo;
+
+layout(location = 0) in vec3 fragDirection;
+
+layout(location = 0) out vec4 outColor;
+
+const vec3 SUN_COLOR = vec3(1.0, 0.95, 0.8);
+const vec3 MOON_COLOR = vec3(0.8, 0.85, 0.95);
+// Cosines of the disc radii
+const float SUN_SIZE = 0.9992;
+const float MOON_SIZE = 0.9994;
+
+void main() {
+    vec3 direction = normalize(fragDirection);
+
+    // Z is up, below the horizon the sky keeps the horizon colour
+    float height = smoothstep(0.0, 0.6, direction.z);
+    vec3 color = mix(ubo.skyHorizon.rgb, ubo.skyZenith.rgb, height);
+
+    float sun = dot(direction, ubo.sunDirection.xyz);
+    color += SUN_COLOR * pow(max(sun, 0.0), 64.0) * 0.4;
+    color = mix(color, SUN_COLOR, smoothstep(SUN_SIZE - 0.0003, SUN_SIZE, sun));
+
+    // Opposite the sun
+    float moon = -sun;
+    color = mix(color, MOON_COLOR, smoothstep(MOON_SIZE - 0.0002, MOON_SIZE, moon));
+
+    outColor = vec4(color, 1.0);
+}
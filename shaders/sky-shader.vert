@@ -0,0 +1,22 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+    vec4 skyZenith;
+    vec4 skyHorizon;
+    vec4 sunDirection;
+    vec4 fog;
+} ubo;
+
+layout(location = 0) out vec3 fragDirection;
+
+void main() {
+    // One triangle covering the screen
+    vec2 position = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2) * 2.0 - 1.0;
+    gl_Position = vec4(position, 1.0, 1.0);
+
+    // Back through the projection and the camera rotation, the camera position doesn't move the sky
+    vec4 viewDirection = inverse(ubo.proj) * vec4(position, 1.0, 1.0);
+    fragDirection = transpose(mat3(ubo.view)) * (viewDirection.xyz / viewDirection.w);
+}
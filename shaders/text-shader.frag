@@ -3,18 +3,25 @@
 layout(binding = 0) uniform sampler2D textures;
 
 layout(location = 0) in vec2 TexCoords;
+layout(location = 1) in vec4 Color;
+layout(location = 2) in vec4 OutlineColor;
+// Outline width and softness in distance field values
+layout(location = 3) in vec2 Edges;
 layout(location = 0) out vec4 outColor;
 
 void main()
 {
-    vec4 sampled = vec4(1.0, 1.0, 1.0, texture(textures, TexCoords).r);
-//    outColor = vec4(1.0, 0.0, 0.0, 1.0) * sampled;
+    // The glyph atlas holds distance fields, the glyph's edge is at 0.5 and values rise inwards
+    float distance = texture(textures, TexCoords).r;
+    // Anti-aliasing over about a pixel on screen, whatever the text size
+    float smoothing = fwidth(distance) * 0.5 + Edges.y;
 
-    /*if (sampled == vec4(1.0, 1.0, 1.0, 1.0)) {
-        outColor = vec4(1.0, 1.0, 1.0, 1.0);
-    } else {
-        outColor = vec4(0.0, 0.0, 5.0, 0.7);
-    }*/
+    float fill = Color.a * smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+    float outlineEdge = 0.5 - Edges.x;
+    float outline = OutlineColor.a * smoothstep(outlineEdge - smoothing, outlineEdge + smoothing, distance);
 
-    outColor = sampled;
-}
\ No newline at end of file
+    // The glyph is drawn over its outline
+    float alpha = fill + outline * (1.0 - fill);
+    vec3 color = (Color.rgb * fill + OutlineColor.rgb * outline * (1.0 - fill)) / max(alpha, 0.0001);
+    outColor = vec4(color, alpha);
+}
@@ -1,11 +1,26 @@
 #version 450
 
-layout(location = 0) in vec4 vertex; // <vec2 pos, vec2 tex>
+layout(push_constant) uniform PushConstants {
+    mat4 projection;
+} pcs;
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec2 inTexCoords;
+layout(location = 2) in vec4 inColor;
+layout(location = 3) in vec4 inOutlineColor;
+layout(location = 4) in vec2 inEdges;
 
 layout(location = 0) out vec2 TexCoords;
+layout(location = 1) out vec4 Color;
+layout(location = 2) out vec4 OutlineColor;
+layout(location = 3) out vec2 Edges;
 
 void main()
 {
-    gl_Position = vec4(vertex.xy, 0.0, 1.0);
-    TexCoords = vertex.zw;
-}
\ No newline at end of file
+    // Positions are in logical pixels of the UI space
+    gl_Position = pcs.projection * vec4(inPosition, 0.0, 1.0);
+    TexCoords = inTexCoords;
+    Color = inColor;
+    OutlineColor = inOutlineColor;
+    Edges = inEdges;
+}
@@ -0,0 +1,17 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    mat4 projection;
+} pcs;
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec4 inColor;
+
+layout(location = 0) out vec4 Color;
+
+void main()
+{
+    // Positions are in logical pixels of the UI space
+    gl_Position = pcs.projection * vec4(inPosition, 0.0, 1.0);
+    Color = inColor;
+}
@@ -1 +1,7 @@
+pub mod gamepad;
+#[cfg(feature = "gilrs")]
+pub mod gilrs_gamepad;
+pub mod input_actions;
 pub mod input_manager;
+pub mod input_recording;
+pub mod mouse_look;
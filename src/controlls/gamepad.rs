@@ -0,0 +1,380 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    pub(crate) const ALL: [GamepadButton; 14] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::LeftBumper,
+        GamepadButton::RightBumper,
+        GamepadButton::Select,
+        GamepadButton::Start,
+        GamepadButton::LeftStick,
+        GamepadButton::RightStick,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            GamepadButton::South => "South",
+            GamepadButton::East => "East",
+            GamepadButton::West => "West",
+            GamepadButton::North => "North",
+            GamepadButton::LeftBumper => "LeftBumper",
+            GamepadButton::RightBumper => "RightBumper",
+            GamepadButton::Select => "Select",
+            GamepadButton::Start => "Start",
+            GamepadButton::LeftStick => "LeftStick",
+            GamepadButton::RightStick => "RightStick",
+            GamepadButton::DPadUp => "DPadUp",
+            GamepadButton::DPadDown => "DPadDown",
+            GamepadButton::DPadLeft => "DPadLeft",
+            GamepadButton::DPadRight => "DPadRight",
+        }
+    }
+}
+
+// Stick axes go from -1 to 1 with up and right positive, triggers go from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    pub(crate) const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            GamepadAxis::LeftStickX => "LeftStickX",
+            GamepadAxis::LeftStickY => "LeftStickY",
+            GamepadAxis::RightStickX => "RightStickX",
+            GamepadAxis::RightStickY => "RightStickY",
+            GamepadAxis::LeftTrigger => "LeftTrigger",
+            GamepadAxis::RightTrigger => "RightTrigger",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum GamepadEvent {
+    Connected,
+    Disconnected,
+    ButtonPressed(GamepadButton),
+    ButtonReleased(GamepadButton),
+    AxisChanged(GamepadAxis, f32),
+}
+
+// Anything that can report gamepad events, polled once per frame
+pub(crate) trait GamepadBackend: Debug {
+    fn poll_events(&mut self) -> Vec<GamepadEvent>;
+}
+
+// Hands out queued events, lets tests and replays drive input without hardware
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MockGamepad {
+    events: VecDeque<GamepadEvent>,
+}
+
+impl MockGamepad {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn push(&mut self, event: GamepadEvent) {
+        self.events.push_back(event);
+    }
+}
+
+impl GamepadBackend for MockGamepad {
+    fn poll_events(&mut self) -> Vec<GamepadEvent> {
+        self.events.drain(..).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ResponseCurve {
+    Linear,
+    Quadratic,
+    Cubic,
+}
+
+impl ResponseCurve {
+    pub(crate) const ALL: [ResponseCurve; 3] = [ResponseCurve::Linear, ResponseCurve::Quadratic, ResponseCurve::Cubic];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ResponseCurve::Linear => "Linear",
+            ResponseCurve::Quadratic => "Quadratic",
+            ResponseCurve::Cubic => "Cubic",
+        }
+    }
+
+    // Keeps 0 and 1 in place, steeper curves give finer control near the center
+    pub(crate) fn apply(&self, value: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Quadratic => value * value,
+            ResponseCurve::Cubic => value * value * value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct AxisSettings {
+    // Input below this is treated as resting
+    pub(crate) dead_zone: f32,
+    // Input above this already counts as fully deflected
+    pub(crate) saturation: f32,
+    pub(crate) curve: ResponseCurve,
+}
+
+impl AxisSettings {
+    pub(crate) fn apply(&self, magnitude: f32) -> f32 {
+        if magnitude <= self.dead_zone {
+            return 0.0;
+        }
+        let range = (self.saturation - self.dead_zone).max(f32::EPSILON);
+        let scaled = ((magnitude - self.dead_zone) / range).clamp(0.0, 1.0);
+        self.curve.apply(scaled)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct GamepadSettings {
+    pub(crate) left_stick: AxisSettings,
+    pub(crate) right_stick: AxisSettings,
+    pub(crate) triggers: AxisSettings,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            left_stick: AxisSettings {
+                dead_zone: 0.15,
+                saturation: 0.95,
+                curve: ResponseCurve::Linear,
+            },
+            right_stick: AxisSettings {
+                dead_zone: 0.15,
+                saturation: 0.95,
+                curve: ResponseCurve::Quadratic,
+            },
+            triggers: AxisSettings {
+                dead_zone: 0.05,
+                saturation: 0.95,
+                curve: ResponseCurve::Linear,
+            },
+        }
+    }
+}
+
+// Dead zone on the stick's length rather than per axis, so diagonals don't snap to the axes
+pub(crate) fn apply_radial_dead_zone(stick: (f32, f32), settings: &AxisSettings) -> (f32, f32) {
+    let magnitude = (stick.0 * stick.0 + stick.1 * stick.1).sqrt();
+    if magnitude <= settings.dead_zone {
+        return (0.0, 0.0);
+    }
+    let scale = settings.apply(magnitude.min(1.0)) / magnitude;
+    (stick.0 * scale, stick.1 * scale)
+}
+
+// Button and axis state of the connected gamepad, frames work like keyboard and mouse
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GamepadState {
+    pub(crate) settings: GamepadSettings,
+    is_connected: bool,
+    currently_pressed: Vec<GamepadButton>,
+    pressed_current_frame: Vec<GamepadButton>,
+    released_current_frame: Vec<GamepadButton>,
+    raw_axes: HashMap<GamepadAxis, f32>,
+    previous_raw_axes: HashMap<GamepadAxis, f32>,
+}
+
+impl GamepadState {
+    pub(crate) fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    pub(crate) fn get_button_down(&self, button: GamepadButton) -> bool {
+        self.pressed_current_frame.contains(&button)
+    }
+
+    pub(crate) fn get_button_up(&self, button: GamepadButton) -> bool {
+        self.released_current_frame.contains(&button)
+    }
+
+    pub(crate) fn get_button(&self, button: GamepadButton) -> bool {
+        self.currently_pressed.contains(&button)
+    }
+
+    // Axis value after dead zone and response curve
+    pub(crate) fn get_axis(&self, axis: GamepadAxis) -> f32 {
+        self.processed_axis(&self.raw_axes, axis)
+    }
+
+    pub(crate) fn get_previous_axis(&self, axis: GamepadAxis) -> f32 {
+        self.processed_axis(&self.previous_raw_axes, axis)
+    }
+
+    pub(crate) fn handle_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected => self.is_connected = true,
+            GamepadEvent::Disconnected => {
+                self.is_connected = false;
+                self.released_current_frame.append(&mut self.currently_pressed);
+                self.raw_axes.clear();
+            }
+            GamepadEvent::ButtonPressed(button) => {
+                if !self.currently_pressed.contains(&button) {
+                    self.currently_pressed.push(button);
+                    self.pressed_current_frame.push(button);
+                }
+            }
+            GamepadEvent::ButtonReleased(button) => {
+                if self.currently_pressed.contains(&button) {
+                    self.currently_pressed.retain(|other| *other != button);
+                    self.released_current_frame.push(button);
+                }
+            }
+            GamepadEvent::AxisChanged(axis, value) => {
+                let range = match axis {
+                    GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => 0.0..=1.0,
+                    _ => -1.0..=1.0,
+                };
+                self.raw_axes
+                    .insert(axis, value.clamp(*range.start(), *range.end()));
+            }
+        }
+    }
+
+    pub(crate) fn detected_new_frame(&mut self) {
+        self.pressed_current_frame.clear();
+        self.released_current_frame.clear();
+        self.previous_raw_axes = self.raw_axes.clone();
+    }
+
+    fn processed_axis(&self, raw_axes: &HashMap<GamepadAxis, f32>, axis: GamepadAxis) -> f32 {
+        let raw = |axis| raw_axes.get(&axis).copied().unwrap_or(0.0);
+        let stick = |x, y, settings: &AxisSettings| apply_radial_dead_zone((raw(x), raw(y)), settings);
+
+        match axis {
+            GamepadAxis::LeftStickX => stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY, &self.settings.left_stick).0,
+            GamepadAxis::LeftStickY => stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY, &self.settings.left_stick).1,
+            GamepadAxis::RightStickX => stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY, &self.settings.right_stick).0,
+            GamepadAxis::RightStickY => stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY, &self.settings.right_stick).1,
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => self.settings.triggers.apply(raw(axis)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear(dead_zone: f32, saturation: f32) -> AxisSettings {
+        AxisSettings {
+            dead_zone,
+            saturation,
+            curve: ResponseCurve::Linear,
+        }
+    }
+
+    #[test]
+    fn test_dead_zone_and_saturation() {
+        let settings = linear(0.2, 0.8);
+
+        assert_eq!(settings.apply(0.1), 0.0);
+        assert_eq!(settings.apply(0.2), 0.0);
+        assert!((settings.apply(0.5) - 0.5).abs() < 0.0001);
+        assert_eq!(settings.apply(0.9), 1.0);
+    }
+
+    #[test]
+    fn test_response_curves() {
+        assert_eq!(ResponseCurve::Linear.apply(0.5), 0.5);
+        assert_eq!(ResponseCurve::Quadratic.apply(0.5), 0.25);
+        assert_eq!(ResponseCurve::Cubic.apply(0.5), 0.125);
+        for curve in [ResponseCurve::Linear, ResponseCurve::Quadratic, ResponseCurve::Cubic] {
+            assert_eq!(curve.apply(0.0), 0.0);
+            assert_eq!(curve.apply(1.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_radial_dead_zone_keeps_direction() {
+        let settings = linear(0.2, 1.0);
+
+        assert_eq!(apply_radial_dead_zone((0.1, 0.1), &settings), (0.0, 0.0));
+
+        let (x, y) = apply_radial_dead_zone((0.6, 0.6), &settings);
+        assert!((x - y).abs() < 0.0001);
+        let length = (x * x + y * y).sqrt();
+        assert!((length - (0.6f32.hypot(0.6) - 0.2) / 0.8).abs() < 0.0001);
+
+        // A small diagonal past the dead zone keeps both components
+        let (x, y) = apply_radial_dead_zone((0.2, 0.2), &settings);
+        assert!(x > 0.0 && y > 0.0);
+    }
+
+    #[test]
+    fn test_state_tracks_frames_and_disconnects() {
+        let mut pad = MockGamepad::new();
+        let mut state = GamepadState::default();
+        pad.push(GamepadEvent::Connected);
+        pad.push(GamepadEvent::ButtonPressed(GamepadButton::South));
+        pad.push(GamepadEvent::AxisChanged(GamepadAxis::RightTrigger, 2.0));
+        for event in pad.poll_events() {
+            state.handle_event(event);
+        }
+
+        assert!(state.is_connected());
+        assert!(state.get_button_down(GamepadButton::South));
+        assert_eq!(state.get_axis(GamepadAxis::RightTrigger), 1.0);
+        assert!(pad.poll_events().is_empty());
+
+        state.detected_new_frame();
+        assert!(state.get_button(GamepadButton::South));
+        assert!(!state.get_button_down(GamepadButton::South));
+        assert_eq!(state.get_previous_axis(GamepadAxis::RightTrigger), 1.0);
+
+        state.handle_event(GamepadEvent::Disconnected);
+        assert!(!state.get_button(GamepadButton::South));
+        assert!(state.get_button_up(GamepadButton::South));
+        assert_eq!(state.get_axis(GamepadAxis::RightTrigger), 0.0);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::controlls::gamepad::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent};
+use anyhow::{anyhow, Result};
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+// Real controllers through gilrs, which reads XInput, evdev or IOKit depending on the platform.
+// Events of every connected controller are reported, so any of them can play.
+#[derive(Debug)]
+pub(crate) struct GilrsGamepad {
+    gilrs: Gilrs,
+    // Controllers plugged in before the game started don't send a connected event
+    connected_at_start: bool,
+}
+
+impl GilrsGamepad {
+    pub(crate) fn new() -> Result<Self> {
+        let gilrs = Gilrs::new().map_err(|error| anyhow!("Couldn't open gamepads: {}", error))?;
+        let connected_at_start = gilrs.gamepads().next().is_some();
+        Ok(Self { gilrs, connected_at_start })
+    }
+}
+
+impl GamepadBackend for GilrsGamepad {
+    fn poll_events(&mut self) -> Vec<GamepadEvent> {
+        let mut events = vec![];
+        if std::mem::take(&mut self.connected_at_start) {
+            events.push(GamepadEvent::Connected);
+        }
+        while let Some(event) = self.gilrs.next_event() {
+            events.extend(translate(event.event));
+        }
+        events
+    }
+}
+
+// Events for buttons and axes the game has no use for are dropped
+fn translate(event: EventType) -> Option<GamepadEvent> {
+    match event {
+        EventType::Connected => Some(GamepadEvent::Connected),
+        EventType::Disconnected => Some(GamepadEvent::Disconnected),
+        EventType::ButtonPressed(button, _) => button_of(button).map(GamepadEvent::ButtonPressed),
+        EventType::ButtonReleased(button, _) => button_of(button).map(GamepadEvent::ButtonReleased),
+        EventType::ButtonChanged(button, value, _) => trigger_of(button).map(|axis| GamepadEvent::AxisChanged(axis, value)),
+        EventType::AxisChanged(axis, value, _) => axis_of(axis).map(|axis| GamepadEvent::AxisChanged(axis, value)),
+        _ => None,
+    }
+}
+
+// gilrs calls the bumpers triggers and the analog triggers `Trigger2`
+fn button_of(button: Button) -> Option<GamepadButton> {
+    match button {
+        Button::South => Some(GamepadButton::South),
+        Button::East => Some(GamepadButton::East),
+        Button::West => Some(GamepadButton::West),
+        Button::North => Some(GamepadButton::North),
+        Button::LeftTrigger => Some(GamepadButton::LeftBumper),
+        Button::RightTrigger => Some(GamepadButton::RightBumper),
+        Button::Select => Some(GamepadButton::Select),
+        Button::Start => Some(GamepadButton::Start),
+        Button::LeftThumb => Some(GamepadButton::LeftStick),
+        Button::RightThumb => Some(GamepadButton::RightStick),
+        Button::DPadUp => Some(GamepadButton::DPadUp),
+        Button::DPadDown => Some(GamepadButton::DPadDown),
+        Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        Button::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None,
+    }
+}
+
+// gilrs reports the analog triggers as buttons with a value, their presses only repeat it
+fn trigger_of(button: Button) -> Option<GamepadAxis> {
+    match button {
+        Button::LeftTrigger2 => Some(GamepadAxis::LeftTrigger),
+        Button::RightTrigger2 => Some(GamepadAxis::RightTrigger),
+        _ => None,
+    }
+}
+
+// Up is positive on both sticks, like the game expects
+fn axis_of(axis: Axis) -> Option<GamepadAxis> {
+    match axis {
+        Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+        Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+        Axis::RightStickX => Some(GamepadAxis::RightStickX),
+        Axis::RightStickY => Some(GamepadAxis::RightStickY),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maps_buttons() {
+        assert_eq!(button_of(Button::South), Some(GamepadButton::South));
+        assert_eq!(button_of(Button::RightTrigger), Some(GamepadButton::RightBumper));
+        assert_eq!(button_of(Button::Mode), None);
+        // Analog triggers are axes, their presses are dropped
+        assert_eq!(button_of(Button::LeftTrigger2), None);
+
+        // Every button of the game can be pressed on a real controller
+        let reachable = [
+            Button::South, Button::East, Button::West, Button::North, Button::LeftTrigger, Button::RightTrigger,
+            Button::Select, Button::Start, Button::LeftThumb, Button::RightThumb,
+            Button::DPadUp, Button::DPadDown, Button::DPadLeft, Button::DPadRight,
+        ]
+        .map(|button| button_of(button).unwrap());
+        assert!(GamepadButton::ALL.iter().all(|button| reachable.contains(button)));
+    }
+
+    #[test]
+    fn test_maps_sticks_and_triggers() {
+        assert_eq!(axis_of(Axis::LeftStickY), Some(GamepadAxis::LeftStickY));
+        assert_eq!(axis_of(Axis::RightStickX), Some(GamepadAxis::RightStickX));
+        assert_eq!(axis_of(Axis::DPadX), None);
+        assert_eq!(trigger_of(Button::LeftTrigger2), Some(GamepadAxis::LeftTrigger));
+        assert_eq!(trigger_of(Button::RightTrigger2), Some(GamepadAxis::RightTrigger));
+        assert_eq!(trigger_of(Button::South), None);
+
+        // Every axis of the game moves on a real controller
+        let sticks = [Axis::LeftStickX, Axis::LeftStickY, Axis::RightStickX, Axis::RightStickY].map(axis_of);
+        let triggers = [Button::LeftTrigger2, Button::RightTrigger2].map(trigger_of);
+        assert!(GamepadAxis::ALL.iter().all(|axis| sticks.contains(&Some(*axis)) || triggers.contains(&Some(*axis))));
+    }
+}
@@ -0,0 +1,759 @@
+use crate::controlls::gamepad::{GamepadAxis, GamepadButton};
+use crate::controlls::input_manager::{InputManager, ScrollWheelDelta};
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use winit::event::{MouseButton, VirtualKeyCode};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Action {
+    Jump,
+    Sneak,
+    BreakBlock,
+    PlaceBlock,
+    GrabCursor,
+    ReleaseCursor,
+    ToggleFullscreen,
+    CycleCamera,
+    ReloadSettings,
+    Screenshot,
+    ToggleDebugHud,
+    Pause,
+    ToggleConsole,
+}
+
+impl Action {
+    pub(crate) const ALL: [Action; 13] = [
+        Action::Jump,
+        Action::Sneak,
+        Action::BreakBlock,
+        Action::PlaceBlock,
+        Action::GrabCursor,
+        Action::ReleaseCursor,
+        Action::ToggleFullscreen,
+        Action::CycleCamera,
+        Action::ReloadSettings,
+        Action::Screenshot,
+        Action::ToggleDebugHud,
+        Action::Pause,
+        Action::ToggleConsole,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Action::Jump => "jump",
+            Action::Sneak => "sneak",
+            Action::BreakBlock => "break_block",
+            Action::PlaceBlock => "place_block",
+            Action::GrabCursor => "grab_cursor",
+            Action::ReleaseCursor => "release_cursor",
+            Action::ToggleFullscreen => "toggle_fullscreen",
+            Action::CycleCamera => "cycle_camera",
+            Action::ReloadSettings => "reload_settings",
+            Action::Screenshot => "screenshot",
+            Action::ToggleDebugHud => "toggle_debug_hud",
+            Action::Pause => "pause",
+            Action::ToggleConsole => "toggle_console",
+        }
+    }
+}
+
+// Actions with a value in -1..1 built from a negative and a positive binding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Axis {
+    MoveForward,
+    MoveRight,
+    LookRight,
+    LookUp,
+}
+
+impl Axis {
+    pub(crate) const ALL: [Axis; 4] = [Axis::MoveForward, Axis::MoveRight, Axis::LookRight, Axis::LookUp];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Axis::MoveForward => "move_forward",
+            Axis::MoveRight => "move_right",
+            Axis::LookRight => "look_right",
+            Axis::LookUp => "look_up",
+        }
+    }
+}
+
+// Which half of a gamepad axis a binding listens to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum AxisDirection {
+    Negative,
+    Positive,
+}
+
+// How far an analog input has to be pushed to count as a pressed button
+const ANALOG_PRESS_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Binding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    Wheel(ScrollWheelDelta),
+    GamepadButton(GamepadButton),
+    GamepadAxis(GamepadAxis, AxisDirection),
+}
+
+impl Binding {
+    pub(crate) fn is_gamepad(&self) -> bool {
+        matches!(self, Binding::GamepadButton(_) | Binding::GamepadAxis(..))
+    }
+
+    fn is_held(&self, input: &InputManager) -> bool {
+        match self {
+            Binding::Key(key) => input.get_key(*key),
+            Binding::Mouse(button) => input.get_key_mouse(*button),
+            // The wheel has no held state, a scroll counts for the frame it happened in
+            Binding::Wheel(delta) => input.get_scroll(*delta),
+            Binding::GamepadButton(button) => input.gamepad.get_button(*button),
+            Binding::GamepadAxis(..) => self.value(input) >= ANALOG_PRESS_THRESHOLD,
+        }
+    }
+
+    fn is_down(&self, input: &InputManager) -> bool {
+        match self {
+            Binding::Key(key) => input.get_key_down(*key),
+            Binding::Mouse(button) => input.get_key_down_mouse(*button),
+            Binding::Wheel(delta) => input.get_scroll(*delta),
+            Binding::GamepadButton(button) => input.gamepad.get_button_down(*button),
+            Binding::GamepadAxis(..) => {
+                self.value(input) >= ANALOG_PRESS_THRESHOLD
+                    && self.previous_value(input) < ANALOG_PRESS_THRESHOLD
+            }
+        }
+    }
+
+    fn is_up(&self, input: &InputManager) -> bool {
+        match self {
+            Binding::Key(key) => input.get_key_up(*key),
+            Binding::Mouse(button) => input.get_key_up_mouse(*button),
+            Binding::Wheel(_) => false,
+            Binding::GamepadButton(button) => input.gamepad.get_button_up(*button),
+            Binding::GamepadAxis(..) => {
+                self.value(input) < ANALOG_PRESS_THRESHOLD
+                    && self.previous_value(input) >= ANALOG_PRESS_THRESHOLD
+            }
+        }
+    }
+
+    // 0..1, digital inputs are either fully on or off
+    fn value(&self, input: &InputManager) -> f32 {
+        match self {
+            Binding::GamepadAxis(axis, direction) => directional(input.gamepad.get_axis(*axis), *direction),
+            _ if self.is_held(input) => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    fn previous_value(&self, input: &InputManager) -> f32 {
+        match self {
+            Binding::GamepadAxis(axis, direction) => directional(input.gamepad.get_previous_axis(*axis), *direction),
+            _ => 0.0,
+        }
+    }
+
+    // `key:Space`, `mouse:Left`, `mouse:4`, `wheel:up`, `pad:South` or `pad_axis:LeftStickY+`
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        let (kind, name) = text
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Binding `{}` has no `kind:` prefix", text.trim()))?;
+
+        match kind {
+            "key" => key_from_name(name)
+                .map(Binding::Key)
+                .ok_or_else(|| anyhow!("Unknown key `{}`", name)),
+            "mouse" => match name {
+                "Left" => Ok(Binding::Mouse(MouseButton::Left)),
+                "Right" => Ok(Binding::Mouse(MouseButton::Right)),
+                "Middle" => Ok(Binding::Mouse(MouseButton::Middle)),
+                other => other
+                    .parse()
+                    .map(|button| Binding::Mouse(MouseButton::Other(button)))
+                    .map_err(|_| anyhow!("Unknown mouse button `{}`", other)),
+            },
+            "wheel" => match name {
+                "up" => Ok(Binding::Wheel(ScrollWheelDelta::Up)),
+                "down" => Ok(Binding::Wheel(ScrollWheelDelta::Down)),
+                other => Err(anyhow!("Unknown wheel direction `{}`", other)),
+            },
+            "pad" => GamepadButton::ALL
+                .into_iter()
+                .find(|button| button.name() == name)
+                .map(Binding::GamepadButton)
+                .ok_or_else(|| anyhow!("Unknown gamepad button `{}`", name)),
+            "pad_axis" => {
+                let (axis_name, direction) = match name.split_at(name.len().saturating_sub(1)) {
+                    (axis_name, "+") => (axis_name, AxisDirection::Positive),
+                    (axis_name, "-") => (axis_name, AxisDirection::Negative),
+                    _ => return Err(anyhow!("Gamepad axis `{}` needs a `+` or `-` suffix", name)),
+                };
+                GamepadAxis::ALL
+                    .into_iter()
+                    .find(|axis| axis.name() == axis_name)
+                    .map(|axis| Binding::GamepadAxis(axis, direction))
+                    .ok_or_else(|| anyhow!("Unknown gamepad axis `{}`", axis_name))
+            }
+            other => Err(anyhow!("Unknown binding kind `{}`", other)),
+        }
+    }
+
+    pub(crate) fn to_config_string(self) -> String {
+        match self {
+            Binding::Key(key) => format!("key:{}", key_name(key)),
+            Binding::Mouse(MouseButton::Left) => "mouse:Left".to_string(),
+            Binding::Mouse(MouseButton::Right) => "mouse:Right".to_string(),
+            Binding::Mouse(MouseButton::Middle) => "mouse:Middle".to_string(),
+            Binding::Mouse(MouseButton::Other(button)) => format!("mouse:{}", button),
+            Binding::Wheel(ScrollWheelDelta::Up) => "wheel:up".to_string(),
+            Binding::Wheel(ScrollWheelDelta::Down) => "wheel:down".to_string(),
+            Binding::GamepadButton(button) => format!("pad:{}", button.name()),
+            Binding::GamepadAxis(axis, AxisDirection::Positive) => format!("pad_axis:{}+", axis.name()),
+            Binding::GamepadAxis(axis, AxisDirection::Negative) => format!("pad_axis:{}-", axis.name()),
+        }
+    }
+}
+
+// The bindings file stores bindings as their `kind:name` text
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_config_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Binding::parse(&text).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct AxisBinding {
+    pub(crate) negative: Binding,
+    pub(crate) positive: Binding,
+}
+
+// Maps named actions to any number of bindings
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct InputActions {
+    actions: HashMap<Action, Vec<Binding>>,
+    axes: HashMap<Axis, Vec<AxisBinding>>,
+}
+
+impl Default for InputActions {
+    fn default() -> Self {
+        let mut actions = Self {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+        };
+
+        actions.bind_axis(Axis::MoveForward, Binding::Key(VirtualKeyCode::S), Binding::Key(VirtualKeyCode::W));
+        actions.bind_axis(Axis::MoveRight, Binding::Key(VirtualKeyCode::A), Binding::Key(VirtualKeyCode::D));
+        actions.bind(Action::Jump, Binding::Key(VirtualKeyCode::Space));
+        actions.bind(Action::Sneak, Binding::Key(VirtualKeyCode::LShift));
+        actions.bind(Action::BreakBlock, Binding::Mouse(MouseButton::Left));
+        actions.bind(Action::PlaceBlock, Binding::Mouse(MouseButton::Right));
+        actions.bind(Action::GrabCursor, Binding::Mouse(MouseButton::Left));
+        actions.bind(Action::GrabCursor, Binding::Mouse(MouseButton::Right));
+        // Pausing releases the cursor too, so the plain release has no key of its own
+        actions.bind(Action::Pause, Binding::Key(VirtualKeyCode::Escape));
+        actions.bind(Action::ToggleFullscreen, Binding::Key(VirtualKeyCode::F11));
+        actions.bind(Action::CycleCamera, Binding::Key(VirtualKeyCode::F5));
+        actions.bind(Action::ReloadSettings, Binding::Key(VirtualKeyCode::F8));
+        actions.bind(Action::Screenshot, Binding::Key(VirtualKeyCode::F2));
+        actions.bind(Action::ToggleDebugHud, Binding::Key(VirtualKeyCode::F3));
+        actions.bind(Action::ToggleConsole, Binding::Key(VirtualKeyCode::Grave));
+
+        // Gamepad
+        let stick = |axis, direction| Binding::GamepadAxis(axis, direction);
+        actions.bind_axis(Axis::MoveForward, stick(GamepadAxis::LeftStickY, AxisDirection::Negative), stick(GamepadAxis::LeftStickY, AxisDirection::Positive));
+        actions.bind_axis(Axis::MoveRight, stick(GamepadAxis::LeftStickX, AxisDirection::Negative), stick(GamepadAxis::LeftStickX, AxisDirection::Positive));
+        actions.bind_axis(Axis::LookRight, stick(GamepadAxis::RightStickX, AxisDirection::Negative), stick(GamepadAxis::RightStickX, AxisDirection::Positive));
+        actions.bind_axis(Axis::LookUp, stick(GamepadAxis::RightStickY, AxisDirection::Negative), stick(GamepadAxis::RightStickY, AxisDirection::Positive));
+        actions.bind(Action::Jump, Binding::GamepadButton(GamepadButton::South));
+        actions.bind(Action::Sneak, Binding::GamepadButton(GamepadButton::East));
+        actions.bind(Action::CycleCamera, Binding::GamepadButton(GamepadButton::North));
+        actions.bind(Action::BreakBlock, stick(GamepadAxis::RightTrigger, AxisDirection::Positive));
+        actions.bind(Action::PlaceBlock, stick(GamepadAxis::LeftTrigger, AxisDirection::Positive));
+        actions
+    }
+}
+
+impl InputActions {
+    // Every action and axis without any binding
+    pub(crate) fn unbound() -> Self {
+        Self {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.actions.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub(crate) fn bind_axis(&mut self, axis: Axis, negative: Binding, positive: Binding) {
+        let binding = AxisBinding { negative, positive };
+        let bindings = self.axes.entry(axis).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub(crate) fn clear_action(&mut self, action: Action) {
+        self.actions.remove(&action);
+    }
+
+    // Replaces the keyboard and mouse bindings of the action, gamepad bindings stay
+    pub(crate) fn rebind(&mut self, action: Action, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(&action) {
+            bindings.retain(|other| other.is_gamepad());
+        }
+        self.bind(action, binding);
+    }
+
+    pub(crate) fn clear_axis(&mut self, axis: Axis) {
+        self.axes.remove(&axis);
+    }
+
+    pub(crate) fn bindings(&self, action: Action) -> &[Binding] {
+        self.actions.get(&action).map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    pub(crate) fn axis_bindings(&self, axis: Axis) -> &[AxisBinding] {
+        self.axes.get(&axis).map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    // Any binding held
+    pub(crate) fn is_active(&self, input: &InputManager, action: Action) -> bool {
+        self.bindings(action).iter().any(|binding| binding.is_held(input))
+    }
+
+    // Any binding pressed this frame
+    pub(crate) fn is_triggered(&self, input: &InputManager, action: Action) -> bool {
+        self.bindings(action).iter().any(|binding| binding.is_down(input))
+    }
+
+    // Any binding released this frame while no other binding keeps the action held
+    pub(crate) fn is_released(&self, input: &InputManager, action: Action) -> bool {
+        self.bindings(action).iter().any(|binding| binding.is_up(input))
+            && !self.is_active(input, action)
+    }
+
+    pub(crate) fn axis_value(&self, input: &InputManager, axis: Axis) -> f32 {
+        let value = self
+            .axis_bindings(axis)
+            .iter()
+            .map(|binding| binding.positive.value(input) - binding.negative.value(input))
+            .sum::<f32>();
+        value.clamp(-1.0, 1.0)
+    }
+
+    // Starts from the defaults, every action listed in the text replaces its bindings
+    pub(crate) fn from_config_str(text: &str) -> Result<Self> {
+        let file = toml::from_str::<BindingsFile>(text)?;
+        let mut actions = Self::default();
+
+        for (action, bindings) in file.actions {
+            actions.clear_action(action);
+            for binding in bindings {
+                actions.bind(action, binding);
+            }
+        }
+        for (axis, pairs) in file.axes {
+            actions.clear_axis(axis);
+            for (negative, positive) in pairs {
+                actions.bind_axis(axis, negative, positive);
+            }
+        }
+
+        Ok(actions)
+    }
+
+    pub(crate) fn to_config_string(&self) -> Result<String> {
+        let file = BindingsFile {
+            actions: Action::ALL
+                .into_iter()
+                .map(|action| (action, self.bindings(action).to_vec()))
+                .collect(),
+            axes: Axis::ALL
+                .into_iter()
+                .map(|axis| {
+                    let pairs = self.axis_bindings(axis).iter().map(|binding| (binding.negative, binding.positive));
+                    (axis, pairs.collect())
+                })
+                .collect(),
+        };
+
+        Ok(format!(
+            "# Input bindings: key:<name>, mouse:<Left|Right|Middle|number>, wheel:<up|down>, pad:<button>, pad_axis:<axis><+|->\n\
+             # Axes take [negative, positive] pairs\n{}",
+            toml::to_string(&file)?
+        ))
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        Self::from_config_str(&fs::read_to_string(path)?)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_config_string()?)?;
+        Ok(())
+    }
+
+    // Writes the defaults when there is no file yet, broken files fall back to the defaults
+    pub(crate) fn load_or_default(path: &Path) -> Self {
+        if !path.exists() {
+            let actions = Self::default();
+            if let Err(error) = actions.save(path) {
+                warn!("Couldn't write default input bindings to {:?}: {}", path, error);
+            }
+            return actions;
+        }
+
+        Self::load(path).unwrap_or_else(|error| {
+            warn!("Couldn't load input bindings from {:?}: {}", path, error);
+            Self::default()
+        })
+    }
+}
+
+fn directional(value: f32, direction: AxisDirection) -> f32 {
+    match direction {
+        AxisDirection::Positive => value.max(0.0),
+        AxisDirection::Negative => (-value).max(0.0),
+    }
+}
+
+// Every action and axis is written, so the file lists what can be bound
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BindingsFile {
+    actions: BTreeMap<Action, Vec<Binding>>,
+    axes: BTreeMap<Axis, Vec<(Binding, Binding)>>,
+}
+
+// Every key has a name, the match makes sure none is left out when winit adds keys
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        const KEY_NAMES: &[(VirtualKeyCode, &str)] = &[$((VirtualKeyCode::$key, stringify!($key))),*];
+
+        pub(crate) fn key_name(key: VirtualKeyCode) -> &'static str {
+            match key {
+                $(VirtualKeyCode::$key => stringify!($key)),*
+            }
+        }
+    };
+}
+
+key_names!(
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24,
+    Snapshot, Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp,
+    Left, Up, Right, Down,
+    Back, Return, Space, Compose, Caret, Numlock,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    NumpadAdd, NumpadDivide, NumpadDecimal, NumpadComma, NumpadEnter, NumpadEquals, NumpadMultiply, NumpadSubtract,
+    AbntC1, AbntC2, Apostrophe, Apps, Asterisk, At, Ax, Backslash, Calculator, Capital, Colon, Comma, Convert,
+    Equals, Grave, Kana, Kanji, LAlt, LBracket, LControl, LShift, LWin, Mail, MediaSelect, MediaStop, Minus,
+    Mute, MyComputer, NavigateForward, NavigateBackward, NextTrack, NoConvert, OEM102, Period, PlayPause, Plus,
+    Power, PrevTrack, RAlt, RBracket, RControl, RShift, RWin, Semicolon, Slash, Sleep, Stop, Sysrq, Tab,
+    Underline, Unlabeled, VolumeDown, VolumeUp, Wake, WebBack, WebFavorites, WebForward, WebHome, WebRefresh,
+    WebSearch, WebStop, Yen, Copy, Paste, Cut,
+);
+
+pub(crate) fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, key_name)| *key_name == name)
+        .map(|(key, _)| *key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controlls::gamepad::{GamepadEvent, MockGamepad};
+    use winit::event::{DeviceId, ElementState, KeyboardInput, ModifiersState};
+
+    #[allow(deprecated)]
+    fn key_event(input: &mut InputManager, key: VirtualKeyCode, state: ElementState, frame: u128) {
+        let event = KeyboardInput {
+            scancode: 0,
+            state,
+            virtual_keycode: Some(key),
+            modifiers: ModifiersState::empty(),
+        };
+        let device_id = unsafe { DeviceId::dummy() };
+        input.detect_keyboard(device_id, event, true, frame).unwrap();
+    }
+
+    fn mouse_event(input: &mut InputManager, button: MouseButton, state: ElementState, frame: u128) {
+        let device_id = unsafe { DeviceId::dummy() };
+        input.detect_mouse(device_id, button, state, frame);
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let actions = InputActions::default();
+        let mut input = InputManager::new();
+
+        key_event(&mut input, VirtualKeyCode::Space, ElementState::Pressed, 1);
+        key_event(&mut input, VirtualKeyCode::W, ElementState::Pressed, 1);
+
+        assert!(actions.is_active(&input, Action::Jump));
+        assert!(actions.is_triggered(&input, Action::Jump));
+        assert!(!actions.is_active(&input, Action::Sneak));
+        assert_eq!(actions.axis_value(&input, Axis::MoveForward), 1.0);
+        assert_eq!(actions.axis_value(&input, Axis::MoveRight), 0.0);
+    }
+
+    #[test]
+    fn test_pressed_released_across_frames() {
+        let actions = InputActions::default();
+        let mut input = InputManager::new();
+
+        key_event(&mut input, VirtualKeyCode::Space, ElementState::Pressed, 1);
+        input.detected_new_frame();
+        assert!(actions.is_active(&input, Action::Jump));
+        assert!(!actions.is_triggered(&input, Action::Jump));
+
+        key_event(&mut input, VirtualKeyCode::Space, ElementState::Released, 2);
+        assert!(!actions.is_active(&input, Action::Jump));
+        assert!(actions.is_released(&input, Action::Jump));
+    }
+
+    #[test]
+    fn test_multiple_bindings_per_action() {
+        let mut actions = InputActions::default();
+        actions.bind(Action::Jump, Binding::Mouse(MouseButton::Middle));
+        let mut input = InputManager::new();
+
+        mouse_event(&mut input, MouseButton::Middle, ElementState::Pressed, 1);
+        key_event(&mut input, VirtualKeyCode::Space, ElementState::Pressed, 1);
+        assert!(actions.is_active(&input, Action::Jump));
+
+        key_event(&mut input, VirtualKeyCode::Space, ElementState::Released, 1);
+        assert!(actions.is_active(&input, Action::Jump));
+        assert!(!actions.is_released(&input, Action::Jump));
+    }
+
+    #[test]
+    fn test_axis_cancels_and_clamps() {
+        let mut actions = InputActions::default();
+        actions.bind_axis(
+            Axis::MoveForward,
+            Binding::Key(VirtualKeyCode::Down),
+            Binding::Key(VirtualKeyCode::Up),
+        );
+        let mut input = InputManager::new();
+
+        key_event(&mut input, VirtualKeyCode::W, ElementState::Pressed, 1);
+        key_event(&mut input, VirtualKeyCode::Up, ElementState::Pressed, 1);
+        assert_eq!(actions.axis_value(&input, Axis::MoveForward), 1.0);
+
+        key_event(&mut input, VirtualKeyCode::S, ElementState::Pressed, 1);
+        key_event(&mut input, VirtualKeyCode::Up, ElementState::Released, 1);
+        assert_eq!(actions.axis_value(&input, Axis::MoveForward), 0.0);
+
+        key_event(&mut input, VirtualKeyCode::W, ElementState::Released, 1);
+        assert_eq!(actions.axis_value(&input, Axis::MoveForward), -1.0);
+    }
+
+    #[test]
+    fn test_wheel_binding() {
+        let mut actions = InputActions::unbound();
+        actions.bind(Action::PlaceBlock, Binding::Wheel(ScrollWheelDelta::Up));
+        let mut input = InputManager::new();
+        let device_id = unsafe { DeviceId::dummy() };
+
+        input.detect_wheel(
+            device_id,
+            winit::event::MouseScrollDelta::LineDelta(0.0, 1.0),
+            winit::event::TouchPhase::Moved,
+            1,
+        );
+
+        assert!(actions.is_triggered(&input, Action::PlaceBlock));
+        input.detected_new_frame();
+        assert!(!actions.is_active(&input, Action::PlaceBlock));
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let mut actions = InputActions::default();
+        actions.clear_action(Action::Jump);
+        actions.bind(Action::Jump, Binding::Key(VirtualKeyCode::J));
+        actions.bind(Action::Jump, Binding::Mouse(MouseButton::Other(4)));
+        actions.clear_action(Action::Sneak);
+        actions.bind_axis(
+            Axis::MoveRight,
+            Binding::Key(VirtualKeyCode::Left),
+            Binding::Key(VirtualKeyCode::Right),
+        );
+
+        let text = actions.to_config_string().unwrap();
+        let loaded = InputActions::from_config_str(&text).unwrap();
+
+        assert_eq!(loaded, actions);
+        assert!(text.contains("jump = [\"key:J\", \"mouse:4\"]"));
+        assert!(text.contains("sneak = []"));
+        assert!(text.contains("move_right = [[\"key:A\", \"key:D\"], "));
+    }
+
+    #[test]
+    fn test_config_overrides_only_listed_actions() {
+        let text = "# custom\n[actions]\njump = [\"key:F\", \"wheel:down\"]\n[axes]\nmove_forward = [[\"key:Down\", \"key:Up\"]]\n";
+
+        let actions = InputActions::from_config_str(text).unwrap();
+
+        assert_eq!(
+            actions.bindings(Action::Jump),
+            &[Binding::Key(VirtualKeyCode::F), Binding::Wheel(ScrollWheelDelta::Down)]
+        );
+        assert_eq!(actions.axis_bindings(Axis::MoveForward).len(), 1);
+        assert_eq!(
+            actions.bindings(Action::Sneak),
+            &[Binding::Key(VirtualKeyCode::LShift), Binding::GamepadButton(GamepadButton::East)]
+        );
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert!(InputActions::from_config_str("[actions]\nfly = [\"key:F\"]").is_err());
+        assert!(InputActions::from_config_str("[actions]\njump = [\"key:NotAKey\"]").is_err());
+        assert!(InputActions::from_config_str("[actions]\njump = [\"Space\"]").is_err());
+        assert!(InputActions::from_config_str("[axes]\nmove_right = [\"key:A\"]").is_err());
+        assert!(InputActions::from_config_str("jump = [\"key:Space\"]").is_err());
+    }
+
+    #[test]
+    fn test_rebind_keeps_gamepad_bindings() {
+        let mut actions = InputActions::default();
+        actions.bind(Action::Jump, Binding::Mouse(MouseButton::Middle));
+
+        actions.rebind(Action::Jump, Binding::Key(VirtualKeyCode::J));
+        assert_eq!(
+            actions.bindings(Action::Jump),
+            &[Binding::GamepadButton(GamepadButton::South), Binding::Key(VirtualKeyCode::J)]
+        );
+
+        // Actions without any binding get their first one
+        actions.rebind(Action::ReleaseCursor, Binding::Key(VirtualKeyCode::Tab));
+        assert_eq!(actions.bindings(Action::ReleaseCursor), &[Binding::Key(VirtualKeyCode::Tab)]);
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let path = std::env::temp_dir().join(format!("input_actions_test_{}.toml", std::process::id()));
+        let mut actions = InputActions::default();
+        actions.bind(Action::ToggleFullscreen, Binding::Key(VirtualKeyCode::Return));
+
+        actions.save(&path).unwrap();
+        let loaded = InputActions::load_or_default(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, actions);
+    }
+
+    #[test]
+    fn test_every_key_round_trips() {
+        // `key_name` matches every VirtualKeyCode, so these are all of them
+        let mut actions = InputActions::default();
+        actions.clear_action(Action::Jump);
+        for (key, name) in KEY_NAMES {
+            assert_eq!(key_name(*key), *name);
+            assert_eq!(key_from_name(name), Some(*key), "{} is named twice", name);
+            actions.bind(Action::Jump, Binding::Key(*key));
+        }
+
+        let path = std::env::temp_dir().join(format!("input_actions_keys_test_{}.toml", std::process::id()));
+        actions.save(&path).unwrap();
+        let loaded = InputActions::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), actions);
+        assert_eq!(actions.bindings(Action::Jump).len(), KEY_NAMES.len());
+    }
+
+    #[test]
+    fn test_gamepad_drives_the_same_actions() {
+        let actions = InputActions::default();
+        let mut input = InputManager::new();
+        let mut pad = MockGamepad::new();
+        pad.push(GamepadEvent::Connected);
+        pad.push(GamepadEvent::AxisChanged(GamepadAxis::LeftStickY, 0.97));
+        pad.push(GamepadEvent::AxisChanged(GamepadAxis::RightStickX, 0.1));
+        pad.push(GamepadEvent::ButtonPressed(GamepadButton::South));
+        input.poll_gamepad(&mut pad, 1);
+
+        assert_eq!(actions.axis_value(&input, Axis::MoveForward), 1.0);
+        assert_eq!(actions.axis_value(&input, Axis::MoveRight), 0.0);
+        assert_eq!(actions.axis_value(&input, Axis::LookRight), 0.0);
+        assert!(actions.is_triggered(&input, Action::Jump));
+
+        // Half way through the usable range after the dead zone
+        pad.push(GamepadEvent::AxisChanged(GamepadAxis::LeftStickY, -0.55));
+        input.poll_gamepad(&mut pad, 2);
+        assert!((actions.axis_value(&input, Axis::MoveForward) + 0.5).abs() < 0.0001);
+        assert!(actions.is_active(&input, Action::Jump));
+        assert!(!actions.is_triggered(&input, Action::Jump));
+    }
+
+    #[test]
+    fn test_trigger_acts_as_button() {
+        let actions = InputActions::default();
+        let mut input = InputManager::new();
+
+        input.detect_gamepad(GamepadEvent::AxisChanged(GamepadAxis::RightTrigger, 0.3), 1);
+        assert!(!actions.is_active(&input, Action::BreakBlock));
+
+        input.detect_gamepad(GamepadEvent::AxisChanged(GamepadAxis::RightTrigger, 0.9), 2);
+        assert!(actions.is_triggered(&input, Action::BreakBlock));
+
+        input.detected_new_frame();
+        assert!(actions.is_active(&input, Action::BreakBlock));
+        assert!(!actions.is_triggered(&input, Action::BreakBlock));
+
+        input.detect_gamepad(GamepadEvent::AxisChanged(GamepadAxis::RightTrigger, 0.0), 3);
+        assert!(actions.is_released(&input, Action::BreakBlock));
+    }
+
+    #[test]
+    fn test_gamepad_bindings_in_config() {
+        let text = "[actions]\njump = [\"pad:North\", \"key:Space\"]\n\
+                    [axes]\nlook_up = [[\"pad_axis:RightStickY-\", \"pad_axis:RightStickY+\"]]\n";
+
+        let actions = InputActions::from_config_str(text).unwrap();
+
+        assert_eq!(
+            actions.bindings(Action::Jump),
+            &[Binding::GamepadButton(GamepadButton::North), Binding::Key(VirtualKeyCode::Space)]
+        );
+        assert_eq!(
+            actions.axis_bindings(Axis::LookUp)[0].positive,
+            Binding::GamepadAxis(GamepadAxis::RightStickY, AxisDirection::Positive)
+        );
+        assert_eq!(InputActions::from_config_str(&actions.to_config_string().unwrap()).unwrap(), actions);
+        assert!(InputActions::from_config_str("[actions]\njump = [\"pad:Z\"]").is_err());
+        assert!(InputActions::from_config_str("[actions]\njump = [\"pad_axis:LeftStickY\"]").is_err());
+    }
+}
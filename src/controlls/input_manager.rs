@@ -1,16 +1,19 @@
 use std::collections::HashMap;
 
 use anyhow::{anyhow, Result};
-use enigo::{Enigo, MouseControllable};
+use crate::controlls::gamepad::{GamepadBackend, GamepadEvent, GamepadState};
+use crate::controlls::input_actions::{Action, Axis, InputActions};
+use crate::controlls::input_recording::{InputRecording, RecordedInput};
+use crate::controlls::mouse_look::MouseLook;
 use winit::event::{
     DeviceId, ElementState, KeyboardInput, MouseButton, MouseScrollDelta, TouchPhase,
     VirtualKeyCode,
 };
-use winit::window::Window;
 
 #[derive(Clone, Debug)]
 pub(crate) struct InputManager {
     last_frame: u128,
+    pub(crate) actions: InputActions,
 
     // Keyboard
     currently_pressed_keyboard: HashMap<VirtualKeyCode, usize>,
@@ -21,30 +24,46 @@ pub(crate) struct InputManager {
     currently_pressed_mouse: HashMap<MouseButton, usize>,
     pressed_current_frame_mouse: Vec<MouseButton>,
     released_current_frame_mouse: Vec<MouseButton>,
-    mouse_delta: (i32, i32),
-    last_mouse_delta: (i32, i32),
+    mouse_delta: (f32, f32),
+    pub(crate) mouse_look: MouseLook,
+    // Physical pixels inside the window, None once the cursor left it
+    cursor_position: Option<(f32, f32)>,
+
+    // Text typed this frame, for text fields rather than bindings
+    typed_characters: Vec<char>,
 
     // Wheel
     scrolled_up: bool,
     scrolled_down: bool,
     scroll_delta: i16,
+
+    // Gamepad
+    pub(crate) gamepad: GamepadState,
+
+    // Recording
+    recording: Option<InputRecording>,
 }
 
 impl InputManager {
     pub(crate) fn new() -> Self {
         Self {
             last_frame: 1,
+            actions: InputActions::default(),
             currently_pressed_keyboard: HashMap::new(),
             pressed_current_frame_keyboard: vec![],
             released_current_frame_keyboard: vec![],
             currently_pressed_mouse: HashMap::new(),
             pressed_current_frame_mouse: vec![],
             released_current_frame_mouse: vec![],
-            mouse_delta: (0, 0),
-            last_mouse_delta: (0, 0),
+            mouse_delta: (0.0, 0.0),
+            mouse_look: MouseLook::default(),
+            cursor_position: None,
+            typed_characters: vec![],
             scrolled_up: false,
             scrolled_down: false,
             scroll_delta: 0,
+            gamepad: GamepadState::default(),
+            recording: None,
         }
     }
 
@@ -61,6 +80,11 @@ impl InputManager {
         self.currently_pressed_keyboard.contains_key(&key_code)
     }
 
+    // Every key pressed this frame, in the order they came in
+    pub(crate) fn get_keys_down(&self) -> &[VirtualKeyCode] {
+        &self.pressed_current_frame_keyboard
+    }
+
     // Mouse
     pub(crate) fn get_key_down_mouse(&self, button: MouseButton) -> bool {
         self.pressed_current_frame_mouse.contains(&button)
@@ -74,10 +98,23 @@ impl InputManager {
         self.currently_pressed_mouse.contains_key(&button)
     }
 
-    pub(crate) fn get_mouse_delta(&self) -> (i32, i32) {
+    pub(crate) fn get_mouse_buttons_down(&self) -> &[MouseButton] {
+        &self.pressed_current_frame_mouse
+    }
+
+    pub(crate) fn get_mouse_delta(&self) -> (f32, f32) {
         self.mouse_delta
     }
 
+    pub(crate) fn get_cursor_position(&self) -> Option<(f32, f32)> {
+        self.cursor_position
+    }
+
+    // Text
+    pub(crate) fn get_typed_characters(&self) -> &[char] {
+        &self.typed_characters
+    }
+
     // Wheel
     pub(crate) fn get_scroll(&self, delta: ScrollWheelDelta) -> bool {
         return match delta {
@@ -90,30 +127,86 @@ impl InputManager {
         self.scroll_delta
     }
 
-    // Handling
-    pub(crate) fn handle_mouse(&mut self, window: &Window, is_cursor_locked: bool) -> Result<()> {
-        let window_inner_position = window.inner_position()?;
-        let window_inner_size = window.inner_size();
+    // Actions
+    pub(crate) fn get_action_down(&self, action: Action) -> bool {
+        self.actions.is_triggered(self, action)
+    }
+
+    pub(crate) fn get_action_up(&self, action: Action) -> bool {
+        self.actions.is_released(self, action)
+    }
+
+    pub(crate) fn get_action(&self, action: Action) -> bool {
+        self.actions.is_active(self, action)
+    }
+
+    pub(crate) fn get_axis(&self, axis: Axis) -> f32 {
+        self.actions.axis_value(self, axis)
+    }
+
+    // Recording
+    pub(crate) fn start_recording(&mut self, tick_rate: u32, max_frame_time: f64) {
+        self.recording = Some(InputRecording::new(self, tick_rate, max_frame_time));
+    }
+
+    pub(crate) fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    // Closes the inputs gathered so far into a frame that took `delta_time` seconds
+    pub(crate) fn end_recorded_frame(&mut self, delta_time: f64) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.end_frame(delta_time);
+        }
+    }
 
-        let window_center_x = window_inner_position.x + window_inner_size.width as i32 / 2;
-        let window_center_y = window_inner_position.y + window_inner_size.height as i32 / 2;
+    // Ends a frame without running the simulation, its input is thrown away. The frame is recorded
+    // as taking no time, so a replay's timestep stays where the paused game left it.
+    pub(crate) fn skip_frame(&mut self) {
+        self.record(RecordedInput::SkippedFrame);
+        self.end_recorded_frame(0.0);
+        self.detected_new_frame();
+    }
 
-        let mouse_position: (i32, i32) = Enigo::mouse_location();
+    fn record(&mut self, input: RecordedInput) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(input);
+        }
+    }
 
-        let x_offset = -(window_center_x - mouse_position.0 - 1);
-        let y_offset = -(window_center_y - mouse_position.1 - 1);
+    // Handling
+    // Turns the raw motion gathered since the last frame into this frame's look delta
+    pub(crate) fn handle_mouse(&mut self, is_cursor_locked: bool) {
+        self.record(RecordedInput::MouseLook { is_cursor_locked });
         if is_cursor_locked {
-            self.mouse_delta = (x_offset, y_offset);
-            Enigo.mouse_move_to(window_center_x, window_center_y);
+            let delta = self.mouse_look.take_delta();
+            self.mouse_delta.0 += delta.0;
+            self.mouse_delta.1 += delta.1;
         } else {
-            self.mouse_delta = (
-                -self.last_mouse_delta.0 + x_offset,
-                -self.last_mouse_delta.1 + y_offset,
-            );
+            self.mouse_look.discard();
         }
+    }
 
-        self.last_mouse_delta = (x_offset, y_offset);
-        Ok(())
+    pub(crate) fn detect_mouse_motion(&mut self, delta: (f64, f64)) {
+        self.record(RecordedInput::MouseMotion(delta));
+        self.mouse_look.add_motion(delta);
+    }
+
+    // The cursor and typed text only drive the UI, not the simulation, so they aren't recorded
+    pub(crate) fn detect_cursor_position(&mut self, position: Option<(f64, f64)>) {
+        self.cursor_position = position.map(|(x, y)| (x as f32, y as f32));
+    }
+
+    pub(crate) fn detect_character(&mut self, character: char, current_frame: u128) {
+        if current_frame != self.last_frame {
+            self.last_frame = current_frame;
+            self.detected_new_frame();
+        }
+        self.typed_characters.push(character);
     }
 
     pub(crate) fn detected_new_frame(&mut self) {
@@ -124,6 +217,9 @@ impl InputManager {
         self.scrolled_up = false;
         self.scrolled_down = false;
         self.scroll_delta = 0;
+        self.mouse_delta = (0.0, 0.0);
+        self.typed_characters.clear();
+        self.gamepad.detected_new_frame();
     }
 
     pub(crate) fn detect_keyboard(
@@ -133,6 +229,11 @@ impl InputManager {
         is_synthetic: bool,
         current_frame: u128,
     ) -> Result<()> {
+        self.record(RecordedInput::Key {
+            key: input.virtual_keycode,
+            state: input.state,
+            frame: current_frame,
+        });
         if current_frame != self.last_frame {
             self.last_frame = current_frame;
             self.detected_new_frame();
@@ -172,6 +273,11 @@ impl InputManager {
         state: ElementState,
         current_frame: u128,
     ) {
+        self.record(RecordedInput::Mouse {
+            button,
+            state,
+            frame: current_frame,
+        });
         if current_frame != self.last_frame {
             self.last_frame = current_frame;
             self.detected_new_frame();
@@ -201,6 +307,10 @@ impl InputManager {
         phase: TouchPhase,
         current_frame: u128,
     ) {
+        self.record(RecordedInput::Wheel {
+            delta,
+            frame: current_frame,
+        });
         if current_frame != self.last_frame {
             self.last_frame = current_frame;
             self.detected_new_frame();
@@ -214,8 +324,28 @@ impl InputManager {
             self.scroll_delta -= 1;
         }
     }
+
+    pub(crate) fn detect_gamepad(&mut self, event: GamepadEvent, current_frame: u128) {
+        self.record(RecordedInput::Gamepad {
+            event,
+            frame: current_frame,
+        });
+        if current_frame != self.last_frame {
+            self.last_frame = current_frame;
+            self.detected_new_frame();
+        }
+
+        self.gamepad.handle_event(event);
+    }
+
+    pub(crate) fn poll_gamepad(&mut self, backend: &mut dyn GamepadBackend, current_frame: u128) {
+        for event in backend.poll_events() {
+            self.detect_gamepad(event, current_frame);
+        }
+    }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) enum ScrollWheelDelta {
     Up,
     Down,
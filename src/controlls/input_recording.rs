@@ -0,0 +1,449 @@
+use crate::controlls::gamepad::{AxisSettings, GamepadAxis, GamepadEvent, GamepadSettings, ResponseCurve};
+use crate::controlls::input_actions::{key_name, Binding, InputActions};
+use crate::controlls::input_manager::InputManager;
+use crate::controlls::mouse_look::MouseSettings;
+use crate::terrain::world::VoxelEdit;
+use anyhow::{anyhow, Result};
+use nalgebra_glm as glm;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use winit::dpi::PhysicalPosition;
+use winit::event::{
+    DeviceId, ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase,
+    VirtualKeyCode,
+};
+
+// One call into InputManager, in the order the event loop made them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RecordedInput {
+    Key { key: Option<VirtualKeyCode>, state: ElementState, frame: u128 },
+    Mouse { button: MouseButton, state: ElementState, frame: u128 },
+    Wheel { delta: MouseScrollDelta, frame: u128 },
+    Gamepad { event: GamepadEvent, frame: u128 },
+    MouseMotion((f64, f64)),
+    // The once per frame handle_mouse call that turns motion into the look delta
+    MouseLook { is_cursor_locked: bool },
+    // A frame the simulation never saw, like one while paused, its input is dropped here
+    SkippedFrame,
+}
+
+impl RecordedInput {
+    // Feeds the input back the same way the event loop delivered it
+    pub(crate) fn apply(&self, input_manager: &mut InputManager) {
+        // InputManager never looks at device ids
+        let device_id = unsafe { DeviceId::dummy() };
+
+        match *self {
+            RecordedInput::Key { key, state, frame } => {
+                #[allow(deprecated)]
+                let input = KeyboardInput {
+                    scancode: 0,
+                    state,
+                    virtual_keycode: key,
+                    modifiers: ModifiersState::empty(),
+                };
+                // Keys without a keycode are rejected just like they were while recording
+                let _ = input_manager.detect_keyboard(device_id, input, false, frame);
+            }
+            RecordedInput::Mouse { button, state, frame } => {
+                input_manager.detect_mouse(device_id, button, state, frame);
+            }
+            RecordedInput::Wheel { delta, frame } => {
+                input_manager.detect_wheel(device_id, delta, TouchPhase::Moved, frame);
+            }
+            RecordedInput::Gamepad { event, frame } => input_manager.detect_gamepad(event, frame),
+            RecordedInput::MouseMotion(delta) => input_manager.detect_mouse_motion(delta),
+            RecordedInput::MouseLook { is_cursor_locked } => input_manager.handle_mouse(is_cursor_locked),
+            RecordedInput::SkippedFrame => input_manager.detected_new_frame(),
+        }
+    }
+
+    fn to_line(self) -> String {
+        let state_name = |state| match state {
+            ElementState::Pressed => "press",
+            ElementState::Released => "release",
+        };
+
+        match self {
+            RecordedInput::Key { key, state, frame } => {
+                let name = key.map(key_name).unwrap_or("?");
+                format!("{} {} key:{}", state_name(state), frame, name)
+            }
+            RecordedInput::Mouse { button, state, frame } => {
+                format!("{} {} {}", state_name(state), frame, Binding::Mouse(button).to_config_string())
+            }
+            RecordedInput::Wheel { delta: MouseScrollDelta::LineDelta(x, y), frame } => {
+                format!("wheel {} {} {}", frame, x, y)
+            }
+            RecordedInput::Wheel { delta: MouseScrollDelta::PixelDelta(position), frame } => {
+                format!("wheel_pixels {} {} {}", frame, position.x, position.y)
+            }
+            RecordedInput::Gamepad { event, frame } => match event {
+                GamepadEvent::Connected => format!("pad_connected {}", frame),
+                GamepadEvent::Disconnected => format!("pad_disconnected {}", frame),
+                GamepadEvent::ButtonPressed(button) => format!("press {} pad:{}", frame, button.name()),
+                GamepadEvent::ButtonReleased(button) => format!("release {} pad:{}", frame, button.name()),
+                GamepadEvent::AxisChanged(axis, value) => format!("pad_axis {} {} {}", frame, axis.name(), value),
+            },
+            RecordedInput::MouseMotion((x, y)) => format!("motion {} {}", x, y),
+            RecordedInput::MouseLook { is_cursor_locked } => {
+                format!("look {}", if is_cursor_locked { "locked" } else { "free" })
+            }
+            RecordedInput::SkippedFrame => "skip".to_string(),
+        }
+    }
+
+    // None when the line isn't an input
+    fn parse(words: &[&str]) -> Result<Option<Self>> {
+        let input = match words {
+            [kind @ ("press" | "release"), frame, binding] => {
+                let state = if *kind == "press" { ElementState::Pressed } else { ElementState::Released };
+                let frame = parse_number(frame)?;
+                if *binding == "key:?" {
+                    return Ok(Some(RecordedInput::Key { key: None, state, frame }));
+                }
+                match (Binding::parse(binding)?, state) {
+                    (Binding::Key(key), _) => RecordedInput::Key { key: Some(key), state, frame },
+                    (Binding::Mouse(button), _) => RecordedInput::Mouse { button, state, frame },
+                    (Binding::GamepadButton(button), ElementState::Pressed) => RecordedInput::Gamepad {
+                        event: GamepadEvent::ButtonPressed(button),
+                        frame,
+                    },
+                    (Binding::GamepadButton(button), ElementState::Released) => RecordedInput::Gamepad {
+                        event: GamepadEvent::ButtonReleased(button),
+                        frame,
+                    },
+                    _ => return Err(anyhow!("`{}` can't be pressed", binding)),
+                }
+            }
+            ["wheel", frame, x, y] => RecordedInput::Wheel {
+                delta: MouseScrollDelta::LineDelta(parse_number(x)?, parse_number(y)?),
+                frame: parse_number(frame)?,
+            },
+            ["wheel_pixels", frame, x, y] => RecordedInput::Wheel {
+                delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(parse_number(x)?, parse_number(y)?)),
+                frame: parse_number(frame)?,
+            },
+            ["pad_connected", frame] => RecordedInput::Gamepad {
+                event: GamepadEvent::Connected,
+                frame: parse_number(frame)?,
+            },
+            ["pad_disconnected", frame] => RecordedInput::Gamepad {
+                event: GamepadEvent::Disconnected,
+                frame: parse_number(frame)?,
+            },
+            ["pad_axis", frame, axis, value] => {
+                let axis = GamepadAxis::ALL
+                    .into_iter()
+                    .find(|other| other.name() == *axis)
+                    .ok_or_else(|| anyhow!("Unknown gamepad axis `{}`", axis))?;
+                RecordedInput::Gamepad {
+                    event: GamepadEvent::AxisChanged(axis, parse_number(value)?),
+                    frame: parse_number(frame)?,
+                }
+            }
+            ["motion", x, y] => RecordedInput::MouseMotion((parse_number(x)?, parse_number(y)?)),
+            ["look", "locked"] => RecordedInput::MouseLook { is_cursor_locked: true },
+            ["look", "free"] => RecordedInput::MouseLook { is_cursor_locked: false },
+            ["skip"] => RecordedInput::SkippedFrame,
+            _ => return Ok(None),
+        };
+        Ok(Some(input))
+    }
+}
+
+// Inputs of one rendered frame and the real time it took, which decides how many ticks ran
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RecordedFrame {
+    pub(crate) delta_time: f64,
+    pub(crate) inputs: Vec<RecordedInput>,
+}
+
+// A session's input stream together with the settings that shaped it and how it ended
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct InputRecording {
+    pub(crate) tick_rate: u32,
+    pub(crate) max_frame_time: f64,
+    pub(crate) actions: InputActions,
+    pub(crate) mouse_settings: MouseSettings,
+    pub(crate) gamepad_settings: GamepadSettings,
+    pub(crate) frames: Vec<RecordedFrame>,
+    pub(crate) expected_position: Option<glm::Vec3>,
+    pub(crate) expected_voxels: Vec<VoxelEdit>,
+    pending_inputs: Vec<RecordedInput>,
+}
+
+impl InputRecording {
+    pub(crate) fn new(input_manager: &InputManager, tick_rate: u32, max_frame_time: f64) -> Self {
+        Self {
+            tick_rate,
+            max_frame_time,
+            actions: input_manager.actions.clone(),
+            mouse_settings: input_manager.mouse_look.settings,
+            gamepad_settings: input_manager.gamepad.settings,
+            frames: vec![],
+            expected_position: None,
+            expected_voxels: vec![],
+            pending_inputs: vec![],
+        }
+    }
+
+    pub(crate) fn push(&mut self, input: RecordedInput) {
+        self.pending_inputs.push(input);
+    }
+
+    pub(crate) fn end_frame(&mut self, delta_time: f64) {
+        self.frames.push(RecordedFrame {
+            delta_time,
+            inputs: std::mem::take(&mut self.pending_inputs),
+        });
+    }
+
+    // A fresh InputManager configured the way the recorded one was
+    pub(crate) fn create_input_manager(&self) -> InputManager {
+        let mut input_manager = InputManager::new();
+        input_manager.actions = self.actions.clone();
+        input_manager.mouse_look.settings = self.mouse_settings;
+        input_manager.gamepad.settings = self.gamepad_settings;
+        input_manager
+    }
+
+    // Floats are written with Display, which reads back to the exact same value
+    pub(crate) fn to_text(&self) -> Result<String> {
+        let mut lines = vec![
+            "# Input recording, replay with `--replay <path>`".to_string(),
+            format!("tick_rate {}", self.tick_rate),
+            format!("max_frame_time {}", self.max_frame_time),
+            format!(
+                "mouse {} {} {}",
+                self.mouse_settings.sensitivity, self.mouse_settings.invert_y, self.mouse_settings.smoothing
+            ),
+        ];
+        for (name, settings) in [
+            ("left_stick", &self.gamepad_settings.left_stick),
+            ("right_stick", &self.gamepad_settings.right_stick),
+            ("triggers", &self.gamepad_settings.triggers),
+        ] {
+            lines.push(format!(
+                "pad_settings {} {} {} {}",
+                name,
+                settings.dead_zone,
+                settings.saturation,
+                settings.curve.name()
+            ));
+        }
+        for line in self.actions.to_config_string()?.lines() {
+            if !line.is_empty() && !line.starts_with('#') {
+                lines.push(format!("bind {}", line));
+            }
+        }
+        if let Some(position) = self.expected_position {
+            lines.push(format!("expect_position {} {} {}", position.x, position.y, position.z));
+        }
+        for edit in &self.expected_voxels {
+            lines.push(format!(
+                "expect_voxel {} {} {} {}",
+                edit.position.x, edit.position.y, edit.position.z, edit.voxel_id
+            ));
+        }
+        for frame in &self.frames {
+            lines.push(format!("frame {}", frame.delta_time));
+            lines.extend(frame.inputs.iter().map(|input| input.to_line()));
+        }
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        Ok(text)
+    }
+
+    pub(crate) fn from_text(text: &str) -> Result<Self> {
+        let mut recording = Self::new(&InputManager::new(), 0, 0.0);
+        let mut bindings = String::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            recording
+                .parse_line(line, &mut bindings)
+                .map_err(|error| anyhow!("Line {}: {}", line_index + 1, error))?;
+        }
+
+        if recording.tick_rate == 0 {
+            return Err(anyhow!("Recording has no tick rate"));
+        }
+        recording.actions = InputActions::from_config_str(&bindings)?;
+        Ok(recording)
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        Self::from_text(&fs::read_to_string(path)?)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_text()?)?;
+        Ok(())
+    }
+
+    fn parse_line(&mut self, line: &str, bindings: &mut String) -> Result<()> {
+        if let Some(binding) = line.strip_prefix("bind ") {
+            bindings.push_str(binding);
+            bindings.push('\n');
+            return Ok(());
+        }
+
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            ["tick_rate", rate] => self.tick_rate = parse_number(rate)?,
+            ["max_frame_time", time] => self.max_frame_time = parse_number(time)?,
+            ["mouse", sensitivity, invert_y, smoothing] => {
+                self.mouse_settings = MouseSettings {
+                    sensitivity: parse_number(sensitivity)?,
+                    invert_y: parse_number(invert_y)?,
+                    smoothing: parse_number(smoothing)?,
+                };
+            }
+            ["pad_settings", name, dead_zone, saturation, curve] => {
+                let settings = AxisSettings {
+                    dead_zone: parse_number(dead_zone)?,
+                    saturation: parse_number(saturation)?,
+                    curve: ResponseCurve::ALL
+                        .into_iter()
+                        .find(|other| other.name() == *curve)
+                        .ok_or_else(|| anyhow!("Unknown response curve `{}`", curve))?,
+                };
+                match *name {
+                    "left_stick" => self.gamepad_settings.left_stick = settings,
+                    "right_stick" => self.gamepad_settings.right_stick = settings,
+                    "triggers" => self.gamepad_settings.triggers = settings,
+                    other => return Err(anyhow!("Unknown gamepad axes `{}`", other)),
+                }
+            }
+            ["expect_position", x, y, z] => {
+                self.expected_position = Some(glm::vec3(parse_number(x)?, parse_number(y)?, parse_number(z)?));
+            }
+            ["expect_voxel", x, y, z, voxel_id] => self.expected_voxels.push(VoxelEdit {
+                position: glm::vec3(parse_number(x)?, parse_number(y)?, parse_number(z)?),
+                voxel_id: parse_number(voxel_id)?,
+            }),
+            // Inputs below a frame line belong to that frame
+            ["frame", delta_time] => self.frames.push(RecordedFrame {
+                delta_time: parse_number(delta_time)?,
+                inputs: vec![],
+            }),
+            _ => {
+                let input = RecordedInput::parse(&words)?.ok_or_else(|| anyhow!("Unknown entry `{}`", line))?;
+                self.frames
+                    .last_mut()
+                    .ok_or_else(|| anyhow!("Input before the first frame"))?
+                    .inputs
+                    .push(input);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<T: FromStr>(text: &str) -> Result<T> {
+    text.parse().map_err(|_| anyhow!("Couldn't read `{}`", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controlls::gamepad::GamepadButton;
+
+    fn sample_recording() -> InputRecording {
+        let mut input_manager = InputManager::new();
+        input_manager.mouse_look.settings.sensitivity = 0.35;
+        input_manager.gamepad.settings.right_stick.curve = ResponseCurve::Cubic;
+        let mut recording = InputRecording::new(&input_manager, 60, 0.4);
+
+        recording.push(RecordedInput::Key { key: Some(VirtualKeyCode::W), state: ElementState::Pressed, frame: 0 });
+        recording.push(RecordedInput::Key { key: None, state: ElementState::Released, frame: 0 });
+        recording.push(RecordedInput::MouseMotion((1.25, -0.1)));
+        recording.push(RecordedInput::MouseLook { is_cursor_locked: true });
+        recording.end_frame(1.0 / 60.0);
+        recording.push(RecordedInput::Mouse { button: MouseButton::Other(5), state: ElementState::Pressed, frame: 1 });
+        recording.push(RecordedInput::Wheel { delta: MouseScrollDelta::LineDelta(0.0, -1.0), frame: 1 });
+        recording.push(RecordedInput::Wheel {
+            delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.5, 3.0)),
+            frame: 1,
+        });
+        recording.push(RecordedInput::Gamepad { event: GamepadEvent::Connected, frame: 1 });
+        recording.push(RecordedInput::Gamepad {
+            event: GamepadEvent::ButtonReleased(GamepadButton::DPadLeft),
+            frame: 1,
+        });
+        recording.push(RecordedInput::Gamepad {
+            event: GamepadEvent::AxisChanged(GamepadAxis::LeftStickY, 0.123456),
+            frame: 1,
+        });
+        recording.end_frame(0.0213);
+        recording.push(RecordedInput::SkippedFrame);
+        recording.end_frame(0.0);
+        recording.end_frame(0.3);
+        recording.expected_position = Some(glm::vec3(0.1, -2.5, 7.0 / 3.0));
+        recording.expected_voxels.push(VoxelEdit { position: glm::vec3(-1, 4, 2), voxel_id: 0 });
+        recording
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let recording = sample_recording();
+
+        let text = recording.to_text().unwrap();
+        let loaded = InputRecording::from_text(&text).unwrap();
+
+        assert_eq!(loaded, recording);
+        assert!(text.contains("press 0 key:W\nrelease 0 key:?\nmotion 1.25 -0.1\nlook locked\n"));
+    }
+
+    #[test]
+    fn test_input_manager_records_frames() {
+        let mut input_manager = InputManager::new();
+        input_manager.start_recording(60, 0.4);
+
+        input_manager.detect_mouse_motion((3.0, 4.0));
+        input_manager.handle_mouse(true);
+        input_manager.end_recorded_frame(0.01);
+        input_manager.detect_gamepad(GamepadEvent::ButtonPressed(GamepadButton::South), 1);
+        input_manager.end_recorded_frame(0.02);
+        let recording = input_manager.stop_recording().unwrap();
+
+        assert!(!input_manager.is_recording());
+        assert_eq!(
+            recording.frames,
+            vec![
+                RecordedFrame {
+                    delta_time: 0.01,
+                    inputs: vec![
+                        RecordedInput::MouseMotion((3.0, 4.0)),
+                        RecordedInput::MouseLook { is_cursor_locked: true },
+                    ],
+                },
+                RecordedFrame {
+                    delta_time: 0.02,
+                    inputs: vec![RecordedInput::Gamepad {
+                        event: GamepadEvent::ButtonPressed(GamepadButton::South),
+                        frame: 1,
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_bad_lines() {
+        let error = InputRecording::from_text("tick_rate 60\nframe 0.1\npress 1 wheel:up\n").unwrap_err();
+        assert!(error.to_string().contains("Line 3"));
+        assert!(InputRecording::from_text("tick_rate 60\npress 1 key:W\n").is_err());
+        assert!(InputRecording::from_text("frame 0.1\n").is_err());
+        assert!(InputRecording::from_text("tick_rate 60\njump 3\n").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// User preferences for turning raw mouse motion into camera rotation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct MouseSettings {
+    pub(crate) sensitivity: f32,
+    pub(crate) invert_y: bool,
+    // 0 disables smoothing, values towards 1 follow the mouse more lazily
+    pub(crate) smoothing: f32,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 1.0,
+            invert_y: false,
+            smoothing: 0.0,
+        }
+    }
+}
+
+// Collects raw device motion between frames and hands out the processed look delta
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MouseLook {
+    pub(crate) settings: MouseSettings,
+    accumulated: (f64, f64),
+    smoothed: (f32, f32),
+}
+
+impl MouseLook {
+    pub(crate) fn new(settings: MouseSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn add_motion(&mut self, delta: (f64, f64)) {
+        self.accumulated.0 += delta.0;
+        self.accumulated.1 += delta.1;
+    }
+
+    // Drops motion gathered while the cursor wasn't captured
+    pub(crate) fn discard(&mut self) {
+        self.accumulated = (0.0, 0.0);
+        self.smoothed = (0.0, 0.0);
+    }
+
+    // Consumes everything accumulated since the last call
+    pub(crate) fn take_delta(&mut self) -> (f32, f32) {
+        let mut target = (
+            self.accumulated.0 as f32 * self.settings.sensitivity,
+            self.accumulated.1 as f32 * self.settings.sensitivity,
+        );
+        if self.settings.invert_y {
+            target.1 = -target.1;
+        }
+        self.accumulated = (0.0, 0.0);
+
+        let smoothing = self.settings.smoothing.clamp(0.0, 0.99);
+        self.smoothed = (
+            self.smoothed.0 * smoothing + target.0 * (1.0 - smoothing),
+            self.smoothed.1 * smoothing + target.1 * (1.0 - smoothing),
+        );
+        self.smoothed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controlls::input_manager::InputManager;
+
+    #[test]
+    fn test_accumulates_motion_between_frames() {
+        let mut look = MouseLook::default();
+
+        look.add_motion((3.0, -1.0));
+        look.add_motion((2.0, 4.0));
+
+        assert_eq!(look.take_delta(), (5.0, 3.0));
+        assert_eq!(look.take_delta(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_sensitivity_and_inversion() {
+        let mut look = MouseLook::new(MouseSettings {
+            sensitivity: 0.5,
+            invert_y: true,
+            smoothing: 0.0,
+        });
+
+        look.add_motion((4.0, 2.0));
+
+        assert_eq!(look.take_delta(), (2.0, -1.0));
+    }
+
+    #[test]
+    fn test_smoothing_spreads_motion_over_frames() {
+        let mut look = MouseLook::new(MouseSettings {
+            sensitivity: 1.0,
+            invert_y: false,
+            smoothing: 0.5,
+        });
+
+        look.add_motion((8.0, 0.0));
+        let mut total = look.take_delta().0;
+        assert_eq!(total, 4.0);
+        for _ in 0..30 {
+            total += look.take_delta().0;
+        }
+
+        assert!((total - 8.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_input_manager_only_looks_while_locked() {
+        let mut input_manager = InputManager::new();
+
+        input_manager.detect_mouse_motion((10.0, 5.0));
+        input_manager.handle_mouse(false);
+        assert_eq!(input_manager.get_mouse_delta(), (0.0, 0.0));
+
+        input_manager.detect_mouse_motion((10.0, 5.0));
+        input_manager.detect_mouse_motion((-4.0, 1.0));
+        input_manager.handle_mouse(true);
+        assert_eq!(input_manager.get_mouse_delta(), (6.0, 6.0));
+
+        input_manager.detected_new_frame();
+        assert_eq!(input_manager.get_mouse_delta(), (0.0, 0.0));
+    }
+}
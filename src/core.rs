@@ -1,9 +1,22 @@
+pub mod aabb;
+pub mod broad_phase;
+pub mod camera;
 pub mod collider;
 pub mod collision;
-pub mod game_object;
+pub mod commands;
+pub mod console_commands;
+pub mod ecs;
+pub mod file_watcher;
+pub mod game_state;
+pub mod headless_renderer;
 pub mod math_functions;
 pub mod quaternion_shared;
+pub mod replay;
+pub mod settings;
 pub mod simplex;
+pub mod simulation;
+pub mod stats;
+pub mod time_of_day;
 pub mod transform;
 pub mod trigonometry_shared;
 pub mod app;
@@ -0,0 +1,179 @@
+use nalgebra_glm as glm;
+
+// Distances below this are treated as touching rather than overlapping.
+const CONTACT_EPSILON: f32 = 0.00001;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Aabb {
+    pub(crate) min: glm::Vec3,
+    pub(crate) max: glm::Vec3,
+}
+
+impl Aabb {
+    pub(crate) fn new(min: glm::Vec3, max: glm::Vec3) -> Self {
+        Self { min, max }
+    }
+
+    // Box standing on `position`, centered on x and y
+    pub(crate) fn from_bottom_center(position: glm::Vec3, width: f32, height: f32) -> Self {
+        let half_width = width * 0.5;
+        Self {
+            min: glm::vec3(position.x - half_width, position.y - half_width, position.z),
+            max: glm::vec3(position.x + half_width, position.y + half_width, position.z + height),
+        }
+    }
+
+    pub(crate) fn from_voxel(x: i32, y: i32, z: i32) -> Self {
+        let min = glm::vec3(x as f32, y as f32, z as f32);
+        Self {
+            min,
+            max: min + glm::vec3(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub(crate) fn offset(&self, offset: glm::Vec3) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    // Grows the box in the direction of `motion` so it covers the whole swept volume
+    pub(crate) fn expand_towards(&self, motion: glm::Vec3) -> Self {
+        let mut expanded = *self;
+        for axis in 0..3 {
+            if motion[axis] < 0.0 {
+                expanded.min[axis] += motion[axis];
+            } else {
+                expanded.max[axis] += motion[axis];
+            }
+        }
+        expanded
+    }
+
+    pub(crate) fn center(&self) -> glm::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub(crate) fn size(&self) -> glm::Vec3 {
+        self.max - self.min
+    }
+
+    pub(crate) fn contains_point(&self, point: glm::Vec3) -> bool {
+        (0..3).all(|axis| point[axis] >= self.min[axis] && point[axis] <= self.max[axis])
+    }
+
+    pub(crate) fn closest_point(&self, point: glm::Vec3) -> glm::Vec3 {
+        glm::clamp_vec(&point, &self.min, &self.max)
+    }
+
+    pub(crate) fn intersects_sphere(&self, center: glm::Vec3, radius: f32) -> bool {
+        glm::distance2(&self.closest_point(center), &center) < radius * radius
+    }
+
+    // Slab test, returns the distance along `direction` at which the ray enters the box
+    pub(crate) fn ray_distance(&self, origin: glm::Vec3, direction: glm::Vec3) -> Option<f32> {
+        let mut near = 0.0_f32;
+        let mut far = f32::INFINITY;
+
+        for axis in 0..3 {
+            if direction[axis].abs() < f32::EPSILON {
+                if origin[axis] < self.min[axis] || origin[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse = 1.0 / direction[axis];
+            let mut entry = (self.min[axis] - origin[axis]) * inverse;
+            let mut exit = (self.max[axis] - origin[axis]) * inverse;
+            if entry > exit {
+                std::mem::swap(&mut entry, &mut exit);
+            }
+
+            near = near.max(entry);
+            far = far.min(exit);
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+
+    pub(crate) fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.overlaps_on_axis(other, axis))
+    }
+
+    fn overlaps_on_axis(&self, other: &Aabb, axis: usize) -> bool {
+        self.max[axis] - CONTACT_EPSILON > other.min[axis]
+            && self.min[axis] + CONTACT_EPSILON < other.max[axis]
+    }
+
+    // Shortens `motion` along `axis` so that moving this box by it doesn't penetrate `other`
+    pub(crate) fn clip_motion(&self, other: &Aabb, axis: usize, motion: f32) -> f32 {
+        let is_in_path = (0..3)
+            .filter(|other_axis| *other_axis != axis)
+            .all(|other_axis| self.overlaps_on_axis(other, other_axis));
+
+        if !is_in_path {
+            return motion;
+        }
+
+        if motion > 0.0 && other.min[axis] >= self.max[axis] - CONTACT_EPSILON {
+            motion.min(other.min[axis] - self.max[axis])
+        } else if motion < 0.0 && other.max[axis] <= self.min[axis] + CONTACT_EPSILON {
+            motion.max(other.max[axis] - self.min[axis])
+        } else {
+            motion
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersects_ignores_touching_faces() {
+        let a = Aabb::from_voxel(0, 0, 0);
+        let b = Aabb::from_voxel(1, 0, 0);
+        let c = a.offset(glm::vec3(0.5, 0.5, 0.0));
+
+        assert!(!a.intersects(&b));
+        assert!(a.intersects(&c));
+        assert!(c.intersects(&b));
+    }
+
+    #[test]
+    fn test_ray_distance() {
+        let a = Aabb::from_voxel(2, 0, 0);
+
+        let hit = a.ray_distance(glm::vec3(0.0, 0.5, 0.5), glm::vec3(1.0, 0.0, 0.0));
+        assert_eq!(hit, Some(2.0));
+        let inside = a.ray_distance(glm::vec3(2.5, 0.5, 0.5), glm::vec3(0.0, 0.0, -1.0));
+        assert_eq!(inside, Some(0.0));
+        assert!(a.ray_distance(glm::vec3(0.0, 0.5, 0.5), glm::vec3(-1.0, 0.0, 0.0)).is_none());
+        assert!(a.ray_distance(glm::vec3(0.0, 1.5, 0.5), glm::vec3(1.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn test_expand_towards() {
+        let a = Aabb::from_voxel(0, 0, 0).expand_towards(glm::vec3(2.0, -1.0, 0.0));
+
+        assert_eq!(a.min, glm::vec3(0.0, -1.0, 0.0));
+        assert_eq!(a.max, glm::vec3(3.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_clip_motion() {
+        let player = Aabb::from_bottom_center(glm::vec3(0.5, 0.5, 2.0), 0.6, 1.8);
+        let floor = Aabb::from_voxel(0, 0, 0);
+        let wall = Aabb::from_voxel(2, 0, 2);
+
+        assert_eq!(player.clip_motion(&floor, 2, -5.0), -1.0);
+        assert_eq!(player.clip_motion(&floor, 2, 5.0), 5.0);
+        assert!((player.clip_motion(&wall, 0, 3.0) - 1.2).abs() < 0.0001);
+        assert_eq!(player.clip_motion(&wall, 1, 3.0), 3.0);
+    }
+}
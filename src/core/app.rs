@@ -1,63 +1,75 @@
-use winit::event::{
-    DeviceId, ElementState, KeyboardInput, MouseButton, MouseScrollDelta, TouchPhase,
-    VirtualKeyCode,
-};
 use std::time::Instant;
 use vulkanalia::{Device, Entry, Instance, vk};
 use vulkanalia::vk::{KhrSwapchainExtension};
-use crate::core::game_object::GameObject;
 use anyhow::{anyhow, Result};
+use log::{info, warn};
 use vulkanalia::loader::{LibloadingLoader, LIBRARY};
+use crate::controlls::gamepad::GamepadBackend;
+#[cfg(feature = "gilrs")]
+use crate::controlls::gilrs_gamepad::GilrsGamepad;
+use crate::controlls::input_actions::{Action, InputActions};
 use crate::controlls::input_manager::InputManager;
 use crate::core::app_data::AppData;
-use crate::{FrameData, graphics, HIGH_DELTA_TIME_LIMIT, LOW_DELTA_TIME_LIMIT, MAX_FRAMES_IN_FLIGHT, VALIDATION_ENABLED};
+use crate::{graphics, HIGH_DELTA_TIME_LIMIT, INPUT_FILE_NAME, SCREENSHOT_DIRECTORY, TICK_RATE};
 use crate::graphics::command_pool::{create_command_pools, create_text_command_pools};
 use crate::graphics::depth_objects::create_depth_objects;
 use crate::graphics::framebuffers::create_framebuffers;
 use crate::graphics::instance::create_instance;
 use crate::graphics::logical_device::create_logical_device;
 use crate::graphics::pipeline::{create_descriptor_set_layout, create_pipeline, create_render_pass};
+use crate::graphics::screenshot::{read_image, save_png, to_rgba, unique_screenshot_path};
 use crate::graphics::swapchain::{create_swapchain, create_swapchain_image_views};
 use crate::graphics::text_pipeline::{create_text_descriptor_set_layout, create_text_pipeline, create_text_render_pass};
 use crate::terrain::world::World;
-use crate::graphics::buffers::{
-    create_text_vertex_index_buffers, create_text_vertex_index_buffers_multi,
-    create_uniform_buffers,
-};
+use crate::graphics::buffers::create_uniform_buffers;
 use crate::graphics::command_buffers::{create_command_buffers, create_text_command_buffers};
+use crate::graphics::console::{Console, LineKind};
+use crate::graphics::debug_hud::DebugHud;
 use crate::graphics::descriptors::{
     create_descriptor_pool, create_descriptor_sets, create_text_descriptor_pool,
     create_text_descriptor_sets,
 };
-use crate::graphics::font_data::FontData;
+use crate::graphics::fonts::Fonts;
+use crate::graphics::highlight_pipeline::create_highlight_pipelines;
+use crate::graphics::hot_reload::{reload_changed, watch_assets, BLOCK_TEXTURE};
+use crate::graphics::menus::{pressed_binding, MenuAction, Menus};
 use crate::graphics::shared_textures::{create_texture_image_from_path, create_texture_image_view};
+use crate::graphics::shadow_maps::{create_shadow_objects, destroy_shadow_objects, record_shadow_passes};
+use crate::graphics::sky_pipeline::create_sky_pipeline;
 use crate::graphics::sync_objects::create_sync_objects;
-use crate::graphics::text_object::{TextObject, TextSettings};
+use crate::graphics::text_objects::{TextDraw, TextObjects};
 
-use crate::graphics::text_textures::{create_bitmaps, Character};
+use crate::graphics::ui::{Ui, UiInput};
+use crate::graphics::ui_pipeline::create_ui_pipeline;
+use crate::graphics::ui_space::UiSpace;
+use crate::graphics::text_textures::{create_text_texture, destroy_text_texture, update_text_texture};
 use crate::graphics::texture_samplers::{
     create_text_texture_sampler, create_world_texture_sampler,
 };
 use crate::graphics::uniform_buffer_object::UniformBufferObject;
-use crate::graphics::vertex::Vertex;
 use crate::player::player_data::PlayerData;
 
-use crate::core::collider::Collider;
-use crate::core::collision::intersects;
+use crate::core::commands::CommandRegistry;
+use crate::core::console_commands::{console_commands, run_command, CommandOutput};
+use crate::core::ecs::registry::Entity;
+use crate::core::file_watcher::FileWatcher;
+use crate::core::game_state::{GameEvent, GameState};
+use crate::core::replay::record_expectations;
+use crate::core::settings::{Settings, WindowMode};
+use crate::core::simulation::{FixedTimestep, Simulation};
+use crate::core::stats::{DebugStats, FrameStats, PlayerStats};
+use crate::core::time_of_day::{sample_sky, SKY_KEYFRAMES};
+use crate::core::transform::Transform;
 
 use nalgebra_glm as glm;
-use std::collections::HashMap;
 use std::mem::size_of;
+use std::path::{Path, PathBuf};
 use std::ptr::copy_nonoverlapping as memcpy;
-use std::u16;
 use vulkanalia::prelude::v1_0::*;
-use vulkanalia::vk::{DeviceV1_2};
 use vulkanalia::vk::{ExtDebugUtilsExtension, KhrSurfaceExtension};
 use vulkanalia::window as vk_window;
 use winit::dpi::LogicalSize;
-use winit::event::{Event, WindowEvent};
-use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::{Fullscreen, Window, WindowBuilder};
+use winit::window::{CursorGrabMode, Fullscreen, Window};
 use crate::terrain::chunk_coord::ChunkCoord;
 
 
@@ -66,36 +78,61 @@ pub(crate) struct App {
     entry: Entry,
     instance: Instance,
     data: AppData,
-    world: World,
+    pub(crate) simulation: Simulation,
+    timestep: FixedTimestep,
     device: Device,
     frame: usize,
     pub(crate) resized: bool,
     start: Instant,
     pub(crate) input_manager: InputManager,
+    // Real controllers through gilrs, None when the platform's gamepad API couldn't be opened or the
+    // game was built without the `gilrs` feature
+    pub(crate) gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    // Where the input recording of this session goes once the window closes
+    recording_path: Option<PathBuf>,
+    pub(crate) settings: Settings,
+    settings_path: PathBuf,
+    input_path: PathBuf,
 
     // State
     pub(crate) is_hovered_by_cursor: bool,
 
     // Game State
     is_cursor_locked: bool,
-    is_playing: bool,
+    game_state: GameState,
+    menus: Menus,
+    console: Console,
+    commands: CommandRegistry,
+    // Set by the pause menu, the event loop closes the window once it sees it
+    pub(crate) quit_requested: bool,
+    // Set by the screenshot key, the next rendered frame is saved
+    screenshot_requested: bool,
+    debug_hud: DebugHud,
+    ui: Ui,
+    // Shaders and textures edited while the game runs are reloaded
+    asset_watcher: FileWatcher,
 
     // Delta Time
     delta_time: f32,
     last_time: Instant,
+    frame_stats: FrameStats,
 
-    is_first_frame: bool,
     pub(crate) frame_count: u128,
 }
 
 impl App {
     #[rustfmt::skip]
-    pub(crate) unsafe fn create(window: &Window, game_objects: &mut Vec<Box<dyn GameObject>>, new_objects: &mut Vec<usize>) -> Result<Self> {
+    pub(crate) unsafe fn create(window: &Window, settings: Settings, settings_path: PathBuf) -> Result<Self> {
         let loader = LibloadingLoader::new(LIBRARY)?;
         let entry = Entry::new(loader).map_err(|b| anyhow!("{}", b))?;
-        let mut data = AppData::default();
+        let mut data = AppData {
+            validation_enabled: settings.validation,
+            frames_in_flight: settings.frames_in_flight,
+            present_mode: settings.present_mode,
+            ..Default::default()
+        };
 
-        let instance = create_instance(window, &entry, &mut data)?;
+        let instance = create_instance(Some(window), &entry, &mut data)?;
         data.surface = vk_window::create_surface(&instance, window)?;
         graphics::physical_device::pick_physical_device(&instance, &mut data)?;
         let device = create_logical_device(&instance, &mut data)?;
@@ -106,30 +143,34 @@ impl App {
 
         create_swapchain(window, &instance, &device, &mut data)?;
         create_swapchain_image_views(&device, &mut data)?;
+        data.ui_space = UiSpace::new(data.swapchain_extent, window.scale_factor() as f32);
         create_render_pass(&instance, &device, &mut data)?;
         create_descriptor_set_layout(&device, &mut data)?;
+        create_shadow_objects(&instance, &device, &mut data)?;
         create_pipeline(&device, &mut data)?;
+        create_sky_pipeline(&device, &mut data)?;
+        create_highlight_pipelines(&device, &mut data)?;
         create_text_render_pass(&instance, &device, &mut data)?;
         create_text_descriptor_set_layout(&device, &mut data)?;
         create_text_pipeline(&device, &mut data)?;
+        create_ui_pipeline(&device, &mut data)?;
         create_command_pools(&instance, &device, &mut data)?;
         create_text_command_pools(&instance, &device, &mut data)?;
         create_depth_objects(&instance, &device, &mut data)?;
         create_framebuffers(&device, &mut data)?;
 
         // 3D
-        (data.texture_image, data.texture_image_memory) = create_texture_image_from_path(&instance, &device,&mut data, "resources/blocks.png")?;
-        data.texture_image_view = create_texture_image_view(&device, &data.texture_image)?;
+        (data.texture_image, data.texture_image_memory) = create_texture_image_from_path(&instance, &device,&mut data, BLOCK_TEXTURE)?;
+        data.texture_image_view = create_texture_image_view(&device, &data.texture_image, vk::Format::R8G8B8A8_SRGB)?;
         data.texture_sampler = create_world_texture_sampler(&device, &mut data)?;
 
         // Text
-        (data.text_characters, data.text_texture_image, data.text_texture_image_memory) = create_bitmaps(&instance, &device, &mut data)?;
+        data.fonts = Fonts::load_default(Path::new(&settings.font));
+        create_text_texture(&instance, &device, &mut data)?;
 
         //data.text_objects.push(TextObject::new("Lorem ipsum dolor sit amet, consectetur adipiscing elit.", glm::vec2(50.0, 50.0), TextSettings::default()));
         //data.text_objects.push(TextObject::new("Dqdo ti e lezbiika i pravi svirki", glm::vec2(10.0, 540.0), TextSettings::default()));
-        data.text_texture_image_view = create_texture_image_view(&device, &data.text_texture_image)?;
         data.text_texture_sampler = create_text_texture_sampler(&device, &mut data)?;
-        create_text_vertex_index_buffers(&instance, &device, &mut data)?;
 
         create_uniform_buffers(&instance, &device, &mut data)?;
 
@@ -144,39 +185,31 @@ impl App {
         create_text_command_buffers(&device, &mut data)?;
         create_sync_objects(&device, &mut data)?;
 
-        let mut player_data = PlayerData::default();
-        player_data.is_grounded = true;
-        player_data.velocity = glm::vec3(0.0, 0.0, 0.0);
-        player_data.horizontal_angle = 1.57;
-        player_data.transform.position = glm::Vec3::new(0.0, 0.0, 50.0);
-        player_data.vertical_angle = 3.14;
-        player_data.mouse_speed = 1.0;
-        player_data.move_speed = 10.0;
-        player_data.reach = 10.0;
-        player_data.reach_step = 0.01;
-        player_data.collider = Collider {
-            vertices: vec![
-                glm::vec3(0.0, 0.0, 0.0),
-                glm::vec3(1.0, 0.0, 0.0),
-                glm::vec3(1.0, 1.0, 0.0),
-                glm::vec3(0.0, 1.0, 0.0),
-                glm::vec3(0.0, 0.0, 1.0),
-                glm::vec3(1.0, 0.0, 1.0),
-                glm::vec3(1.0, 1.0, 1.0),
-                glm::vec3(0.0, 1.0, 1.0),
-            ],
-        };
+        let mut simulation = Simulation::new(world, TICK_RATE);
+        spawn_default_player(&mut simulation);
+
+        let gamepad_backend = open_gamepad_backend();
 
-        new_objects.push(game_objects.len());
-        game_objects.push(Box::new(player_data));
+        let mut asset_watcher = FileWatcher::default();
+        watch_assets(&mut asset_watcher);
 
-        Ok(Self {
+        let mut input_manager = InputManager::new();
+        let input_path = settings_path.with_file_name(INPUT_FILE_NAME);
+        input_manager.actions = InputActions::load_or_default(&input_path);
+
+        let mut app = Self {
             entry,
             instance,
             data,
             device,
-            world: World { chunks: Default::default() },
-            input_manager: InputManager::new(),
+            simulation,
+            timestep: FixedTimestep::new(TICK_RATE, HIGH_DELTA_TIME_LIMIT),
+            input_manager,
+            gamepad_backend,
+            recording_path: None,
+            settings,
+            settings_path,
+            input_path,
             frame: 0,
             resized: false,
             start: Instant::now(),
@@ -184,41 +217,67 @@ impl App {
             last_time: Instant::now(),
             is_hovered_by_cursor: false,
             is_cursor_locked: false,
-            is_playing: true,
-            is_first_frame: true,
+            game_state: GameState::default(),
+            menus: Menus::default(),
+            console: Console::default(),
+            commands: console_commands(),
+            quit_requested: false,
+            screenshot_requested: false,
+            debug_hud: DebugHud::default(),
+            ui: Ui::default(),
+            asset_watcher,
+            frame_stats: FrameStats::default(),
             frame_count: 0,
-        })
+        };
+        app.apply_window_mode(window);
+        app.apply_gameplay_settings();
+        Ok(app)
     }
 
     #[rustfmt::skip]
-    pub(crate) unsafe fn render(&mut self, window: &Window, game_objects: &mut Vec<Box<dyn GameObject>>, new_objects: &mut Vec<usize>) -> Result<()> {
+    pub(crate) unsafe fn render(&mut self, window: &Window) -> Result<()> {
         let in_flight_fence = self.data.in_flight_fences[self.frame];
 
         self.device
             .wait_for_fences(&[in_flight_fence], true, u64::MAX)?;
 
-        let player = game_objects.get_mut(0).unwrap().as_any_mut().downcast_mut::<PlayerData>().unwrap();
-
-        let frame_data_player = FrameData {
-            frame_count: self.frame_count,
-            delta_time: self.delta_time,
-            input_manager: &self.input_manager,
-        };
-
         // Handle Delta time
         let current_time = Instant::now();
-        let delta_time_duration = current_time - self.last_time;
-        let mut delta_time_in_seconds = delta_time_duration.as_secs_f64();
-        if delta_time_in_seconds < LOW_DELTA_TIME_LIMIT {
-            delta_time_in_seconds = LOW_DELTA_TIME_LIMIT;
-        } else if delta_time_in_seconds > HIGH_DELTA_TIME_LIMIT {
-            delta_time_in_seconds = HIGH_DELTA_TIME_LIMIT;
-        }
+        let frame_time = (current_time - self.last_time).as_secs_f64();
         self.last_time = current_time;
-        self.delta_time = delta_time_in_seconds as f32;
+        self.delta_time = frame_time as f32;
+        self.frame_stats.record(self.delta_time);
 
         self.frame_count += 1;
 
+        // Input
+        self.input_manager.handle_mouse(self.is_cursor_locked);
+        if let Some(backend) = self.gamepad_backend.as_mut() {
+            self.input_manager.poll_gamepad(backend.as_mut(), self.simulation.tick_count());
+        }
+
+        // The starting chunks are generated with the world, streamed ones don't hold up the game
+        if self.game_state == GameState::Loading && self.simulation.world.stats().loaded_chunks > 0 {
+            self.change_state(window, GameEvent::Loaded);
+        }
+
+        // Simulation
+        if self.game_state.systems().simulation {
+            self.input_manager.end_recorded_frame(frame_time);
+            let ticks = self.timestep.advance(frame_time);
+            if ticks > 0 {
+                self.update_ui(window);
+                self.handle_frame_input(window);
+            }
+            self.simulation.run_ticks(ticks, &mut self.input_manager);
+        } else {
+            // Menus run every frame instead, the timestep stays where the game stopped
+            self.update_ui(window);
+            self.handle_frame_input(window);
+            self.input_manager.skip_frame();
+        }
+        self.draw_debug_hud();
+
         let result = self.device.acquire_next_image_khr(
             self.data.swapchain,
             u64::MAX,
@@ -240,10 +299,23 @@ impl App {
 
         self.data.images_in_flight[image_index] = in_flight_fence;
 
+        let changed_assets = self.asset_watcher.poll(current_time);
+        if !changed_assets.is_empty() {
+            reload_changed(&self.instance, &self.device, &mut self.data, &changed_assets)?;
+        }
+        if self.data.text_objects.needs_update(self.frame) {
+            self.update_text_objects()?;
+        }
+        if self.ui.needs_upload() {
+            self.data.ui_buffers.mark_stale();
+            self.ui.mark_uploaded();
+        }
+        if self.data.ui_buffers.needs_upload(self.frame) {
+            self.update_ui_buffers()?;
+        }
         self.update_text_command_buffer(image_index)?;
         self.update_command_buffer(image_index)?;
-        let player = game_objects.get_mut(0).unwrap().as_any_mut().downcast_mut::<PlayerData>().unwrap();
-        self.update_uniform_buffer(player, image_index)?;
+        self.update_uniform_buffer(image_index)?;
 
         let wait_semaphores = &[self.data.image_available_semaphores[self.frame]];
         let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
@@ -260,6 +332,14 @@ impl App {
         self.device
             .queue_submit(self.data.graphics_queue, &[submit_info], in_flight_fence)?;
 
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            match self.capture_screenshot(image_index, in_flight_fence) {
+                Ok(path) => info!("Saved screenshot to {:?}", path),
+                Err(error) => warn!("Couldn't take a screenshot: {}", error),
+            }
+        }
+
         let swapchains = &[self.data.swapchain];
         let image_indices = &[image_index as u32];
         let present_info = vk::PresentInfoKHR::builder()
@@ -280,96 +360,172 @@ impl App {
             return Err(anyhow!(e));
         }
 
+        let player_pos = self.simulation.player_transform().unwrap().position;
+
+        //TODO: update view distance
+        /*// Terrain
+        self.simulation.world.update_view_distance(
+            player_pos.x as i32,
+            player_pos.y as i32,
+            player_pos.z as i32,
+            &self.instance,
+            &self.device,
+            &mut self.data,
+        )?;*/
+
+        self.frame = (self.frame + 1) % self.data.frames_in_flight;
+
+        Ok(())
+    }
+
+    // Window level actions, run once for every batch of simulation ticks or every frame in menus
+    fn handle_frame_input(&mut self, window: &Window) {
+        if self.is_hovered_by_cursor
+            && self.game_state.systems().cursor_grab
+            && !self.is_cursor_locked
+            && !self.ui.wants_cursor()
+            && self.input_manager.get_action_down(Action::GrabCursor)
         {
-            let player = game_objects.get_mut(0).unwrap().as_any_mut().downcast_mut::<PlayerData>().unwrap();
+            self.lock_cursor(window);
+        }
 
-            player.velocity.z -= 9.81 * self.delta_time;
+        if self.input_manager.get_action_down(Action::ReleaseCursor) {
+            self.unlock_cursor(window);
+        }
 
-            if player.is_grounded {
-                player.velocity.z = 0.0;
-            }
+        if self.input_manager.get_action_down(Action::ToggleFullscreen) {
+            self.toggle_fullscreen(window);
+        }
 
-            player.transform.position.z += player.velocity.z * self.delta_time;
+        if self.input_manager.get_action_down(Action::Screenshot) {
+            self.screenshot_requested = true;
         }
 
-        let frame_data = FrameData {
-            frame_count: self.frame_count,
-            delta_time: self.delta_time,
-            input_manager: &self.input_manager,
-        };
+        if self.input_manager.get_action_down(Action::ToggleDebugHud) {
+            self.debug_hud.toggle(&mut self.data.text_objects);
+        }
 
-        // Call start on new objects
-        new_objects.iter().for_each(|index| {
-            let object_index = new_objects.get(*index).unwrap();
-            game_objects.get_mut(*object_index).unwrap().start(frame_data.clone());
-        });
-        new_objects.clear();
+        if self.input_manager.get_action_down(Action::ReloadSettings) {
+            let settings = Settings::load_or_default(&self.settings_path);
+            if let Err(error) = unsafe { self.apply_settings(window, settings) } {
+                warn!("Couldn't apply settings from {:?}: {}", self.settings_path, error);
+            }
+        }
+    }
 
-        // Call update on all objects
-        game_objects.iter_mut().for_each(|obj| {
-            obj.update(frame_data.clone());
-        });
+    // Applies whatever changed compared to the current settings without restarting
+    pub(crate) unsafe fn apply_settings(&mut self, window: &Window, settings: Settings) -> Result<()> {
+        let previous = std::mem::replace(&mut self.settings, settings);
+        let settings = self.settings.clone();
 
-        if self.is_hovered_by_cursor
-            && !self.is_cursor_locked
-            && (self.input_manager.get_key_down_mouse(MouseButton::Left) || self.input_manager.get_key_down_mouse(MouseButton::Right))
-        {
-            self.lock_cursor();
-            window.set_cursor_visible(false);
+        if settings.window_mode != previous.window_mode || settings.resolution != previous.resolution {
+            self.apply_window_mode(window);
         }
+        self.apply_gameplay_settings();
 
-        if self.input_manager.get_key_down(VirtualKeyCode::Escape) {
-            self.unlock_cursor();
-            window.set_cursor_visible(true);
+        if settings.needs_restart(&previous) {
+            info!("Validation layer changes take effect after a restart");
         }
 
-        // Input
-        self.input_manager.handle_mouse(window, self.is_cursor_locked).expect("Couldn't handle mouse delta input");
+        if settings.needs_swapchain_recreation(&previous) {
+            self.data.present_mode = settings.present_mode;
+            if settings.frames_in_flight != previous.frames_in_flight {
+                self.recreate_sync_objects(settings.frames_in_flight)?;
+            }
+            self.recreate_swapchain(window)?;
+        }
+        info!("Applied settings from {:?}", self.settings_path);
+        Ok(())
+    }
 
-        if self.input_manager.get_key_down(VirtualKeyCode::F11) {
-            self.toggle_fullscreen(window);
+    fn apply_gameplay_settings(&mut self) {
+        let mouse_settings = &mut self.input_manager.mouse_look.settings;
+        mouse_settings.sensitivity = self.settings.mouse_sensitivity;
+        mouse_settings.invert_y = self.settings.invert_mouse_y;
+        mouse_settings.smoothing = self.settings.mouse_smoothing;
+        if let Some(mut player) = self.simulation.player_mut() {
+            player.camera.settings = self.settings.camera_settings();
         }
-        if self.input_manager.get_key_down(VirtualKeyCode::Space) {
-            game_objects.get_mut(0).unwrap().as_any_mut().downcast_mut::<PlayerData>().unwrap().is_grounded = !game_objects.get(0).unwrap().as_any().downcast_ref::<PlayerData>().unwrap().is_grounded;
+    }
+
+    #[rustfmt::skip]
+    fn apply_window_mode(&mut self, window: &Window) {
+        let (width, height) = self.settings.resolution;
+        match self.settings.window_mode {
+            WindowMode::Windowed => {
+                window.set_fullscreen(None);
+                window.set_inner_size(LogicalSize::new(width, height));
+            }
+            WindowMode::Borderless => window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor()))),
+            WindowMode::Fullscreen => {
+                // Prefers a video mode with the configured resolution, otherwise the monitor's first one
+                let video_mode = window.current_monitor().and_then(|monitor| {
+                    let mut video_modes = monitor.video_modes().collect::<Vec<_>>();
+                    let matching = video_modes.iter().position(|mode| mode.size().width == width && mode.size().height == height);
+                    match matching {
+                        Some(index) => Some(video_modes.swap_remove(index)),
+                        None => video_modes.into_iter().next(),
+                    }
+                });
+                match video_mode {
+                    Some(video_mode) => window.set_fullscreen(Some(Fullscreen::Exclusive(video_mode))),
+                    None => window.set_fullscreen(Some(Fullscreen::Borderless(None))),
+                }
+            }
         }
+        self.resized = true;
+    }
 
-        let player_pos = game_objects.get(0).unwrap().as_any().downcast_ref::<PlayerData>().unwrap().transform.position;
+    // Waits for the frame to finish and copies the swapchain image out before it's presented
+    unsafe fn capture_screenshot(&self, image_index: usize, in_flight_fence: vk::Fence) -> Result<PathBuf> {
+        if !self.data.swapchain_supports_capture {
+            return Err(anyhow!("The surface doesn't allow copying from swapchain images"));
+        }
+        self.device.wait_for_fences(&[in_flight_fence], true, u64::MAX)?;
 
-        //TODO: update view distance
-        /*// Terrain
-        self.world.update_view_distance(
-            player_pos.x as i32,
-            player_pos.y as i32,
-            player_pos.z as i32,
+        let extent = self.data.swapchain_extent;
+        let texels = read_image(
             &self.instance,
             &self.device,
-            &mut self.data,
-        )?;*/
+            &self.data,
+            self.data.swapchain_images[image_index],
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            extent,
+        )?;
+        let rgba = to_rgba(&texels, self.data.swapchain_format)?;
 
-        self.input_manager.detected_new_frame();
+        let stamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+        let path = unique_screenshot_path(Path::new(SCREENSHOT_DIRECTORY), &stamp);
+        save_png(&path, extent.width, extent.height, &rgba)?;
+        Ok(path)
+    }
 
-        self.frame = (self.frame + 1) % MAX_FRAMES_IN_FLIGHT;
+    // Replays start from a fresh session, so this has to be called before the first frame
+    pub(crate) fn start_recording(&mut self, path: PathBuf) {
+        self.input_manager.start_recording(TICK_RATE, HIGH_DELTA_TIME_LIMIT);
+        self.recording_path = Some(path);
+    }
 
+    pub(crate) fn finish_recording(&mut self) -> Result<()> {
+        let (Some(mut recording), Some(path)) = (self.input_manager.stop_recording(), self.recording_path.take()) else {
+            return Ok(());
+        };
+        record_expectations(&mut recording, &self.simulation);
+        recording.save(&path)?;
+        info!("Saved input recording of {} frames to {:?}", recording.frames.len(), path);
         Ok(())
     }
 
-    #[rustfmt::skip]
+    // Only lasts for this session, the settings file keeps its window mode
     fn toggle_fullscreen(&mut self, window: &Window) {
-        if window.fullscreen().is_some() {
-            window.set_fullscreen(None);
+        self.settings.window_mode = if window.fullscreen().is_some() {
+            WindowMode::Windowed
+        } else if cfg!(any(target_os = "macos", unix)) {
+            WindowMode::Borderless
         } else {
-            window.current_monitor().map(|monitor| {
-                monitor.video_modes().next().map(|video_mode| {
-                    if cfg!(any(target_os = "macos", unix)) {
-                        window.set_fullscreen(Some(Fullscreen::Borderless(Some(monitor))));
-                    } else {
-                        window.set_fullscreen(Some(Fullscreen::Exclusive(video_mode)));
-                    }
-                })
-            });
-        }
-        self.resized = true;
-        self.is_first_frame = true;
+            WindowMode::Fullscreen
+        };
+        self.apply_window_mode(window);
     }
 
     #[rustfmt::skip]
@@ -390,7 +546,7 @@ impl App {
         let command_buffer = command_buffers[model_index];
 
         // TODO: Properly handle errors
-        let chunk = self.world.get_chunk_by_index(model_index).unwrap();
+        let chunk = self.simulation.world.get_chunk_by_index(model_index).unwrap();
 
         if !chunk.should_draw() {
             return Err(anyhow!("Don't draw chunk"));
@@ -449,10 +605,114 @@ impl App {
     }
 
     #[rustfmt::skip]
-    unsafe fn update_text_secondary_command_buffer(&mut self, image_index: usize, object_index: usize) -> Result<vk::CommandBuffer> {
+    unsafe fn update_sky_secondary_command_buffer(&mut self, image_index: usize, draw_index: usize) -> Result<vk::CommandBuffer> {
+        let command_buffers = &mut self.data.secondary_command_buffers[image_index];
+
+        while draw_index >= command_buffers.len() {
+            let allocate_info = vk::CommandBufferAllocateInfo::builder()
+                .command_pool(self.data.command_pools[image_index])
+                .level(vk::CommandBufferLevel::SECONDARY)
+                .command_buffer_count(1);
+
+            let command_buffer = self.device.allocate_command_buffers(&allocate_info)?[0];
+            command_buffers.push(command_buffer);
+        }
+
+        let command_buffer = command_buffers[draw_index];
+
+        let inheritance_info = vk::CommandBufferInheritanceInfo::builder()
+            .render_pass(self.data.render_pass)
+            .subpass(0)
+            .framebuffer(self.data.framebuffers[image_index]);
+
+        let info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE)
+            .inheritance_info(&inheritance_info);
+
+        self.device.begin_command_buffer(command_buffer, &info)?;
+
+        self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.data.sky_pipeline);
+        self.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.data.sky_pipeline_layout,
+            0,
+            &[self.data.descriptor_sets[image_index]],
+            &[],
+        );
+        // One triangle covering the screen
+        self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+
+        self.device.end_command_buffer(command_buffer)?;
+        Ok(command_buffer)
+    }
+
+    #[rustfmt::skip]
+    unsafe fn update_highlight_secondary_command_buffer(&mut self, image_index: usize, draw_index: usize, voxel: glm::IVec3, crack_stage: Option<u32>) -> Result<vk::CommandBuffer> {
+        let command_buffers = &mut self.data.secondary_command_buffers[image_index];
+
+        while draw_index >= command_buffers.len() {
+            let allocate_info = vk::CommandBufferAllocateInfo::builder()
+                .command_pool(self.data.command_pools[image_index])
+                .level(vk::CommandBufferLevel::SECONDARY)
+                .command_buffer_count(1);
+
+            let command_buffer = self.device.allocate_command_buffers(&allocate_info)?[0];
+            command_buffers.push(command_buffer);
+        }
+
+        let command_buffer = command_buffers[draw_index];
+
+        let inheritance_info = vk::CommandBufferInheritanceInfo::builder()
+            .render_pass(self.data.render_pass)
+            .subpass(0)
+            .framebuffer(self.data.framebuffers[image_index]);
+
+        let info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE)
+            .inheritance_info(&inheritance_info);
+
+        self.device.begin_command_buffer(command_buffer, &info)?;
+
+        // The shaders build the vertices from the voxel's corner, the stage picks the cracks
+        let stage = crack_stage.unwrap_or(0) as f32;
+        let push_constants = glm::vec4(voxel.x as f32, voxel.y as f32, voxel.z as f32, stage);
+        let (_, push_constant_bytes, _) = push_constants.as_slice().align_to::<u8>();
+
+        self.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.data.highlight_pipeline_layout,
+            0,
+            &[self.data.descriptor_sets[image_index]],
+            &[],
+        );
+        self.device.cmd_push_constants(
+            command_buffer,
+            self.data.highlight_pipeline_layout,
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            push_constant_bytes,
+        );
+
+        if crack_stage.is_some() {
+            self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.data.crack_pipeline);
+            // Two triangles on each of the six faces
+            self.device.cmd_draw(command_buffer, 36, 1, 0, 0);
+        }
+        self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.data.outline_pipeline);
+        // Two ends for each of the twelve edges
+        self.device.cmd_draw(command_buffer, 24, 1, 0, 0);
+
+        self.device.end_command_buffer(command_buffer)?;
+        Ok(command_buffer)
+    }
+
+    #[rustfmt::skip]
+    unsafe fn update_text_secondary_command_buffer(&mut self, image_index: usize, draw_index: usize, draw: TextDraw) -> Result<vk::CommandBuffer> {
         let command_buffers = &mut self.data.text_secondary_command_buffers[image_index];
 
-        while object_index >= command_buffers.len() {
+        while draw_index >= command_buffers.len() {
             let allocate_info = vk::CommandBufferAllocateInfo::builder()
                 .command_pool(self.data.text_command_pools[image_index])
                 .level(vk::CommandBufferLevel::SECONDARY)
@@ -462,9 +722,7 @@ impl App {
             command_buffers.push(command_buffer);
         }
 
-        let command_buffer = command_buffers[object_index];
-
-        let text_object = self.data.text_objects.get(object_index);
+        let command_buffer = command_buffers[draw_index];
 
         let inheritance_info = vk::CommandBufferInheritanceInfo::builder()
             .render_pass(self.data.text_render_pass)
@@ -482,8 +740,8 @@ impl App {
             vk::PipelineBindPoint::GRAPHICS,
             self.data.text_pipeline,
         );
-        self.device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.data.text_vertex_buffer], &[0]);
-        self.device.cmd_bind_index_buffer(command_buffer, self.data.text_index_buffer, 0, vk::IndexType::UINT32);
+        self.device.cmd_bind_vertex_buffers(command_buffer, 0, &[draw.vertex_buffer], &[0]);
+        self.device.cmd_bind_index_buffer(command_buffer, draw.index_buffer, 0, vk::IndexType::UINT32);
         self.device.cmd_bind_descriptor_sets(
             command_buffer,
             vk::PipelineBindPoint::GRAPHICS,
@@ -492,8 +750,68 @@ impl App {
             &[self.data.text_descriptor_sets[image_index]],
             &[],
         );
+        let projection = self.data.ui_space.projection();
+        let (_, projection_bytes, _) = projection.as_slice().align_to::<u8>();
+        self.device.cmd_push_constants(
+            command_buffer,
+            self.data.text_pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            projection_bytes,
+        );
+
+        self.device.cmd_draw_indexed(command_buffer, draw.index_count, 1, 0, 0, 0);
+
+        self.device.end_command_buffer(command_buffer)?;
+        Ok(command_buffer)
+    }
+
+    #[rustfmt::skip]
+    unsafe fn update_ui_secondary_command_buffer(&mut self, image_index: usize, draw_index: usize) -> Result<vk::CommandBuffer> {
+        let command_buffers = &mut self.data.text_secondary_command_buffers[image_index];
+
+        while draw_index >= command_buffers.len() {
+            let allocate_info = vk::CommandBufferAllocateInfo::builder()
+                .command_pool(self.data.text_command_pools[image_index])
+                .level(vk::CommandBufferLevel::SECONDARY)
+                .command_buffer_count(1);
+
+            let command_buffer = self.device.allocate_command_buffers(&allocate_info)?[0];
+            command_buffers.push(command_buffer);
+        }
+
+        let command_buffer = command_buffers[draw_index];
+
+        let inheritance_info = vk::CommandBufferInheritanceInfo::builder()
+            .render_pass(self.data.text_render_pass)
+            .subpass(0)
+            .framebuffer(self.data.framebuffers[image_index]);
+
+        let info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE)
+            .inheritance_info(&inheritance_info);
+
+        self.device.begin_command_buffer(command_buffer, &info)?;
+
+        let buffers = self.data.ui_buffers.frame(self.frame);
+        self.device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.data.ui_pipeline,
+        );
+        self.device.cmd_bind_vertex_buffers(command_buffer, 0, &[buffers.vertex_buffer], &[0]);
+        self.device.cmd_bind_index_buffer(command_buffer, buffers.index_buffer, 0, vk::IndexType::UINT32);
+        let projection = self.data.ui_space.projection();
+        let (_, projection_bytes, _) = projection.as_slice().align_to::<u8>();
+        self.device.cmd_push_constants(
+            command_buffer,
+            self.data.ui_pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            projection_bytes,
+        );
 
-        self.device.cmd_draw_indexed(command_buffer, self.data.text_index_buffer_length as u32, 1, 0, 0, 0);
+        self.device.cmd_draw_indexed(command_buffer, buffers.index_count, 1, 0, 0, 0);
 
         self.device.end_command_buffer(command_buffer)?;
         Ok(command_buffer)
@@ -511,13 +829,19 @@ impl App {
 
         self.device.begin_command_buffer(command_buffer, &info)?;
 
+        // No chunk casts shadows while they aren't drawn (see the TODO below), the passes still leave
+        // a cleared map for the world's fragment shader
+        record_shadow_passes(&self.device, &self.data, command_buffer, self.data.descriptor_sets[image_index], &[]);
+
         let render_area = vk::Rect2D::builder()
             .offset(vk::Offset2D::default())
             .extent(self.data.swapchain_extent);
 
+        // The sky covers it, but it matches the horizon in case it's missing
+        let horizon = sample_sky(&SKY_KEYFRAMES, self.simulation.clock.time()).horizon;
         let color_clear_value = vk::ClearValue {
             color: vk::ClearColorValue {
-                float32: [0.0, 0.0, 0.0, 0.0],
+                float32: [horizon.x, horizon.y, horizon.z, 1.0],
             },
         };
 
@@ -538,13 +862,26 @@ impl App {
         self.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
 
         let mut secondary_command_buffers = Vec::<vk::CommandBuffer>::new();
+        // Behind everything, so it goes first
+        match self.update_sky_secondary_command_buffer(image_index, secondary_command_buffers.len()) {
+            Ok(buffer) => secondary_command_buffers.push(buffer),
+            Err(error) => {println!("Error updating sky secondary command buffer: {}", error)},
+        }
         //TOOD: FIX
-        /*for chunk_index in 0..self.world.chunks_len() {
+        /*for chunk_index in 0..self.simulation.world.chunks_len() {
             match self.update_secondary_command_buffer(image_index, chunk_index) {
                 Ok(buffer) => secondary_command_buffers.push(buffer),
                 Err(_) => {},
             }
         }*/
+        // Drawn over the chunks, the depth test still hides it behind other voxels
+        if let Some((voxel, crack_stage)) = self.block_highlight() {
+            let draw_index = secondary_command_buffers.len();
+            match self.update_highlight_secondary_command_buffer(image_index, draw_index, voxel, crack_stage) {
+                Ok(buffer) => secondary_command_buffers.push(buffer),
+                Err(error) => {println!("Error updating highlight secondary command buffer: {}", error)},
+            }
+        }
 
         self.device.cmd_execute_commands(command_buffer, &secondary_command_buffers);
 
@@ -594,8 +931,16 @@ impl App {
         self.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
 
         let mut secondary_command_buffers = Vec::<vk::CommandBuffer>::new();
-        for text_index in 0..self.data.text_objects.len() {
-            match self.update_text_secondary_command_buffer(image_index, text_index) {
+        // UI rectangles go first, their labels are drawn over them
+        if !self.data.ui_buffers.frame(self.frame).is_empty() {
+            match self.update_ui_secondary_command_buffer(image_index, 0) {
+                Ok(buffer) => secondary_command_buffers.push(buffer),
+                Err(error) => {println!("Error updating UI secondary command buffer: {}", error)},
+            }
+        }
+        let first_text_draw = secondary_command_buffers.len();
+        for (draw_index, draw) in self.data.text_objects.draws(self.frame).into_iter().enumerate() {
+            match self.update_text_secondary_command_buffer(image_index, first_text_draw + draw_index, draw) {
                 Ok(buffer) => secondary_command_buffers.push(buffer),
                 Err(error) => {println!("Error updating text secondary command buffer: {}", error)},
             }
@@ -611,41 +956,17 @@ impl App {
     }
 
     #[rustfmt::skip]
-    unsafe fn update_uniform_buffer(&self, player: &mut PlayerData, image_index: usize) -> Result<()> {
+    unsafe fn update_uniform_buffer(&self, image_index: usize) -> Result<()> {
         // MVP
 
-        let look_direction = glm::vec3(
-            (player.vertical_angle.cos() * player.horizontal_angle.sin()) as f32,
-            (player.vertical_angle.cos() * player.horizontal_angle.cos()) as f32,
-            player.vertical_angle.sin() as f32,
-        );
-
-        let right = glm::vec3(
-            (player.horizontal_angle - 3.14 / 2.0).sin() as f32,
-            (player.horizontal_angle - 3.14 / 2.0).cos() as f32,
-            0.0,
-        );
+        let player = self.simulation.player().unwrap();
+        let player_entity = self.simulation.player_entity().unwrap();
+        let position = self.simulation.get_interpolated_transform(player_entity, self.timestep.alpha()).unwrap().position;
 
-        let up = glm::cross(&right, &look_direction);
+        let camera = player.camera.camera(player.eye_position(position), player.yaw, player.pitch);
+        let aspect_ratio = self.data.swapchain_extent.width as f32 / self.data.swapchain_extent.height as f32;
 
-        let center = glm::vec3(
-            player.transform.position.x + look_direction.x as f32,
-            player.transform.position.y + look_direction.y as f32,
-            player.transform.position.z + look_direction.z as f32,
-        );
-
-        let view = glm::look_at(&player.transform.position, &center, &up);
-
-        let mut proj = glm::perspective_rh_zo(
-            self.data.swapchain_extent.width as f32 / self.data.swapchain_extent.height as f32,
-            glm::radians(&glm::vec1(90.0))[0],
-            0.1,
-            100.0,
-        );
-
-        proj[(1, 1)] *= -1.0;
-
-        let ubo = UniformBufferObject { view, proj };
+        let ubo = UniformBufferObject::new(&camera, aspect_ratio, &self.simulation.clock, self.settings.shadows);
 
         // Copy
 
@@ -664,20 +985,195 @@ impl App {
         Ok(())
     }
 
+    // The voxel under the crosshair and the crack stage while it's being broken
+    fn block_highlight(&self) -> Option<(glm::IVec3, Option<u32>)> {
+        if self.game_state == GameState::Loading {
+            return None;
+        }
+        let player = self.simulation.player()?;
+        let position = self.simulation.player_transform()?.position;
+        let target = player.find_target_voxel(&self.simulation.world, position)?;
+        let crack_stage = player.breaking.stage().filter(|_| player.breaking.target() == Some(target.position));
+        Some((target.position, crack_stage))
+    }
+
+    // Only the buffers of this frame in flight are rewritten, the others are updated when their frame comes around
+    unsafe fn update_text_objects(&mut self) -> Result<()> {
+        let mut text_objects = std::mem::take(&mut self.data.text_objects);
+        let result = text_objects
+            .layout(&mut self.data.fonts, &self.data.ui_space)
+            .and_then(|_| update_text_texture(&self.instance, &self.device, &mut self.data))
+            .and_then(|_| text_objects.update_buffers(&self.instance, &self.device, &self.data, self.frame));
+        self.data.text_objects = text_objects;
+        result
+    }
+
+    unsafe fn update_ui_buffers(&mut self) -> Result<()> {
+        let mut buffers = std::mem::take(&mut self.data.ui_buffers);
+        let result = buffers.upload(&self.instance, &self.device, &self.data, self.frame, self.ui.mesh());
+        self.data.ui_buffers = buffers;
+        result
+    }
+
+    // Runs the UI for this frame's input. Open screens need the cursor, so they release it.
+    fn update_ui(&mut self, window: &Window) {
+        let input = UiInput::from_input_manager(&self.input_manager, &self.data.ui_space);
+        self.ui.begin_frame(input, self.data.ui_space);
+
+        // The pause key also backs out of menus, unless it's the key being bound
+        let mut chosen = vec![];
+        if self.input_manager.get_action_down(Action::Pause) && !self.menus.is_rebinding() {
+            chosen.push(MenuAction::Transition(self.game_state.pause_event()));
+        }
+        let toggle_console = self.input_manager.get_action_down(Action::ToggleConsole) && !self.menus.is_rebinding();
+        if toggle_console {
+            chosen.push(MenuAction::Transition(GameEvent::ToggleConsole));
+        }
+        let pressed = pressed_binding(&self.input_manager);
+        chosen.extend(self.menus.draw(
+            &mut self.ui,
+            self.game_state,
+            &mut self.settings,
+            &mut self.input_manager.actions,
+            pressed,
+        ));
+        if self.game_state == GameState::Playing {
+            self.ui.crosshair();
+        }
+        if self.game_state == GameState::Console {
+            // The toggle key types a character too, it shouldn't end up in the line
+            if !toggle_console {
+                let submitted = self.console.handle_input(
+                    self.input_manager.get_typed_characters(),
+                    self.input_manager.get_keys_down(),
+                    &self.commands,
+                );
+                if let Some(line) = submitted {
+                    self.run_console_line(&line);
+                }
+            }
+            self.console.draw(&mut self.ui, self.data.ui_space.size().x);
+        }
+        self.ui.end_frame(&mut self.data.text_objects);
+
+        for action in chosen {
+            match action {
+                MenuAction::Transition(event) => self.change_state(window, event),
+                MenuAction::SettingsChanged => self.apply_gameplay_settings(),
+                // The world has no save format yet, only settings and bindings outlive the session
+                MenuAction::SaveAndQuit => {
+                    self.save_preferences();
+                    self.quit_requested = true;
+                }
+            }
+        }
+
+        if self.ui.wants_cursor() && self.is_cursor_locked {
+            self.unlock_cursor(window);
+        }
+    }
+
+    fn change_state(&mut self, window: &Window, event: GameEvent) {
+        let previous = self.game_state;
+        self.game_state = previous.transition(event);
+        if self.game_state == previous {
+            return;
+        }
+        info!("Game state changed from {:?} to {:?}", previous, self.game_state);
+
+        // Leaving the menus keeps what was changed in them
+        if matches!(previous, GameState::InMenu(_)) && !matches!(self.game_state, GameState::InMenu(_)) {
+            self.save_preferences();
+        }
+        // Back from a menu the player expects to look around right away
+        let was_in_menu = matches!(previous, GameState::Paused | GameState::InMenu(_) | GameState::Console);
+        if was_in_menu && self.game_state.systems().cursor_grab && self.is_hovered_by_cursor {
+            self.lock_cursor(window);
+        }
+    }
+
+    fn run_console_line(&mut self, line: &str) {
+        let result = self.commands.parse(line).and_then(|command| match command {
+            Some(command) => run_command(&command, &self.commands, &mut self.simulation, &self.frame_stats).map(Some),
+            None => Ok(None),
+        });
+        match result {
+            Ok(Some(CommandOutput::Print(text))) => self.console.print(&text, LineKind::Output),
+            Ok(Some(CommandOutput::Clear)) => self.console.clear(),
+            Ok(None) => return,
+            Err(error) => self.console.print(&error.to_string(), LineKind::Error),
+        }
+        // Commands bypass the recorded input, a replay won't see what they changed
+        if self.input_manager.is_recording() {
+            warn!("Ran console command `{}` while recording, the replay will differ", line.trim());
+        }
+    }
+
+    // Only the settings the menus edit are written, the file keeps its own window mode since
+    // toggling fullscreen lasts for the session
+    fn save_preferences(&self) {
+        let mut settings = Settings::load(&self.settings_path).unwrap_or_else(|_| self.settings.clone());
+        settings.copy_menu_settings(&self.settings);
+        if let Err(error) = settings.save(&self.settings_path) {
+            warn!("Couldn't save settings to {:?}: {}", self.settings_path, error);
+        }
+        if let Err(error) = self.input_manager.actions.save(&self.input_path) {
+            warn!("Couldn't save input bindings to {:?}: {}", self.input_path, error);
+        }
+    }
+
+    fn draw_debug_hud(&mut self) {
+        if !self.debug_hud.is_visible() {
+            return;
+        }
+        let player = self.simulation.player().zip(self.simulation.player_transform()).map(|(player, transform)| PlayerStats {
+            position: transform.position,
+            yaw: player.yaw,
+            pitch: player.pitch,
+        });
+        let stats = DebugStats {
+            frames: &self.frame_stats,
+            player,
+            world: self.simulation.world.stats(),
+            buffers: vec![self.data.text_objects.buffer_stats()],
+        };
+        self.debug_hud.draw(&mut self.data.text_objects, &stats, self.delta_time);
+    }
+
+    // Text shown on top of the world, changes are uploaded before the next frame
+    pub(crate) fn texts(&mut self) -> &mut TextObjects {
+        &mut self.data.text_objects
+    }
+
+    unsafe fn recreate_sync_objects(&mut self, frames_in_flight: usize) -> Result<()> {
+        self.device.device_wait_idle()?;
+        self.data.in_flight_fences.drain(..).for_each(|f| self.device.destroy_fence(f, None));
+        self.data.render_finished_semaphores.drain(..).for_each(|s| self.device.destroy_semaphore(s, None));
+        self.data.image_available_semaphores.drain(..).for_each(|s| self.device.destroy_semaphore(s, None));
+
+        self.data.frames_in_flight = frames_in_flight;
+        self.frame = 0;
+        create_sync_objects(&self.device, &mut self.data)
+    }
+
     #[rustfmt::skip]
     unsafe fn recreate_swapchain(&mut self, window: &Window) -> Result<()> {
         self.device.device_wait_idle()?;
         self.destroy_swapchain();
         create_swapchain(window, &self.instance, &self.device, &mut self.data)?;
         create_swapchain_image_views(&self.device, &mut self.data)?;
+        self.data.ui_space = UiSpace::new(self.data.swapchain_extent, window.scale_factor() as f32);
 
         // 3D pipeline
         create_render_pass(&self.instance, &self.device, &mut self.data)?;
         create_pipeline(&self.device, &mut self.data)?;
+        create_sky_pipeline(&self.device, &mut self.data)?;
+        create_highlight_pipelines(&self.device, &mut self.data)?;
 
         // Text Pipeline
         create_text_render_pass(&self.instance, &self.device, &mut self.data)?;
         create_text_pipeline(&self.device, &mut self.data)?;
+        create_ui_pipeline(&self.device, &mut self.data)?;
 
         create_depth_objects(&self.instance, &self.device, &mut self.data)?;
         create_framebuffers(&self.device, &mut self.data)?;
@@ -691,6 +1187,7 @@ impl App {
 
         create_command_buffers(&self.device, &mut self.data)?;
         create_text_command_buffers(&self.device, &mut self.data)?;
+        self.data.text_objects.mark_all_dirty();
         self.data.images_in_flight.resize(self.data.swapchain_images.len(), vk::Fence::null());
         Ok(())
     }
@@ -705,11 +1202,9 @@ impl App {
         self.data.in_flight_fences.iter().for_each(|f| self.device.destroy_fence(*f, None));
         self.data.render_finished_semaphores.iter().for_each(|s| self.device.destroy_semaphore(*s, None));
         self.data.image_available_semaphores.iter().for_each(|s| self.device.destroy_semaphore(*s, None));
-        self.world.destroy(&self.device);
-        self.device.free_memory(self.data.text_index_buffer_memory, None);
-        self.device.destroy_buffer(self.data.text_index_buffer, None);
-        self.device.free_memory(self.data.text_vertex_buffer_memory, None);
-        self.device.destroy_buffer(self.data.text_vertex_buffer, None);
+        self.simulation.world.destroy(&self.device);
+        self.data.text_objects.destroy(&self.device);
+        self.data.ui_buffers.destroy(&self.device);
         self.device.free_memory(self.data.index_buffer_memory, None);
         self.device.destroy_buffer(self.data.index_buffer, None);
         self.device.free_memory(self.data.vertex_buffer_memory, None);
@@ -719,16 +1214,15 @@ impl App {
         self.device.free_memory(self.data.texture_image_memory, None);
         self.device.destroy_image(self.data.texture_image, None);
         self.device.destroy_sampler(self.data.text_texture_sampler, None);
-        self.device.destroy_image_view(self.data.text_texture_image_view, None);
-        self.device.free_memory(self.data.text_texture_image_memory, None);
-        self.device.destroy_image(self.data.text_texture_image, None);
+        destroy_text_texture(&self.device, &self.data);
         self.device.destroy_command_pool(self.data.command_pool, None);
+        destroy_shadow_objects(&self.device, &mut self.data);
         self.device.destroy_descriptor_set_layout(self.data.descriptor_set_layout, None);
         self.device.destroy_descriptor_set_layout(self.data.text_descriptor_set_layout, None);
         self.device.destroy_device(None);
         self.instance.destroy_surface_khr(self.data.surface, None);
 
-        if VALIDATION_ENABLED {
+        if self.data.validation_enabled {
             self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
         }
         self.instance.destroy_instance(None);
@@ -746,21 +1240,76 @@ impl App {
         self.data.framebuffers.iter().for_each(|f| self.device.destroy_framebuffer(*f, None));
         self.device.destroy_pipeline(self.data.pipeline, None);
         self.device.destroy_pipeline_layout(self.data.pipeline_layout, None);
+        self.device.destroy_pipeline(self.data.sky_pipeline, None);
+        self.device.destroy_pipeline_layout(self.data.sky_pipeline_layout, None);
+        self.device.destroy_pipeline(self.data.outline_pipeline, None);
+        self.device.destroy_pipeline(self.data.crack_pipeline, None);
+        self.device.destroy_pipeline_layout(self.data.highlight_pipeline_layout, None);
         self.device.destroy_render_pass(self.data.render_pass, None);
         self.device.destroy_pipeline(self.data.text_pipeline, None);
         self.device.destroy_pipeline_layout(self.data.text_pipeline_layout, None);
+        self.device.destroy_pipeline(self.data.ui_pipeline, None);
+        self.device.destroy_pipeline_layout(self.data.ui_pipeline_layout, None);
         self.device.destroy_render_pass(self.data.text_render_pass, None);
         self.data.swapchain_image_views.iter().for_each(|v| self.device.destroy_image_view(*v, None));
         self.device.destroy_swapchain_khr(self.data.swapchain, None);
     }
 
-    fn lock_cursor(&mut self) {
+    // Look input comes from raw device motion, so the cursor only has to stay inside the window.
+    // Not every platform supports both grab modes.
+    fn lock_cursor(&mut self, window: &Window) {
+        let grabbed = window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined));
+        if let Err(error) = grabbed {
+            warn!("Couldn't grab the cursor: {}", error);
+        }
+        window.set_cursor_visible(false);
         self.is_cursor_locked = true;
-        self.is_first_frame = true;
     }
 
-    pub(crate) fn unlock_cursor(&mut self) {
+    pub(crate) fn unlock_cursor(&mut self, window: &Window) {
+        let _ = window.set_cursor_grab(CursorGrabMode::None);
+        window.set_cursor_visible(true);
         self.is_cursor_locked = false;
-        self.is_first_frame = true;
     }
-}
\ No newline at end of file
+}
+
+// The player as a new session starts, shared by the window and the headless replay
+pub(crate) fn spawn_default_player(simulation: &mut Simulation) -> Entity {
+    let player_data = PlayerData {
+        yaw: -std::f32::consts::FRAC_PI_2,
+        mouse_speed: 1.0,
+        stick_look_speed: 3.0,
+        move_speed: 10.0,
+        reach: 10.0,
+        reach_step: 0.01,
+        selected_voxel: 2,
+        ..Default::default()
+    };
+
+    let player_transform = Transform {
+        position: glm::Vec3::new(0.0, 0.0, 50.0),
+        ..Default::default()
+    };
+    let player_collider = player_data.controller.collider();
+
+    simulation.spawn_player(player_data, player_transform, player_collider)
+}
+
+#[cfg(feature = "gilrs")]
+fn open_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    match GilrsGamepad::new() {
+        Ok(backend) => Some(Box::new(backend)),
+        Err(error) => {
+            warn!("Playing without gamepads: {}", error);
+            None
+        }
+    }
+}
+
+// Keyboard and mouse only, gilrs needs libudev on Linux
+#[cfg(not(feature = "gilrs"))]
+fn open_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    None
+}
@@ -1,12 +1,18 @@
-use std::collections::HashMap;
 use vulkanalia::vk;
-use crate::graphics::font_data::FontData;
-use crate::graphics::text_object::TextObject;
-use crate::graphics::text_textures::Character;
+use vulkanalia::vk::Handle;
+use crate::core::settings::PresentMode;
+use crate::graphics::fonts::Fonts;
+use crate::graphics::text_objects::TextObjects;
+use crate::graphics::ui_buffers::UiFrameBuffers;
+use crate::graphics::ui_space::UiSpace;
 use crate::graphics::vertex::Vertex;
 
 #[derive(Clone, Debug, Default)]
 pub(crate) struct AppData {
+    // Settings the Vulkan setup reads, copied from Settings
+    pub(crate) validation_enabled: bool,
+    pub(crate) frames_in_flight: usize,
+    pub(crate) present_mode: PresentMode,
     // Debug
     pub(crate) messenger: vk::DebugUtilsMessengerEXT,
     // Surface
@@ -21,6 +27,8 @@ pub(crate) struct AppData {
     pub(crate) swapchain: vk::SwapchainKHR,
     pub(crate) swapchain_images: Vec<vk::Image>,
     pub(crate) swapchain_image_views: Vec<vk::ImageView>,
+    // Whether swapchain images can be copied out for screenshots
+    pub(crate) swapchain_supports_capture: bool,
     // Pipeline
     pub(crate) render_pass: vk::RenderPass,
     pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
@@ -31,6 +39,26 @@ pub(crate) struct AppData {
     pub(crate) text_descriptor_set_layout: vk::DescriptorSetLayout,
     pub(crate) text_pipeline_layout: vk::PipelineLayout,
     pub(crate) text_pipeline: vk::Pipeline,
+    // UI Pipeline, draws in the text render pass
+    pub(crate) ui_pipeline_layout: vk::PipelineLayout,
+    pub(crate) ui_pipeline: vk::Pipeline,
+    // Sky Pipeline, draws first in the world render pass
+    pub(crate) sky_pipeline_layout: vk::PipelineLayout,
+    pub(crate) sky_pipeline: vk::Pipeline,
+    // Highlight Pipelines, the targeted voxel's outline and cracks in the world render pass
+    pub(crate) highlight_pipeline_layout: vk::PipelineLayout,
+    pub(crate) outline_pipeline: vk::Pipeline,
+    pub(crate) crack_pipeline: vk::Pipeline,
+    // Shadow Maps, a depth layer per cascade drawn from the sun or moon before the world render pass
+    pub(crate) shadow_image: vk::Image,
+    pub(crate) shadow_image_memory: vk::DeviceMemory,
+    pub(crate) shadow_image_view: vk::ImageView,
+    pub(crate) shadow_layer_views: Vec<vk::ImageView>,
+    pub(crate) shadow_sampler: vk::Sampler,
+    pub(crate) shadow_render_pass: vk::RenderPass,
+    pub(crate) shadow_framebuffers: Vec<vk::Framebuffer>,
+    pub(crate) shadow_pipeline_layout: vk::PipelineLayout,
+    pub(crate) shadow_pipeline: vk::Pipeline,
     // Framebuffers
     pub(crate) framebuffers: Vec<vk::Framebuffer>,
     // Command Pool
@@ -70,15 +98,12 @@ pub(crate) struct AppData {
     pub(crate) text_command_buffers: Vec<vk::CommandBuffer>,
     pub(crate) text_secondary_command_buffers: Vec<Vec<vk::CommandBuffer>>,
     // Text
-    pub(crate) text_characters: HashMap<u32, Character>,
-    pub(crate) text_objects: Vec<TextObject>,
-    pub(crate) font_data: FontData,
-    // Text Buffers
-    pub(crate) text_vertex_buffer: vk::Buffer,
-    pub(crate) text_vertex_buffer_memory: vk::DeviceMemory,
-    pub(crate) text_index_buffer_length: usize,
-    pub(crate) text_index_buffer: vk::Buffer,
-    pub(crate) text_index_buffer_memory: vk::DeviceMemory,
+    pub(crate) fonts: Fonts,
+    pub(crate) text_objects: TextObjects,
+    // Logical pixel space text and HUD are laid out in, follows the swapchain and the window's DPI
+    pub(crate) ui_space: UiSpace,
+    // UI
+    pub(crate) ui_buffers: UiFrameBuffers,
     // Sync Objects
     pub(crate) image_available_semaphores: Vec<vk::Semaphore>,
     pub(crate) render_finished_semaphores: Vec<vk::Semaphore>,
@@ -89,3 +114,10 @@ pub(crate) struct AppData {
     pub(crate) depth_image_memory: vk::DeviceMemory,
     pub(crate) depth_image_view: vk::ImageView,
 }
+
+impl AppData {
+    // Rendering into an offscreen image, there is no window surface or swapchain
+    pub(crate) fn is_headless(&self) -> bool {
+        self.surface.is_null()
+    }
+}
@@ -0,0 +1,417 @@
+use crate::core::aabb::Aabb;
+use crate::terrain::chunk_coord::ChunkCoord;
+use nalgebra_glm as glm;
+use std::collections::{HashMap, HashSet};
+
+// Uniform grid over object bounds, used to find which colliders are worth testing precisely
+#[derive(Debug)]
+pub(crate) struct BroadPhase {
+    cell_size: f32,
+    cells: HashMap<ChunkCoord, Vec<usize>>,
+    bounds: HashMap<usize, Aabb>,
+}
+
+impl BroadPhase {
+    pub(crate) fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    pub(crate) fn ids(&self) -> Vec<usize> {
+        self.bounds.keys().copied().collect()
+    }
+
+    pub(crate) fn get_bounds(&self, id: usize) -> Option<&Aabb> {
+        self.bounds.get(&id)
+    }
+
+    // Inserts the object or moves it if it is already tracked
+    pub(crate) fn update(&mut self, id: usize, bounds: Aabb) {
+        if let Some(previous) = self.bounds.get(&id) {
+            if self.cell_range(previous) == self.cell_range(&bounds) {
+                self.bounds.insert(id, bounds);
+                return;
+            }
+            self.remove(id);
+        }
+
+        for cell in self.cells_in(&bounds) {
+            self.cells.entry(cell).or_default().push(id);
+        }
+        self.bounds.insert(id, bounds);
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) -> Option<Aabb> {
+        let bounds = self.bounds.remove(&id)?;
+        for cell in self.cells_in(&bounds) {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        Some(bounds)
+    }
+
+    // Pairs of objects whose bounds overlap, each pair once with the lower id first
+    pub(crate) fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = HashSet::new();
+        for ids in self.cells.values() {
+            for (index, a) in ids.iter().enumerate() {
+                for b in &ids[index + 1..] {
+                    let pair = (*a.min(b), *a.max(b));
+                    if !pairs.contains(&pair) && self.bounds[a].intersects(&self.bounds[b]) {
+                        pairs.insert(pair);
+                    }
+                }
+            }
+        }
+
+        let mut pairs = pairs.into_iter().collect::<Vec<_>>();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    pub(crate) fn query_aabb(&self, area: &Aabb) -> Vec<usize> {
+        self.query(area, |bounds| bounds.intersects(area))
+    }
+
+    pub(crate) fn query_sphere(&self, center: glm::Vec3, radius: f32) -> Vec<usize> {
+        let extent = glm::vec3(radius, radius, radius);
+        let area = Aabb::new(center - extent, center + extent);
+        self.query(&area, |bounds| bounds.intersects_sphere(center, radius))
+    }
+
+    // Objects hit by the ray within `max_distance`, nearest first
+    pub(crate) fn query_ray(
+        &self,
+        origin: glm::Vec3,
+        direction: glm::Vec3,
+        max_distance: f32,
+    ) -> Vec<(usize, f32)> {
+        if glm::length2(&direction) < f32::EPSILON || self.is_empty() {
+            return vec![];
+        }
+        let direction = glm::normalize(&direction);
+
+        let mut hits = HashMap::new();
+        self.traverse_ray(origin, direction, max_distance, |ids| {
+            for id in ids {
+                if hits.contains_key(id) {
+                    continue;
+                }
+                if let Some(distance) = self.bounds[id].ray_distance(origin, direction) {
+                    if distance <= max_distance {
+                        hits.insert(*id, distance);
+                    }
+                }
+            }
+        });
+
+        let mut hits = hits.into_iter().collect::<Vec<_>>();
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        hits
+    }
+
+    fn query(&self, area: &Aabb, filter: impl Fn(&Aabb) -> bool) -> Vec<usize> {
+        let mut found = HashSet::new();
+        for cell in self.cells_in(area) {
+            if let Some(ids) = self.cells.get(&cell) {
+                found.extend(ids.iter().filter(|id| filter(&self.bounds[*id])));
+            }
+        }
+
+        let mut found = found.into_iter().collect::<Vec<_>>();
+        found.sort_unstable();
+        found
+    }
+
+    // Walks the grid cells pierced by the ray in order (Amanatides & Woo)
+    fn traverse_ray(
+        &self,
+        origin: glm::Vec3,
+        direction: glm::Vec3,
+        max_distance: f32,
+        mut visit: impl FnMut(&[usize]),
+    ) {
+        let mut cell = self.cell_of(origin);
+        let mut step = [0; 3];
+        let mut next_boundary = [f32::INFINITY; 3];
+        let mut boundary_spacing = [f32::INFINITY; 3];
+
+        let start = [cell.x, cell.y, cell.z];
+        for axis in 0..3 {
+            if direction[axis].abs() < f32::EPSILON {
+                continue;
+            }
+            step[axis] = if direction[axis] > 0.0 { 1 } else { -1 };
+            let boundary = (start[axis] + (step[axis] + 1) / 2) as f32 * self.cell_size;
+            next_boundary[axis] = (boundary - origin[axis]) / direction[axis];
+            boundary_spacing[axis] = self.cell_size / direction[axis].abs();
+        }
+
+        loop {
+            if let Some(ids) = self.cells.get(&cell) {
+                visit(ids);
+            }
+
+            let axis = (0..3)
+                .min_by(|a, b| next_boundary[*a].total_cmp(&next_boundary[*b]))
+                .unwrap();
+            if next_boundary[axis] > max_distance {
+                break;
+            }
+
+            match axis {
+                0 => cell.add_x(step[0]),
+                1 => cell.add_y(step[1]),
+                _ => cell.add_z(step[2]),
+            }
+            next_boundary[axis] += boundary_spacing[axis];
+        }
+    }
+
+    fn cell_of(&self, point: glm::Vec3) -> ChunkCoord {
+        ChunkCoord {
+            x: (point.x / self.cell_size).floor() as i32,
+            y: (point.y / self.cell_size).floor() as i32,
+            z: (point.z / self.cell_size).floor() as i32,
+        }
+    }
+
+    fn cell_range(&self, bounds: &Aabb) -> (ChunkCoord, ChunkCoord) {
+        (self.cell_of(bounds.min), self.cell_of(bounds.max))
+    }
+
+    fn cells_in(&self, bounds: &Aabb) -> Vec<ChunkCoord> {
+        let (min, max) = self.cell_range(bounds);
+        let mut cells = vec![];
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    cells.push(ChunkCoord { x, y, z });
+                }
+            }
+        }
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::collider::Collider;
+    use crate::core::collision::intersects;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const OBJECT_COUNT: usize = 400;
+
+    fn random_point(rng: &mut StdRng, range: f32) -> glm::Vec3 {
+        glm::vec3(
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+        )
+    }
+
+    fn random_collider(rng: &mut StdRng) -> Collider {
+        let center = random_point(rng, 40.0);
+        match rng.gen_range(0..3) {
+            0 => Collider::sphere(center, rng.gen_range(0.2..3.0)),
+            1 => Collider::cuboid(
+                center,
+                glm::vec3(
+                    rng.gen_range(0.2..3.0),
+                    rng.gen_range(0.2..3.0),
+                    rng.gen_range(0.2..3.0),
+                ),
+            ),
+            _ => Collider::capsule(
+                center,
+                center + random_point(rng, 2.0),
+                rng.gen_range(0.2..1.0),
+            ),
+        }
+    }
+
+    fn random_scene(seed: u64) -> (Vec<Collider>, BroadPhase) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let colliders = (0..OBJECT_COUNT)
+            .map(|_| random_collider(&mut rng))
+            .collect::<Vec<_>>();
+
+        let mut broad_phase = BroadPhase::new(8.0);
+        for (id, collider) in colliders.iter().enumerate() {
+            broad_phase.update(id, collider.get_bounds().unwrap());
+        }
+        (colliders, broad_phase)
+    }
+
+    fn brute_force_pairs(broad_phase: &BroadPhase) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for a in 0..OBJECT_COUNT {
+            for b in a + 1..OBJECT_COUNT {
+                let bounds_a = broad_phase.get_bounds(a).unwrap();
+                let bounds_b = broad_phase.get_bounds(b).unwrap();
+                if bounds_a.intersects(bounds_b) {
+                    pairs.push((a, b));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn test_candidate_pairs_match_brute_force() {
+        let (_, broad_phase) = random_scene(1);
+
+        let pairs = broad_phase.candidate_pairs();
+
+        assert!(!pairs.is_empty());
+        assert_eq!(pairs, brute_force_pairs(&broad_phase));
+    }
+
+    #[test]
+    fn test_narrow_phase_on_candidates_matches_brute_force() {
+        let (colliders, broad_phase) = random_scene(2);
+
+        let from_candidates = broad_phase
+            .candidate_pairs()
+            .into_iter()
+            .filter(|(a, b)| intersects(&colliders[*a], &colliders[*b]))
+            .collect::<Vec<_>>();
+
+        let mut brute_force = vec![];
+        for a in 0..OBJECT_COUNT {
+            for b in a + 1..OBJECT_COUNT {
+                if intersects(&colliders[a], &colliders[b]) {
+                    brute_force.push((a, b));
+                }
+            }
+        }
+
+        assert!(!brute_force.is_empty());
+        assert_eq!(from_candidates, brute_force);
+    }
+
+    #[test]
+    fn test_pairs_follow_moving_objects() {
+        let (colliders, mut broad_phase) = random_scene(3);
+        let mut rng = StdRng::seed_from_u64(4);
+
+        for (id, collider) in colliders.iter().enumerate() {
+            if id % 3 == 0 {
+                let moved = collider.compensate_position(random_point(&mut rng, 12.0));
+                broad_phase.update(id, moved.get_bounds().unwrap());
+            }
+        }
+        for id in (0..OBJECT_COUNT).filter(|id| id % 7 == 0) {
+            broad_phase.remove(id);
+        }
+
+        let expected = brute_force_pairs_without_removed(&broad_phase);
+        assert_eq!(broad_phase.candidate_pairs(), expected);
+        assert_eq!(broad_phase.len(), OBJECT_COUNT - OBJECT_COUNT.div_ceil(7));
+    }
+
+    fn brute_force_pairs_without_removed(broad_phase: &BroadPhase) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for a in 0..OBJECT_COUNT {
+            for b in a + 1..OBJECT_COUNT {
+                if let (Some(bounds_a), Some(bounds_b)) =
+                    (broad_phase.get_bounds(a), broad_phase.get_bounds(b))
+                {
+                    if bounds_a.intersects(bounds_b) {
+                        pairs.push((a, b));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn test_aabb_and_sphere_queries_match_brute_force() {
+        let (_, broad_phase) = random_scene(5);
+        let mut rng = StdRng::seed_from_u64(6);
+
+        for _ in 0..50 {
+            let min = random_point(&mut rng, 45.0);
+            let area = Aabb::new(min, min + glm::abs(&random_point(&mut rng, 15.0)));
+            let expected = (0..OBJECT_COUNT)
+                .filter(|id| broad_phase.get_bounds(*id).unwrap().intersects(&area))
+                .collect::<Vec<_>>();
+            assert_eq!(broad_phase.query_aabb(&area), expected);
+
+            let center = random_point(&mut rng, 45.0);
+            let radius = rng.gen_range(0.5..12.0);
+            let expected = (0..OBJECT_COUNT)
+                .filter(|id| {
+                    let bounds = broad_phase.get_bounds(*id).unwrap();
+                    bounds.intersects_sphere(center, radius)
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(broad_phase.query_sphere(center, radius), expected);
+        }
+    }
+
+    #[test]
+    fn test_ray_query_matches_brute_force() {
+        let (_, broad_phase) = random_scene(7);
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut total_hits = 0;
+
+        for _ in 0..100 {
+            let origin = random_point(&mut rng, 50.0);
+            let direction = glm::normalize(&random_point(&mut rng, 1.0));
+            let max_distance = rng.gen_range(5.0..80.0);
+
+            let mut expected = (0..OBJECT_COUNT)
+                .filter_map(|id| {
+                    let bounds = broad_phase.get_bounds(id).unwrap();
+                    let distance = bounds.ray_distance(origin, direction)?;
+                    (distance <= max_distance).then_some((id, distance))
+                })
+                .collect::<Vec<_>>();
+            expected.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+            let hits = broad_phase.query_ray(origin, direction, max_distance);
+            total_hits += hits.len();
+            assert_eq!(hits.len(), expected.len());
+            for (hit, expected) in hits.iter().zip(&expected) {
+                assert_eq!(hit.0, expected.0);
+                assert!((hit.1 - expected.1).abs() < 0.001);
+            }
+        }
+
+        assert!(total_hits > 0);
+    }
+
+    #[test]
+    fn test_axis_aligned_ray_through_negative_cells() {
+        let mut broad_phase = BroadPhase::new(4.0);
+        broad_phase.update(0, Aabb::from_voxel(-10, 0, 0));
+        broad_phase.update(1, Aabb::from_voxel(-3, 0, 0));
+        broad_phase.update(2, Aabb::from_voxel(-3, 2, 0));
+
+        let hits = broad_phase.query_ray(glm::vec3(0.5, 0.5, 0.5), glm::vec3(-1.0, 0.0, 0.0), 20.0);
+
+        assert_eq!(hits, vec![(1, 2.5), (0, 9.5)]);
+        assert!(broad_phase
+            .query_ray(glm::vec3(0.5, 0.5, 0.5), glm::vec3(-1.0, 0.0, 0.0), 2.0)
+            .is_empty());
+    }
+}
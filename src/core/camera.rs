@@ -0,0 +1,228 @@
+use nalgebra_glm as glm;
+use std::f32::consts::FRAC_PI_2;
+
+// The world is Z-up. Yaw 0 looks along +Y and turns towards +X, pitch 0 is level and positive looks up.
+pub(crate) fn world_up() -> glm::Vec3 {
+    glm::vec3(0.0, 0.0, 1.0)
+}
+
+// Keeps the view just short of straight up or down, where the look direction and up vector line up
+pub(crate) const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+pub(crate) fn direction_from_angles(yaw: f32, pitch: f32) -> glm::Vec3 {
+    glm::vec3(pitch.cos() * yaw.sin(), pitch.cos() * yaw.cos(), pitch.sin())
+}
+
+// Horizontal right vector, unaffected by pitch
+pub(crate) fn right_from_yaw(yaw: f32) -> glm::Vec3 {
+    glm::vec3(yaw.cos(), -yaw.sin(), 0.0)
+}
+
+// Rotates the camera's local axes (right +X, forward +Y, up +Z) into the world
+pub(crate) fn orientation_from_angles(yaw: f32, pitch: f32) -> glm::Quat {
+    let yaw_rotation = glm::quat_angle_axis(-yaw, &world_up());
+    let pitch_rotation = glm::quat_angle_axis(pitch, &glm::vec3(1.0, 0.0, 0.0));
+    yaw_rotation * pitch_rotation
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CameraSettings {
+    pub(crate) fov_degrees: f32,
+    pub(crate) near: f32,
+    pub(crate) far: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            fov_degrees: 90.0,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Camera {
+    pub(crate) position: glm::Vec3,
+    pub(crate) orientation: glm::Quat,
+    pub(crate) settings: CameraSettings,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: glm::vec3(0.0, 0.0, 0.0),
+            orientation: glm::quat_identity(),
+            settings: CameraSettings::default(),
+        }
+    }
+}
+
+impl Camera {
+    pub(crate) fn from_angles(position: glm::Vec3, yaw: f32, pitch: f32, settings: CameraSettings) -> Self {
+        Self {
+            position,
+            orientation: orientation_from_angles(yaw, pitch),
+            settings,
+        }
+    }
+
+    // Points the camera at `target` while keeping it upright
+    pub(crate) fn looking_at(position: glm::Vec3, target: glm::Vec3, settings: CameraSettings) -> Self {
+        let direction = target - position;
+        if glm::length2(&direction) == 0.0 {
+            return Self {
+                position,
+                settings,
+                ..Default::default()
+            };
+        }
+
+        let direction = direction.normalize();
+        let yaw = direction.x.atan2(direction.y);
+        let pitch = direction.z.clamp(-1.0, 1.0).asin().clamp(-MAX_PITCH, MAX_PITCH);
+        Self::from_angles(position, yaw, pitch, settings)
+    }
+
+    pub(crate) fn forward(&self) -> glm::Vec3 {
+        glm::quat_rotate_vec3(&self.orientation, &glm::vec3(0.0, 1.0, 0.0))
+    }
+
+    pub(crate) fn right(&self) -> glm::Vec3 {
+        glm::quat_rotate_vec3(&self.orientation, &glm::vec3(1.0, 0.0, 0.0))
+    }
+
+    pub(crate) fn up(&self) -> glm::Vec3 {
+        glm::quat_rotate_vec3(&self.orientation, &glm::vec3(0.0, 0.0, 1.0))
+    }
+
+    pub(crate) fn view_matrix(&self) -> glm::Mat4 {
+        glm::look_at(&self.position, &(self.position + self.forward()), &self.up())
+    }
+
+    // Vulkan clip space: depth from 0 to 1 and Y pointing down
+    pub(crate) fn projection_matrix(&self, aspect_ratio: f32) -> glm::Mat4 {
+        let mut projection = glm::perspective_rh_zo(
+            aspect_ratio,
+            self.settings.fov_degrees.to_radians(),
+            self.settings.near,
+            self.settings.far,
+        );
+        projection[(1, 1)] *= -1.0;
+        projection
+    }
+
+    // Blends position, orientation and lens, t 0 gives self and 1 gives other
+    pub(crate) fn lerp(&self, other: &Camera, t: f32) -> Camera {
+        Camera {
+            position: glm::lerp(&self.position, &other.position, t),
+            orientation: glm::quat_slerp(&self.orientation, &other.orientation, t),
+            settings: CameraSettings {
+                fov_degrees: glm::lerp_scalar(self.settings.fov_degrees, other.settings.fov_degrees, t),
+                near: glm::lerp_scalar(self.settings.near, other.settings.near, t),
+                far: glm::lerp_scalar(self.settings.far, other.settings.far, t),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: glm::Vec3, b: glm::Vec3) {
+        assert!(glm::distance(&a, &b) < 0.0001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_angle_conventions() {
+        assert_close(direction_from_angles(0.0, 0.0), glm::vec3(0.0, 1.0, 0.0));
+        assert_close(direction_from_angles(FRAC_PI_2, 0.0), glm::vec3(1.0, 0.0, 0.0));
+        assert_close(direction_from_angles(0.0, FRAC_PI_2), glm::vec3(0.0, 0.0, 1.0));
+        assert_close(right_from_yaw(0.0), glm::vec3(1.0, 0.0, 0.0));
+        assert_close(right_from_yaw(FRAC_PI_2), glm::vec3(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_orientation_matches_angles() {
+        for (yaw, pitch) in [(0.3, -0.4), (2.5, 1.2), (-1.7, 0.0), (4.0, -1.5)] {
+            let camera = Camera::from_angles(glm::vec3(1.0, 2.0, 3.0), yaw, pitch, CameraSettings::default());
+
+            assert_close(camera.forward(), direction_from_angles(yaw, pitch));
+            assert_close(camera.right(), right_from_yaw(yaw));
+            assert_close(camera.up(), glm::cross(&camera.right(), &camera.forward()));
+            assert!(camera.up().z >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_looking_at() {
+        let settings = CameraSettings::default();
+        let camera = Camera::looking_at(glm::vec3(0.0, 0.0, 0.0), glm::vec3(-3.0, 3.0, 0.0), settings);
+        assert_close(camera.forward(), glm::vec3(-1.0, 1.0, 0.0).normalize());
+
+        // Straight down is clamped instead of producing a degenerate view
+        let camera = Camera::looking_at(glm::vec3(0.0, 0.0, 5.0), glm::vec3(0.0, 0.0, 0.0), settings);
+        assert!(camera.forward().z < -0.99);
+        assert!(camera.view_matrix().iter().all(|value| value.is_finite()));
+    }
+
+    #[test]
+    fn test_view_matrix_puts_forward_on_negative_z() {
+        let camera = Camera::from_angles(glm::vec3(4.0, -2.0, 1.0), 0.8, 0.2, CameraSettings::default());
+        let ahead = camera.position + camera.forward() * 5.0;
+        let to_the_right = camera.position + camera.right() * 2.0;
+
+        let ahead = camera.view_matrix() * glm::vec4(ahead.x, ahead.y, ahead.z, 1.0);
+        let to_the_right = camera.view_matrix() * glm::vec4(to_the_right.x, to_the_right.y, to_the_right.z, 1.0);
+
+        assert_close(ahead.xyz(), glm::vec3(0.0, 0.0, -5.0));
+        assert_close(to_the_right.xyz(), glm::vec3(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_projection_depth_range() {
+        let camera = Camera {
+            settings: CameraSettings {
+                fov_degrees: 70.0,
+                near: 0.5,
+                far: 250.0,
+            },
+            ..Default::default()
+        };
+        let projection = camera.projection_matrix(16.0 / 9.0);
+
+        let near = projection * glm::vec4(0.0, 0.0, -0.5, 1.0);
+        let far = projection * glm::vec4(0.0, 0.0, -250.0, 1.0);
+        assert!((near.z / near.w).abs() < 0.0001);
+        assert!((far.z / far.w - 1.0).abs() < 0.0001);
+
+        // Y is flipped for Vulkan, so a point above the center lands in the upper half
+        let above = projection * glm::vec4(0.0, 1.0, -2.0, 1.0);
+        assert!(above.y / above.w < 0.0);
+        let half_height = (35.0f32).to_radians().tan() * 2.0;
+        assert!((above.y / above.w + 1.0 / half_height).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let settings = CameraSettings::default();
+        let from = Camera::from_angles(glm::vec3(0.0, 0.0, 0.0), 0.0, 0.0, settings);
+        let to = Camera {
+            settings: CameraSettings {
+                fov_degrees: 60.0,
+                ..settings
+            },
+            ..Camera::from_angles(glm::vec3(2.0, 4.0, 0.0), FRAC_PI_2, 0.0, settings)
+        };
+
+        assert_eq!(from.lerp(&to, 0.0).position, from.position);
+        assert_close(from.lerp(&to, 1.0).forward(), to.forward());
+
+        let halfway = from.lerp(&to, 0.5);
+        assert_close(halfway.position, glm::vec3(1.0, 2.0, 0.0));
+        assert_close(halfway.forward(), direction_from_angles(FRAC_PI_2 / 2.0, 0.0));
+        assert_eq!(halfway.settings.fov_degrees, 75.0);
+    }
+}
@@ -1,31 +1,200 @@
+use crate::core::aabb::Aabb;
 use nalgebra_glm as glm;
 
-#[derive(Debug, Clone, Default)]
-pub(crate) struct Collider {
-    pub(crate) vertices: Vec<glm::Vec3>,
+// Convex shapes that GJK can work with through their support function
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Collider {
+    ConvexHull {
+        vertices: Vec<glm::Vec3>,
+    },
+    // Axis aligned box
+    Cuboid {
+        center: glm::Vec3,
+        half_extents: glm::Vec3,
+    },
+    Sphere {
+        center: glm::Vec3,
+        radius: f32,
+    },
+    // Segment from `start` to `end` swept by a sphere
+    Capsule {
+        start: glm::Vec3,
+        end: glm::Vec3,
+        radius: f32,
+    },
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Collider::ConvexHull { vertices: vec![] }
+    }
 }
 
 impl Collider {
+    pub(crate) fn convex_hull(vertices: Vec<glm::Vec3>) -> Self {
+        Collider::ConvexHull { vertices }
+    }
+
+    pub(crate) fn cuboid(center: glm::Vec3, half_extents: glm::Vec3) -> Self {
+        Collider::Cuboid {
+            center,
+            half_extents,
+        }
+    }
+
+    pub(crate) fn sphere(center: glm::Vec3, radius: f32) -> Self {
+        Collider::Sphere { center, radius }
+    }
+
+    pub(crate) fn capsule(start: glm::Vec3, end: glm::Vec3, radius: f32) -> Self {
+        Collider::Capsule { start, end, radius }
+    }
+
+    // Support function: the point of the shape furthest along `direction`
     pub(crate) fn find_furthest_point(&self, direction: glm::Vec3) -> glm::Vec3 {
-        let mut furthest = self.vertices[0];
-        let mut max_distance = -f32::INFINITY;
-        for vertex in &self.vertices {
-            let distance = glm::dot(vertex, &direction);
-            if distance > max_distance {
-                max_distance = distance;
-                furthest = *vertex;
+        match self {
+            Collider::ConvexHull { vertices } => {
+                let mut furthest = glm::zero();
+                let mut max_distance = -f32::INFINITY;
+                for vertex in vertices {
+                    let distance = glm::dot(vertex, &direction);
+                    if distance > max_distance {
+                        max_distance = distance;
+                        furthest = *vertex;
+                    }
+                }
+                furthest
+            }
+            Collider::Cuboid {
+                center,
+                half_extents,
+            } => glm::vec3(
+                center.x + half_extents.x.copysign(direction.x),
+                center.y + half_extents.y.copysign(direction.y),
+                center.z + half_extents.z.copysign(direction.z),
+            ),
+            Collider::Sphere { center, radius } => center + sphere_offset(direction, *radius),
+            Collider::Capsule { start, end, radius } => {
+                let endpoint = if glm::dot(end, &direction) > glm::dot(start, &direction) {
+                    end
+                } else {
+                    start
+                };
+                endpoint + sphere_offset(direction, *radius)
             }
         }
-        furthest
+    }
+
+    // Tight axis aligned bounds, None for a hull without vertices
+    pub(crate) fn get_bounds(&self) -> Option<Aabb> {
+        if let Collider::ConvexHull { vertices } = self {
+            if vertices.is_empty() {
+                return None;
+            }
+        }
+
+        let mut min = glm::Vec3::zeros();
+        let mut max = glm::Vec3::zeros();
+        for axis in 0..3 {
+            let mut direction = glm::Vec3::zeros();
+            direction[axis] = 1.0;
+            max[axis] = self.find_furthest_point(direction)[axis];
+            min[axis] = self.find_furthest_point(-direction)[axis];
+        }
+        Some(Aabb::new(min, max))
     }
 
     pub(crate) fn compensate_position(&self, position: glm::Vec3) -> Collider {
-        let vertices = self
-            .vertices
-            .iter()
-            .map(|vertex| vertex + position)
-            .collect::<Vec<_>>();
+        match self {
+            Collider::ConvexHull { vertices } => Collider::ConvexHull {
+                vertices: vertices.iter().map(|vertex| vertex + position).collect(),
+            },
+            Collider::Cuboid {
+                center,
+                half_extents,
+            } => Collider::Cuboid {
+                center: center + position,
+                half_extents: *half_extents,
+            },
+            Collider::Sphere { center, radius } => Collider::Sphere {
+                center: center + position,
+                radius: *radius,
+            },
+            Collider::Capsule { start, end, radius } => Collider::Capsule {
+                start: start + position,
+                end: end + position,
+                radius: *radius,
+            },
+        }
+    }
+}
+
+fn sphere_offset(direction: glm::Vec3, radius: f32) -> glm::Vec3 {
+    let length = glm::length(&direction);
+    if length > f32::EPSILON {
+        direction * (radius / length)
+    } else {
+        glm::vec3(radius, 0.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_support_points() {
+        let direction = glm::vec3(1.0, -2.0, 0.5);
+
+        let cuboid = Collider::cuboid(glm::vec3(1.0, 1.0, 1.0), glm::vec3(0.5, 1.0, 2.0));
+        assert_eq!(cuboid.find_furthest_point(direction), glm::vec3(1.5, 0.0, 3.0));
+
+        let sphere = Collider::sphere(glm::vec3(0.0, 0.0, 1.0), 2.0);
+        let point = sphere.find_furthest_point(glm::vec3(0.0, 3.0, 0.0));
+        assert!(glm::distance(&point, &glm::vec3(0.0, 2.0, 1.0)) < 0.0001);
 
-        Collider { vertices }
+        let capsule = Collider::capsule(glm::vec3(0.0, 0.0, 0.0), glm::vec3(0.0, 0.0, 2.0), 0.5);
+        let point = capsule.find_furthest_point(glm::vec3(0.0, 0.0, -1.0));
+        assert!(glm::distance(&point, &glm::vec3(0.0, 0.0, -0.5)) < 0.0001);
+
+        let hull = Collider::convex_hull(vec![
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(0.0, -1.0, 0.0),
+        ]);
+        assert_eq!(hull.find_furthest_point(direction), glm::vec3(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let capsule = Collider::capsule(glm::vec3(0.0, 0.0, 0.0), glm::vec3(2.0, 0.0, 1.0), 0.5);
+        let bounds = capsule.get_bounds().unwrap();
+        assert_eq!(bounds.min, glm::vec3(-0.5, -0.5, -0.5));
+        assert_eq!(bounds.max, glm::vec3(2.5, 0.5, 1.5));
+
+        let sphere = Collider::sphere(glm::vec3(1.0, 2.0, 3.0), 1.0);
+        let bounds = sphere.get_bounds().unwrap();
+        assert_eq!(bounds.min, glm::vec3(0.0, 1.0, 2.0));
+        assert_eq!(bounds.max, glm::vec3(2.0, 3.0, 4.0));
+
+        assert!(Collider::default().get_bounds().is_none());
+    }
+
+    #[test]
+    fn test_compensate_position_moves_support() {
+        let offset = glm::vec3(2.0, -3.0, 4.0);
+        let direction = glm::vec3(0.3, 0.4, -1.0);
+        let colliders = [
+            Collider::convex_hull(vec![glm::vec3(0.0, 0.0, 0.0), glm::vec3(1.0, 1.0, 1.0)]),
+            Collider::cuboid(glm::vec3(0.0, 0.0, 0.0), glm::vec3(1.0, 1.0, 1.0)),
+            Collider::sphere(glm::vec3(1.0, 0.0, 0.0), 1.0),
+            Collider::capsule(glm::vec3(0.0, 0.0, 0.0), glm::vec3(0.0, 1.0, 0.0), 0.5),
+        ];
+
+        for collider in &colliders {
+            let moved = collider.compensate_position(offset);
+            let expected = collider.find_furthest_point(direction) + offset;
+            assert!(glm::distance(&moved.find_furthest_point(direction), &expected) < 0.0001);
+        }
     }
 }
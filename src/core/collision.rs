@@ -1,205 +1,633 @@
 use crate::core::collider::Collider;
 use crate::core::math_functions::vector_triple_product;
-use crate::core::simplex::Simplex;
+use crate::core::simplex::{Simplex, SupportPoint};
 use nalgebra_glm as glm;
-use std::mem;
 
-fn support(collider_a: &Collider, collider_b: &Collider, direction: glm::Vec3) -> glm::Vec3 {
-    collider_a.find_furthest_point(direction) - collider_b.find_furthest_point(-direction)
+const GJK_MAX_ITERATIONS: usize = 64;
+const EPA_MAX_ITERATIONS: usize = 64;
+const EPA_TOLERANCE: f32 = 0.0001;
+// Squared lengths below this are treated as zero when building directions and normals
+const DEGENERATE_EPSILON: f32 = 0.0000000001;
+
+// Result of the narrow phase for two overlapping colliders
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ContactManifold {
+    // Points from A towards B; moving B along it by `depth` separates the shapes
+    pub(crate) normal: glm::Vec3,
+    pub(crate) depth: f32,
+    // Deepest point of A inside B and of B inside A
+    pub(crate) point_on_a: glm::Vec3,
+    pub(crate) point_on_b: glm::Vec3,
+}
+
+fn support(collider_a: &Collider, collider_b: &Collider, direction: glm::Vec3) -> SupportPoint {
+    let on_a = collider_a.find_furthest_point(direction);
+    let on_b = collider_b.find_furthest_point(-direction);
+    SupportPoint {
+        point: on_a - on_b,
+        on_a,
+        on_b,
+    }
+}
+
+fn same_direction(direction: glm::Vec3, ao: glm::Vec3) -> bool {
+    glm::dot(&direction, &ao) > 0.0
+}
+
+fn perpendicular(vector: glm::Vec3) -> glm::Vec3 {
+    let axis = if vector.x.abs() < vector.y.abs() {
+        glm::vec3(1.0, 0.0, 0.0)
+    } else {
+        glm::vec3(0.0, 1.0, 0.0)
+    };
+    glm::cross(&vector, &axis)
+}
+
+// Direction from the segment towards the origin, perpendicular to the segment
+fn segment_direction(edge: glm::Vec3, ao: glm::Vec3) -> glm::Vec3 {
+    let direction = vector_triple_product(&edge, &ao, &edge);
+    if glm::length2(&direction) < DEGENERATE_EPSILON {
+        // The origin lies on the segment, any perpendicular direction works
+        perpendicular(edge)
+    } else {
+        direction
+    }
 }
 
 fn next_simplex(points: &mut Simplex, direction: &mut glm::Vec3) -> bool {
-    return match points.size() {
+    match points.size() {
         2 => line(points, direction),
         3 => triangle(points, direction),
         4 => tetrahedron(points, direction),
         _ => false,
-    };
-}
-
-fn same_direction(direction: glm::Vec3, ao: glm::Vec3) -> bool {
-    glm::dot(&direction, &ao) > 0.0
+    }
 }
 
 fn line(points: &mut Simplex, direction: &mut glm::Vec3) -> bool {
-    let a = points[0];
-    let b = points[1];
+    let a = *points.get(0);
+    let b = *points.get(1);
 
-    let ab = b - a;
-    let ao = -a;
+    let ab = b.point - a.point;
+    let ao = -a.point;
 
     if same_direction(ab, ao) {
-        let new_direction = vector_triple_product(&ab, &ao, &ab);
-        let _ = mem::replace(direction, new_direction);
+        *direction = segment_direction(ab, ao);
     } else {
-        points[0] = a;
-        points[1] = glm::vec3(0.0, 0.0, 0.0);
-        points[2] = glm::vec3(0.0, 0.0, 0.0);
-        points[3] = glm::vec3(0.0, 0.0, 0.0);
-
-        let _ = mem::replace(direction, ao);
+        points.assign(&[a]);
+        *direction = ao;
     }
 
     false
 }
 
 fn triangle(points: &mut Simplex, direction: &mut glm::Vec3) -> bool {
-    let a = points[0];
-    let b = points[1];
-    let c = points[2];
+    let a = *points.get(0);
+    let b = *points.get(1);
+    let c = *points.get(2);
 
-    let ab = b - a;
-    let ac = c - a;
-    let ao = -a;
+    let ab = b.point - a.point;
+    let ac = c.point - a.point;
+    let ao = -a.point;
 
     let abc = glm::cross(&ab, &ac);
 
-    if same_direction(glm::cross(&abc, &ao), ao) {
+    if glm::length2(&abc) < DEGENERATE_EPSILON {
+        points.assign(&[a, b]);
+        return line(points, direction);
+    }
+
+    if same_direction(glm::cross(&abc, &ac), ao) {
         if same_direction(ac, ao) {
-            points[0] = a;
-            points[1] = c;
-            points[2] = glm::vec3(0.0, 0.0, 0.0);
-            points[3] = glm::vec3(0.0, 0.0, 0.0);
-            let new_direction = vector_triple_product(&ac, &ao, &ac);
-            let _ = mem::replace(direction, new_direction);
+            points.assign(&[a, c]);
+            *direction = segment_direction(ac, ao);
         } else {
-            return line(&mut Simplex::from_list(vec![a, b]), direction);
+            points.assign(&[a, b]);
+            return line(points, direction);
         }
+    } else if same_direction(glm::cross(&ab, &abc), ao) {
+        points.assign(&[a, b]);
+        return line(points, direction);
+    } else if same_direction(abc, ao) {
+        *direction = abc;
     } else {
-        if same_direction(glm::cross(&ab, &abc), ao) {
-            return line(&mut Simplex::from_list(vec![a, b]), direction);
-        } else {
-            if same_direction(abc, ao) {
-                let _ = mem::replace(direction, abc);
-            } else {
-                points[0] = a;
-                points[1] = b;
-                points[2] = c;
-                points[3] = glm::vec3(0.0, 0.0, 0.0);
-
-                let _ = mem::replace(direction, -abc);
-            }
-        }
+        points.assign(&[a, c, b]);
+        *direction = -abc;
     }
 
     false
 }
 
 fn tetrahedron(points: &mut Simplex, direction: &mut glm::Vec3) -> bool {
-    let a = points[0];
-    let b = points[1];
-    let c = points[2];
-    let d = points[3];
+    let a = *points.get(0);
+    let b = *points.get(1);
+    let c = *points.get(2);
+    let d = *points.get(3);
 
-    let ab = b - a;
-    let ac = c - a;
-    let ad = d - a;
-    let ao = -a;
+    let ab = b.point - a.point;
+    let ac = c.point - a.point;
+    let ad = d.point - a.point;
+    let ao = -a.point;
 
     let abc = glm::cross(&ab, &ac);
     let acd = glm::cross(&ac, &ad);
     let adb = glm::cross(&ad, &ab);
 
+    // A flat tetrahedron can't enclose the origin, search off its plane instead
+    if glm::dot(&abc, &ad).abs() < DEGENERATE_EPSILON {
+        points.assign(&[a, b, c]);
+        return triangle(points, direction);
+    }
+
     if same_direction(abc, ao) {
-        return triangle(&mut Simplex::from_list(vec![a, b, c]), direction);
+        points.assign(&[a, b, c]);
+        return triangle(points, direction);
     }
 
     if same_direction(acd, ao) {
-        return triangle(&mut Simplex::from_list(vec![a, c, d]), direction);
+        points.assign(&[a, c, d]);
+        return triangle(points, direction);
     }
 
     if same_direction(adb, ao) {
-        return triangle(&mut Simplex::from_list(vec![a, d, b]), direction);
+        points.assign(&[a, d, b]);
+        return triangle(points, direction);
     }
 
-    println!("\n\n\n {}, {}, {}, {}", a, b, c, d);
-
     true
 }
 
-pub(crate) fn intersects(collider_a: &Collider, collider_b: &Collider) -> bool {
-    let mut support_vector = support(collider_a, collider_b, glm::zero());
+// Returns a tetrahedron enclosing the origin if the colliders overlap
+fn gjk(collider_a: &Collider, collider_b: &Collider) -> Option<Simplex> {
+    let mut support_point = support(collider_a, collider_b, glm::vec3(1.0, 0.0, 0.0));
     let mut points = Simplex::empty();
-    points.push_front(support_vector);
-    let mut direction = -support_vector;
+    points.push_front(support_point);
+    let mut direction = -support_point.point;
 
-    loop {
-        support_vector = support(collider_a, collider_b, direction);
+    for _ in 0..GJK_MAX_ITERATIONS {
+        if glm::length2(&direction) < DEGENERATE_EPSILON {
+            return None;
+        }
+
+        support_point = support(collider_a, collider_b, direction);
 
-        if glm::dot(&direction, &support_vector) < 0.0 {
-            return false;
+        if glm::dot(&direction, &support_point.point) <= 0.0 {
+            return None;
         }
 
-        points.push_front(support_vector);
+        points.push_front(support_point);
 
         if next_simplex(&mut points, &mut direction) {
-            println!("epa {:?}", epa(points.clone(), collider_a, collider_b));
-            return true;
+            return Some(points);
         }
     }
+
+    None
+}
+
+// Cheap overlap test, shapes that exactly touch may be reported either way
+pub(crate) fn intersects(collider_a: &Collider, collider_b: &Collider) -> bool {
+    gjk(collider_a, collider_b).is_some()
 }
 
-static TOLERANCE: f32 = 0.00001;
+// Penetration info for overlapping colliders, touching shapes give None
+pub(crate) fn collide(collider_a: &Collider, collider_b: &Collider) -> Option<ContactManifold> {
+    let simplex = gjk(collider_a, collider_b)?;
+    let contact = epa(&simplex, collider_a, collider_b);
+    (contact.depth > EPA_TOLERANCE).then_some(contact)
+}
 
-fn epa(
-    mut termination_simplex: Simplex,
-    collider_a: &Collider,
-    collider_b: &Collider,
-) -> (glm::Vec3, f32) {
-    loop {
-        let edge = find_closest_edge(&termination_simplex);
+struct Face {
+    indices: [usize; 3],
+    normal: glm::Vec3,
+    distance: f32,
+}
 
-        let p = support(collider_a, collider_b, edge.normal);
+impl Face {
+    fn new(polytope: &[SupportPoint], indices: [usize; 3]) -> Self {
+        let a = polytope[indices[0]].point;
+        let b = polytope[indices[1]].point;
+        let c = polytope[indices[2]].point;
+
+        let normal = glm::cross(&(b - a), &(c - a));
+        if glm::length2(&normal) < DEGENERATE_EPSILON {
+            // Sliver faces can't be the closest one
+            return Self {
+                indices,
+                normal: glm::zero(),
+                distance: f32::MAX,
+            };
+        }
 
-        let d = p.dot(&edge.normal);
+        let mut normal = glm::normalize(&normal);
+        let mut distance = glm::dot(&normal, &a);
+        if distance < 0.0 {
+            normal = -normal;
+            distance = -distance;
+        }
 
-        if d - edge.distance < TOLERANCE {
-            return (edge.normal, d);
-        } else {
-            termination_simplex.set(edge.index, p);
+        Self {
+            indices,
+            normal,
+            distance,
         }
     }
+
+    fn edges(&self) -> [(usize, usize); 3] {
+        let [a, b, c] = self.indices;
+        [(a, b), (b, c), (c, a)]
+    }
 }
 
-fn find_closest_edge(simplex: &Simplex) -> Edge {
-    let distance = f32::MAX;
+// Edges shared by two removed faces are interior to the hole and get dropped
+fn add_if_unique_edge(edges: &mut Vec<(usize, usize)>, edge: (usize, usize)) {
+    let existing = edges
+        .iter()
+        .position(|&(a, b)| (a, b) == edge || (b, a) == edge);
 
-    let mut closest = Edge {
-        distance: 0.0,
-        normal: Default::default(),
-        index: 0,
-    };
+    match existing {
+        Some(index) => {
+            edges.swap_remove(index);
+        }
+        None => edges.push(edge),
+    }
+}
 
-    for i in 0..simplex.size() as usize {
-        let j = if i + 1 == simplex.size() as usize {
-            0
-        } else {
-            i + 1
-        };
+fn closest_face(faces: &[Face]) -> usize {
+    let mut closest = 0;
+    for (index, face) in faces.iter().enumerate() {
+        if face.distance < faces[closest].distance {
+            closest = index;
+        }
+    }
+    closest
+}
 
-        let a = simplex.get(i);
-        let b = simplex.get(j);
+// Expands the GJK tetrahedron until its closest face lies on the Minkowski difference boundary
+fn epa(simplex: &Simplex, collider_a: &Collider, collider_b: &Collider) -> ContactManifold {
+    let mut polytope = simplex.as_slice().to_vec();
+    let mut faces = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]]
+        .into_iter()
+        .map(|indices| Face::new(&polytope, indices))
+        .collect::<Vec<_>>();
 
-        let e = glm::vec3(b.x - a.x, b.y - a.y, b.z - a.z);
+    let mut closest = closest_face(&faces);
 
-        let oa = a;
+    for _ in 0..EPA_MAX_ITERATIONS {
+        let normal = faces[closest].normal;
+        let distance = faces[closest].distance;
 
-        let n = &vector_triple_product(&e, oa, &e).normalize() as &glm::Vec3;
+        let support_point = support(collider_a, collider_b, normal);
+        if glm::dot(&normal, &support_point.point) - distance < EPA_TOLERANCE {
+            break;
+        }
 
-        let d = n.dot(a);
+        let mut edges = vec![];
+        let mut index = 0;
+        while index < faces.len() {
+            let face = &faces[index];
+            let to_support = support_point.point - polytope[face.indices[0]].point;
+            if same_direction(face.normal, to_support) {
+                for edge in face.edges() {
+                    add_if_unique_edge(&mut edges, edge);
+                }
+                faces.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
 
-        if d < distance {
-            closest.distance = d;
-            closest.normal = *n;
-            closest.index = j;
+        let new_index = polytope.len();
+        polytope.push(support_point);
+        for (a, b) in edges {
+            faces.push(Face::new(&polytope, [a, b, new_index]));
         }
+
+        closest = closest_face(&faces);
     }
 
-    closest
+    contact_from_face(&polytope, &faces[closest])
 }
 
-struct Edge {
-    distance: f32,
-    normal: glm::Vec3,
-    index: usize,
+fn contact_from_face(polytope: &[SupportPoint], face: &Face) -> ContactManifold {
+    let a = polytope[face.indices[0]];
+    let b = polytope[face.indices[1]];
+    let c = polytope[face.indices[2]];
+
+    // Origin projected on the face, mapped back onto both shapes
+    let projection = face.normal * face.distance;
+    let (u, v, w) = barycentric(projection, a.point, b.point, c.point);
+
+    ContactManifold {
+        normal: face.normal,
+        depth: face.distance,
+        point_on_a: a.on_a * u + b.on_a * v + c.on_a * w,
+        point_on_b: a.on_b * u + b.on_b * v + c.on_b * w,
+    }
+}
+
+fn barycentric(point: glm::Vec3, a: glm::Vec3, b: glm::Vec3, c: glm::Vec3) -> (f32, f32, f32) {
+    let v0 = b - a;
+    let v1 = c - a;
+    let v2 = point - a;
+
+    let d00 = glm::dot(&v0, &v0);
+    let d01 = glm::dot(&v0, &v1);
+    let d11 = glm::dot(&v1, &v1);
+    let d20 = glm::dot(&v2, &v0);
+    let d21 = glm::dot(&v2, &v1);
+
+    let denominator = d00 * d11 - d01 * d01;
+    if denominator.abs() < DEGENERATE_EPSILON {
+        return (1.0, 0.0, 0.0);
+    }
+
+    let v = (d11 * d20 - d01 * d21) / denominator;
+    let w = (d00 * d21 - d01 * d20) / denominator;
+    (1.0 - v - w, v, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 0.01;
+
+    fn assert_vec_near(actual: glm::Vec3, expected: glm::Vec3) {
+        assert!(
+            glm::distance(&actual, &expected) < EPSILON,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    fn unit_cube_hull() -> Collider {
+        Collider::convex_hull(vec![
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(1.0, 1.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(0.0, 0.0, 1.0),
+            glm::vec3(1.0, 0.0, 1.0),
+            glm::vec3(1.0, 1.0, 1.0),
+            glm::vec3(0.0, 1.0, 1.0),
+        ])
+    }
+
+    fn cube(center: glm::Vec3, half_extent: f32) -> Collider {
+        Collider::cuboid(center, glm::vec3(half_extent, half_extent, half_extent))
+    }
+
+    // Pushing B out along the contact normal must separate the pair
+    fn assert_resolves(a: &Collider, b: &Collider) {
+        let contact = collide(a, b).expect("colliders should overlap");
+        assert!(contact.depth > 0.0);
+        assert!((glm::length(&contact.normal) - 1.0).abs() < 0.0001);
+
+        let resolved = b.compensate_position(contact.normal * (contact.depth + EPSILON));
+        assert!(!intersects(a, &resolved));
+        let barely = b.compensate_position(contact.normal * (contact.depth - EPSILON));
+        assert!(intersects(a, &barely));
+    }
+
+    #[test]
+    fn test_separated_spheres() {
+        let a = Collider::sphere(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = Collider::sphere(glm::vec3(2.1, 0.0, 0.0), 1.0);
+        let c = Collider::sphere(glm::vec3(1.3, 1.3, 1.3), 1.0);
+
+        assert!(!intersects(&a, &b));
+        assert!(!intersects(&a, &c));
+        assert!(collide(&a, &b).is_none());
+    }
+
+    #[test]
+    fn test_overlapping_spheres() {
+        let a = Collider::sphere(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = Collider::sphere(glm::vec3(1.5, 0.0, 0.0), 1.0);
+
+        let contact = collide(&a, &b).unwrap();
+
+        assert_vec_near(contact.normal, glm::vec3(1.0, 0.0, 0.0));
+        assert!((contact.depth - 0.5).abs() < EPSILON);
+        assert_vec_near(contact.point_on_a, glm::vec3(1.0, 0.0, 0.0));
+        assert_vec_near(contact.point_on_b, glm::vec3(0.5, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_concentric_spheres() {
+        let a = Collider::sphere(glm::vec3(3.0, -2.0, 1.0), 1.0);
+        let b = Collider::sphere(glm::vec3(3.0, -2.0, 1.0), 0.5);
+
+        let contact = collide(&a, &b).unwrap();
+
+        // Every direction is equally deep here, the worst case for EPA on curved shapes
+        assert!(contact.depth <= 1.5 && contact.depth > 1.4);
+        assert!((glm::length(&contact.normal) - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_overlapping_boxes() {
+        let a = cube(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = cube(glm::vec3(1.5, 0.2, 0.1), 1.0);
+
+        let contact = collide(&a, &b).unwrap();
+
+        assert_vec_near(contact.normal, glm::vec3(1.0, 0.0, 0.0));
+        assert!((contact.depth - 0.5).abs() < EPSILON);
+        assert!((contact.point_on_a.x - 1.0).abs() < EPSILON);
+        assert!((contact.point_on_b.x - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_boxes_touching_have_no_contact() {
+        let a = cube(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = cube(glm::vec3(2.0, 0.0, 0.0), 1.0);
+        let c = cube(glm::vec3(2.0, 2.0, 2.0), 1.0);
+
+        assert!(collide(&a, &b).is_none());
+        assert!(collide(&a, &c).is_none());
+    }
+
+    #[test]
+    fn test_boxes_separated_diagonally() {
+        let a = cube(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = cube(glm::vec3(2.01, 1.5, -1.5), 1.0);
+
+        assert!(!intersects(&a, &b));
+        assert!(intersects(&a, &b.compensate_position(glm::vec3(-0.02, 0.0, 0.0))));
+    }
+
+    #[test]
+    fn test_deep_box_overlap_picks_shallowest_axis() {
+        let a = Collider::cuboid(glm::vec3(0.0, 0.0, 0.0), glm::vec3(2.0, 2.0, 0.5));
+        let b = cube(glm::vec3(0.3, -0.2, 0.8), 0.5);
+
+        let contact = collide(&a, &b).unwrap();
+
+        assert_vec_near(contact.normal, glm::vec3(0.0, 0.0, 1.0));
+        assert!((contact.depth - 0.2).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_box_and_sphere() {
+        let a = cube(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = Collider::sphere(glm::vec3(0.2, -0.3, 1.3), 0.5);
+
+        let contact = collide(&a, &b).unwrap();
+
+        assert_vec_near(contact.normal, glm::vec3(0.0, 0.0, 1.0));
+        assert!((contact.depth - 0.2).abs() < EPSILON);
+        assert!((glm::distance(&contact.point_on_b, &glm::vec3(0.2, -0.3, 1.3)) - 0.5).abs() < EPSILON);
+        assert!((contact.point_on_a.z - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_sphere_near_box_corner() {
+        let a = cube(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let corner = glm::vec3(1.0, 1.0, 1.0);
+        let outward = glm::normalize(&glm::vec3(1.0, 1.0, 1.0));
+
+        let apart = Collider::sphere(corner + outward * 0.55, 0.5);
+        let touching = Collider::sphere(corner + outward * 0.4, 0.5);
+
+        assert!(!intersects(&a, &apart));
+        let contact = collide(&a, &touching).unwrap();
+        assert_vec_near(contact.normal, outward);
+        assert!((contact.depth - 0.1).abs() < EPSILON);
+        assert_vec_near(contact.point_on_a, corner);
+    }
+
+    #[test]
+    fn test_capsule_and_sphere() {
+        let a = Collider::capsule(glm::vec3(0.0, 0.0, 0.0), glm::vec3(0.0, 0.0, 2.0), 0.5);
+        let side = Collider::sphere(glm::vec3(0.8, 0.0, 1.0), 0.5);
+        let above = Collider::sphere(glm::vec3(0.0, 0.0, 2.9), 0.5);
+        let apart = Collider::sphere(glm::vec3(1.1, 0.0, 1.0), 0.5);
+
+        let contact = collide(&a, &side).unwrap();
+        assert_vec_near(contact.normal, glm::vec3(1.0, 0.0, 0.0));
+        assert!((contact.depth - 0.2).abs() < EPSILON);
+
+        let contact = collide(&a, &above).unwrap();
+        assert_vec_near(contact.normal, glm::vec3(0.0, 0.0, 1.0));
+        assert!((contact.depth - 0.1).abs() < EPSILON);
+
+        assert!(!intersects(&a, &apart));
+    }
+
+    #[test]
+    fn test_capsule_lying_on_box() {
+        let ground = Collider::cuboid(glm::vec3(0.0, 0.0, -1.0), glm::vec3(5.0, 5.0, 1.0));
+        let capsule = Collider::capsule(glm::vec3(-1.0, 0.0, 0.4), glm::vec3(1.0, 0.0, 0.4), 0.5);
+
+        let contact = collide(&ground, &capsule).unwrap();
+
+        assert_vec_near(contact.normal, glm::vec3(0.0, 0.0, 1.0));
+        assert!((contact.depth - 0.1).abs() < EPSILON);
+        assert!(contact.point_on_b.x.abs() <= 1.0 + EPSILON);
+    }
+
+    #[test]
+    fn test_convex_hull_matches_cuboid() {
+        let hull = unit_cube_hull();
+        let cuboid = cube(glm::vec3(0.5, 0.5, 0.5), 0.5);
+        let other = Collider::sphere(glm::vec3(0.5, 1.3, 0.4), 0.4);
+
+        let from_hull = collide(&hull, &other).unwrap();
+        let from_cuboid = collide(&cuboid, &other).unwrap();
+
+        assert_vec_near(from_hull.normal, from_cuboid.normal);
+        assert!((from_hull.depth - from_cuboid.depth).abs() < EPSILON);
+        assert!((from_hull.depth - 0.1).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_tetrahedron_hull() {
+        let tetrahedron = Collider::convex_hull(vec![
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(0.0, 0.0, 1.0),
+        ]);
+        // The slanted face is at distance 1/sqrt(3) from the origin
+        let normal = glm::normalize(&glm::vec3(1.0, 1.0, 1.0));
+        let face_distance = 1.0 / 3.0_f32.sqrt();
+
+        let apart = Collider::sphere(normal * (face_distance + 0.3), 0.25);
+        let touching = Collider::sphere(normal * (face_distance + 0.2), 0.25);
+
+        assert!(!intersects(&tetrahedron, &apart));
+        let contact = collide(&tetrahedron, &touching).unwrap();
+        assert_vec_near(contact.normal, normal);
+        assert!((contact.depth - 0.05).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_swapping_colliders_flips_normal() {
+        let a = cube(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = Collider::sphere(glm::vec3(0.0, 1.6, 0.0), 0.8);
+
+        let forward = collide(&a, &b).unwrap();
+        let backward = collide(&b, &a).unwrap();
+
+        assert_vec_near(forward.normal, -backward.normal);
+        assert!((forward.depth - backward.depth).abs() < EPSILON);
+        assert_vec_near(forward.point_on_a, backward.point_on_b);
+        assert_vec_near(forward.point_on_b, backward.point_on_a);
+    }
+
+    #[test]
+    fn test_contact_points_span_the_penetration() {
+        let pairs = [
+            (cube(glm::vec3(0.0, 0.0, 0.0), 1.0), cube(glm::vec3(0.4, 1.7, -0.3), 1.0)),
+            (
+                Collider::sphere(glm::vec3(0.0, 0.0, 0.0), 1.0),
+                Collider::sphere(glm::vec3(0.6, -0.8, 0.9), 1.0),
+            ),
+            (
+                Collider::capsule(glm::vec3(0.0, 0.0, 0.0), glm::vec3(0.0, 0.0, 1.5), 0.4),
+                cube(glm::vec3(0.0, 0.7, 0.7), 0.5),
+            ),
+        ];
+
+        for (a, b) in &pairs {
+            let contact = collide(a, b).unwrap();
+            assert_vec_near(
+                contact.point_on_a - contact.point_on_b,
+                contact.normal * contact.depth,
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolving_along_normal_separates() {
+        let pairs = [
+            (cube(glm::vec3(0.0, 0.0, 0.0), 1.0), cube(glm::vec3(1.2, 0.9, 0.3), 0.7)),
+            (unit_cube_hull(), Collider::sphere(glm::vec3(0.9, 0.8, 1.1), 0.4)),
+            (
+                Collider::capsule(glm::vec3(0.0, 0.0, 0.0), glm::vec3(1.0, 1.0, 0.0), 0.3),
+                Collider::sphere(glm::vec3(0.5, 0.2, 0.1), 0.4),
+            ),
+            (
+                Collider::sphere(glm::vec3(0.0, 0.0, 0.0), 1.0),
+                cube(glm::vec3(0.2, 0.1, 0.0), 0.3),
+            ),
+        ];
+
+        for (a, b) in &pairs {
+            assert_resolves(a, b);
+        }
+    }
+
+    #[test]
+    fn test_moving_player_collider() {
+        let player = unit_cube_hull().compensate_position(glm::vec3(10.0, 10.0, 5.0));
+        let block = cube(glm::vec3(10.5, 10.5, 4.7), 0.5);
+
+        let contact = collide(&block, &player).unwrap();
+
+        assert_vec_near(contact.normal, glm::vec3(0.0, 0.0, 1.0));
+        assert!((contact.depth - 0.2).abs() < EPSILON);
+    }
 }
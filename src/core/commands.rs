@@ -0,0 +1,311 @@
+use anyhow::{anyhow, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ArgKind {
+    Integer,
+    Number,
+    // One of a fixed set of words, which tab completion offers
+    Choice(&'static [&'static str]),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ArgSpec {
+    pub(crate) name: &'static str,
+    pub(crate) kind: ArgKind,
+}
+
+impl ArgSpec {
+    pub(crate) const fn integer(name: &'static str) -> Self {
+        Self { name, kind: ArgKind::Integer }
+    }
+
+    pub(crate) const fn number(name: &'static str) -> Self {
+        Self { name, kind: ArgKind::Number }
+    }
+
+    pub(crate) const fn choice(name: &'static str, choices: &'static [&'static str]) -> Self {
+        Self { name, kind: ArgKind::Choice(choices) }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CommandSpec {
+    pub(crate) name: &'static str,
+    pub(crate) args: &'static [ArgSpec],
+    // Arguments after these may be left out, starting from the last one
+    pub(crate) required: usize,
+    pub(crate) help: &'static str,
+}
+
+impl CommandSpec {
+    // Like `tp <x> <y> <z>`, optional arguments in brackets
+    pub(crate) fn usage(&self) -> String {
+        let mut usage = self.name.to_string();
+        for (index, arg) in self.args.iter().enumerate() {
+            let name = match arg.kind {
+                ArgKind::Choice(choices) => choices.join("|"),
+                _ => arg.name.to_string(),
+            };
+            if index < self.required {
+                usage += &format!(" <{}>", name);
+            } else {
+                usage += &format!(" [{}]", name);
+            }
+        }
+        usage
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ArgValue {
+    Integer(i32),
+    Number(f32),
+    Choice(&'static str),
+}
+
+// A line that named a registered command with valid arguments
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Command {
+    pub(crate) name: &'static str,
+    pub(crate) args: Vec<ArgValue>,
+}
+
+impl Command {
+    pub(crate) fn integer(&self, index: usize) -> Option<i32> {
+        match self.args.get(index) {
+            Some(ArgValue::Integer(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    // Integers are numbers too
+    pub(crate) fn number(&self, index: usize) -> Option<f32> {
+        match self.args.get(index) {
+            Some(ArgValue::Number(value)) => Some(*value),
+            Some(ArgValue::Integer(value)) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn choice(&self, index: usize) -> Option<&'static str> {
+        match self.args.get(index) {
+            Some(ArgValue::Choice(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// The result of tab completing a line
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Completion {
+    // The line with its last word extended as far as every candidate agrees
+    pub(crate) line: String,
+    pub(crate) candidates: Vec<&'static str>,
+}
+
+// Knows the commands and checks lines against them, running them is up to the caller
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CommandRegistry {
+    commands: Vec<CommandSpec>,
+}
+
+impl CommandRegistry {
+    // A command registered twice replaces the earlier one
+    pub(crate) fn register(&mut self, spec: CommandSpec) {
+        self.commands.retain(|other| other.name != spec.name);
+        self.commands.push(spec);
+        self.commands.sort_by_key(|spec| spec.name);
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&CommandSpec> {
+        self.commands.iter().find(|spec| spec.name == name)
+    }
+
+    pub(crate) fn commands(&self) -> &[CommandSpec] {
+        &self.commands
+    }
+
+    // Ok(None) for a blank line
+    pub(crate) fn parse(&self, line: &str) -> Result<Option<Command>> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(None);
+        };
+        let spec = self.get(name).ok_or_else(|| anyhow!("Unknown command `{}`, `help` lists them", name))?;
+        let words = words.collect::<Vec<_>>();
+        if words.len() < spec.required || words.len() > spec.args.len() {
+            return Err(anyhow!("Usage: {}", spec.usage()));
+        }
+
+        let args = words
+            .iter()
+            .zip(spec.args)
+            .map(|(word, arg)| parse_arg(word, arg))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(Command { name: spec.name, args }))
+    }
+
+    // Completes command names and choice arguments, numbers have nothing to offer
+    pub(crate) fn complete(&self, line: &str) -> Completion {
+        let ends_word = line.is_empty() || line.ends_with(char::is_whitespace);
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (word, index) = match (ends_word, words.last()) {
+            (false, Some(word)) => (*word, words.len() - 1),
+            _ => ("", words.len()),
+        };
+
+        let options = if index == 0 {
+            self.commands.iter().map(|spec| spec.name).collect::<Vec<_>>()
+        } else {
+            match self.get(words[0]).and_then(|spec| spec.args.get(index - 1)) {
+                Some(ArgSpec { kind: ArgKind::Choice(choices), .. }) => choices.to_vec(),
+                _ => vec![],
+            }
+        };
+        let candidates = options.into_iter().filter(|option| option.starts_with(word)).collect::<Vec<_>>();
+
+        let mut line = line.to_string();
+        if let Some(first) = candidates.first() {
+            let shared = candidates.iter().fold(*first, |shared, candidate| common_prefix(shared, candidate));
+            line.push_str(&shared[word.len()..]);
+            if candidates.len() == 1 {
+                line.push(' ');
+            }
+        }
+        Completion { line, candidates }
+    }
+}
+
+fn parse_arg(word: &str, arg: &ArgSpec) -> Result<ArgValue> {
+    match arg.kind {
+        ArgKind::Integer => word
+            .parse()
+            .map(ArgValue::Integer)
+            .map_err(|_| anyhow!("<{}> has to be a whole number, not `{}`", arg.name, word)),
+        ArgKind::Number => match word.parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(ArgValue::Number(value)),
+            _ => Err(anyhow!("<{}> has to be a number, not `{}`", arg.name, word)),
+        },
+        ArgKind::Choice(choices) => choices
+            .iter()
+            .copied()
+            .find(|choice| *choice == word)
+            .map(ArgValue::Choice)
+            .ok_or_else(|| anyhow!("<{}> has to be one of {}, not `{}`", arg.name, choices.join(", "), word)),
+    }
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let length = a
+        .char_indices()
+        .zip(b.chars())
+        .take_while(|((_, a), b)| a == b)
+        .last()
+        .map_or(0, |((index, character), _)| index + character.len_utf8());
+    &a[..length]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Argument lists have to be constants to live as long as the specs
+    const POSITION: &[ArgSpec] = &[ArgSpec::number("x"), ArgSpec::number("y"), ArgSpec::number("z")];
+    const SEED: &[ArgSpec] = &[ArgSpec::integer("seed")];
+    const MODE: &[ArgSpec] = &[ArgSpec::choice("mode", &["on", "off"])];
+
+    fn registry() -> CommandRegistry {
+        let mut registry = CommandRegistry::default();
+        registry.register(CommandSpec {
+            name: "tp",
+            args: POSITION,
+            required: 3,
+            help: "Teleports",
+        });
+        registry.register(CommandSpec {
+            name: "seed",
+            args: SEED,
+            required: 0,
+            help: "Shows or sets the seed",
+        });
+        registry.register(CommandSpec {
+            name: "fly",
+            args: MODE,
+            required: 0,
+            help: "Toggles flying",
+        });
+        registry.register(CommandSpec {
+            name: "fill",
+            args: &[],
+            required: 0,
+            help: "Fills",
+        });
+        registry
+    }
+
+    #[test]
+    fn test_parses_arguments() {
+        let registry = registry();
+
+        let command = registry.parse("  tp 1 -2.5 3e1 ").unwrap().unwrap();
+        assert_eq!(command.name, "tp");
+        assert_eq!(command.number(0), Some(1.0));
+        assert_eq!(command.number(1), Some(-2.5));
+        assert_eq!(command.number(2), Some(30.0));
+
+        assert_eq!(registry.parse("seed").unwrap().unwrap().args, vec![]);
+        assert_eq!(registry.parse("seed 42").unwrap().unwrap().integer(0), Some(42));
+        assert_eq!(registry.parse("fly off").unwrap().unwrap().choice(0), Some("off"));
+        assert_eq!(registry.parse("   ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejects_bad_lines() {
+        let registry = registry();
+        let error = |line| registry.parse(line).unwrap_err().to_string();
+
+        assert_eq!(error("jump"), "Unknown command `jump`, `help` lists them");
+        assert_eq!(error("tp 1 2"), "Usage: tp <x> <y> <z>");
+        assert_eq!(error("seed 1 2"), "Usage: seed [seed]");
+        assert_eq!(error("tp 1 two 3"), "<y> has to be a number, not `two`");
+        assert_eq!(error("tp 1 NaN 3"), "<y> has to be a number, not `NaN`");
+        assert_eq!(error("seed 1.5"), "<seed> has to be a whole number, not `1.5`");
+        assert_eq!(error("fly maybe"), "<mode> has to be one of on, off, not `maybe`");
+    }
+
+    #[test]
+    fn test_usage_and_registration() {
+        let mut registry = registry();
+        assert_eq!(registry.get("fly").unwrap().usage(), "fly [on|off]");
+
+        // Sorted by name, and a second registration replaces the first
+        registry.register(CommandSpec {
+            name: "seed",
+            args: &[],
+            required: 0,
+            help: "Shows the seed",
+        });
+        let names = registry.commands().iter().map(|spec| spec.name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["fill", "fly", "seed", "tp"]);
+        assert_eq!(registry.get("seed").unwrap().help, "Shows the seed");
+    }
+
+    #[test]
+    fn test_tab_completion() {
+        let registry = registry();
+
+        // A single match is finished with a space for the next argument
+        assert_eq!(registry.complete("t"), Completion { line: "tp ".to_string(), candidates: vec!["tp"] });
+        // Several matches extend as far as they agree
+        assert_eq!(registry.complete("f"), Completion { line: "f".to_string(), candidates: vec!["fill", "fly"] });
+        assert_eq!(registry.complete("fl").line, "fly ");
+        assert_eq!(registry.complete("").candidates.len(), 4);
+
+        // Choices complete, numbers and unknown commands don't
+        assert_eq!(registry.complete("fly o").candidates, vec!["on", "off"]);
+        assert_eq!(registry.complete("fly of").line, "fly off ");
+        assert!(registry.complete("tp 1").candidates.is_empty());
+        assert!(registry.complete("jump ").candidates.is_empty());
+        assert_eq!(registry.complete("tp 1").line, "tp 1");
+    }
+}
@@ -0,0 +1,344 @@
+use crate::core::commands::{ArgSpec, Command, CommandRegistry, CommandSpec};
+use crate::core::simulation::Simulation;
+use crate::core::stats::{FrameStats, PlayerStats};
+use crate::terrain::chunk_coord::ChunkCoord;
+use crate::terrain::voxel::voxel_types::VOXEL_TYPES;
+use crate::terrain::world::VoxelId;
+use anyhow::{anyhow, Result};
+use nalgebra_glm as glm;
+
+// Bigger fills are refused, they'd stall the frame they run in
+const MAX_FILL_VOXELS: i64 = 64 * 64 * 64;
+const MAX_MOVE_SPEED: f32 = 100.0;
+
+const COMMANDS: [CommandSpec; 10] = [
+    CommandSpec {
+        name: "clear",
+        args: &[],
+        required: 0,
+        help: "Empties the console",
+    },
+    CommandSpec {
+        name: "fill",
+        args: &[
+            ArgSpec::integer("x1"),
+            ArgSpec::integer("y1"),
+            ArgSpec::integer("z1"),
+            ArgSpec::integer("x2"),
+            ArgSpec::integer("y2"),
+            ArgSpec::integer("z2"),
+            ArgSpec::integer("voxel"),
+        ],
+        required: 7,
+        help: "Sets every voxel of the box between two corners",
+    },
+    CommandSpec {
+        name: "fly",
+        args: &[ArgSpec::choice("mode", &["on", "off"])],
+        required: 0,
+        help: "Turns gravity off for the player, toggles without a mode",
+    },
+    CommandSpec {
+        name: "help",
+        args: &[ArgSpec::choice("command", &["clear", "fill", "fly", "help", "regen", "seed", "setblock", "speed", "stats", "tp"])],
+        required: 0,
+        help: "Lists the commands or explains one",
+    },
+    CommandSpec {
+        name: "regen",
+        args: &[ArgSpec::integer("x"), ArgSpec::integer("y"), ArgSpec::integer("z")],
+        required: 0,
+        help: "Generates a chunk again, the player's one without coordinates",
+    },
+    CommandSpec {
+        name: "seed",
+        args: &[ArgSpec::integer("seed")],
+        required: 0,
+        help: "Shows or sets the world seed, chunks generated afterwards use it",
+    },
+    CommandSpec {
+        name: "setblock",
+        args: &[ArgSpec::integer("x"), ArgSpec::integer("y"), ArgSpec::integer("z"), ArgSpec::integer("voxel")],
+        required: 4,
+        help: "Sets one voxel",
+    },
+    CommandSpec {
+        name: "speed",
+        args: &[ArgSpec::number("speed")],
+        required: 0,
+        help: "Shows or sets the player's move speed",
+    },
+    CommandSpec {
+        name: "stats",
+        args: &[],
+        required: 0,
+        help: "Prints frame, player and world statistics",
+    },
+    CommandSpec {
+        name: "tp",
+        args: &[ArgSpec::number("x"), ArgSpec::number("y"), ArgSpec::number("z")],
+        required: 3,
+        help: "Moves the player",
+    },
+];
+
+// What running a command asks of the console
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum CommandOutput {
+    Print(String),
+    Clear,
+}
+
+pub(crate) fn console_commands() -> CommandRegistry {
+    let mut registry = CommandRegistry::default();
+    for spec in COMMANDS {
+        registry.register(spec);
+    }
+    registry
+}
+
+// Changes the simulation directly, outside of ticks and input recordings
+pub(crate) fn run_command(
+    command: &Command,
+    registry: &CommandRegistry,
+    simulation: &mut Simulation,
+    frames: &FrameStats,
+) -> Result<CommandOutput> {
+    let output = match command.name {
+        "clear" => return Ok(CommandOutput::Clear),
+        "help" => help(command, registry),
+        "tp" => {
+            let position = glm::vec3(number(command, 0)?, number(command, 1)?, number(command, 2)?);
+            if !simulation.teleport_player(position) {
+                return Err(anyhow!("There's no player to move"));
+            }
+            format!("Teleported to {:.2} {:.2} {:.2}", position.x, position.y, position.z)
+        }
+        "setblock" => {
+            let position = glm::vec3(integer(command, 0)?, integer(command, 1)?, integer(command, 2)?);
+            let voxel_id = voxel_id(command, 3)?;
+            simulation.world.set_voxel_id(position.x, position.y, position.z, voxel_id);
+            format!("Set {} {} {} to {}", position.x, position.y, position.z, voxel_id)
+        }
+        "fill" => fill(command, simulation)?,
+        "seed" => match command.integer(0) {
+            // Negative seeds are as good as any other
+            Some(seed) => {
+                simulation.world.set_seed(seed as u32);
+                format!("Seed set to {}, `regen` applies it to loaded chunks", seed as u32)
+            }
+            None => format!("Seed: {}", simulation.world.seed()),
+        },
+        "regen" => {
+            let coord = match command.args.len() {
+                0 => player_stats(simulation)?.chunk(),
+                3 => ChunkCoord { x: integer(command, 0)?, y: integer(command, 1)?, z: integer(command, 2)? },
+                _ => return Err(anyhow!("Usage: regen [<x> <y> <z>]")),
+            };
+            simulation.world.generate_chunk_voxel_map(&coord);
+            format!("Regenerated chunk {} {} {}", coord.x, coord.y, coord.z)
+        }
+        "speed" => {
+            let mut player = simulation.player_mut().ok_or_else(|| anyhow!("There's no player"))?;
+            match command.number(0) {
+                Some(speed) if speed <= 0.0 || speed > MAX_MOVE_SPEED => {
+                    return Err(anyhow!("<speed> has to be above 0 and at most {}", MAX_MOVE_SPEED));
+                }
+                Some(speed) => {
+                    player.move_speed = speed;
+                    format!("Move speed set to {}", speed)
+                }
+                None => format!("Move speed: {}", player.move_speed),
+            }
+        }
+        "fly" => {
+            let mut player = simulation.player_mut().ok_or_else(|| anyhow!("There's no player"))?;
+            player.is_flying = match command.choice(0) {
+                Some(mode) => mode == "on",
+                None => !player.is_flying,
+            };
+            format!("Flying {}", if player.is_flying { "on" } else { "off" })
+        }
+        "stats" => stats(simulation, frames),
+        name => return Err(anyhow!("`{}` is registered but does nothing", name)),
+    };
+    Ok(CommandOutput::Print(output))
+}
+
+fn help(command: &Command, registry: &CommandRegistry) -> String {
+    match command.choice(0).and_then(|name| registry.get(name)) {
+        Some(spec) => format!("{}\n{}", spec.usage(), spec.help),
+        None => registry
+            .commands()
+            .iter()
+            .map(|spec| format!("{} - {}", spec.usage(), spec.help))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+fn fill(command: &Command, simulation: &mut Simulation) -> Result<String> {
+    let first = glm::vec3(integer(command, 0)?, integer(command, 1)?, integer(command, 2)?);
+    let second = glm::vec3(integer(command, 3)?, integer(command, 4)?, integer(command, 5)?);
+    let voxel_id = voxel_id(command, 6)?;
+    let min = glm::min2(&first, &second);
+    let max = glm::max2(&first, &second);
+
+    // Wide enough for corners at both ends of the i32 range
+    let length = |axis: usize| max[axis] as i64 - min[axis] as i64 + 1;
+    let count = length(0).saturating_mul(length(1)).saturating_mul(length(2));
+    if count > MAX_FILL_VOXELS {
+        return Err(anyhow!("That's {} voxels, fill at most {} at once", count, MAX_FILL_VOXELS));
+    }
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                simulation.world.set_voxel_id(x, y, z, voxel_id);
+            }
+        }
+    }
+    Ok(format!("Filled {} voxels with {}", count, voxel_id))
+}
+
+fn stats(simulation: &Simulation, frames: &FrameStats) -> String {
+    let world = simulation.world.stats();
+    let mut lines = vec![
+        format!("{:.0} fps, {:.2} ms per frame", frames.fps(), frames.average_frame_time() * 1000.0),
+        format!("Tick {}", simulation.tick_count()),
+    ];
+    if let Ok(player) = player_stats(simulation) {
+        let chunk = player.chunk();
+        lines.push(format!(
+            "Player at {:.2} {:.2} {:.2} in chunk {} {} {}",
+            player.position.x, player.position.y, player.position.z, chunk.x, chunk.y, chunk.z
+        ));
+    }
+    lines.push(format!("{} chunks loaded, {} meshed", world.loaded_chunks, world.meshed_chunks));
+    lines.join("\n")
+}
+
+fn player_stats(simulation: &Simulation) -> Result<PlayerStats> {
+    let transform = simulation.player_transform().ok_or_else(|| anyhow!("There's no player"))?;
+    let player = simulation.player().ok_or_else(|| anyhow!("There's no player"))?;
+    Ok(PlayerStats { position: transform.position, yaw: player.yaw, pitch: player.pitch })
+}
+
+// The registry checked the arguments, a missing one means the spec and this file disagree
+fn integer(command: &Command, index: usize) -> Result<i32> {
+    command.integer(index).ok_or_else(|| anyhow!("`{}` is missing argument {}", command.name, index + 1))
+}
+
+fn number(command: &Command, index: usize) -> Result<f32> {
+    command.number(index).ok_or_else(|| anyhow!("`{}` is missing argument {}", command.name, index + 1))
+}
+
+fn voxel_id(command: &Command, index: usize) -> Result<VoxelId> {
+    let id = integer(command, index)?;
+    if id < 0 || id as usize >= VOXEL_TYPES.len() {
+        return Err(anyhow!("<voxel> has to be between 0 and {}, not {}", VOXEL_TYPES.len() - 1, id));
+    }
+    Ok(id as VoxelId)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::collider::Collider;
+    use crate::core::transform::Transform;
+    use crate::player::player_data::PlayerData;
+    use crate::terrain::world::World;
+
+    struct Harness {
+        registry: CommandRegistry,
+        simulation: Simulation,
+        frames: FrameStats,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let mut simulation = Simulation::new(World::load(glm::vec3(0.0, 0.0, 0.0)), 60);
+            let transform = Transform { position: glm::vec3(0.5, 0.5, 8.0), ..Default::default() };
+            simulation.spawn_player(PlayerData::default(), transform, Collider::default());
+            Self { registry: console_commands(), simulation, frames: FrameStats::default() }
+        }
+
+        fn run(&mut self, line: &str) -> Result<String> {
+            let command = self.registry.parse(line)?.unwrap();
+            match run_command(&command, &self.registry, &mut self.simulation, &self.frames)? {
+                CommandOutput::Print(text) => Ok(text),
+                CommandOutput::Clear => Ok(String::new()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_command_is_registered_and_helped() {
+        let mut harness = Harness::new();
+        let help = harness.run("help").unwrap();
+        assert_eq!(help.lines().count(), COMMANDS.len());
+        assert!(help.lines().any(|line| line == "tp <x> <y> <z> - Moves the player"));
+        assert_eq!(harness.run("help fly").unwrap(), "fly [on|off]\nTurns gravity off for the player, toggles without a mode");
+
+        let names = COMMANDS.iter().map(|spec| spec.name).collect::<Vec<_>>();
+        assert_eq!(harness.registry.complete("help ").candidates, names);
+        assert_eq!(harness.registry.parse("clear").unwrap().map(|command| command.name), Some("clear"));
+    }
+
+    #[test]
+    fn test_player_commands() {
+        let mut harness = Harness::new();
+
+        harness.run("tp 10 -5 20.5").unwrap();
+        assert_eq!(harness.simulation.player_transform().unwrap().position, glm::vec3(10.0, -5.0, 20.5));
+
+        assert_eq!(harness.run("speed 12").unwrap(), "Move speed set to 12");
+        assert_eq!(harness.simulation.player().unwrap().move_speed, 12.0);
+        assert!(harness.run("speed 0").is_err());
+        assert!(harness.run("speed 500").is_err());
+        assert_eq!(harness.run("speed").unwrap(), "Move speed: 12");
+
+        assert_eq!(harness.run("fly").unwrap(), "Flying on");
+        assert!(harness.simulation.player().unwrap().is_flying);
+        assert_eq!(harness.run("fly on").unwrap(), "Flying on");
+        assert_eq!(harness.run("fly off").unwrap(), "Flying off");
+        assert!(!harness.simulation.player().unwrap().is_flying);
+    }
+
+    #[test]
+    fn test_voxel_commands() {
+        let mut harness = Harness::new();
+
+        harness.run("setblock 1 2 -3 2").unwrap();
+        assert_eq!(harness.simulation.world.get_voxel_id(1, 2, -3), 2);
+        assert_eq!(
+            harness.run("setblock 0 0 0 255").unwrap_err().to_string(),
+            format!("<voxel> has to be between 0 and {}, not 255", VOXEL_TYPES.len() - 1)
+        );
+
+        // Corners in any order
+        assert_eq!(harness.run("fill 2 2 1 0 0 0 1").unwrap(), "Filled 18 voxels with 1");
+        assert_eq!(harness.simulation.world.get_voxel_id(0, 2, 1), 1);
+        assert_eq!(harness.simulation.world.get_voxel_id(2, 0, 0), 1);
+        assert_eq!(harness.simulation.world.get_voxel_id(3, 0, 0), 0);
+        assert!(harness.run("fill 0 0 0 1000 1000 1000 1").is_err());
+
+        // Console edits aren't gameplay edits
+        assert!(harness.simulation.world.edits().is_empty());
+    }
+
+    #[test]
+    fn test_world_commands() {
+        let mut harness = Harness::new();
+
+        assert_eq!(harness.run("seed").unwrap(), "Seed: 0");
+        harness.run("seed -1").unwrap();
+        assert_eq!(harness.simulation.world.seed(), u32::MAX);
+
+        assert_eq!(harness.run("regen").unwrap(), "Regenerated chunk 0 0 0");
+        assert_eq!(harness.run("regen 1 -2 0").unwrap(), "Regenerated chunk 1 -2 0");
+        assert!(harness.run("regen 1").is_err());
+
+        let stats = harness.run("stats").unwrap();
+        assert!(stats.contains("Player at 0.50 0.50 8.00 in chunk 0 0 0"), "{}", stats);
+    }
+}
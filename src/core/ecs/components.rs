@@ -0,0 +1,14 @@
+use nalgebra_glm as glm;
+
+// Transform and Collider are used as components directly
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Velocity(pub(crate) glm::Vec3);
+
+// What the controlling player asked for this tick, consumed by movement
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct PlayerInput {
+    pub(crate) wish_direction: glm::Vec3,
+    pub(crate) wants_jump: bool,
+    pub(crate) is_sneaking: bool,
+}
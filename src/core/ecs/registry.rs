@@ -0,0 +1,332 @@
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+// Index into the component storages, the generation tells apart reused indices
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub(crate) fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+// Components of one type, stored by entity index
+pub(crate) struct ComponentStorage<T> {
+    slots: Vec<Option<(u32, T)>>,
+}
+
+impl<T> ComponentStorage<T> {
+    fn new() -> Self {
+        Self { slots: vec![] }
+    }
+
+    pub(crate) fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index())? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index())? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.slots.iter().flatten().count()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let (generation, component) = slot.as_ref()?;
+            Some((
+                Entity {
+                    index: index as u32,
+                    generation: *generation,
+                },
+                component,
+            ))
+        })
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            let (generation, component) = slot.as_mut()?;
+            Some((
+                Entity {
+                    index: index as u32,
+                    generation: *generation,
+                },
+                component,
+            ))
+        })
+    }
+
+    fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if self.slots.len() <= entity.index() {
+            self.slots.resize_with(entity.index() + 1, || None);
+        }
+        let previous = self.slots[entity.index()].replace((entity.generation, component));
+        match previous {
+            Some((generation, component)) if generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slots.get_mut(entity.index())?;
+        match slot {
+            Some((generation, _)) if *generation == entity.generation => {
+                slot.take().map(|(_, component)| component)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Lets the registry drop components of a despawned entity without knowing their type
+trait AnyStorage {
+    fn remove_entity(&mut self, entity: Entity);
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyStorage for ComponentStorage<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Owns every entity and its components. Storages are borrowed separately, so a system can
+// hold several component types mutably at once; borrowing the same type twice panics.
+#[derive(Default)]
+pub(crate) struct Registry {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free_indices: Vec<u32>,
+    storages: HashMap<TypeId, RefCell<Box<dyn AnyStorage>>>,
+}
+
+impl Debug for Registry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registry")
+            .field("entities", &self.len())
+            .field("storages", &self.storages.len())
+            .finish()
+    }
+}
+
+impl Registry {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free_indices.pop() {
+            self.alive[index as usize] = true;
+            return Entity {
+                index,
+                generation: self.generations[index as usize],
+            };
+        }
+
+        let index = self.generations.len() as u32;
+        self.generations.push(0);
+        self.alive.push(true);
+        Entity {
+            index,
+            generation: 0,
+        }
+    }
+
+    pub(crate) fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        for storage in self.storages.values() {
+            storage.borrow_mut().remove_entity(entity);
+        }
+
+        let index = entity.index();
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free_indices.push(entity.index);
+        true
+    }
+
+    pub(crate) fn is_alive(&self, entity: Entity) -> bool {
+        self.alive.get(entity.index()).copied().unwrap_or(false)
+            && self.generations[entity.index()] == entity.generation
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.alive.iter().filter(|alive| **alive).count()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn entities(&self) -> Vec<Entity> {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive)
+            .map(|(index, _)| Entity {
+                index: index as u32,
+                generation: self.generations[index],
+            })
+            .collect()
+    }
+
+    // Returns the replaced component, dead entities are ignored
+    pub(crate) fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        let storage = self
+            .storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(ComponentStorage::<T>::new())));
+        storage
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .unwrap()
+            .insert(entity, component)
+    }
+
+    pub(crate) fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storage_mut::<T>()?.remove(entity)
+    }
+
+    pub(crate) fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.storage::<T>()
+            .is_some_and(|storage| storage.contains(entity))
+    }
+
+    pub(crate) fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.storage::<T>()?, |storage| storage.get(entity)).ok()
+    }
+
+    pub(crate) fn get_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.storage_mut::<T>()?, |storage| storage.get_mut(entity)).ok()
+    }
+
+    pub(crate) fn entities_with<T: 'static>(&self) -> Vec<Entity> {
+        self.storage::<T>()
+            .map(|storage| storage.iter().map(|(entity, _)| entity).collect())
+            .unwrap_or_default()
+    }
+
+    // None until a component of this type has been inserted
+    pub(crate) fn storage<T: 'static>(&self) -> Option<Ref<'_, ComponentStorage<T>>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        Some(Ref::map(storage.borrow(), |storage| {
+            storage.as_any().downcast_ref::<ComponentStorage<T>>().unwrap()
+        }))
+    }
+
+    pub(crate) fn storage_mut<T: 'static>(&self) -> Option<RefMut<'_, ComponentStorage<T>>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        Some(RefMut::map(storage.borrow_mut(), |storage| {
+            storage.as_any_mut().downcast_mut::<ComponentStorage<T>>().unwrap()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut registry = Registry::new();
+        let entity = registry.spawn();
+
+        assert!(registry.insert(entity, Health(10)).is_none());
+        assert_eq!(registry.insert(entity, Health(20)), Some(Health(10)));
+        assert_eq!(*registry.get::<Health>(entity).unwrap(), Health(20));
+        assert!(registry.get::<Name>(entity).is_none());
+
+        registry.get_mut::<Health>(entity).unwrap().0 -= 5;
+        assert_eq!(registry.remove::<Health>(entity), Some(Health(15)));
+        assert!(!registry.has::<Health>(entity));
+    }
+
+    #[test]
+    fn test_despawn_drops_components_and_reuses_index() {
+        let mut registry = Registry::new();
+        let first = registry.spawn();
+        registry.insert(first, Health(1));
+        registry.insert(first, Name("first"));
+
+        assert!(registry.despawn(first));
+        assert!(!registry.despawn(first));
+        let second = registry.spawn();
+
+        assert_eq!(first.index(), second.index());
+        assert_ne!(first, second);
+        assert!(!registry.is_alive(first));
+        assert!(registry.get::<Health>(second).is_none());
+        assert!(registry.insert(first, Health(2)).is_none());
+        assert!(registry.get::<Health>(first).is_none());
+        assert_eq!(registry.entities(), vec![second]);
+    }
+
+    #[test]
+    fn test_borrowing_several_storages() {
+        let mut registry = Registry::new();
+        for index in 0..4 {
+            let entity = registry.spawn();
+            registry.insert(entity, Health(index));
+            if index % 2 == 0 {
+                registry.insert(entity, Name("even"));
+            }
+        }
+
+        let names = registry.storage::<Name>().unwrap();
+        let mut healths = registry.storage_mut::<Health>().unwrap();
+        for (entity, _) in names.iter() {
+            healths.get_mut(entity).unwrap().0 += 100;
+        }
+
+        let values = healths.iter().map(|(_, health)| health.0).collect::<Vec<_>>();
+        assert_eq!(values, vec![100, 1, 102, 3]);
+        assert_eq!(names.len(), 2);
+    }
+}
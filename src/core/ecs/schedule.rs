@@ -0,0 +1,111 @@
+use crate::core::ecs::registry::Registry;
+use crate::terrain::world::World;
+use crate::FrameData;
+
+// Systems run stage by stage, in insertion order within a stage
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum SystemStage {
+    Input,
+    Update,
+    Physics,
+    Late,
+}
+
+// Everything outside the registry a system may touch during a tick
+pub(crate) struct SystemContext<'a> {
+    pub(crate) world: &'a mut World,
+    pub(crate) frame_data: FrameData<'a>,
+}
+
+pub(crate) trait System {
+    fn name(&self) -> &str;
+
+    fn run(&mut self, registry: &mut Registry, context: &mut SystemContext);
+}
+
+#[derive(Default)]
+pub(crate) struct Schedule {
+    systems: Vec<(SystemStage, Box<dyn System>)>,
+}
+
+impl Schedule {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn add_system(&mut self, stage: SystemStage, system: Box<dyn System>) {
+        let position = self
+            .systems
+            .iter()
+            .position(|(other, _)| *other > stage)
+            .unwrap_or(self.systems.len());
+        self.systems.insert(position, (stage, system));
+    }
+
+    pub(crate) fn with_system(mut self, stage: SystemStage, system: Box<dyn System>) -> Self {
+        self.add_system(stage, system);
+        self
+    }
+
+    pub(crate) fn system_names(&self) -> Vec<&str> {
+        self.systems.iter().map(|(_, system)| system.name()).collect()
+    }
+
+    pub(crate) fn run(&mut self, registry: &mut Registry, context: &mut SystemContext) {
+        for (_, system) in &mut self.systems {
+            system.run(registry, context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controlls::input_manager::InputManager;
+    use nalgebra_glm as glm;
+
+    struct Log(Vec<String>);
+
+    struct Push(&'static str);
+
+    impl System for Push {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&mut self, registry: &mut Registry, context: &mut SystemContext) {
+            let entity = registry.entities_with::<Log>()[0];
+            registry.get_mut::<Log>(entity).unwrap().0.push(self.0.to_string());
+        }
+    }
+
+    #[test]
+    fn test_runs_in_stage_order() {
+        let mut schedule = Schedule::new()
+            .with_system(SystemStage::Physics, Box::new(Push("physics")))
+            .with_system(SystemStage::Input, Box::new(Push("input")))
+            .with_system(SystemStage::Late, Box::new(Push("late")))
+            .with_system(SystemStage::Physics, Box::new(Push("physics 2")))
+            .with_system(SystemStage::Update, Box::new(Push("update")));
+
+        let mut registry = Registry::new();
+        let entity = registry.spawn();
+        registry.insert(entity, Log(vec![]));
+
+        let input_manager = InputManager::new();
+        let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        let mut context = SystemContext {
+            world: &mut world,
+            frame_data: FrameData {
+                delta_time: 0.05,
+                frame_count: 1,
+                input_manager: &input_manager,
+            },
+        };
+        schedule.run(&mut registry, &mut context);
+
+        let expected = vec!["input", "update", "physics", "physics 2", "late"];
+        assert_eq!(registry.get::<Log>(entity).unwrap().0, expected);
+        assert_eq!(schedule.system_names(), expected);
+    }
+}
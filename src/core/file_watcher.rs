@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// Often enough that saving a file feels instant, rare enough that the checks cost nothing
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Notices files changing on disk by comparing their modification times
+#[derive(Clone, Debug)]
+pub(crate) struct FileWatcher {
+    // None while the file doesn't exist
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new(POLL_INTERVAL)
+    }
+}
+
+impl FileWatcher {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            files: Vec::new(),
+            interval,
+            last_poll: None,
+        }
+    }
+
+    // Changes from now on are reported, watching a file twice does nothing
+    pub(crate) fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        if self.files.iter().all(|(watched, _)| watched != path) {
+            self.files.push((path.to_path_buf(), modified(path)));
+        }
+    }
+
+    // The files changed since the last check, at most once every interval
+    pub(crate) fn poll(&mut self, now: Instant) -> Vec<PathBuf> {
+        if self.last_poll.is_some_and(|last_poll| now.duration_since(last_poll) < self.interval) {
+            return Vec::new();
+        }
+        self.last_poll = Some(now);
+        self.changed()
+    }
+
+    // The files changed since the last check. Created and deleted files count as changed.
+    pub(crate) fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, last_modified) in &mut self.files {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::File;
+
+    fn touch(path: &Path, seconds: u64) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)).unwrap();
+    }
+
+    #[test]
+    fn test_reports_changed_files() {
+        let directory = env::temp_dir().join(format!("file_watcher_test_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let (shader, texture) = (directory.join("shader.frag"), directory.join("blocks.png"));
+        fs::write(&shader, "void main() {}").unwrap();
+        fs::write(&texture, [0u8; 4]).unwrap();
+        touch(&shader, 1_000);
+        touch(&texture, 1_000);
+
+        let mut watcher = FileWatcher::default();
+        watcher.watch(&shader);
+        watcher.watch(&texture);
+        watcher.watch(&shader);
+        assert!(watcher.changed().is_empty());
+
+        touch(&shader, 2_000);
+        assert_eq!(watcher.changed(), vec![shader.clone()]);
+        // Reported once
+        assert!(watcher.changed().is_empty());
+
+        fs::remove_file(&texture).unwrap();
+        assert_eq!(watcher.changed(), vec![texture.clone()]);
+        fs::write(&texture, [0u8; 4]).unwrap();
+        assert_eq!(watcher.changed(), vec![texture]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_polls_once_per_interval() {
+        let directory = env::temp_dir().join(format!("file_watcher_poll_test_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("sky-shader.frag");
+        fs::write(&path, "void main() {}").unwrap();
+        touch(&path, 1_000);
+
+        let mut watcher = FileWatcher::new(Duration::from_secs(1));
+        watcher.watch(&path);
+        let start = Instant::now();
+        assert!(watcher.poll(start).is_empty());
+
+        touch(&path, 2_000);
+        assert!(watcher.poll(start + Duration::from_millis(500)).is_empty());
+        assert_eq!(watcher.poll(start + Duration::from_secs(1)), vec![path]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
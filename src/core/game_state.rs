@@ -0,0 +1,151 @@
+// Screens reached from the pause menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Menu {
+    Settings,
+    Keybinds,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum GameState {
+    // The starting world is still being built
+    #[default]
+    Loading,
+    Playing,
+    Paused,
+    InMenu(Menu),
+    // The developer console is open over the stopped game
+    Console,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GameEvent {
+    Loaded,
+    Pause,
+    Resume,
+    Open(Menu),
+    // One screen up, from the pause menu that's back into the game
+    Back,
+    ToggleConsole,
+}
+
+// What runs while the game is in a state, everything else is skipped that frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct StateSystems {
+    // Ticks the world and the player, which is also the only place gameplay actions are read
+    pub(crate) simulation: bool,
+    // Clicking into the window may capture the cursor for looking around
+    pub(crate) cursor_grab: bool,
+    // A screen is drawn over the world and takes the cursor
+    pub(crate) menu: bool,
+}
+
+impl GameState {
+    // Events that make no sense in the current state leave it unchanged
+    pub(crate) fn transition(self, event: GameEvent) -> Self {
+        match (self, event) {
+            (GameState::Loading, GameEvent::Loaded) => GameState::Playing,
+            (GameState::Playing, GameEvent::Pause) => GameState::Paused,
+            (GameState::Paused | GameState::InMenu(_), GameEvent::Resume) => GameState::Playing,
+            (GameState::Paused, GameEvent::Back) => GameState::Playing,
+            (GameState::Paused | GameState::InMenu(_), GameEvent::Open(menu)) => GameState::InMenu(menu),
+            (GameState::InMenu(Menu::Settings), GameEvent::Back) => GameState::Paused,
+            (GameState::InMenu(Menu::Keybinds), GameEvent::Back) => GameState::InMenu(Menu::Settings),
+            (GameState::Playing, GameEvent::ToggleConsole) => GameState::Console,
+            (GameState::Console, GameEvent::ToggleConsole | GameEvent::Back | GameEvent::Resume) => GameState::Playing,
+            (state, _) => state,
+        }
+    }
+
+    // The pause key pauses the game and backs out of menus
+    pub(crate) fn pause_event(self) -> GameEvent {
+        match self {
+            GameState::Playing => GameEvent::Pause,
+            _ => GameEvent::Back,
+        }
+    }
+
+    pub(crate) fn systems(self) -> StateSystems {
+        match self {
+            GameState::Loading => StateSystems { simulation: false, cursor_grab: false, menu: true },
+            GameState::Playing => StateSystems { simulation: true, cursor_grab: true, menu: false },
+            GameState::Paused | GameState::InMenu(_) | GameState::Console => {
+                StateSystems { simulation: false, cursor_grab: false, menu: true }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        let state = GameState::default().transition(GameEvent::Loaded);
+        assert_eq!(state, GameState::Playing);
+
+        let paused = state.transition(GameEvent::Pause);
+        assert_eq!(paused, GameState::Paused);
+        assert_eq!(paused.transition(GameEvent::Resume), GameState::Playing);
+
+        let settings = paused.transition(GameEvent::Open(Menu::Settings));
+        let keybinds = settings.transition(GameEvent::Open(Menu::Keybinds));
+        assert_eq!(keybinds, GameState::InMenu(Menu::Keybinds));
+        assert_eq!(keybinds.transition(GameEvent::Resume), GameState::Playing);
+    }
+
+    #[test]
+    fn test_back_walks_up_the_menus() {
+        let mut state = GameState::InMenu(Menu::Keybinds);
+        let mut visited = vec![];
+        for _ in 0..3 {
+            state = state.transition(state.pause_event());
+            visited.push(state);
+        }
+        assert_eq!(
+            visited,
+            vec![GameState::InMenu(Menu::Settings), GameState::Paused, GameState::Playing]
+        );
+        // Once playing the same key pauses again
+        assert_eq!(state.transition(state.pause_event()), GameState::Paused);
+    }
+
+    #[test]
+    fn test_console_opens_over_the_game() {
+        let console = GameState::Playing.transition(GameEvent::ToggleConsole);
+        assert_eq!(console, GameState::Console);
+        assert_eq!(console.transition(GameEvent::ToggleConsole), GameState::Playing);
+        // The pause key closes it instead of pausing
+        assert_eq!(console.transition(console.pause_event()), GameState::Playing);
+    }
+
+    #[test]
+    fn test_invalid_events_keep_the_state() {
+        assert_eq!(GameState::Loading.transition(GameEvent::Pause), GameState::Loading);
+        assert_eq!(GameState::Loading.transition(GameEvent::Open(Menu::Settings)), GameState::Loading);
+        assert_eq!(GameState::Playing.transition(GameEvent::Loaded), GameState::Playing);
+        assert_eq!(GameState::Playing.transition(GameEvent::Open(Menu::Keybinds)), GameState::Playing);
+        assert_eq!(GameState::Paused.transition(GameEvent::Pause), GameState::Paused);
+        assert_eq!(GameState::Paused.transition(GameEvent::ToggleConsole), GameState::Paused);
+        assert_eq!(GameState::Console.transition(GameEvent::Pause), GameState::Console);
+    }
+
+    #[test]
+    fn test_only_playing_runs_the_simulation() {
+        let states = [
+            GameState::Loading,
+            GameState::Playing,
+            GameState::Paused,
+            GameState::InMenu(Menu::Settings),
+            GameState::InMenu(Menu::Keybinds),
+            GameState::Console,
+        ];
+        for state in states {
+            let systems = state.systems();
+            let playing = state == GameState::Playing;
+            assert_eq!(systems.simulation, playing, "{:?}", state);
+            assert_eq!(systems.cursor_grab, playing, "{:?}", state);
+            assert_eq!(systems.menu, !playing, "{:?}", state);
+        }
+    }
+}
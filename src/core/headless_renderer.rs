@@ -0,0 +1,409 @@
+use crate::core::app_data::AppData;
+use crate::core::camera::Camera;
+use crate::core::settings::Settings;
+use crate::core::time_of_day::WorldClock;
+use crate::graphics::command_buffers::create_command_buffers;
+use crate::graphics::command_pool::create_command_pools;
+use crate::graphics::depth_objects::create_depth_objects;
+use crate::graphics::descriptors::{create_descriptor_pool, create_descriptor_sets};
+use crate::graphics::framebuffers::create_framebuffers;
+use crate::graphics::hot_reload::BLOCK_TEXTURE;
+use crate::graphics::instance::create_instance;
+use crate::graphics::logical_device::create_logical_device;
+use crate::graphics::physical_device::pick_physical_device;
+use crate::graphics::pipeline::{create_descriptor_set_layout, create_pipeline, create_render_pass};
+use crate::graphics::buffers::create_uniform_buffers;
+use crate::graphics::screenshot::{read_image, save_png, to_rgba};
+use crate::graphics::shared_buffers::create_device_local_buffer;
+use crate::graphics::shared_images::create_image;
+use crate::graphics::shadow_maps::{create_shadow_objects, destroy_shadow_objects, record_shadow_passes, ShadowCaster};
+use crate::graphics::sky_pipeline::create_sky_pipeline;
+use crate::graphics::shared_textures::{create_texture_image_from_path, create_texture_image_view};
+use crate::graphics::swapchain::create_swapchain_image_views;
+use crate::graphics::texture_samplers::create_world_texture_sampler;
+use crate::graphics::uniform_buffer_object::UniformBufferObject;
+use crate::terrain::chunk_coord::ChunkCoord;
+use crate::terrain::world::World;
+use anyhow::{anyhow, Result};
+use log::info;
+use nalgebra_glm as glm;
+use std::mem::size_of;
+use std::path::Path;
+use std::ptr::copy_nonoverlapping as memcpy;
+use vulkanalia::loader::{LibloadingLoader, LIBRARY};
+use vulkanalia::prelude::v1_0::*;
+use vulkanalia::vk::ExtDebugUtilsExtension;
+
+// The swapchain format the window usually gets, so screenshots and offscreen renders look alike
+const OFFSCREEN_FORMAT: vk::Format = vk::Format::B8G8R8A8_SRGB;
+
+#[derive(Debug)]
+struct ChunkBuffers {
+    vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+    index_count: u32,
+    model: glm::Mat4,
+}
+
+// Renders the world into an offscreen colour image instead of a window, for tools and image
+// regression tests. Any Vulkan driver works, including software ones like lavapipe.
+#[derive(Debug)]
+pub(crate) struct HeadlessRenderer {
+    entry: Entry,
+    instance: Instance,
+    device: Device,
+    data: AppData,
+    // The offscreen image stands in for the only swapchain image
+    color_image_memory: vk::DeviceMemory,
+    chunks: Vec<ChunkBuffers>,
+}
+
+impl HeadlessRenderer {
+    #[rustfmt::skip]
+    pub(crate) unsafe fn create(width: u32, height: u32, validation: bool) -> Result<Self> {
+        let loader = LibloadingLoader::new(LIBRARY)?;
+        let entry = Entry::new(loader).map_err(|b| anyhow!("{}", b))?;
+        let mut data = AppData {
+            validation_enabled: validation,
+            frames_in_flight: 1,
+            ..Default::default()
+        };
+
+        let instance = create_instance(None, &entry, &mut data)?;
+        pick_physical_device(&instance, &mut data)?;
+        let device = create_logical_device(&instance, &mut data)?;
+
+        data.swapchain_format = OFFSCREEN_FORMAT;
+        data.swapchain_extent = vk::Extent2D { width, height };
+        let (color_image, color_image_memory) = create_image(
+            &instance,
+            &device,
+            &data,
+            width,
+            height,
+            OFFSCREEN_FORMAT,
+            vk::ImageTiling::OPTIMAL,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )?;
+        data.swapchain_images = vec![color_image];
+        create_swapchain_image_views(&device, &mut data)?;
+
+        create_render_pass(&instance, &device, &mut data)?;
+        create_descriptor_set_layout(&device, &mut data)?;
+        create_shadow_objects(&instance, &device, &mut data)?;
+        create_pipeline(&device, &mut data)?;
+        create_sky_pipeline(&device, &mut data)?;
+        create_command_pools(&instance, &device, &mut data)?;
+        create_depth_objects(&instance, &device, &mut data)?;
+        create_framebuffers(&device, &mut data)?;
+
+        (data.texture_image, data.texture_image_memory) = create_texture_image_from_path(&instance, &device, &mut data, BLOCK_TEXTURE)?;
+        data.texture_image_view = create_texture_image_view(&device, &data.texture_image, vk::Format::R8G8B8A8_SRGB)?;
+        data.texture_sampler = create_world_texture_sampler(&device, &mut data)?;
+
+        create_uniform_buffers(&instance, &device, &mut data)?;
+        create_descriptor_pool(&device, &mut data)?;
+        create_descriptor_sets(&device, &mut data)?;
+        create_command_buffers(&device, &mut data)?;
+
+        Ok(Self {
+            entry,
+            instance,
+            device,
+            data,
+            color_image_memory,
+            chunks: vec![],
+        })
+    }
+
+    // Meshes every generated chunk and replaces whatever was uploaded before
+    pub(crate) unsafe fn upload_world(&mut self, world: &World) -> Result<()> {
+        self.destroy_chunks();
+        for (coord, chunk) in &world.chunks {
+            let Some(mesh) = world.build_chunk_mesh(coord) else {
+                continue;
+            };
+            if mesh.indices().is_empty() {
+                continue;
+            }
+
+            let (vertex_buffer, vertex_buffer_memory) = create_device_local_buffer(
+                &self.instance, &self.device, &self.data, mesh.vertices(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
+            let (index_buffer, index_buffer_memory) = create_device_local_buffer(
+                &self.instance, &self.device, &self.data, mesh.indices(), vk::BufferUsageFlags::INDEX_BUFFER)?;
+            self.chunks.push(ChunkBuffers {
+                vertex_buffer,
+                vertex_buffer_memory,
+                index_buffer,
+                index_buffer_memory,
+                index_count: mesh.indices().len() as u32,
+                model: chunk.get_model_matrix(),
+            });
+        }
+        Ok(())
+    }
+
+    // Draws the uploaded world from `camera` at the clock's time of day and returns the frame as RGBA
+    // rows, top row first
+    #[rustfmt::skip]
+    pub(crate) unsafe fn render(&mut self, camera: &Camera, clock: &WorldClock, shadows: bool) -> Result<Vec<u8>> {
+        let extent = self.data.swapchain_extent;
+        let ubo = UniformBufferObject::new(camera, extent.width as f32 / extent.height as f32, clock, shadows);
+        let memory = self.device.map_memory(
+            self.data.uniform_buffers_memory[0],
+            0,
+            size_of::<UniformBufferObject>() as u64,
+            vk::MemoryMapFlags::empty(),
+        )?;
+        memcpy(&ubo, memory.cast(), 1);
+        self.device.unmap_memory(self.data.uniform_buffers_memory[0]);
+
+        self.device.reset_command_pool(self.data.command_pools[0], vk::CommandPoolResetFlags::empty())?;
+        let command_buffer = self.data.command_buffers[0];
+        let info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        self.device.begin_command_buffer(command_buffer, &info)?;
+
+        let casters = if shadows {
+            self.chunks
+                .iter()
+                .map(|chunk| ShadowCaster {
+                    vertex_buffer: chunk.vertex_buffer,
+                    index_buffer: chunk.index_buffer,
+                    index_count: chunk.index_count,
+                    model: chunk.model,
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        record_shadow_passes(&self.device, &self.data, command_buffer, self.data.descriptor_sets[0], &casters);
+
+        let render_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(extent);
+        let color_clear_value = vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: ubo.clear_color(),
+            },
+        };
+        let depth_clear_value = vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        };
+        let clear_values = &[color_clear_value, depth_clear_value];
+        let info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.data.render_pass)
+            .framebuffer(self.data.framebuffers[0])
+            .render_area(render_area)
+            .clear_values(clear_values);
+        self.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::INLINE);
+
+        self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.data.sky_pipeline);
+        self.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.data.sky_pipeline_layout,
+            0,
+            &[self.data.descriptor_sets[0]],
+            &[],
+        );
+        self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+
+        self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.data.pipeline);
+        self.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.data.pipeline_layout,
+            0,
+            &[self.data.descriptor_sets[0]],
+            &[],
+        );
+        let opacity: f32 = 1.0;
+        for chunk in &self.chunks {
+            let (_, model_bytes, _) = chunk.model.as_slice().align_to::<u8>();
+            self.device.cmd_bind_vertex_buffers(command_buffer, 0, &[chunk.vertex_buffer], &[0]);
+            self.device.cmd_bind_index_buffer(command_buffer, chunk.index_buffer, 0, vk::IndexType::UINT32);
+            self.device.cmd_push_constants(command_buffer, self.data.pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, model_bytes);
+            self.device.cmd_push_constants(command_buffer, self.data.pipeline_layout, vk::ShaderStageFlags::FRAGMENT, 64, &opacity.to_ne_bytes());
+            self.device.cmd_draw_indexed(command_buffer, chunk.index_count, 1, 0, 0, 0);
+        }
+
+        self.device.cmd_end_render_pass(command_buffer);
+        self.device.end_command_buffer(command_buffer)?;
+
+        let command_buffers = &[command_buffer];
+        let submit_info = vk::SubmitInfo::builder().command_buffers(command_buffers);
+        self.device.queue_submit(self.data.graphics_queue, &[submit_info], vk::Fence::null())?;
+        self.device.queue_wait_idle(self.data.graphics_queue)?;
+
+        // The render pass leaves the image ready to be copied
+        let texels = read_image(
+            &self.instance,
+            &self.device,
+            &self.data,
+            self.data.swapchain_images[0],
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            extent,
+        )?;
+        to_rgba(&texels, self.data.swapchain_format)
+    }
+
+    pub(crate) fn extent(&self) -> vk::Extent2D {
+        self.data.swapchain_extent
+    }
+
+    unsafe fn destroy_chunks(&mut self) {
+        for chunk in self.chunks.drain(..) {
+            self.device.destroy_buffer(chunk.vertex_buffer, None);
+            self.device.free_memory(chunk.vertex_buffer_memory, None);
+            self.device.destroy_buffer(chunk.index_buffer, None);
+            self.device.free_memory(chunk.index_buffer_memory, None);
+        }
+    }
+
+    #[rustfmt::skip]
+    pub(crate) unsafe fn destroy(&mut self) {
+        self.device.device_wait_idle().unwrap();
+
+        self.destroy_chunks();
+        self.device.destroy_image_view(self.data.depth_image_view, None);
+        self.device.free_memory(self.data.depth_image_memory, None);
+        self.device.destroy_image(self.data.depth_image, None);
+        self.device.destroy_descriptor_pool(self.data.descriptor_pool, None);
+        self.data.uniform_buffers_memory.iter().for_each(|m| self.device.free_memory(*m, None));
+        self.data.uniform_buffers.iter().for_each(|b| self.device.destroy_buffer(*b, None));
+        self.data.framebuffers.iter().for_each(|f| self.device.destroy_framebuffer(*f, None));
+        self.device.destroy_pipeline(self.data.pipeline, None);
+        self.device.destroy_pipeline_layout(self.data.pipeline_layout, None);
+        self.device.destroy_pipeline(self.data.sky_pipeline, None);
+        self.device.destroy_pipeline_layout(self.data.sky_pipeline_layout, None);
+        self.device.destroy_render_pass(self.data.render_pass, None);
+        self.data.swapchain_image_views.iter().for_each(|v| self.device.destroy_image_view(*v, None));
+        self.data.swapchain_images.iter().for_each(|i| self.device.destroy_image(*i, None));
+        self.device.free_memory(self.color_image_memory, None);
+        self.device.destroy_sampler(self.data.texture_sampler, None);
+        self.device.destroy_image_view(self.data.texture_image_view, None);
+        self.device.free_memory(self.data.texture_image_memory, None);
+        self.device.destroy_image(self.data.texture_image, None);
+        self.data.command_pools.iter().for_each(|p| self.device.destroy_command_pool(*p, None));
+        self.device.destroy_command_pool(self.data.command_pool, None);
+        destroy_shadow_objects(&self.device, &mut self.data);
+        self.device.destroy_descriptor_set_layout(self.data.descriptor_set_layout, None);
+        self.device.destroy_device(None);
+
+        if self.data.validation_enabled {
+            self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
+        }
+        self.instance.destroy_instance(None);
+    }
+}
+
+// Looks over the starting chunk from above one corner, the view used by `--render`
+pub(crate) fn overview_camera(settings: &Settings) -> Camera {
+    Camera::looking_at(glm::vec3(-16.0, -16.0, 56.0), glm::vec3(16.0, 16.0, 16.0), settings.camera_settings())
+}
+
+// Noon, so the terrain is fully lit
+pub(crate) fn overview_clock() -> WorldClock {
+    let mut clock = WorldClock::default();
+    clock.set_time(0.5);
+    clock
+}
+
+// Generates the starting world the same way App::create does and saves one frame of it
+pub(crate) unsafe fn render_start_world(path: &Path, settings: &Settings) -> Result<()> {
+    let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+    world.generate_chunk_voxel_map(&ChunkCoord::zero());
+
+    let (width, height) = settings.resolution;
+    let mut renderer = HeadlessRenderer::create(width, height, settings.validation)?;
+    let result = renderer
+        .upload_world(&world)
+        .and_then(|_| renderer.render(&overview_camera(settings), &overview_clock(), settings.shadows));
+    renderer.destroy();
+
+    save_png(path, width, height, &result?)?;
+    info!("Rendered a {}x{} frame to {:?}", width, height, path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::screenshot::{count_different_pixels, load_png};
+    use std::env;
+
+    // Rendered by lavapipe, rewrite it with `UPDATE_REFERENCE_IMAGES=1 cargo test -- --ignored` after
+    // changing how the world looks on purpose
+    const START_WORLD_REFERENCE: &str = "resources/reference/start_world.png";
+
+    // Other drivers and lavapipe versions round colours and triangle edges a little differently
+    const CHANNEL_TOLERANCE: u8 = 8;
+    const MAX_DIFFERENT_PIXELS: usize = 320 * 180 / 100;
+
+    // One frame of the starting world per entry, with or without shadows
+    unsafe fn render_start_world_frames(width: u32, height: u32, shadows: &[bool]) -> Vec<Vec<u8>> {
+        let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        world.generate_chunk_voxel_map(&ChunkCoord::zero());
+        let settings = Settings::default();
+
+        let mut renderer = HeadlessRenderer::create(width, height, false).unwrap();
+        renderer.upload_world(&world).unwrap();
+        let frames = shadows
+            .iter()
+            .map(|shadows| renderer.render(&overview_camera(&settings), &overview_clock(), *shadows).unwrap())
+            .collect();
+        renderer.destroy();
+        frames
+    }
+
+    // Needs a Vulkan driver, run with `cargo test -- --ignored` (lavapipe works without a GPU)
+    #[test]
+    #[ignore]
+    fn test_render_is_repeatable() {
+        let frames = unsafe { render_start_world_frames(160, 90, &[true, true, false]) };
+        let (first, second, unshadowed) = (&frames[0], &frames[1], &frames[2]);
+
+        assert_eq!(first.len(), 160 * 90 * 4);
+        assert_eq!(count_different_pixels(first, second, 0).unwrap(), 0);
+        // Something other than a single colour was drawn
+        assert!(first.chunks_exact(4).any(|pixel| pixel != &first[..4]));
+        // The terrain shadows parts of itself
+        assert!(count_different_pixels(first, unshadowed, 0).unwrap() > 0);
+    }
+
+    // Catches meshing and shading changes, needs a Vulkan driver like the test above
+    #[test]
+    #[ignore]
+    fn test_start_world_matches_reference() {
+        let reference = Path::new(START_WORLD_REFERENCE);
+        // Only ever written on request, a missing reference must not turn into a passing test
+        if env::var_os("UPDATE_REFERENCE_IMAGES").is_some() {
+            let frame = unsafe { render_start_world_frames(320, 180, &[true]) }.swap_remove(0);
+            save_png(reference, 320, 180, &frame).unwrap();
+            return;
+        }
+        assert!(
+            reference.exists(),
+            "{} is missing, generate it on lavapipe with `UPDATE_REFERENCE_IMAGES=1 cargo test -- --ignored` and commit it",
+            START_WORLD_REFERENCE
+        );
+
+        let (width, height, expected) = load_png(reference)
+            .unwrap_or_else(|error| panic!("Couldn't load {}: {}", START_WORLD_REFERENCE, error));
+        assert_eq!((width, height), (320, 180));
+        let frame = unsafe { render_start_world_frames(320, 180, &[true]) }.swap_remove(0);
+        let different = count_different_pixels(&expected, &frame, CHANNEL_TOLERANCE).unwrap();
+        if different > MAX_DIFFERENT_PIXELS {
+            let actual = env::temp_dir().join("start_world_actual.png");
+            save_png(&actual, 320, 180, &frame).unwrap();
+            panic!("{} pixels differ from {}, the render was saved to {:?}", different, START_WORLD_REFERENCE, actual);
+        }
+    }
+}
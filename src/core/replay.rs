@@ -0,0 +1,246 @@
+use crate::controlls::input_recording::InputRecording;
+use crate::core::app::spawn_default_player;
+use crate::core::simulation::{FixedTimestep, Simulation};
+use crate::terrain::chunk_coord::ChunkCoord;
+use crate::terrain::world::{VoxelEdit, World};
+use anyhow::{anyhow, Result};
+use log::info;
+use nalgebra_glm as glm;
+use std::path::Path;
+
+// Replays are deterministic, this only absorbs float noise from printing positions
+const POSITION_TOLERANCE: f32 = 0.0001;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ReplayReport {
+    pub(crate) frames: usize,
+    pub(crate) ticks: u128,
+    pub(crate) player_position: Option<glm::Vec3>,
+    // Every way the replay ended up different from the recording's expectations
+    pub(crate) mismatches: Vec<String>,
+}
+
+impl ReplayReport {
+    pub(crate) fn is_success(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+// Stores how the session ended, so a replay can tell whether it reproduced it
+pub(crate) fn record_expectations(recording: &mut InputRecording, simulation: &Simulation) {
+    recording.expected_position = simulation.player_transform().map(|transform| transform.position);
+
+    recording.expected_voxels.clear();
+    for edit in simulation.world.edits() {
+        let position = edit.position;
+        if recording.expected_voxels.iter().any(|other| other.position == position) {
+            continue;
+        }
+        recording.expected_voxels.push(VoxelEdit {
+            position,
+            voxel_id: simulation.world.get_voxel_id(position.x, position.y, position.z),
+        });
+    }
+}
+
+// Plays the recording into the simulation exactly like the window loop fed the original session
+pub(crate) fn replay(simulation: &mut Simulation, recording: &InputRecording) -> Result<ReplayReport> {
+    let recorded_delta_time = 1.0 / recording.tick_rate as f32;
+    if simulation.delta_time() != recorded_delta_time {
+        return Err(anyhow!(
+            "Recording ticks every {}s, the simulation every {}s",
+            recorded_delta_time,
+            simulation.delta_time()
+        ));
+    }
+
+    let mut input_manager = recording.create_input_manager();
+    let mut timestep = FixedTimestep::new(recording.tick_rate, recording.max_frame_time);
+    let start_tick = simulation.tick_count();
+    for frame in &recording.frames {
+        for input in &frame.inputs {
+            input.apply(&mut input_manager);
+        }
+        let ticks = timestep.advance(frame.delta_time);
+        simulation.run_ticks(ticks, &mut input_manager);
+    }
+
+    let player_position = simulation.player_transform().map(|transform| transform.position);
+    let mut mismatches = vec![];
+    if let Some(expected) = recording.expected_position {
+        match player_position {
+            Some(position) if glm::distance(&position, &expected) <= POSITION_TOLERANCE => {}
+            Some(position) => mismatches.push(format!(
+                "Player ended at ({}, {}, {}), expected ({}, {}, {})",
+                position.x, position.y, position.z, expected.x, expected.y, expected.z
+            )),
+            None => mismatches.push("There is no player to compare".to_string()),
+        }
+    }
+    for edit in &recording.expected_voxels {
+        let position = edit.position;
+        let voxel_id = simulation.world.get_voxel_id(position.x, position.y, position.z);
+        if voxel_id != edit.voxel_id {
+            mismatches.push(format!(
+                "Voxel at ({}, {}, {}) is {}, expected {}",
+                position.x, position.y, position.z, voxel_id, edit.voxel_id
+            ));
+        }
+    }
+
+    Ok(ReplayReport {
+        frames: recording.frames.len(),
+        ticks: simulation.tick_count() - start_tick,
+        player_position,
+        mismatches,
+    })
+}
+
+// Replays a recording file without a window, the world is set up the same way App::create does
+pub(crate) fn run_headless(path: &Path) -> Result<ReplayReport> {
+    let recording = InputRecording::load(path)?;
+
+    let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+    world.generate_chunk_voxel_map(&ChunkCoord::zero());
+    let mut simulation = Simulation::new(world, recording.tick_rate);
+    spawn_default_player(&mut simulation);
+
+    let report = replay(&mut simulation, &recording)?;
+    info!("Replayed {} frames in {} ticks from {:?}", report.frames, report.ticks, path);
+    if !report.is_success() {
+        return Err(anyhow!("Replay of {:?} diverged:\n{}", path, report.mismatches.join("\n")));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controlls::input_manager::InputManager;
+    use crate::core::transform::Transform;
+    use crate::player::character_controller::CharacterController;
+    use crate::player::player_data::PlayerData;
+    use winit::event::{DeviceId, ElementState, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode};
+
+    const TICK_RATE: u32 = 60;
+
+    fn test_simulation() -> Simulation {
+        let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        for x in -8..8 {
+            for y in -8..8 {
+                world.set_voxel_id(x, y, 0, 2);
+            }
+        }
+        world.set_voxel_id(0, 3, 1, 2);
+
+        let mut simulation = Simulation::new(world, TICK_RATE);
+        let player = PlayerData {
+            yaw: std::f32::consts::PI,
+            pitch: -0.46,
+            mouse_speed: 1.0,
+            move_speed: 4.0,
+            reach: 5.0,
+            reach_step: 0.01,
+            selected_voxel: 2,
+            controller: CharacterController::default(),
+            ..Default::default()
+        };
+        let transform = Transform {
+            position: glm::vec3(0.5, 0.5, 1.0),
+            ..Default::default()
+        };
+        let collider = player.controller.collider();
+        simulation.spawn_player(player, transform, collider);
+        simulation
+    }
+
+    #[allow(deprecated)]
+    fn key(input: &mut InputManager, key: VirtualKeyCode, state: ElementState, frame: u128) {
+        let event = KeyboardInput {
+            scancode: 0,
+            state,
+            virtual_keycode: Some(key),
+            modifiers: ModifiersState::empty(),
+        };
+        input.detect_keyboard(unsafe { DeviceId::dummy() }, event, false, frame).unwrap();
+    }
+
+    // Drives a session the way App::render does, with uneven frame times and a pause
+    fn record_session() -> (InputRecording, Simulation) {
+        let mut simulation = test_simulation();
+        let mut input = InputManager::new();
+        let mut timestep = FixedTimestep::new(TICK_RATE, 0.4);
+        input.start_recording(TICK_RATE, 0.4);
+
+        let frame_times = [0.016, 0.021, 0.009, 0.033, 0.017];
+        for frame in 0..60 {
+            let tick = simulation.tick_count();
+            // Clicks in the pause menu never reach the player
+            let paused = (20..25).contains(&frame);
+            match frame {
+                2 => key(&mut input, VirtualKeyCode::W, ElementState::Pressed, tick),
+                5 | 21 => input.detect_mouse(unsafe { DeviceId::dummy() }, MouseButton::Left, ElementState::Pressed, tick),
+                6 | 22 => input.detect_mouse(unsafe { DeviceId::dummy() }, MouseButton::Left, ElementState::Released, tick),
+                30 => key(&mut input, VirtualKeyCode::D, ElementState::Pressed, tick),
+                40 => key(&mut input, VirtualKeyCode::W, ElementState::Released, tick),
+                50 => input.detect_mouse(unsafe { DeviceId::dummy() }, MouseButton::Right, ElementState::Pressed, tick),
+                _ => {}
+            }
+            input.detect_mouse_motion((frame as f64 * 0.5, 0.0));
+            input.handle_mouse(!paused);
+            if paused {
+                input.skip_frame();
+                continue;
+            }
+
+            let frame_time = frame_times[frame % frame_times.len()];
+            input.end_recorded_frame(frame_time);
+            let ticks = timestep.advance(frame_time);
+            simulation.run_ticks(ticks, &mut input);
+        }
+
+        let mut recording = input.stop_recording().unwrap();
+        record_expectations(&mut recording, &simulation);
+        (recording, simulation)
+    }
+
+    #[test]
+    fn test_replay_reproduces_session() {
+        let (recording, original) = record_session();
+        assert!(!original.world.edits().is_empty());
+        assert_ne!(recording.expected_position, Some(glm::vec3(0.5, 0.5, 1.0)));
+
+        let loaded = InputRecording::from_text(&recording.to_text().unwrap()).unwrap();
+        let mut simulation = test_simulation();
+        let report = replay(&mut simulation, &loaded).unwrap();
+
+        assert!(report.is_success(), "{:?}", report.mismatches);
+        assert_eq!(report.ticks, original.tick_count());
+        assert_eq!(report.player_position, original.player_transform().map(|transform| transform.position));
+        assert_eq!(simulation.world.edits(), original.world.edits());
+    }
+
+    #[test]
+    fn test_replay_reports_divergence() {
+        let (mut recording, _) = record_session();
+        recording.expected_position = recording.expected_position.map(|position| position + glm::vec3(1.0, 0.0, 0.0));
+        recording.expected_voxels.push(VoxelEdit {
+            position: glm::vec3(7, 7, 0),
+            voxel_id: 0,
+        });
+
+        let report = replay(&mut test_simulation(), &recording).unwrap();
+
+        assert_eq!(report.mismatches.len(), 2);
+        assert!(report.mismatches[0].starts_with("Player ended at"));
+        assert!(report.mismatches[1].starts_with("Voxel at (7, 7, 0) is 2"));
+    }
+
+    #[test]
+    fn test_replay_needs_matching_tick_rate() {
+        let (recording, _) = record_session();
+        let mut simulation = Simulation::new(World::load(glm::vec3(0.0, 0.0, 0.0)), 30);
+
+        assert!(replay(&mut simulation, &recording).is_err());
+    }
+}
@@ -0,0 +1,428 @@
+use crate::core::camera::CameraSettings;
+use crate::terrain::constants::CHUNK_SIZE;
+use crate::{DEFAULT_FONT_PATH, SETTINGS_DIRECTORY_NAME, SETTINGS_FILE_NAME};
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use vulkanalia::vk;
+
+// Bumped whenever a setting is renamed or changes meaning, older files are migrated when loaded
+pub(crate) const SETTINGS_VERSION: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum WindowMode {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+// Preferred swapchain present mode, FIFO is used when the preference isn't supported
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PresentMode {
+    // Vsync
+    Fifo,
+    // Vsync without blocking, newer frames replace queued ones
+    #[default]
+    Mailbox,
+    // No vsync, may tear
+    Immediate,
+}
+
+impl PresentMode {
+    pub(crate) fn choose(&self, available: &[vk::PresentModeKHR]) -> vk::PresentModeKHR {
+        let preferred = match self {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        };
+        // Every device has to support FIFO
+        available
+            .iter()
+            .cloned()
+            .find(|mode| *mode == preferred)
+            .unwrap_or(vk::PresentModeKHR::FIFO)
+    }
+}
+
+// Unknown settings are an error, so a misspelled one isn't silently ignored
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Settings {
+    pub(crate) window_mode: WindowMode,
+    // Size of the window when it isn't fullscreen, in logical pixels
+    pub(crate) resolution: (u32, u32),
+    pub(crate) present_mode: PresentMode,
+    pub(crate) frames_in_flight: usize,
+    pub(crate) fov: f32,
+    // In chunks, also decides the camera's far plane
+    pub(crate) view_distance: u32,
+    pub(crate) mouse_sensitivity: f32,
+    pub(crate) invert_mouse_y: bool,
+    // 0 follows the mouse directly, values towards 1 follow it more lazily
+    pub(crate) mouse_smoothing: f32,
+    // Sun and moon shadows on the terrain
+    pub(crate) shadows: bool,
+    // Path of the font file used for text, only read at startup
+    pub(crate) font: String,
+    // Only read at startup
+    pub(crate) validation: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowMode::Windowed,
+            resolution: (1024, 576),
+            present_mode: PresentMode::default(),
+            frames_in_flight: 2,
+            fov: 90.0,
+            view_distance: 4,
+            mouse_sensitivity: 1.0,
+            invert_mouse_y: false,
+            mouse_smoothing: 0.0,
+            shadows: true,
+            font: DEFAULT_FONT_PATH.to_string(),
+            validation: false,
+        }
+    }
+}
+
+impl Settings {
+    pub(crate) fn camera_settings(&self) -> CameraSettings {
+        CameraSettings {
+            fov_degrees: self.fov,
+            near: 0.1,
+            far: (self.view_distance * CHUNK_SIZE as u32) as f32,
+        }
+    }
+
+    // Present mode and frames in flight only take effect with new swapchain and sync objects
+    pub(crate) fn needs_swapchain_recreation(&self, previous: &Settings) -> bool {
+        self.present_mode != previous.present_mode || self.frames_in_flight != previous.frames_in_flight
+    }
+
+    pub(crate) fn needs_restart(&self, previous: &Settings) -> bool {
+        self.validation != previous.validation || self.font != previous.font
+    }
+
+    // Takes over what the settings menu edits, the rest stays as it is
+    pub(crate) fn copy_menu_settings(&mut self, edited: &Settings) {
+        self.fov = edited.fov;
+        self.view_distance = edited.view_distance;
+        self.mouse_sensitivity = edited.mouse_sensitivity;
+        self.invert_mouse_y = edited.invert_mouse_y;
+        self.mouse_smoothing = edited.mouse_smoothing;
+        self.shadows = edited.shadows;
+    }
+
+    // Keys missing from the text keep their defaults
+    pub(crate) fn from_config_str(text: &str) -> Result<Self> {
+        Ok(Self::parse(text)?.0)
+    }
+
+    // Also returns the version the text was written with
+    fn parse(text: &str) -> Result<(Self, u32)> {
+        let mut table = text.parse::<Table>()?;
+        let version = match table.remove("version") {
+            Some(value) => value
+                .as_integer()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| anyhow!("Invalid version `{}`", value))?,
+            // The first settings files didn't store a version
+            None => 1,
+        };
+        if version > SETTINGS_VERSION {
+            return Err(anyhow!("Settings version {} is newer than the supported {}", version, SETTINGS_VERSION));
+        }
+        migrate(&mut table, version)?;
+
+        let settings = table.try_into::<Settings>()?;
+        settings.validate()?;
+        Ok((settings, version))
+    }
+
+    // Ranges serde can't check
+    fn validate(&self) -> Result<()> {
+        check_range("frames_in_flight", self.frames_in_flight, 1, 3)?;
+        check_range("fov", self.fov, 30.0, 150.0)?;
+        check_range("view_distance", self.view_distance, 1, 32)?;
+        check_range("mouse_sensitivity", self.mouse_sensitivity, 0.01, 100.0)?;
+        // Full smoothing would never move the camera
+        check_range("mouse_smoothing", self.mouse_smoothing, 0.0, 0.99)?;
+        if self.resolution.0 == 0 || self.resolution.1 == 0 {
+            return Err(anyhow!("`resolution` must not be zero, got {:?}", self.resolution));
+        }
+        if self.font.is_empty() {
+            return Err(anyhow!("`font` expects a path"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_config_string(&self) -> Result<String> {
+        Ok(format!(
+            "# Game settings\n\
+             # window_mode: windowed, borderless or fullscreen\n\
+             # present_mode: fifo (vsync), mailbox or immediate (no vsync)\n\
+             # view_distance is in chunks, font and validation need a restart\n\
+             version = {}\n{}",
+            SETTINGS_VERSION,
+            toml::to_string(self)?
+        ))
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        Self::from_config_str(&fs::read_to_string(path)?)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_config_string()?)?;
+        Ok(())
+    }
+
+    // Writes the defaults when there is no file yet and rewrites migrated files in the current format.
+    // Broken files fall back to the defaults and are left alone.
+    pub(crate) fn load_or_default(path: &Path) -> Self {
+        if !path.exists() {
+            let settings = Self::default();
+            if let Err(error) = settings.save(path) {
+                warn!("Couldn't write default settings to {:?}: {}", path, error);
+            }
+            return settings;
+        }
+
+        let parsed = fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Self::parse(&text));
+        match parsed {
+            Ok((settings, version)) => {
+                if version < SETTINGS_VERSION {
+                    info!("Migrating settings in {:?} to version {}", path, SETTINGS_VERSION);
+                    if let Err(error) = settings.save(path) {
+                        warn!("Couldn't save migrated settings to {:?}: {}", path, error);
+                    }
+                }
+                settings
+            }
+            Err(error) => {
+                warn!("Couldn't load settings from {:?}: {}", path, error);
+                Self::default()
+            }
+        }
+    }
+}
+
+// Brings a table written by an older version up to SETTINGS_VERSION
+fn migrate(table: &mut Table, version: u32) -> Result<()> {
+    if version < 2 {
+        // Version 1 had `vsync` and `fullscreen` switches and the size as `width` and `height`
+        if let Some(vsync) = table.remove("vsync") {
+            let present_mode = if as_bool("vsync", &vsync)? { PresentMode::Fifo } else { PresentMode::Immediate };
+            table.insert("present_mode".to_string(), Value::try_from(present_mode)?);
+        }
+        if let Some(fullscreen) = table.remove("fullscreen") {
+            let window_mode = if as_bool("fullscreen", &fullscreen)? { WindowMode::Borderless } else { WindowMode::Windowed };
+            table.insert("window_mode".to_string(), Value::try_from(window_mode)?);
+        }
+        let width = table.remove("width");
+        let height = table.remove("height");
+        if width.is_some() || height.is_some() {
+            let defaults = Settings::default().resolution;
+            let width = width.unwrap_or(Value::Integer(defaults.0.into()));
+            let height = height.unwrap_or(Value::Integer(defaults.1.into()));
+            table.insert("resolution".to_string(), Value::Array(vec![width, height]));
+        }
+    }
+    Ok(())
+}
+
+fn as_bool(key: &str, value: &Value) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| anyhow!("`{}` expects true or false, got `{}`", key, value))
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(key: &str, value: T, min: T, max: T) -> Result<()> {
+    if value < min || value > max {
+        return Err(anyhow!("`{}` must be between {} and {}, got {}", key, min, max, value));
+    }
+    Ok(())
+}
+
+// The per user config directory of the platform, or the working directory when it can't be found
+pub(crate) fn default_settings_path() -> PathBuf {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    let config_directory = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| home().map(|home| home.join(".config")))
+    };
+
+    match config_directory {
+        Some(directory) => directory.join(SETTINGS_DIRECTORY_NAME).join(SETTINGS_FILE_NAME),
+        None => PathBuf::from("config").join(SETTINGS_FILE_NAME),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_round_trip() {
+        let settings = Settings::default();
+        let text = settings.to_config_string().unwrap();
+
+        assert!(text.contains("version = 2\n"));
+        assert!(text.contains("present_mode = \"mailbox\"\n"));
+        assert_eq!(Settings::from_config_str(&text).unwrap(), settings);
+        assert_eq!(Settings::from_config_str("").unwrap(), settings);
+    }
+
+    #[test]
+    fn test_parses_every_setting() {
+        let text = "version = 2\nwindow_mode = \"fullscreen\"\nresolution = [1920, 1080]\npresent_mode = \"immediate\"\n\
+                    frames_in_flight = 3\nfov = 75.5\nview_distance = 8\nmouse_sensitivity = 0.25\ninvert_mouse_y = true\n\
+                    mouse_smoothing = 0.5\nshadows = false\nfont = \"fonts/My Font.ttf\"\nvalidation = true\n";
+        let settings = Settings::from_config_str(text).unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                window_mode: WindowMode::Fullscreen,
+                resolution: (1920, 1080),
+                present_mode: PresentMode::Immediate,
+                frames_in_flight: 3,
+                fov: 75.5,
+                view_distance: 8,
+                mouse_sensitivity: 0.25,
+                invert_mouse_y: true,
+                mouse_smoothing: 0.5,
+                shadows: false,
+                font: "fonts/My Font.ttf".to_string(),
+                validation: true,
+            }
+        );
+        assert_eq!(settings.camera_settings().far, 256.0);
+    }
+
+    #[test]
+    fn test_rejects_bad_values() {
+        for text in [
+            "fov = \"wide\"",
+            "fov = 500",
+            "frames_in_flight = 0",
+            "resolution = 1920",
+            "resolution = [1920]",
+            "resolution = [0, 1080]",
+            "window_mode = \"maximized\"",
+            "validation = \"yes\"",
+            "font = \"\"",
+            "shadows = \"high\"",
+            "mouse_smoothing = 1.0",
+            "invert_mouse_y = 1",
+            "version = 3",
+            "version = \"2\"",
+            "not toml",
+        ] {
+            assert!(Settings::from_config_str(text).is_err(), "{}", text);
+        }
+
+        let error = Settings::from_config_str("present_mode = \"vsync\"").unwrap_err().to_string();
+        assert!(error.contains("`fifo`, `mailbox`, `immediate`"), "{}", error);
+        let error = Settings::from_config_str("frames_in_flight = 4").unwrap_err().to_string();
+        assert!(error.contains("between 1 and 3"), "{}", error);
+    }
+
+    #[test]
+    fn test_migrates_version_one() {
+        let text = "# Old settings\nvsync = true\nfullscreen = true\nwidth = 1280\nheight = 720\nfov = 100\n";
+        let settings = Settings::from_config_str(text).unwrap();
+
+        assert_eq!(settings.present_mode, PresentMode::Fifo);
+        assert_eq!(settings.window_mode, WindowMode::Borderless);
+        assert_eq!(settings.resolution, (1280, 720));
+        assert_eq!(settings.fov, 100.0);
+
+        let settings = Settings::from_config_str("vsync = false\nheight = 900").unwrap();
+        assert_eq!(settings.present_mode, PresentMode::Immediate);
+        assert_eq!(settings.resolution, (1024, 900));
+
+        // Version 2 files don't know the old keys
+        assert!(Settings::from_config_str("version = 2\nvsync = true").is_err());
+    }
+
+    #[test]
+    fn test_load_or_default_writes_and_migrates_files() {
+        let directory = env::temp_dir().join(format!("settings_test_{}", std::process::id()));
+        let path = directory.join("settings.toml");
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(Settings::load_or_default(&path), Settings::default());
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+
+        fs::write(&path, "vsync = true\nwidth = 800\nheight = 600\n").unwrap();
+        let settings = Settings::load_or_default(&path);
+        assert_eq!(settings.resolution, (800, 600));
+        assert_eq!(fs::read_to_string(&path).unwrap(), settings.to_config_string().unwrap());
+
+        // Broken files are kept so they can be fixed by hand
+        fs::write(&path, "fov = 9000\n").unwrap();
+        assert_eq!(Settings::load_or_default(&path), Settings::default());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fov = 9000\n");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_present_mode_falls_back_to_fifo() {
+        let available = [vk::PresentModeKHR::FIFO, vk::PresentModeKHR::MAILBOX];
+
+        assert_eq!(PresentMode::Mailbox.choose(&available), vk::PresentModeKHR::MAILBOX);
+        assert_eq!(PresentMode::Fifo.choose(&available), vk::PresentModeKHR::FIFO);
+        assert_eq!(PresentMode::Immediate.choose(&available), vk::PresentModeKHR::FIFO);
+    }
+
+    #[test]
+    fn test_changes_that_need_recreation() {
+        let settings = Settings::default();
+
+        assert!(!Settings { fov: 60.0, ..settings.clone() }.needs_swapchain_recreation(&settings));
+        assert!(Settings { present_mode: PresentMode::Fifo, ..settings.clone() }.needs_swapchain_recreation(&settings));
+        assert!(Settings { frames_in_flight: 3, ..settings.clone() }.needs_swapchain_recreation(&settings));
+        assert!(Settings { validation: true, ..settings.clone() }.needs_restart(&settings));
+        assert!(Settings { font: "other.ttf".to_string(), ..settings.clone() }.needs_restart(&settings));
+    }
+
+    #[test]
+    fn test_copies_menu_settings() {
+        let edited = Settings {
+            window_mode: WindowMode::Fullscreen,
+            fov: 70.0,
+            view_distance: 12,
+            mouse_sensitivity: 2.5,
+            invert_mouse_y: true,
+            mouse_smoothing: 0.3,
+            shadows: false,
+            ..Settings::default()
+        };
+        let mut saved = Settings::default();
+        saved.copy_menu_settings(&edited);
+
+        // Fullscreen only lasts for the session
+        assert_eq!(saved, Settings { window_mode: WindowMode::Windowed, ..edited });
+    }
+}
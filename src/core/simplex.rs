@@ -1,18 +1,26 @@
 use nalgebra_glm as glm;
-use std::cmp::{max, min};
+use std::cmp::min;
 use std::fmt::{Debug, Formatter};
 use std::ops;
 
+// Point on the Minkowski difference together with the points on both shapes that produced it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct SupportPoint {
+    pub(crate) point: glm::Vec3,
+    pub(crate) on_a: glm::Vec3,
+    pub(crate) on_b: glm::Vec3,
+}
+
 #[derive(Clone)]
 pub(crate) struct Simplex {
-    points: [glm::Vec3; 4],
+    points: [SupportPoint; 4],
     size: u8,
 }
 
 impl Debug for Simplex {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Simplex")
-            .field("points", &self.points)
+            .field("points", &&self.points[..self.size as usize])
             .field("size", &self.size)
             .finish()
     }
@@ -21,23 +29,25 @@ impl Debug for Simplex {
 impl Simplex {
     pub(crate) fn empty() -> Self {
         Self {
-            points: [glm::zero(); 4],
+            points: [SupportPoint::default(); 4],
             size: 0,
         }
     }
 
-    pub(crate) fn from_list(points: Vec<glm::Vec3>) -> Self {
-        let mut new = Self {
-            points: [glm::zero(); 4],
-            size: points.len() as u8,
-        };
-        for i in 0..points.len() {
-            new.points[i] = points[i];
-        }
+    pub(crate) fn from_list(points: &[SupportPoint]) -> Self {
+        let mut new = Self::empty();
+        new.assign(points);
         new
     }
 
-    pub(crate) fn push_front(&mut self, point: glm::Vec3) {
+    // Replaces the contents, used when GJK reduces the simplex to a sub-feature
+    pub(crate) fn assign(&mut self, points: &[SupportPoint]) {
+        let size = min(points.len(), 4);
+        self.points[..size].copy_from_slice(&points[..size]);
+        self.size = size as u8;
+    }
+
+    pub(crate) fn push_front(&mut self, point: SupportPoint) {
         self.points = [point, self.points[0], self.points[1], self.points[2]];
         self.size = min(self.size + 1, 4);
     }
@@ -46,20 +56,12 @@ impl Simplex {
         self.size
     }
 
-    pub(crate) fn get(&self, index: usize) -> &glm::Vec3 {
-        self.points.get(index).unwrap()
-    }
-
-    pub(crate) fn set(&mut self, index: usize, value: glm::Vec3) {
-        self.points[index] = value;
-    }
-
-    fn begin(&self) -> glm::Vec3 {
-        self.points[0]
+    pub(crate) fn get(&self, index: usize) -> &SupportPoint {
+        &self.points[..self.size as usize][index]
     }
 
-    fn end(&self) -> glm::Vec3 {
-        self.points[max(0, self.size - 1) as usize]
+    pub(crate) fn as_slice(&self) -> &[SupportPoint] {
+        &self.points[..self.size as usize]
     }
 }
 
@@ -67,12 +69,6 @@ impl ops::Index<u8> for Simplex {
     type Output = glm::Vec3;
 
     fn index(&self, index: u8) -> &Self::Output {
-        &self.points[index as usize]
-    }
-}
-
-impl ops::IndexMut<u8> for Simplex {
-    fn index_mut(&mut self, index: u8) -> &mut Self::Output {
-        &mut self.points[index as usize]
+        &self.get(index as usize).point
     }
 }
@@ -0,0 +1,472 @@
+use crate::controlls::input_manager::InputManager;
+use crate::core::broad_phase::BroadPhase;
+use crate::core::collider::Collider;
+use crate::core::collision::{collide, ContactManifold};
+use crate::core::ecs::components::{PlayerInput, Velocity};
+use crate::core::ecs::registry::{Entity, Registry};
+use crate::core::ecs::schedule::{Schedule, System, SystemContext, SystemStage};
+use crate::core::time_of_day::WorldClock;
+use crate::core::transform::Transform;
+use crate::player::player_data::PlayerData;
+use crate::player::player_systems::{PlayerBlockEditSystem, PlayerInputSystem, PlayerMovementSystem};
+use crate::terrain::constants::CHUNK_SIZE;
+use crate::terrain::world::World;
+use crate::FrameData;
+use nalgebra_glm as glm;
+use std::cell::{Ref, RefMut};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+// Accumulates real frame time and tells how many fixed ticks should run
+#[derive(Clone, Debug)]
+pub(crate) struct FixedTimestep {
+    tick_duration: f64,
+    accumulator: f64,
+    // Frames longer than this are clamped so a hitch can't queue up endless ticks
+    max_frame_time: f64,
+}
+
+impl FixedTimestep {
+    pub(crate) fn new(tick_rate: u32, max_frame_time: f64) -> Self {
+        Self {
+            tick_duration: 1.0 / tick_rate as f64,
+            accumulator: 0.0,
+            max_frame_time,
+        }
+    }
+
+    pub(crate) fn advance(&mut self, frame_time: f64) -> u32 {
+        self.accumulator += frame_time.clamp(0.0, self.max_frame_time);
+
+        let mut ticks = 0;
+        while self.accumulator >= self.tick_duration {
+            self.accumulator -= self.tick_duration;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    // How far between the last two ticks the current frame is, in 0..1
+    pub(crate) fn alpha(&self) -> f32 {
+        (self.accumulator / self.tick_duration) as f32
+    }
+
+    pub(crate) fn tick_duration(&self) -> f64 {
+        self.tick_duration
+    }
+}
+
+// Integrates velocity for everything that isn't moved by a character controller
+pub(crate) struct VelocitySystem;
+
+impl System for VelocitySystem {
+    fn name(&self) -> &str {
+        "velocity"
+    }
+
+    fn run(&mut self, registry: &mut Registry, context: &mut SystemContext) {
+        let (Some(velocities), Some(mut transforms)) = (
+            registry.storage::<Velocity>(),
+            registry.storage_mut::<Transform>(),
+        ) else {
+            return;
+        };
+
+        for (entity, velocity) in velocities.iter() {
+            if registry.has::<PlayerData>(entity) {
+                continue;
+            }
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.position += velocity.0 * context.frame_data.delta_time;
+            }
+        }
+    }
+}
+
+// Everything that advances with game time, independent of rendering
+pub(crate) struct Simulation {
+    pub(crate) world: World,
+    pub(crate) registry: Registry,
+    pub(crate) clock: WorldClock,
+    schedule: Schedule,
+    player: Option<Entity>,
+    previous_transforms: HashMap<Entity, Transform>,
+    broad_phase: BroadPhase,
+    // Overlapping entity pairs found during the last tick
+    contacts: Vec<(Entity, Entity, ContactManifold)>,
+    delta_time: f32,
+    tick_count: u128,
+}
+
+impl Debug for Simulation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Simulation")
+            .field("world", &self.world)
+            .field("registry", &self.registry)
+            .field("clock", &self.clock)
+            .field("systems", &self.schedule.system_names())
+            .field("contacts", &self.contacts.len())
+            .field("delta_time", &self.delta_time)
+            .field("tick_count", &self.tick_count)
+            .finish()
+    }
+}
+
+impl Simulation {
+    pub(crate) fn new(world: World, tick_rate: u32) -> Self {
+        let schedule = Schedule::new()
+            .with_system(SystemStage::Input, Box::new(PlayerInputSystem))
+            .with_system(SystemStage::Update, Box::new(PlayerBlockEditSystem))
+            .with_system(SystemStage::Physics, Box::new(PlayerMovementSystem))
+            .with_system(SystemStage::Physics, Box::new(VelocitySystem));
+
+        Self {
+            world,
+            registry: Registry::new(),
+            clock: WorldClock::default(),
+            schedule,
+            player: None,
+            previous_transforms: HashMap::new(),
+            broad_phase: BroadPhase::new(CHUNK_SIZE as f32),
+            contacts: vec![],
+            delta_time: 1.0 / tick_rate as f32,
+            tick_count: 0,
+        }
+    }
+
+    pub(crate) fn add_system(&mut self, stage: SystemStage, system: Box<dyn System>) {
+        self.schedule.add_system(stage, system);
+    }
+
+    // Spawns the locally controlled player with everything its systems need
+    pub(crate) fn spawn_player(&mut self, player: PlayerData, transform: Transform, collider: Collider) -> Entity {
+        let entity = self.registry.spawn();
+        self.registry.insert(entity, player);
+        self.registry.insert(entity, transform);
+        self.registry.insert(entity, collider);
+        self.registry.insert(entity, Velocity::default());
+        self.registry.insert(entity, PlayerInput::default());
+        self.player = Some(entity);
+        entity
+    }
+
+    pub(crate) fn tick(&mut self, input_manager: &InputManager) {
+        self.tick_count += 1;
+        self.clock.advance(self.delta_time);
+
+        self.previous_transforms.clear();
+        if let Some(transforms) = self.registry.storage::<Transform>() {
+            for (entity, transform) in transforms.iter() {
+                self.previous_transforms.insert(entity, transform.clone());
+            }
+        }
+
+        let mut context = SystemContext {
+            world: &mut self.world,
+            frame_data: FrameData {
+                frame_count: self.tick_count,
+                delta_time: self.delta_time,
+                input_manager,
+            },
+        };
+        self.schedule.run(&mut self.registry, &mut context);
+
+        self.update_contacts();
+    }
+
+    // Runs the ticks of one frame, presses are only seen by the first of them
+    pub(crate) fn run_ticks(&mut self, ticks: u32, input_manager: &mut InputManager) {
+        for _ in 0..ticks {
+            self.tick(input_manager);
+            input_manager.detected_new_frame();
+        }
+    }
+
+    fn update_contacts(&mut self) {
+        let mut colliders = HashMap::new();
+        if let Some(storage) = self.registry.storage::<Collider>() {
+            for (entity, collider) in storage.iter() {
+                let position = self
+                    .registry
+                    .get::<Transform>(entity)
+                    .map_or(glm::vec3(0.0, 0.0, 0.0), |transform| transform.position);
+                colliders.insert(entity.index(), (entity, collider.compensate_position(position)));
+            }
+        }
+
+        for id in self.broad_phase.ids() {
+            if !colliders.contains_key(&id) {
+                self.broad_phase.remove(id);
+            }
+        }
+        for (id, (_, collider)) in &colliders {
+            match collider.get_bounds() {
+                Some(bounds) => self.broad_phase.update(*id, bounds),
+                None => {
+                    self.broad_phase.remove(*id);
+                }
+            }
+        }
+
+        self.contacts = self
+            .broad_phase
+            .candidate_pairs()
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let (entity_a, collider_a) = &colliders[&a];
+                let (entity_b, collider_b) = &colliders[&b];
+                Some((*entity_a, *entity_b, collide(collider_a, collider_b)?))
+            })
+            .collect();
+    }
+
+    // Transform blended between the previous and the latest tick for rendering
+    pub(crate) fn get_interpolated_transform(&self, entity: Entity, alpha: f32) -> Option<Transform> {
+        let current = self.registry.get::<Transform>(entity)?;
+        match self.previous_transforms.get(&entity) {
+            Some(previous) => Some(previous.interpolate(&current, alpha)),
+            None => Some(current.clone()),
+        }
+    }
+
+    pub(crate) fn player_entity(&self) -> Option<Entity> {
+        self.player
+    }
+
+    pub(crate) fn player(&self) -> Option<Ref<'_, PlayerData>> {
+        self.registry.get::<PlayerData>(self.player?)
+    }
+
+    pub(crate) fn player_mut(&self) -> Option<RefMut<'_, PlayerData>> {
+        self.registry.get_mut::<PlayerData>(self.player?)
+    }
+
+    pub(crate) fn player_transform(&self) -> Option<Transform> {
+        Some(self.registry.get::<Transform>(self.player?)?.clone())
+    }
+
+    // Moves the player and stops it, rendering doesn't blend from the old position
+    pub(crate) fn teleport_player(&mut self, position: glm::Vec3) -> bool {
+        let Some(entity) = self.player else {
+            return false;
+        };
+        let Some(transform) = self.registry.get_mut::<Transform>(entity).map(|mut transform| {
+            transform.position = position;
+            transform.clone()
+        }) else {
+            return false;
+        };
+        self.previous_transforms.insert(entity, transform);
+        if let Some(mut velocity) = self.registry.get_mut::<Velocity>(entity) {
+            velocity.0 = glm::vec3(0.0, 0.0, 0.0);
+        }
+        true
+    }
+
+    pub(crate) fn broad_phase(&self) -> &BroadPhase {
+        &self.broad_phase
+    }
+
+    pub(crate) fn contacts(&self) -> &[(Entity, Entity, ContactManifold)] {
+        &self.contacts
+    }
+
+    pub(crate) fn tick_count(&self) -> u128 {
+        self.tick_count
+    }
+
+    pub(crate) fn delta_time(&self) -> f32 {
+        self.delta_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records how often it ran, to check the schedule drives custom systems
+    struct TickCounter(u32);
+
+    impl System for TickCounter {
+        fn name(&self) -> &str {
+            "tick_counter"
+        }
+
+        fn run(&mut self, registry: &mut Registry, context: &mut SystemContext) {
+            self.0 += 1;
+            for entity in registry.entities_with::<Velocity>() {
+                if let Some(mut transform) = registry.get_mut::<Transform>(entity) {
+                    transform.scale.x = self.0 as f32;
+                }
+            }
+        }
+    }
+
+    fn flat_world() -> World {
+        let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        for x in -4..=4 {
+            for y in -4..=4 {
+                world.set_voxel_id(x, y, 0, 2);
+            }
+        }
+        world
+    }
+
+    fn spawn_ball(simulation: &mut Simulation, x: f32, speed: f32) -> Entity {
+        let entity = simulation.registry.spawn();
+        let transform = Transform {
+            position: glm::vec3(x, 0.0, 0.0),
+            ..Default::default()
+        };
+        simulation.registry.insert(entity, transform);
+        simulation.registry.insert(entity, Velocity(glm::vec3(speed, 0.0, 0.0)));
+        simulation.registry.insert(entity, Collider::sphere(glm::vec3(0.0, 0.0, 0.0), 0.5));
+        entity
+    }
+
+    fn falling_player_simulation() -> Simulation {
+        let mut simulation = Simulation::new(flat_world(), 60);
+        let transform = Transform {
+            position: glm::vec3(0.5, 0.5, 8.0),
+            ..Default::default()
+        };
+        simulation.spawn_player(PlayerData::default(), transform, Collider::default());
+        simulation
+    }
+
+    #[test]
+    fn test_fixed_timestep_counts_ticks() {
+        let mut timestep = FixedTimestep::new(8, 1.0);
+
+        assert_eq!(timestep.advance(0.0625), 0);
+        assert_eq!(timestep.advance(0.0625), 1);
+        assert_eq!(timestep.advance(0.25), 2);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        assert_eq!(timestep.advance(0.03125), 0);
+        assert_eq!(timestep.alpha(), 0.25);
+    }
+
+    #[test]
+    fn test_fixed_timestep_clamps_long_frames() {
+        let mut timestep = FixedTimestep::new(4, 1.0);
+
+        assert_eq!(timestep.advance(10.0), 4);
+        assert_eq!(timestep.advance(-1.0), 0);
+    }
+
+    #[test]
+    fn test_systems_run_every_tick() {
+        let mut simulation = Simulation::new(World::load(glm::vec3(0.0, 0.0, 0.0)), 20);
+        simulation.add_system(SystemStage::Late, Box::new(TickCounter(0)));
+        let ball = spawn_ball(&mut simulation, 0.0, 2.0);
+
+        simulation.run_ticks(5, &mut InputManager::new());
+
+        let transform = simulation.registry.get::<Transform>(ball).unwrap();
+        assert_eq!(transform.scale.x, 5.0);
+        assert!((transform.position.x - 0.5).abs() < 0.0001);
+        assert_eq!(simulation.tick_count(), 5);
+    }
+
+    #[test]
+    fn test_interpolates_between_ticks() {
+        let mut simulation = Simulation::new(World::load(glm::vec3(0.0, 0.0, 0.0)), 10);
+        let ball = spawn_ball(&mut simulation, 0.0, 1.0);
+        let other = spawn_ball(&mut simulation, 5.0, 0.0);
+
+        simulation.run_ticks(2, &mut InputManager::new());
+
+        let halfway = simulation.get_interpolated_transform(ball, 0.5).unwrap();
+        assert!((halfway.position.x - 0.15).abs() < 0.0001);
+        let latest = simulation.get_interpolated_transform(ball, 1.0).unwrap();
+        assert!((latest.position.x - 0.2).abs() < 0.0001);
+
+        simulation.registry.despawn(other);
+        assert!(simulation.get_interpolated_transform(other, 0.5).is_none());
+    }
+
+    #[test]
+    fn test_reports_contacts_between_entities() {
+        let mut simulation = Simulation::new(World::load(glm::vec3(0.0, 0.0, 0.0)), 10);
+        let a = spawn_ball(&mut simulation, 0.0, 1.0);
+        let b = spawn_ball(&mut simulation, 0.8, 1.0);
+        let c = spawn_ball(&mut simulation, 5.0, 1.0);
+
+        simulation.run_ticks(3, &mut InputManager::new());
+
+        let contacts = simulation.contacts();
+        assert_eq!(contacts.len(), 1);
+        let (first, second, manifold) = contacts[0];
+        assert_eq!((first, second), (a, b));
+        assert!((manifold.depth - 0.2).abs() < 0.01);
+        assert_eq!(simulation.broad_phase().query_sphere(glm::vec3(5.3, 0.0, 0.0), 1.0), vec![c.index()]);
+
+        simulation.registry.despawn(b);
+        simulation.run_ticks(1, &mut InputManager::new());
+        assert!(simulation.contacts().is_empty());
+        assert_eq!(simulation.broad_phase().len(), 2);
+    }
+
+    #[test]
+    fn test_player_is_an_entity() {
+        let mut simulation = falling_player_simulation();
+        let player = simulation.player_entity().unwrap();
+
+        assert!(simulation.registry.has::<Velocity>(player));
+        assert!(simulation.registry.has::<PlayerInput>(player));
+        assert_eq!(simulation.registry.entities_with::<PlayerData>(), vec![player]);
+
+        simulation.run_ticks(1, &mut InputManager::new());
+        assert!(simulation.player_transform().unwrap().position.z < 8.0);
+    }
+
+    #[test]
+    fn test_result_does_not_depend_on_frame_rate() {
+        let mut input_manager = InputManager::new();
+
+        let mut stepped = falling_player_simulation();
+        stepped.run_ticks(90, &mut input_manager);
+
+        let mut framed = falling_player_simulation();
+        let mut timestep = FixedTimestep::new(60, 0.25);
+        let frame_times = [1.0 / 144.0, 1.0 / 30.0, 1.0 / 75.0, 0.1];
+        let mut frame = 0;
+        while framed.tick_count() < 90 {
+            let ticks = timestep.advance(frame_times[frame % frame_times.len()]);
+            let ticks = ticks.min((90 - framed.tick_count()) as u32);
+            framed.run_ticks(ticks, &mut input_manager);
+            frame += 1;
+        }
+
+        let stepped_position = stepped.player_transform().unwrap().position;
+        let framed_position = framed.player_transform().unwrap().position;
+        assert_eq!(stepped_position, framed_position);
+        assert!(stepped.player().unwrap().is_grounded);
+        assert!((stepped_position.z - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_teleport_stops_the_player() {
+        let mut simulation = falling_player_simulation();
+        simulation.run_ticks(10, &mut InputManager::new());
+
+        assert!(simulation.teleport_player(glm::vec3(2.0, -3.0, 20.0)));
+
+        let player = simulation.player_entity().unwrap();
+        assert_eq!(simulation.registry.get::<Velocity>(player).unwrap().0, glm::vec3(0.0, 0.0, 0.0));
+        let transform = simulation.get_interpolated_transform(player, 0.5).unwrap();
+        assert_eq!(transform.position, glm::vec3(2.0, -3.0, 20.0));
+        assert!(!Simulation::new(flat_world(), 60).teleport_player(glm::vec3(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_flying_player_ignores_gravity() {
+        let mut simulation = falling_player_simulation();
+        simulation.player_mut().unwrap().is_flying = true;
+
+        simulation.run_ticks(30, &mut InputManager::new());
+
+        assert_eq!(simulation.player_transform().unwrap().position.z, 8.0);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::terrain::chunk_coord::ChunkCoord;
+use crate::terrain::constants::CHUNK_SIZE;
+use nalgebra_glm as glm;
+use std::collections::VecDeque;
+
+// Frames the frame time history keeps, two seconds at 60 FPS
+const FRAME_HISTORY: usize = 120;
+
+// Times of the latest frames in seconds, oldest first
+#[derive(Clone, Debug)]
+pub(crate) struct FrameStats {
+    frame_times: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new(FRAME_HISTORY)
+    }
+}
+
+impl FrameStats {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub(crate) fn record(&mut self, frame_time: f32) {
+        if self.frame_times.len() == self.capacity {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time.max(0.0));
+    }
+
+    pub(crate) fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.frame_times.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.frame_times.is_empty()
+    }
+
+    // Frames per second over the whole history, steadier than the latest frame alone
+    pub(crate) fn fps(&self) -> f32 {
+        let total = self.frame_times().sum::<f32>();
+        if total > 0.0 {
+            self.frame_times.len() as f32 / total
+        } else {
+            0.0
+        }
+    }
+
+    pub(crate) fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        self.frame_times().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    // The slowest frame, stutters show up here long before they move the average
+    pub(crate) fn max_frame_time(&self) -> f32 {
+        self.frame_times().fold(0.0, f32::max)
+    }
+}
+
+// Chunks of the world and the meshes built from them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct WorldStats {
+    pub(crate) loaded_chunks: usize,
+    pub(crate) meshed_chunks: usize,
+    pub(crate) vertices: usize,
+    pub(crate) indices: usize,
+}
+
+// GPU memory handed out by one allocator, in bytes
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BufferStats {
+    pub(crate) name: String,
+    pub(crate) used: usize,
+    pub(crate) capacity: usize,
+}
+
+impl BufferStats {
+    pub(crate) fn new(name: impl Into<String>, used: usize, capacity: usize) -> Self {
+        Self {
+            name: name.into(),
+            used,
+            capacity,
+        }
+    }
+}
+
+// Where the player is and where they look, angles as in the camera module
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct PlayerStats {
+    pub(crate) position: glm::Vec3,
+    pub(crate) yaw: f32,
+    pub(crate) pitch: f32,
+}
+
+impl PlayerStats {
+    pub(crate) fn chunk(&self) -> ChunkCoord {
+        let size = CHUNK_SIZE as i32;
+        ChunkCoord {
+            x: (self.position.x.floor() as i32).div_euclid(size),
+            y: (self.position.y.floor() as i32).div_euclid(size),
+            z: (self.position.z.floor() as i32).div_euclid(size),
+        }
+    }
+
+    // Compass direction of the horizontal view, north is +Y and east is +X
+    pub(crate) fn facing(&self) -> &'static str {
+        let quarter = (self.yaw / std::f32::consts::FRAC_PI_2).round().rem_euclid(4.0) as u32;
+        match quarter {
+            0 => "north (+Y)",
+            1 => "east (+X)",
+            2 => "south (-Y)",
+            _ => "west (-X)",
+        }
+    }
+}
+
+// Everything the debug HUD shows, gathered without touching the renderer
+#[derive(Clone, Debug)]
+pub(crate) struct DebugStats<'a> {
+    pub(crate) frames: &'a FrameStats,
+    pub(crate) player: Option<PlayerStats>,
+    pub(crate) world: WorldStats,
+    pub(crate) buffers: Vec<BufferStats>,
+}
+
+// Binary units, like GPU memory is usually reported
+pub(crate) fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    #[test]
+    fn test_frame_history() {
+        let mut frames = FrameStats::new(4);
+        assert_eq!((frames.fps(), frames.average_frame_time(), frames.max_frame_time()), (0.0, 0.0, 0.0));
+
+        for frame_time in [0.1, 0.02, 0.02, 0.02, 0.02] {
+            frames.record(frame_time);
+        }
+        // The oldest frame fell out of the history
+        assert_eq!(frames.len(), 4);
+        assert!((frames.fps() - 50.0).abs() < 0.01);
+        assert!((frames.average_frame_time() - 0.02).abs() < 1e-6);
+
+        frames.record(0.05);
+        assert_eq!(frames.max_frame_time(), 0.05);
+        assert_eq!(frames.frame_times().collect::<Vec<_>>(), vec![0.02, 0.02, 0.02, 0.05]);
+    }
+
+    #[test]
+    fn test_player_chunk_and_facing() {
+        let player = PlayerStats {
+            position: glm::vec3(31.9, -0.5, 64.0),
+            yaw: 0.1,
+            pitch: 0.0,
+        };
+        assert_eq!(player.chunk(), ChunkCoord { x: 0, y: -1, z: 2 });
+        assert_eq!(player.facing(), "north (+Y)");
+
+        let facing = |yaw: f32| PlayerStats { yaw, ..player }.facing();
+        assert_eq!(facing(FRAC_PI_2), "east (+X)");
+        assert_eq!(facing(PI), "south (-Y)");
+        assert_eq!(facing(-FRAC_PI_2), "west (-X)");
+        assert_eq!(facing(-PI * 2.0 + 0.2), "north (+Y)");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -0,0 +1,182 @@
+use nalgebra_glm as glm;
+use std::f32::consts::TAU;
+
+// Real seconds for a full day
+pub(crate) const DAY_LENGTH: f32 = 1200.0;
+// Mid morning
+const START_TIME: f32 = 0.3;
+
+// Time of day as a fraction of the day, 0 is midnight and 0.5 noon. Advanced by simulation
+// ticks, so replays see the same sky.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct WorldClock {
+    time: f32,
+    pub(crate) day_length: f32,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self {
+            time: START_TIME,
+            day_length: DAY_LENGTH,
+        }
+    }
+}
+
+impl WorldClock {
+    pub(crate) fn advance(&mut self, delta_time: f32) {
+        self.set_time(self.time + delta_time / self.day_length);
+    }
+
+    pub(crate) fn time(&self) -> f32 {
+        self.time
+    }
+
+    // Wraps around, 1.25 is a quarter past midnight like 0.25
+    pub(crate) fn set_time(&mut self, time: f32) {
+        self.time = time.rem_euclid(1.0);
+    }
+
+    // Like "06:30"
+    pub(crate) fn format(&self) -> String {
+        let minutes = (self.time * 24.0 * 60.0) as u32;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    // Rises in the east (+X) at 06:00, highest at noon and sets in the west
+    pub(crate) fn sun_direction(&self) -> glm::Vec3 {
+        let angle = (self.time - 0.25) * TAU;
+        glm::vec3(angle.cos(), 0.0, angle.sin())
+    }
+
+    // What casts shadows, the sun by day and the moon opposite it by night
+    pub(crate) fn light_direction(&self) -> glm::Vec3 {
+        let sun = self.sun_direction();
+        if sun.z >= 0.0 { sun } else { -sun }
+    }
+}
+
+// The sky at one time of day, colours in between are blended from the keyframes around them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SkyKeyframe {
+    pub(crate) time: f32,
+    pub(crate) zenith: glm::Vec3,
+    // Also the fog colour, so far terrain fades into the sky
+    pub(crate) horizon: glm::Vec3,
+    // Multiplies the terrain colour
+    pub(crate) light: f32,
+}
+
+const fn keyframe(time: f32, zenith: [f32; 3], horizon: [f32; 3], light: f32) -> SkyKeyframe {
+    SkyKeyframe {
+        time,
+        zenith: glm::Vec3::new(zenith[0], zenith[1], zenith[2]),
+        horizon: glm::Vec3::new(horizon[0], horizon[1], horizon[2]),
+        light,
+    }
+}
+
+// Sorted by time, the last one blends into the first one across midnight
+pub(crate) const SKY_KEYFRAMES: [SkyKeyframe; 6] = [
+    keyframe(0.0, [0.01, 0.01, 0.04], [0.03, 0.04, 0.08], 0.2),
+    keyframe(0.22, [0.05, 0.06, 0.15], [0.35, 0.22, 0.2], 0.3),
+    keyframe(0.28, [0.25, 0.45, 0.8], [0.95, 0.6, 0.4], 0.75),
+    keyframe(0.5, [0.2, 0.45, 0.9], [0.65, 0.8, 0.95], 1.0),
+    keyframe(0.72, [0.25, 0.35, 0.7], [0.95, 0.5, 0.3], 0.75),
+    keyframe(0.78, [0.04, 0.05, 0.14], [0.25, 0.15, 0.2], 0.3),
+];
+
+// Blends the keyframes on either side of `time`
+pub(crate) fn sample_sky(keyframes: &[SkyKeyframe], time: f32) -> SkyKeyframe {
+    let time = time.rem_euclid(1.0);
+    let next_index = keyframes.iter().position(|keyframe| keyframe.time > time).unwrap_or(0);
+    let previous_index = (next_index + keyframes.len() - 1) % keyframes.len();
+    let (previous, next) = (keyframes[previous_index], keyframes[next_index]);
+
+    // Across midnight the next keyframe is a day later
+    let span = (next.time - previous.time).rem_euclid(1.0);
+    let t = if span == 0.0 { 0.0 } else { (time - previous.time).rem_euclid(1.0) / span };
+    SkyKeyframe {
+        time,
+        zenith: glm::lerp(&previous.zenith, &next.zenith, t),
+        horizon: glm::lerp(&previous.horizon, &next.horizon, t),
+        light: glm::lerp_scalar(previous.light, next.light, t),
+    }
+}
+
+// Fog starts this far into the view distance and hides everything at the far plane
+const FOG_START: f32 = 0.6;
+
+pub(crate) fn fog_range(far: f32) -> (f32, f32) {
+    (far * FOG_START, far)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: glm::Vec3, b: glm::Vec3) {
+        assert!(glm::distance(&a, &b) < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_clock_wraps_around() {
+        let mut clock = WorldClock { time: 0.9, day_length: 100.0 };
+        clock.advance(20.0);
+        assert!((clock.time() - 0.1).abs() < 1e-5);
+
+        clock.set_time(-0.25);
+        assert_eq!(clock.time(), 0.75);
+        assert_eq!(clock.format(), "18:00");
+        clock.set_time(0.5 + 30.0 / 1440.0);
+        assert_eq!(clock.format(), "12:30");
+    }
+
+    #[test]
+    fn test_sun_path() {
+        let mut clock = WorldClock::default();
+        clock.set_time(0.5);
+        assert_close(clock.sun_direction(), glm::vec3(0.0, 0.0, 1.0));
+        clock.set_time(0.25);
+        assert_close(clock.sun_direction(), glm::vec3(1.0, 0.0, 0.0));
+        clock.set_time(0.0);
+        assert_close(clock.sun_direction(), glm::vec3(0.0, 0.0, -1.0));
+        assert_close(clock.light_direction(), glm::vec3(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_keyframes_are_sorted() {
+        assert!(SKY_KEYFRAMES.windows(2).all(|pair| pair[0].time < pair[1].time));
+        assert!(SKY_KEYFRAMES.iter().all(|keyframe| (0.0..1.0).contains(&keyframe.time)));
+        // Noon is the brightest
+        let noon = sample_sky(&SKY_KEYFRAMES, 0.5);
+        assert!(SKY_KEYFRAMES.iter().all(|keyframe| keyframe.light <= noon.light));
+    }
+
+    #[test]
+    fn test_samples_blend_between_keyframes() {
+        let keyframes = [
+            keyframe(0.25, [0.0, 0.0, 0.0], [1.0, 1.0, 1.0], 0.0),
+            keyframe(0.75, [1.0, 0.0, 0.0], [0.0, 0.0, 0.0], 1.0),
+        ];
+        let exact = sample_sky(&keyframes, 0.75);
+        assert_close(exact.zenith, glm::vec3(1.0, 0.0, 0.0));
+        assert_eq!(exact.light, 1.0);
+
+        let between = sample_sky(&keyframes, 0.375);
+        assert_close(between.zenith, glm::vec3(0.25, 0.0, 0.0));
+        assert_close(between.horizon, glm::vec3(0.75, 0.75, 0.75));
+
+        // From the last keyframe over midnight to the first one
+        assert_eq!(sample_sky(&keyframes, 1.0).light, 0.5);
+        assert_eq!(sample_sky(&keyframes, 0.125).light, 0.25);
+        assert_eq!(sample_sky(&keyframes, 0.875).light, 0.75);
+    }
+
+    #[test]
+    fn test_fog_ends_at_the_far_plane() {
+        let (start, end) = fog_range(128.0);
+        assert_eq!(end, 128.0);
+        assert!(start > 0.0 && start < end);
+    }
+}
@@ -16,6 +16,15 @@ impl Transform {
         //(rotate_z(self.rotation.z) * rotate_y(self.rotation.y) * rotate_x(self.rotation.x))        (rotate(self.rotation)
     }
 
+    // Linear blend towards `other`, alpha 0 gives self and 1 gives other
+    pub(crate) fn interpolate(&self, other: &Transform, alpha: f32) -> Transform {
+        Transform {
+            position: glm::lerp(&self.position, &other.position, alpha),
+            rotation: glm::lerp(&self.rotation, &other.rotation, alpha),
+            scale: glm::lerp(&self.scale, &other.scale, alpha),
+        }
+    }
+
     pub(crate) fn euler_to_quat(&self) -> glm::Qua<f32> {
         let x_radians = self.rotation.x.to_radians_fast();
         let y_radians = self.rotation.y.to_radians_fast();
@@ -1,28 +1,46 @@
 pub mod buffers;
 pub mod command_buffers;
 pub mod command_pool;
+pub mod console;
+pub mod debug_hud;
 pub mod depth_objects;
 pub mod descriptors;
 pub mod font_data;
+pub mod fonts;
 pub mod framebuffers;
+pub mod glyph_atlas;
+pub mod highlight_pipeline;
+pub mod hot_reload;
 pub mod instance;
 pub mod logical_device;
+pub mod menus;
 pub mod model_data;
 pub mod models;
 pub mod physical_device;
 pub mod pipeline;
 pub mod queue_family_indices;
+pub mod screenshot;
+pub mod sdf;
+pub mod shader_files;
+pub mod shadow_cascades;
+pub mod shadow_maps;
 pub mod shared_buffers;
 pub mod shared_images;
 pub mod shared_textures;
 pub mod single_time_commands;
+pub mod sky_pipeline;
 pub mod swapchain;
 pub mod swapchain_support;
 pub mod sync_objects;
 pub mod text_object;
+pub mod text_objects;
 pub mod text_pipeline;
 pub mod text_textures;
 pub mod texture_samplers;
 pub mod texturing_shared;
+pub mod ui;
+pub mod ui_buffers;
+pub mod ui_pipeline;
+pub mod ui_space;
 pub mod uniform_buffer_object;
 pub mod vertex;
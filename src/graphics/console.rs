@@ -0,0 +1,260 @@
+use crate::core::commands::CommandRegistry;
+use crate::graphics::ui::Ui;
+use crate::graphics::ui_space::Anchor;
+use nalgebra_glm as glm;
+use std::collections::VecDeque;
+use winit::event::VirtualKeyCode;
+
+// Older lines are dropped
+const MAX_LINES: usize = 200;
+// Lines shown above the input line
+const VISIBLE_LINES: usize = 12;
+const PROMPT: &str = "> ";
+
+// Sent as characters by winit, like the text fields of the UI read them
+const BACKSPACE: char = '\u{8}';
+const RETURN: char = '\r';
+const TAB: char = '\t';
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LineKind {
+    // A submitted line, echoed with the prompt
+    Input,
+    Output,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ConsoleLine {
+    pub(crate) text: String,
+    pub(crate) kind: LineKind,
+}
+
+// The drop-down console: the line being typed, what was printed and what was submitted before.
+// Running the submitted lines is up to the App.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Console {
+    input: String,
+    lines: VecDeque<ConsoleLine>,
+    // Oldest first, without repeats of the same line in a row
+    history: Vec<String>,
+    // Index into the history while walking it with the arrow keys
+    browsing: Option<usize>,
+}
+
+impl Console {
+    pub(crate) fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub(crate) fn lines(&self) -> impl Iterator<Item = &ConsoleLine> {
+        self.lines.iter()
+    }
+
+    // Every line of `text` becomes a console line
+    pub(crate) fn print(&mut self, text: &str, kind: LineKind) {
+        for line in text.lines() {
+            self.lines.push_back(ConsoleLine { text: line.to_string(), kind });
+        }
+        while self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    // Edits the input line with this frame's typing, returns the line once it's submitted
+    pub(crate) fn handle_input(&mut self, characters: &[char], keys: &[VirtualKeyCode], registry: &CommandRegistry) -> Option<String> {
+        for key in keys {
+            match key {
+                VirtualKeyCode::Up => self.browse_older(),
+                VirtualKeyCode::Down => self.browse_newer(),
+                _ => {}
+            }
+        }
+
+        for character in characters {
+            match *character {
+                BACKSPACE => {
+                    self.input.pop();
+                }
+                RETURN | '\n' => return Some(self.submit()),
+                TAB => self.complete(registry),
+                character if !character.is_control() => self.input.push(character),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    pub(crate) fn draw(&self, ui: &mut Ui, width: f32) {
+        ui.panel("Console", Anchor::Top, glm::vec2(0.0, 0.0), width, |ui| {
+            let skipped = self.lines.len().saturating_sub(VISIBLE_LINES);
+            for line in self.lines.iter().skip(skipped) {
+                let color = match line.kind {
+                    LineKind::Input => ui.theme.hint,
+                    LineKind::Output => ui.theme.text,
+                    LineKind::Error => ui.theme.error,
+                };
+                ui.colored_label(&line.text, color);
+            }
+            let accent = ui.theme.accent;
+            ui.colored_label(&format!("{}{}|", PROMPT, self.input), accent);
+        });
+    }
+
+    fn submit(&mut self) -> String {
+        let line = std::mem::take(&mut self.input);
+        self.browsing = None;
+        self.print(&format!("{}{}", PROMPT, line), LineKind::Input);
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        line
+    }
+
+    // Several candidates are listed, so the next tab can be aimed
+    fn complete(&mut self, registry: &CommandRegistry) {
+        let completion = registry.complete(&self.input);
+        self.input = completion.line;
+        if completion.candidates.len() > 1 {
+            self.print(&completion.candidates.join("  "), LineKind::Output);
+        }
+    }
+
+    fn browse_older(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match self.browsing {
+            Some(index) => index.saturating_sub(1),
+            None => self.history.len() - 1,
+        };
+        self.browsing = Some(index);
+        self.input = self.history[index].clone();
+    }
+
+    // Past the newest line the input is empty again
+    fn browse_newer(&mut self) {
+        let Some(index) = self.browsing else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.browsing = Some(index + 1);
+            self.input = self.history[index + 1].clone();
+        } else {
+            self.browsing = None;
+            self.input.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::commands::{ArgSpec, CommandSpec};
+    use crate::graphics::text_objects::TextObjects;
+    use crate::graphics::ui::UiInput;
+    use crate::graphics::ui_space::UiSpace;
+    use vulkanalia::vk;
+
+    const VALUE: &[ArgSpec] = &[ArgSpec::number("value")];
+
+    fn registry() -> CommandRegistry {
+        let mut registry = CommandRegistry::default();
+        for name in ["fill", "fly", "tp"] {
+            registry.register(CommandSpec { name, args: VALUE, required: 0, help: "" });
+        }
+        registry
+    }
+
+    fn typed(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn test_typing_and_submitting() {
+        let mut console = Console::default();
+        let registry = registry();
+
+        assert_eq!(console.handle_input(&typed("tp 1 2 3x"), &[], &registry), None);
+        assert_eq!(console.handle_input(&[BACKSPACE, '\u{1b}'], &[], &registry), None);
+        assert_eq!(console.input(), "tp 1 2 3");
+
+        assert_eq!(console.handle_input(&[RETURN], &[], &registry), Some("tp 1 2 3".to_string()));
+        assert_eq!(console.input(), "");
+        assert_eq!(
+            console.lines().collect::<Vec<_>>(),
+            vec![&ConsoleLine { text: "> tp 1 2 3".to_string(), kind: LineKind::Input }]
+        );
+    }
+
+    #[test]
+    fn test_tab_completes_and_lists_candidates() {
+        let mut console = Console::default();
+        let registry = registry();
+
+        console.handle_input(&typed("f\t"), &[], &registry);
+        assert_eq!(console.input(), "f");
+        assert_eq!(console.lines().last().unwrap().text, "fill  fly");
+
+        console.handle_input(&typed("l\t"), &[], &registry);
+        assert_eq!(console.input(), "fly ");
+    }
+
+    #[test]
+    fn test_history_browsing() {
+        let mut console = Console::default();
+        let registry = registry();
+        for line in ["tp 1 2 3\r", "fly\r", "fly\r", "\r"] {
+            console.handle_input(&typed(line), &[], &registry);
+        }
+
+        let mut browse = |key| {
+            console.handle_input(&[], &[key], &registry);
+            console.input().to_string()
+        };
+        // Repeats and blank lines aren't kept, the oldest line stops the walk
+        assert_eq!(browse(VirtualKeyCode::Up), "fly");
+        assert_eq!(browse(VirtualKeyCode::Up), "tp 1 2 3");
+        assert_eq!(browse(VirtualKeyCode::Up), "tp 1 2 3");
+        assert_eq!(browse(VirtualKeyCode::Down), "fly");
+        assert_eq!(browse(VirtualKeyCode::Down), "");
+    }
+
+    #[test]
+    fn test_keeps_the_latest_lines() {
+        let mut console = Console::default();
+        for index in 0..MAX_LINES {
+            console.print(&format!("{}\nsecond", index), LineKind::Output);
+        }
+        assert_eq!(console.lines().count(), MAX_LINES);
+        assert_eq!(console.lines().next().unwrap().text, format!("{}", MAX_LINES / 2));
+
+        console.clear();
+        assert_eq!(console.lines().count(), 0);
+    }
+
+    #[test]
+    fn test_draws_the_last_lines_and_the_input() {
+        let mut console = Console::default();
+        for index in 0..20 {
+            console.print(&format!("line {}", index), LineKind::Output);
+        }
+        console.handle_input(&typed("fl"), &[], &registry());
+
+        let mut ui = Ui::default();
+        let mut texts = TextObjects::default();
+        ui.begin_frame(UiInput::default(), UiSpace { extent: vk::Extent2D { width: 800, height: 600 }, scale_factor: 1.0 });
+        console.draw(&mut ui, 800.0);
+        ui.end_frame(&mut texts);
+
+        let contents = texts.iter().map(|text| text.get_content()).collect::<Vec<_>>();
+        assert_eq!(contents.len(), 1 + VISIBLE_LINES + 1);
+        assert_eq!(contents[1], "line 8");
+        assert_eq!(contents.last(), Some(&"> fl|"));
+        assert!(ui.wants_cursor());
+    }
+}
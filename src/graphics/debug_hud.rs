@@ -0,0 +1,224 @@
+use crate::core::stats::{format_bytes, DebugStats};
+use crate::graphics::text_object::{TextObject, TextOutline, TextSettings, TextSpan, TextStyle};
+use crate::graphics::text_objects::{TextHandle, TextObjects};
+use nalgebra_glm as glm;
+use std::fmt::Write;
+
+// Numbers changing every frame can't be read, and every change means uploading the text again
+const REFRESH_INTERVAL: f32 = 0.25;
+// Pixels per font unit, about 16 pixel text
+const SCALE: f32 = 0.008;
+const MARGIN: f32 = 8.0;
+
+// Each bar of the frame time graph shows the slowest of the frames it covers
+const GRAPH_BARS: usize = 40;
+// Frames at least this slow get a full bar
+const GRAPH_MAX_FRAME_TIME: f32 = 1.0 / 20.0;
+// Block elements from an eighth to a full line
+const GRAPH_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Overlay with performance and world numbers, toggled with F3. It's immediate mode: the caller
+// hands over fresh stats every frame and the HUD owns no state besides its text.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DebugHud {
+    visible: bool,
+    text: Option<TextHandle>,
+    since_refresh: f32,
+}
+
+impl DebugHud {
+    pub(crate) fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub(crate) fn toggle(&mut self, texts: &mut TextObjects) {
+        self.visible = !self.visible;
+        if let Some(handle) = self.text.take() {
+            texts.remove(handle);
+        }
+        self.since_refresh = 0.0;
+    }
+
+    pub(crate) fn draw(&mut self, texts: &mut TextObjects, stats: &DebugStats, delta_time: f32) {
+        if !self.visible {
+            return;
+        }
+        self.since_refresh += delta_time;
+        let text = self.text.filter(|handle| texts.contains(*handle));
+        if text.is_some() && self.since_refresh < REFRESH_INTERVAL {
+            return;
+        }
+        self.since_refresh = 0.0;
+
+        let spans = debug_spans(stats);
+        match text {
+            Some(handle) => {
+                texts.set_spans(handle, spans);
+            }
+            None => {
+                let outline = TextOutline::new(1.0, glm::vec4(0.0, 0.0, 0.0, 0.8));
+                let settings = TextSettings::new(SCALE, false).set_outline(Some(outline));
+                self.text = Some(texts.add(TextObject::from_spans(spans, glm::vec2(MARGIN, MARGIN), settings)));
+            }
+        }
+    }
+}
+
+// The HUD's lines, the frame time graph comes right after the FPS
+pub(crate) fn debug_spans(stats: &DebugStats) -> Vec<TextSpan> {
+    let style = TextStyle::default();
+    let frames = stats.frames;
+    let mut spans = vec![TextSpan::new(
+        format!(
+            "{:.0} FPS ({:.1} ms avg, {:.1} ms max)\n",
+            frames.fps(),
+            frames.average_frame_time() * 1000.0,
+            frames.max_frame_time() * 1000.0
+        ),
+        style,
+    )];
+    spans.extend(frame_time_graph(&frames.frame_times().collect::<Vec<_>>(), GRAPH_BARS));
+
+    let mut lines = String::new();
+    if let Some(player) = stats.player {
+        let (position, chunk) = (player.position, player.chunk());
+        let _ = write!(lines, "\nXYZ: {:.2} / {:.2} / {:.2}", position.x, position.y, position.z);
+        let _ = write!(lines, "\nChunk: {} {} {}", chunk.x, chunk.y, chunk.z);
+        let _ = write!(
+            lines,
+            "\nFacing: {}, yaw {:.1}°, pitch {:.1}°",
+            player.facing(),
+            player.yaw.to_degrees(),
+            player.pitch.to_degrees()
+        );
+    }
+    let world = stats.world;
+    let _ = write!(lines, "\nChunks: {} loaded, {} meshed", world.loaded_chunks, world.meshed_chunks);
+    let _ = write!(lines, "\nVertices: {}, indices: {}", world.vertices, world.indices);
+    for buffer in &stats.buffers {
+        let _ = write!(lines, "\n{} buffers: {} / {}", buffer.name, format_bytes(buffer.used), format_bytes(buffer.capacity));
+    }
+    spans.push(TextSpan::new(lines, style));
+    spans
+}
+
+// One line of bars, newest on the right. Green frames make 60 FPS, yellow ones 30 FPS.
+pub(crate) fn frame_time_graph(frame_times: &[f32], bars: usize) -> Vec<TextSpan> {
+    if frame_times.is_empty() || bars == 0 {
+        return vec![];
+    }
+    let frames_per_bar = frame_times.len().div_ceil(bars);
+    // The first bar is the one left short, so the newest frames always fill a whole bar
+    let first = frame_times.len() % frames_per_bar;
+    let groups = (first > 0).then(|| &frame_times[..first]).into_iter().chain(frame_times[first..].chunks(frames_per_bar));
+
+    let mut spans: Vec<TextSpan> = vec![];
+    for group in groups {
+        let frame_time = group.iter().copied().fold(0.0, f32::max);
+        let height = (frame_time / GRAPH_MAX_FRAME_TIME * GRAPH_BLOCKS.len() as f32).ceil() as usize;
+        let block = GRAPH_BLOCKS[height.clamp(1, GRAPH_BLOCKS.len()) - 1];
+        let color = if frame_time <= 1.0 / 60.0 {
+            glm::vec4(0.3, 0.9, 0.3, 1.0)
+        } else if frame_time <= 1.0 / 30.0 {
+            glm::vec4(0.95, 0.85, 0.2, 1.0)
+        } else {
+            glm::vec4(0.95, 0.25, 0.2, 1.0)
+        };
+
+        // Neighbouring bars of one colour share a span
+        match spans.last_mut() {
+            Some(span) if span.style.color == color => span.text.push(block),
+            _ => spans.push(TextSpan::new(block.to_string(), TextStyle::new(color))),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::stats::{BufferStats, FrameStats, PlayerStats, WorldStats};
+
+    fn stats(frames: &FrameStats) -> DebugStats<'_> {
+        DebugStats {
+            frames,
+            player: Some(PlayerStats {
+                position: glm::vec3(1.0, -2.0, 40.0),
+                yaw: std::f32::consts::PI,
+                pitch: 0.0,
+            }),
+            world: WorldStats {
+                loaded_chunks: 3,
+                meshed_chunks: 2,
+                vertices: 100,
+                indices: 150,
+            },
+            buffers: vec![BufferStats::new("Text", 1024, 2048)],
+        }
+    }
+
+    fn text(spans: &[TextSpan]) -> String {
+        spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_graph_bars_and_colors() {
+        let spans = frame_time_graph(&[1.0 / 120.0, 1.0 / 120.0, 1.0 / 120.0, 1.0 / 40.0, 1.0 / 10.0], 5);
+
+        assert_eq!(text(&spans), "▂▂▂▄█");
+        // Fast frames are green, slow ones yellow and red
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].text, "▂▂▂");
+        assert!(spans[1].style.color.x > 0.9 && spans[1].style.color.y > 0.8);
+        assert!(spans[2].style.color.y < 0.5);
+    }
+
+    #[test]
+    fn test_graph_keeps_the_newest_frames_together() {
+        // Seven frames in three bars, the short bar is the oldest one
+        let frame_times = [0.06, 0.001, 0.001, 0.001, 0.001, 0.001, 0.06];
+        assert_eq!(text(&frame_time_graph(&frame_times, 3)), "█▁█");
+        assert!(frame_time_graph(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_lines_show_every_stat() {
+        let mut frames = FrameStats::default();
+        frames.record(0.02);
+        let content = text(&debug_spans(&stats(&frames)));
+
+        assert!(content.starts_with("50 FPS (20.0 ms avg, 20.0 ms max)\n"), "{}", content);
+        assert!(content.contains("\nXYZ: 1.00 / -2.00 / 40.00\nChunk: 0 -1 1\n"));
+        assert!(content.contains("\nFacing: south (-Y), yaw 180.0°, pitch 0.0°"));
+        assert!(content.contains("\nChunks: 3 loaded, 2 meshed\nVertices: 100, indices: 150"));
+        assert!(content.ends_with("\nText buffers: 1.0 KiB / 2.0 KiB"));
+    }
+
+    #[test]
+    fn test_toggle_and_refresh() {
+        let mut texts = TextObjects::default();
+        let mut hud = DebugHud::default();
+        let mut frames = FrameStats::default();
+        frames.record(0.02);
+
+        // Hidden, nothing is drawn
+        hud.draw(&mut texts, &stats(&frames), 0.02);
+        assert!(texts.is_empty());
+
+        hud.toggle(&mut texts);
+        hud.draw(&mut texts, &stats(&frames), 0.02);
+        let handle = hud.text.unwrap();
+        let first = texts.get(handle).unwrap().get_content().to_string();
+
+        // New numbers wait for the refresh interval
+        frames.record(0.04);
+        hud.draw(&mut texts, &stats(&frames), 0.1);
+        assert_eq!(texts.get(handle).unwrap().get_content(), first);
+        hud.draw(&mut texts, &stats(&frames), 0.2);
+        assert_ne!(texts.get(handle).unwrap().get_content(), first);
+
+        hud.toggle(&mut texts);
+        assert!(!hud.is_visible());
+        assert!(texts.is_empty());
+    }
+}
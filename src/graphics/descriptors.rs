@@ -10,9 +10,10 @@ pub(crate) unsafe fn create_descriptor_pool(device: &Device, data: &mut AppData)
         .type_(vk::DescriptorType::UNIFORM_BUFFER)
         .descriptor_count(data.swapchain_images.len() as u32);
 
+    // The block texture and the shadow map
     let sampler_size = vk::DescriptorPoolSize::builder()
         .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-        .descriptor_count(data.swapchain_images.len() as u32);
+        .descriptor_count(data.swapchain_images.len() as u32 * 2);
 
     let pool_sizes = &[ubo_size, sampler_size];
     let info = vk::DescriptorPoolCreateInfo::builder()
@@ -81,7 +82,20 @@ pub(crate) unsafe fn create_descriptor_sets(device: &Device, data: &mut AppData)
             .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
             .image_info(image_info);
 
-        device.update_descriptor_sets(&[ubo_write, sampler_write], &[] as &[vk::CopyDescriptorSet]);
+        let info = vk::DescriptorImageInfo::builder()
+            .image_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL)
+            .image_view(data.shadow_image_view)
+            .sampler(data.shadow_sampler);
+
+        let shadow_map_info = &[info];
+        let shadow_map_write = vk::WriteDescriptorSet::builder()
+            .dst_set(data.descriptor_sets[i])
+            .dst_binding(2)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(shadow_map_info);
+
+        device.update_descriptor_sets(&[ubo_write, sampler_write, shadow_map_write], &[] as &[vk::CopyDescriptorSet]);
     }
 
     Ok(())
@@ -102,7 +116,14 @@ pub(crate) unsafe fn create_text_descriptor_sets(
 
     // Update
 
-    for i in 0..data.swapchain_images.len() {
+    update_text_descriptor_sets(device, data);
+
+    Ok(())
+}
+
+// Points the text descriptor sets at the current text texture, which is replaced when the glyph atlas grows
+pub(crate) unsafe fn update_text_descriptor_sets(device: &Device, data: &AppData) {
+    for descriptor_set in &data.text_descriptor_sets {
         let info = vk::DescriptorImageInfo::builder()
             .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
             .image_view(data.text_texture_image_view)
@@ -110,7 +131,7 @@ pub(crate) unsafe fn create_text_descriptor_sets(
 
         let image_info = &[info];
         let sampler_write = vk::WriteDescriptorSet::builder()
-            .dst_set(data.text_descriptor_sets[i])
+            .dst_set(*descriptor_set)
             .dst_binding(0)
             .dst_array_element(0)
             .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
@@ -118,6 +139,24 @@ pub(crate) unsafe fn create_text_descriptor_sets(
 
         device.update_descriptor_sets(&[sampler_write], &[] as &[vk::CopyDescriptorSet]);
     }
+}
 
-    Ok(())
+// Points the world descriptor sets at the current block texture, which is replaced when it's reloaded
+pub(crate) unsafe fn update_texture_descriptor_sets(device: &Device, data: &AppData) {
+    for descriptor_set in &data.descriptor_sets {
+        let info = vk::DescriptorImageInfo::builder()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(data.texture_image_view)
+            .sampler(data.texture_sampler);
+
+        let image_info = &[info];
+        let sampler_write = vk::WriteDescriptorSet::builder()
+            .dst_set(*descriptor_set)
+            .dst_binding(1)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(image_info);
+
+        device.update_descriptor_sets(&[sampler_write], &[] as &[vk::CopyDescriptorSet]);
+    }
 }
@@ -1,7 +1,17 @@
+use std::collections::HashMap;
+
 #[derive(Clone, Debug, Default)]
 pub(crate) struct FontData {
     pub(crate) line_gap: i16,
     pub(crate) global_bounding_box: Rect,
+    // Adjustment of the advance between two characters in font units, from the font's kern table
+    pub(crate) kerning: HashMap<(u32, u32), i16>,
+}
+
+impl FontData {
+    pub(crate) fn kerning(&self, left: u32, right: u32) -> i16 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
@@ -0,0 +1,360 @@
+use crate::graphics::font_data::{FontData, Rect};
+use crate::graphics::glyph_atlas::{AtlasRegion, GlyphAtlas};
+use crate::graphics::sdf::distance_field;
+use anyhow::{anyhow, Result};
+use fontdue::FontSettings;
+use log::warn;
+use nalgebra_glm as glm;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// Used when the configured font can't be loaded and for characters other fonts don't have
+const EMBEDDED_FONT: &[u8] = include_bytes!("../../resources/fonts/DejaVuSans.ttf");
+const EMBEDDED_FONT_NAME: &str = "DejaVu Sans (embedded)";
+
+// Every glyph is stored once as a distance field this many pixels per em, which stays sharp at
+// any text size. The spread is how far outlines and soft edges can reach past the glyph.
+const SDF_SIZE: f32 = 48.0;
+const SDF_SPREAD: u32 = 8;
+// Glyphs are rasterized this many times larger so the outline is found with sub pixel precision
+const SDF_SUPERSAMPLING: u32 = 2;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Character {
+    pub(crate) character: u32,
+    pub(crate) texture_coordinates: Option<(f32, f32, f32, f32)>,
+    pub(crate) size: glm::Vec2,
+    pub(crate) bearing: glm::Vec2,
+    pub(crate) bounding_box: Rect,
+    pub(crate) advance: u16,
+    // Font units between the distance field values 0 and 1
+    pub(crate) distance_range: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct FontId(usize);
+
+#[derive(Clone)]
+pub(crate) struct Font {
+    name: String,
+    font: fontdue::Font,
+    font_data: FontData,
+}
+
+impl fmt::Debug for Font {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Font").field("name", &self.name).field("font_data", &self.font_data).finish()
+    }
+}
+
+impl Font {
+    pub(crate) fn from_bytes(name: impl Into<String>, bytes: &[u8]) -> Result<Self> {
+        let name = name.into();
+        let face = ttf_parser::Face::parse(bytes, 0).map_err(|error| anyhow!("Couldn't parse font {}: {}", name, error))?;
+        let font = fontdue::Font::from_bytes(bytes, FontSettings::default())
+            .map_err(|error| anyhow!("Couldn't load font {}: {}", name, error))?;
+        let font_data = FontData {
+            line_gap: face.line_gap(),
+            global_bounding_box: Rect::from_ttf_parser_rect(face.global_bounding_box()),
+            kerning: kerning_pairs(&face, &font),
+        };
+        Ok(Self { name, font, font_data })
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|error| anyhow!("Couldn't read font {:?}: {}", path, error))?;
+        Self::from_bytes(path.display().to_string(), &bytes)
+    }
+
+    pub(crate) fn embedded() -> Self {
+        Self::from_bytes(EMBEDDED_FONT_NAME, EMBEDDED_FONT).expect("The embedded font is valid")
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn units_per_em(&self) -> f32 {
+        self.font.units_per_em()
+    }
+
+    fn has_glyph(&self, char: char) -> bool {
+        self.font.lookup_glyph_index(char) != 0
+    }
+}
+
+// Every loaded font face and the glyphs rasterized from them so far. Glyphs are added to the atlas
+// the first time some text uses them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Fonts {
+    fonts: Vec<Font>,
+    characters: HashMap<FontId, HashMap<u32, Character>>,
+    regions: HashMap<(FontId, u32), AtlasRegion>,
+    atlas: GlyphAtlas,
+}
+
+impl Fonts {
+    // The font at `path` becomes the default font, the embedded font is always loaded as a fallback
+    pub(crate) fn load_default(path: &Path) -> Self {
+        let mut fonts = Self::default();
+        match Font::load(path) {
+            Ok(font) => {
+                fonts.add_font(font);
+            }
+            Err(error) => warn!("{}, using the embedded font", error),
+        }
+        fonts.add_font(Font::embedded());
+        fonts
+    }
+
+    pub(crate) fn add_font(&mut self, font: Font) -> FontId {
+        self.fonts.push(font);
+        FontId(self.fonts.len() - 1)
+    }
+
+    pub(crate) fn load_font(&mut self, path: &Path) -> Result<FontId> {
+        Ok(self.add_font(Font::load(path)?))
+    }
+
+    pub(crate) fn font(&self, id: FontId) -> Option<&Font> {
+        self.fonts.get(id.0)
+    }
+
+    // Unknown ids fall back to the default font
+    pub(crate) fn font_data(&self, id: FontId) -> Option<&FontData> {
+        self.font_or_default(id).map(|font| &font.font_data)
+    }
+
+    pub(crate) fn atlas(&self) -> &GlyphAtlas {
+        &self.atlas
+    }
+
+    pub(crate) fn atlas_mut(&mut self) -> &mut GlyphAtlas {
+        &mut self.atlas
+    }
+
+    // Rasterizes the characters that aren't in the atlas yet. Returns whether the atlas grew, which
+    // moves the texture coordinates of everything already laid out.
+    pub(crate) fn prepare(&mut self, id: FontId, chars: &[u32]) -> Result<bool> {
+        let id = self.resolve(id);
+        let Some(font) = self.fonts.get(id.0) else {
+            return Ok(false);
+        };
+        // Glyph metrics are stored in the units of the requested font, whichever face draws them
+        let units_per_pixel = font.units_per_em() / SDF_SIZE;
+        let height = self.atlas.height();
+
+        let characters = self.characters.entry(id).or_default();
+        for &code in chars {
+            let Some(char) = char::from_u32(code) else {
+                continue;
+            };
+            if characters.contains_key(&code) || char.is_control() {
+                continue;
+            }
+
+            let face = if font.has_glyph(char) {
+                font
+            } else {
+                self.fonts.iter().find(|other| other.has_glyph(char)).unwrap_or(font)
+            };
+            // Characters no font has are drawn as the missing glyph box of the requested font
+            let (metrics, coverage) = face.font.rasterize(char, SDF_SIZE * SDF_SUPERSAMPLING as f32);
+            let to_units = |pixels: f32| (pixels * units_per_pixel).round() as i16;
+            let (region, bounding_box) = if metrics.width == 0 || metrics.height == 0 {
+                (AtlasRegion::default(), Rect::default())
+            } else {
+                let field = distance_field(metrics.width as u32, metrics.height as u32, &coverage, SDF_SPREAD, SDF_SUPERSAMPLING);
+                let region = self.atlas.insert(field.width, field.height, &field.values)?;
+                // The field reaches past the glyph by the spread on every side
+                let x_min = metrics.xmin as f32 / SDF_SUPERSAMPLING as f32 - SDF_SPREAD as f32;
+                let y_min = metrics.ymin as f32 / SDF_SUPERSAMPLING as f32 - SDF_SPREAD as f32;
+                let bounding_box = Rect {
+                    x_min: to_units(x_min),
+                    y_min: to_units(y_min),
+                    x_max: to_units(x_min + field.width as f32),
+                    y_max: to_units(y_min + field.height as f32),
+                };
+                (region, bounding_box)
+            };
+            self.regions.insert((id, code), region);
+
+            characters.insert(code, Character {
+                character: code,
+                texture_coordinates: Some(self.atlas.texture_coordinates(&region)),
+                size: glm::vec2(bounding_box.width() as f32, bounding_box.height() as f32),
+                bearing: glm::vec2(bounding_box.x_min as f32, bounding_box.y_max as f32),
+                bounding_box,
+                advance: (metrics.advance_width / SDF_SUPERSAMPLING as f32 * units_per_pixel).round().max(0.0) as u16,
+                distance_range: (SDF_SPREAD * 2) as f32 * units_per_pixel,
+            });
+        }
+
+        let grew = self.atlas.height() != height;
+        if grew {
+            self.update_texture_coordinates();
+        }
+        Ok(grew)
+    }
+
+    // The glyphs prepared for text of this font
+    pub(crate) fn characters(&self, id: FontId) -> Option<&HashMap<u32, Character>> {
+        self.characters.get(&self.resolve(id))
+    }
+
+    fn resolve(&self, id: FontId) -> FontId {
+        if id.0 < self.fonts.len() {
+            id
+        } else {
+            FontId::default()
+        }
+    }
+
+    fn font_or_default(&self, id: FontId) -> Option<&Font> {
+        self.fonts.get(self.resolve(id).0)
+    }
+
+    fn update_texture_coordinates(&mut self) {
+        for (&(id, code), region) in &self.regions {
+            if let Some(character) = self.characters.get_mut(&id).and_then(|characters| characters.get_mut(&code)) {
+                character.texture_coordinates = Some(self.atlas.texture_coordinates(region));
+            }
+        }
+    }
+}
+
+// Horizontal pairs of the kern table, keyed by character instead of glyph so layout doesn't need the font
+fn kerning_pairs(face: &ttf_parser::Face, font: &fontdue::Font) -> HashMap<(u32, u32), i16> {
+    let mut chars_of_glyph = HashMap::<u16, Vec<u32>>::new();
+    for (char, glyph) in font.chars() {
+        chars_of_glyph.entry(glyph.get()).or_default().push(*char as u32);
+    }
+
+    let mut kerning = HashMap::new();
+    let Some(table) = face.tables().kern else {
+        return kerning;
+    };
+    for subtable in table.subtables {
+        let ttf_parser::kern::Format::Format0(pairs) = subtable.format else {
+            continue;
+        };
+        if !subtable.horizontal || subtable.variable || subtable.has_cross_stream {
+            continue;
+        }
+        for pair in pairs.pairs {
+            let (Some(lefts), Some(rights)) = (chars_of_glyph.get(&pair.left().0), chars_of_glyph.get(&pair.right().0)) else {
+                continue;
+            };
+            for left in lefts {
+                for right in rights {
+                    kerning.entry((*left, *right)).or_insert(pair.value);
+                }
+            }
+        }
+    }
+    kerning
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<u32> {
+        text.chars().map(|char| char as u32).collect()
+    }
+
+    #[test]
+    fn test_missing_font_falls_back_to_embedded() {
+        let fonts = Fonts::load_default(Path::new("does/not/exist.ttf"));
+
+        assert_eq!(fonts.font(FontId::default()).unwrap().name(), EMBEDDED_FONT_NAME);
+        assert!(fonts.font_data(FontId::default()).unwrap().global_bounding_box.height() > 0);
+        assert!(Fonts::default().load_font(Path::new("does/not/exist.ttf")).is_err());
+        assert!(Font::from_bytes("broken", &[0, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_rasterizes_glyphs_on_demand() {
+        let mut fonts = Fonts::default();
+        let id = fonts.add_font(Font::embedded());
+        assert!(fonts.characters(id).is_none());
+
+        // Cyrillic and ASCII, control characters are left out
+        fonts.prepare(id, &chars("Привет, world\t")).unwrap();
+        let characters = fonts.characters(id).unwrap();
+        assert_eq!(characters.len(), 13);
+        assert!(!characters.contains_key(&('\t' as u32)));
+
+        let character = &characters[&('П' as u32)];
+        assert!(character.bounding_box.width() > 0 && character.bounding_box.height() > 0);
+        assert!(character.advance > 0);
+        let (x_start, x_end, y_start, y_end) = character.texture_coordinates.unwrap();
+        assert!(x_start < x_end && y_start < y_end && x_end <= 1.0 && y_end <= 1.0);
+
+        // Spaces have an advance but nothing to draw
+        let space = &characters[&(' ' as u32)];
+        assert!(space.advance > 0 && space.bounding_box.width() == 0);
+
+        assert!(fonts.atlas_mut().take_changed());
+        fonts.prepare(id, &chars("world")).unwrap();
+        assert!(!fonts.atlas_mut().take_changed());
+    }
+
+    #[test]
+    fn test_reads_kerning_pairs() {
+        let font = Font::embedded();
+        let kerning = |left: char, right: char| font.font_data.kerning(left as u32, right as u32);
+
+        // Classic pairs pull together, unrelated ones don't change
+        assert!(kerning('A', 'V') < 0);
+        assert!(kerning('T', 'o') < 0);
+        assert_eq!(kerning('l', 'l'), 0);
+    }
+
+    #[test]
+    fn test_glyphs_are_distance_fields() {
+        let mut fonts = Fonts::default();
+        let id = fonts.add_font(Font::embedded());
+        let units_per_em = fonts.font(id).unwrap().units_per_em();
+        fonts.prepare(id, &chars("l")).unwrap();
+        let character = fonts.characters(id).unwrap()[&('l' as u32)].clone();
+        let region = fonts.regions[&(id, 'l' as u32)];
+
+        // The spread on both sides in font units
+        assert_eq!(character.distance_range, 16.0 * units_per_em / SDF_SIZE);
+        let (metrics, _) = Font::embedded().font.rasterize('l', SDF_SIZE);
+        let padded_width = (metrics.width as f32 + 16.0) * units_per_em / SDF_SIZE;
+        assert!((character.bounding_box.width() as f32 - padded_width).abs() < units_per_em / SDF_SIZE * 1.5);
+
+        // Across the middle of the stem the field rises above the edge value and falls off again
+        let atlas = fonts.atlas();
+        let row = (region.y + region.height / 2) * atlas.width();
+        let values = (region.x..region.x + region.width)
+            .map(|x| atlas.pixels()[((row + x) * 4) as usize])
+            .collect::<Vec<_>>();
+        // The borders are about the spread away from the stem
+        assert!(values[0] < 16 && values[values.len() - 1] < 16, "{:?}", values);
+        let inside = values.iter().filter(|value| **value > 128).count() as f32;
+        assert!((inside - metrics.width as f32).abs() <= 1.0, "{:?}", values);
+        assert!(values.windows(2).filter(|pair| (pair[0] > 128) != (pair[1] > 128)).count() == 2);
+    }
+
+    #[test]
+    fn test_growing_atlas_updates_texture_coordinates() {
+        let mut fonts = Fonts::default();
+        let id = fonts.add_font(Font::embedded());
+        fonts.atlas = GlyphAtlas::new(256, 32, 4096);
+
+        assert!(fonts.prepare(id, &chars("A")).unwrap());
+        let before = fonts.characters(id).unwrap()[&('A' as u32)].texture_coordinates.unwrap();
+        let height = fonts.atlas().height();
+
+        assert!(fonts.prepare(id, &chars("BCDEFGHIJKLMNOP")).unwrap());
+        let after = fonts.characters(id).unwrap()[&('A' as u32)].texture_coordinates.unwrap();
+        let ratio = height as f32 / fonts.atlas().height() as f32;
+        assert_eq!((after.0, after.1), (before.0, before.1));
+        assert!((after.2 - before.2 * ratio).abs() < 1e-6 && (after.3 - before.3 * ratio).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::{anyhow, Result};
+
+// Empty border around every glyph so linear filtering doesn't pick up the neighbours
+const PADDING: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct AtlasRegion {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    // Where the next glyph on this shelf goes
+    x: u32,
+}
+
+// RGBA texture that glyphs are packed into row by row as they are first needed. It only grows
+// downwards, so horizontal texture coordinates never change and vertical ones are halved.
+#[derive(Clone, Debug)]
+pub(crate) struct GlyphAtlas {
+    width: u32,
+    height: u32,
+    max_height: u32,
+    pixels: Vec<u8>,
+    shelves: Vec<Shelf>,
+    // Pixels changed since the texture was last uploaded
+    changed: bool,
+}
+
+impl Default for GlyphAtlas {
+    fn default() -> Self {
+        Self::new(1024, 256, 8192)
+    }
+}
+
+impl GlyphAtlas {
+    pub(crate) fn new(width: u32, height: u32, max_height: u32) -> Self {
+        Self {
+            width,
+            height,
+            max_height: max_height.max(height),
+            pixels: vec![0; (width * height * 4) as usize],
+            shelves: vec![],
+            changed: true,
+        }
+    }
+
+    pub(crate) fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub(crate) fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // Returns whether the texture needs uploading again and resets it
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+
+    // Copies an 8 bit coverage bitmap into the atlas, growing it when it's full
+    pub(crate) fn insert(&mut self, width: u32, height: u32, coverage: &[u8]) -> Result<AtlasRegion> {
+        if coverage.len() != (width * height) as usize {
+            return Err(anyhow!("A {}x{} glyph needs {} bytes, got {}", width, height, width * height, coverage.len()));
+        }
+        if width == 0 || height == 0 {
+            return Ok(AtlasRegion::default());
+        }
+        let (padded_width, padded_height) = (width + PADDING * 2, height + PADDING * 2);
+        if padded_width > self.width {
+            return Err(anyhow!("A {} pixel wide glyph doesn't fit into the {} pixel wide atlas", width, self.width));
+        }
+
+        let region = match self.find_space(padded_width, padded_height) {
+            Some(region) => region,
+            None => {
+                let bottom = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+                let mut height = self.height;
+                while bottom + padded_height > height {
+                    height *= 2;
+                }
+                if height > self.max_height {
+                    return Err(anyhow!("The glyph atlas is full at {}x{}", self.width, self.height));
+                }
+                self.grow(height);
+                self.shelves.push(Shelf {
+                    y: bottom,
+                    height: padded_height,
+                    x: 0,
+                });
+                self.find_space(padded_width, padded_height).unwrap()
+            }
+        };
+
+        let region = AtlasRegion {
+            x: region.x + PADDING,
+            y: region.y + PADDING,
+            width,
+            height,
+        };
+        for row in 0..height {
+            for column in 0..width {
+                let value = coverage[(row * width + column) as usize];
+                let index = (((region.y + row) * self.width + region.x + column) * 4) as usize;
+                self.pixels[index..index + 4].copy_from_slice(&[value; 4]);
+            }
+        }
+        self.changed = true;
+        Ok(region)
+    }
+
+    // Texture coordinates as (x_start, x_end, y_start, y_end), like Character stores them
+    pub(crate) fn texture_coordinates(&self, region: &AtlasRegion) -> (f32, f32, f32, f32) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        (
+            region.x as f32 / width,
+            (region.x + region.width) as f32 / width,
+            region.y as f32 / height,
+            (region.y + region.height) as f32 / height,
+        )
+    }
+
+    // A free spot on an existing shelf that isn't much taller than the glyph, or a new shelf
+    fn find_space(&mut self, width: u32, height: u32) -> Option<AtlasRegion> {
+        let atlas_width = self.width;
+        let fits = |shelf: &Shelf| shelf.height >= height && shelf.height <= height * 3 / 2 + PADDING * 2 && shelf.x + width <= atlas_width;
+        if let Some(shelf) = self.shelves.iter_mut().find(|shelf| fits(shelf)) {
+            let region = AtlasRegion {
+                x: shelf.x,
+                y: shelf.y,
+                width,
+                height,
+            };
+            shelf.x += width;
+            return Some(region);
+        }
+
+        let bottom = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+        if bottom + height > self.height {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y: bottom,
+            height,
+            x: width,
+        });
+        Some(AtlasRegion {
+            x: 0,
+            y: bottom,
+            width,
+            height,
+        })
+    }
+
+    fn grow(&mut self, height: u32) {
+        self.pixels.resize((self.width * height * 4) as usize, 0);
+        self.height = height;
+        self.changed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph(width: u32, height: u32, value: u8) -> Vec<u8> {
+        vec![value; (width * height) as usize]
+    }
+
+    fn overlaps(a: &AtlasRegion, b: &AtlasRegion) -> bool {
+        a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    #[test]
+    fn test_packs_without_overlap() {
+        let mut atlas = GlyphAtlas::new(64, 64, 64);
+        let mut regions = vec![];
+        for size in [10, 12, 8, 10, 20, 6, 12, 10, 9, 14] {
+            regions.push(atlas.insert(size, size, &glyph(size, size, 255)).unwrap());
+        }
+
+        for (index, region) in regions.iter().enumerate() {
+            assert!(region.x + region.width <= 64 && region.y + region.height <= 64);
+            for other in &regions[index + 1..] {
+                assert!(!overlaps(region, other), "{:?} overlaps {:?}", region, other);
+            }
+        }
+    }
+
+    #[test]
+    fn test_copies_coverage_with_padding() {
+        let mut atlas = GlyphAtlas::new(16, 16, 16);
+        assert!(atlas.take_changed());
+        assert!(!atlas.take_changed());
+
+        let region = atlas.insert(2, 1, &[10, 20]).unwrap();
+        assert_eq!(region, AtlasRegion { x: 1, y: 1, width: 2, height: 1 });
+        assert!(atlas.take_changed());
+
+        let pixel = |x: u32, y: u32| atlas.pixels()[((y * 16 + x) * 4) as usize];
+        assert_eq!((pixel(0, 1), pixel(1, 1), pixel(2, 1), pixel(3, 1)), (0, 10, 20, 0));
+        assert_eq!(atlas.texture_coordinates(&region), (1.0 / 16.0, 3.0 / 16.0, 1.0 / 16.0, 2.0 / 16.0));
+    }
+
+    #[test]
+    fn test_grows_downwards_until_the_limit() {
+        let mut atlas = GlyphAtlas::new(32, 16, 64);
+        let first = atlas.insert(30, 14, &glyph(30, 14, 1)).unwrap();
+        let second = atlas.insert(30, 14, &glyph(30, 14, 2)).unwrap();
+
+        assert_eq!(atlas.height(), 32);
+        assert_eq!(atlas.pixels().len(), 32 * 32 * 4);
+        assert_eq!(second.y, first.y + 16);
+        // Existing pixels stay where they were
+        assert_eq!(atlas.pixels()[((first.y * 32 + first.x) * 4) as usize], 1);
+
+        atlas.insert(30, 30, &glyph(30, 30, 3)).unwrap();
+        assert_eq!(atlas.height(), 64);
+        assert!(atlas.insert(30, 14, &glyph(30, 14, 4)).is_err());
+        assert!(atlas.insert(40, 4, &glyph(40, 4, 5)).is_err());
+        assert!(atlas.insert(2, 2, &[0]).is_err());
+    }
+
+    #[test]
+    fn test_empty_glyphs_take_no_space() {
+        let mut atlas = GlyphAtlas::new(16, 16, 16);
+        assert_eq!(atlas.insert(0, 0, &[]).unwrap(), AtlasRegion::default());
+        assert_eq!(atlas.insert(14, 14, &glyph(14, 14, 1)).unwrap().x, 1);
+    }
+}
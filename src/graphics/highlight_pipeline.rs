@@ -0,0 +1,197 @@
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+use crate::core::app_data::AppData;
+use crate::graphics::shader_files::{CRACK_FRAG, CRACK_VERT, OUTLINE_FRAG, OUTLINE_VERT};
+
+// Pulls the overlays in front of the voxel faces they lie on
+const DEPTH_BIAS_CONSTANT: f32 = -1.0;
+const DEPTH_BIAS_SLOPE: f32 = -1.5;
+
+// The outline of the targeted voxel and the cracks of the one being broken. Both are drawn in the
+// world render pass after the chunks and build their vertices from the voxel position alone, so
+// they need no vertex buffers.
+pub(crate) unsafe fn create_highlight_pipelines(device: &Device, data: &mut AppData) -> Result<()> {
+    // Push Constant Ranges
+
+    // The voxel position and the crack stage
+    let push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+        .offset(0)
+        .size(16 /*4 x 4 byte floats (one vec4)*/);
+
+    // Layout
+
+    // Only the view and projection of the world descriptor sets are read
+    let set_layouts = &[data.descriptor_set_layout];
+    let push_constant_ranges = &[push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.highlight_pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+
+    // Create
+
+    data.outline_pipeline = create_highlight_pipeline(
+        device,
+        data,
+        &OUTLINE_VERT.load(),
+        &OUTLINE_FRAG.load(),
+        vk::PrimitiveTopology::LINE_LIST,
+    )?;
+    data.crack_pipeline = create_highlight_pipeline(
+        device,
+        data,
+        &CRACK_VERT.load(),
+        &CRACK_FRAG.load(),
+        vk::PrimitiveTopology::TRIANGLE_LIST,
+    )?;
+
+    Ok(())
+}
+
+unsafe fn create_highlight_pipeline(
+    device: &Device,
+    data: &AppData,
+    vert: &[u8],
+    frag: &[u8],
+    topology: vk::PrimitiveTopology,
+) -> Result<vk::Pipeline> {
+    // Stages
+
+    let vert_shader_module = create_shader_module(device, vert)?;
+    let frag_shader_module = create_shader_module(device, frag)?;
+
+    let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(vert_shader_module)
+        .name(b"main\0");
+
+    let frag_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::FRAGMENT)
+        .module(frag_shader_module)
+        .name(b"main\0");
+
+    // Vertex Input State
+
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder();
+
+    // Input Assembly State
+
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(topology)
+        .primitive_restart_enable(false);
+
+    // Viewport State
+
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(data.swapchain_extent.width as f32)
+        .height(data.swapchain_extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    let scissor = vk::Rect2D::builder()
+        .offset(vk::Offset2D { x: 0, y: 0 })
+        .extent(data.swapchain_extent);
+
+    let viewports = &[viewport];
+    let scissors = &[scissor];
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewports(viewports)
+        .scissors(scissors);
+
+    // Rasterization State
+
+    // Faces are seen from outside and lines have no side, nothing is culled
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(true)
+        .depth_bias_constant_factor(DEPTH_BIAS_CONSTANT)
+        .depth_bias_clamp(0.0)
+        .depth_bias_slope_factor(DEPTH_BIAS_SLOPE);
+
+    // Multisample State
+
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(false)
+        .rasterization_samples(vk::SampleCountFlags::_1);
+
+    // Color Blend State
+
+    let attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vk::ColorComponentFlags::all())
+        .blend_enable(true)
+        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+        .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+        .alpha_blend_op(vk::BlendOp::ADD);
+
+    let attachments = &[attachment];
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(attachments)
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+    //Depth stencil state
+
+    // Hidden by whatever is in front of the voxel, without hiding anything themselves
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(false)
+        .depth_compare_op(vk::CompareOp::LESS_OR_EQUAL)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    // Create
+
+    let stages = &[vert_stage, frag_stage];
+    let info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .layout(data.highlight_pipeline_layout)
+        .render_pass(data.render_pass)
+        .subpass(0);
+
+    let pipeline = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0;
+
+    // Cleanup
+
+    device.destroy_shader_module(vert_shader_module, None);
+    device.destroy_shader_module(frag_shader_module, None);
+
+    Ok(pipeline)
+}
+
+unsafe fn create_shader_module(device: &Device, bytecode: &[u8]) -> Result<vk::ShaderModule> {
+    let bytecode = Vec::<u8>::from(bytecode);
+    let (prefix, code, suffix) = bytecode.align_to::<u32>();
+    if !prefix.is_empty() || !suffix.is_empty() {
+        return Err(anyhow!("Shader bytecode is not properly aligned."));
+    }
+
+    let info = vk::ShaderModuleCreateInfo::builder()
+        .code_size(bytecode.len())
+        .code(code);
+
+    Ok(device.create_shader_module(&info, None)?)
+}
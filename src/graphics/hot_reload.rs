@@ -0,0 +1,136 @@
+use anyhow::Result;
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use vulkanalia::prelude::v1_0::*;
+use crate::core::app_data::AppData;
+use crate::core::file_watcher::FileWatcher;
+use crate::graphics::descriptors::update_texture_descriptor_sets;
+use crate::graphics::highlight_pipeline::create_highlight_pipelines;
+use crate::graphics::pipeline::create_pipeline;
+use crate::graphics::shader_files::{affected_pipelines, PipelineKind, SHADER_FILES};
+use crate::graphics::shadow_maps::create_shadow_pipeline;
+use crate::graphics::shared_textures::{create_texture_image_from_path, create_texture_image_view};
+use crate::graphics::sky_pipeline::create_sky_pipeline;
+use crate::graphics::text_pipeline::create_text_pipeline;
+use crate::graphics::ui_pipeline::create_ui_pipeline;
+
+pub(crate) const BLOCK_TEXTURE: &str = "resources/blocks.png";
+
+// The shader sources and the block texture
+pub(crate) fn watch_assets(watcher: &mut FileWatcher) {
+    SHADER_FILES.iter().for_each(|file| watcher.watch(file.path));
+    watcher.watch(BLOCK_TEXTURE);
+}
+
+// Rebuilds whatever the changed files go into. A pipeline whose shaders don't compile and a texture
+// that doesn't load are logged and the last good one is kept, so a typo doesn't end the game.
+pub(crate) unsafe fn reload_changed(instance: &Instance, device: &Device, data: &mut AppData, changed: &[PathBuf]) -> Result<()> {
+    let kinds = affected_pipelines(changed);
+    let texture_changed = changed.iter().any(|path| path == Path::new(BLOCK_TEXTURE));
+    if kinds.is_empty() && !texture_changed {
+        return Ok(());
+    }
+
+    // Nothing may be drawing with what gets replaced
+    device.device_wait_idle()?;
+
+    for kind in kinds {
+        match reload_pipeline(device, data, kind) {
+            Ok(()) => info!("Reloaded the {:?} pipeline", kind),
+            Err(reload_error) => error!("Kept the last {:?} pipeline: {}", kind, reload_error),
+        }
+    }
+    if texture_changed {
+        match reload_block_texture(instance, device, data) {
+            Ok(()) => info!("Reloaded {}", BLOCK_TEXTURE),
+            Err(reload_error) => error!("Kept the last block texture, {} didn't load: {}", BLOCK_TEXTURE, reload_error),
+        }
+    }
+    Ok(())
+}
+
+unsafe fn reload_pipeline(device: &Device, data: &mut AppData, kind: PipelineKind) -> Result<()> {
+    // The create functions load the stored SPIR-V, which goes back to the last good one if creating fails
+    let compiled = kind.compile()?;
+    let last_good = kind.loaded();
+    kind.store(compiled);
+
+    let old = handles(data, kind);
+    let created = match kind {
+        PipelineKind::World => create_pipeline(device, data),
+        PipelineKind::Sky => create_sky_pipeline(device, data),
+        PipelineKind::Highlight => create_highlight_pipelines(device, data),
+        PipelineKind::Shadow => create_shadow_pipeline(device, data),
+        PipelineKind::Text => create_text_pipeline(device, data),
+        PipelineKind::Ui => create_ui_pipeline(device, data),
+    };
+
+    let new = handles(data, kind);
+    if created.is_ok() {
+        destroy_unused(device, &old, &new);
+    } else {
+        // Creating can fail half way, after some of the handles were replaced
+        destroy_unused(device, &new, &old);
+        set_handles(data, kind, &old);
+        kind.store(last_good);
+    }
+    created
+}
+
+type Handles = (vk::PipelineLayout, Vec<vk::Pipeline>);
+
+// Destroys the handles that aren't also kept
+unsafe fn destroy_unused(device: &Device, (layout, pipelines): &Handles, (kept_layout, kept_pipelines): &Handles) {
+    pipelines
+        .iter()
+        .filter(|pipeline| !kept_pipelines.contains(pipeline))
+        .for_each(|pipeline| device.destroy_pipeline(*pipeline, None));
+    if layout != kept_layout {
+        device.destroy_pipeline_layout(*layout, None);
+    }
+}
+
+// The layout and the pipelines of a kind
+fn handles(data: &AppData, kind: PipelineKind) -> Handles {
+    match kind {
+        PipelineKind::World => (data.pipeline_layout, vec![data.pipeline]),
+        PipelineKind::Sky => (data.sky_pipeline_layout, vec![data.sky_pipeline]),
+        PipelineKind::Highlight => (data.highlight_pipeline_layout, vec![data.outline_pipeline, data.crack_pipeline]),
+        PipelineKind::Shadow => (data.shadow_pipeline_layout, vec![data.shadow_pipeline]),
+        PipelineKind::Text => (data.text_pipeline_layout, vec![data.text_pipeline]),
+        PipelineKind::Ui => (data.ui_pipeline_layout, vec![data.ui_pipeline]),
+    }
+}
+
+fn set_handles(data: &mut AppData, kind: PipelineKind, (layout, pipelines): &Handles) {
+    match kind {
+        PipelineKind::World => (data.pipeline_layout, data.pipeline) = (*layout, pipelines[0]),
+        PipelineKind::Sky => (data.sky_pipeline_layout, data.sky_pipeline) = (*layout, pipelines[0]),
+        PipelineKind::Highlight => {
+            (data.highlight_pipeline_layout, data.outline_pipeline, data.crack_pipeline) = (*layout, pipelines[0], pipelines[1])
+        }
+        PipelineKind::Shadow => (data.shadow_pipeline_layout, data.shadow_pipeline) = (*layout, pipelines[0]),
+        PipelineKind::Text => (data.text_pipeline_layout, data.text_pipeline) = (*layout, pipelines[0]),
+        PipelineKind::Ui => (data.ui_pipeline_layout, data.ui_pipeline) = (*layout, pipelines[0]),
+    }
+}
+
+unsafe fn reload_block_texture(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
+    let (image, memory) = create_texture_image_from_path(instance, device, data, BLOCK_TEXTURE)?;
+    let view = match create_texture_image_view(device, &image, vk::Format::R8G8B8A8_SRGB) {
+        Ok(view) => view,
+        Err(view_error) => {
+            device.destroy_image(image, None);
+            device.free_memory(memory, None);
+            return Err(view_error);
+        }
+    };
+
+    device.destroy_image_view(data.texture_image_view, None);
+    device.free_memory(data.texture_image_memory, None);
+    device.destroy_image(data.texture_image, None);
+    (data.texture_image, data.texture_image_memory, data.texture_image_view) = (image, memory, view);
+
+    update_texture_descriptor_sets(device, data);
+    Ok(())
+}
@@ -9,11 +9,12 @@ use vulkanalia::vk::ExtDebugUtilsExtension;
 use vulkanalia::window as vk_window;
 use winit::window::Window;
 
-use crate::{VALIDATION_ENABLED, VALIDATION_LAYER};
+use crate::VALIDATION_LAYER;
 use crate::core::app_data::AppData;
 
+// Without a window only the instance is created, there is no surface extension to enable
 pub(crate) unsafe fn create_instance(
-    window: &Window,
+    window: Option<&Window>,
     entry: &Entry,
     data: &mut AppData,
 ) -> Result<Instance> {
@@ -34,11 +35,11 @@ pub(crate) unsafe fn create_instance(
         .map(|l| l.layer_name)
         .collect::<HashSet<_>>();
 
-    if VALIDATION_ENABLED && !available_layers.contains(&VALIDATION_LAYER) {
+    if data.validation_enabled && !available_layers.contains(&VALIDATION_LAYER) {
         return Err(anyhow!("Validation layer requested but not supported."));
     }
 
-    let layers = if VALIDATION_ENABLED {
+    let layers = if data.validation_enabled {
         vec![VALIDATION_LAYER.as_ptr()]
     } else {
         Vec::new()
@@ -46,12 +47,14 @@ pub(crate) unsafe fn create_instance(
 
     // Extensions
 
-    let mut extensions = vk_window::get_required_instance_extensions(window)
+    let mut extensions = window
+        .map(|window| vk_window::get_required_instance_extensions(window))
+        .unwrap_or(&[])
         .iter()
         .map(|e| e.as_ptr())
         .collect::<Vec<_>>();
 
-    if VALIDATION_ENABLED {
+    if data.validation_enabled {
         extensions.push(vk::EXT_DEBUG_UTILS_EXTENSION.name.as_ptr());
     }
 
@@ -67,7 +70,7 @@ pub(crate) unsafe fn create_instance(
         .message_type(vk::DebugUtilsMessageTypeFlagsEXT::all())
         .user_callback(Some(debug_callback));
 
-    if VALIDATION_ENABLED {
+    if data.validation_enabled {
         info = info.push_next(&mut debug_info);
     }
 
@@ -75,7 +78,7 @@ pub(crate) unsafe fn create_instance(
 
     // Messenger
 
-    if VALIDATION_ENABLED {
+    if data.validation_enabled {
         data.messenger = instance.create_debug_utils_messenger_ext(&debug_info, None)?;
     }
 
@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use anyhow::Result;
 
 use crate::graphics::queue_family_indices::QueueFamilyIndices;
-use crate::{DEVICE_EXTENSIONS, VALIDATION_ENABLED, VALIDATION_LAYER};
+use crate::{DEVICE_EXTENSIONS, VALIDATION_LAYER};
 
 use vulkanalia::prelude::v1_0::*;
 use crate::core::app_data::AppData;
@@ -32,7 +32,7 @@ pub(crate) unsafe fn create_logical_device(
 
     // Layers
 
-    let layers = if VALIDATION_ENABLED {
+    let layers = if data.validation_enabled {
         vec![VALIDATION_LAYER.as_ptr()]
     } else {
         vec![]
@@ -42,6 +42,7 @@ pub(crate) unsafe fn create_logical_device(
 
     let extensions = DEVICE_EXTENSIONS
         .iter()
+        .filter(|_| !data.is_headless())
         .map(|n| n.as_ptr())
         .collect::<Vec<_>>();
 
@@ -0,0 +1,313 @@
+use crate::controlls::input_actions::{Action, Binding, InputActions};
+use crate::controlls::input_manager::InputManager;
+use crate::core::game_state::{GameEvent, GameState, Menu};
+use crate::core::settings::Settings;
+use crate::graphics::ui::Ui;
+use crate::graphics::ui_space::Anchor;
+use nalgebra_glm as glm;
+use winit::event::VirtualKeyCode;
+
+const MENU_WIDTH: f32 = 260.0;
+const KEYBINDS_WIDTH: f32 = 420.0;
+
+// What the player picked in a menu this frame, the App carries it out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MenuAction {
+    Transition(GameEvent),
+    // Gameplay settings were edited and have to reach the camera and mouse look
+    SettingsChanged,
+    SaveAndQuit,
+}
+
+// The screens of every state but playing. Only the action waiting for a new binding is kept
+// between frames, everything else is read from the settings and bindings as they are drawn.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Menus {
+    rebinding: Option<Action>,
+}
+
+impl Menus {
+    // While true the next key or button press is taken as a binding, including the pause key
+    pub(crate) fn is_rebinding(&self) -> bool {
+        self.rebinding.is_some()
+    }
+
+    // `pressed` is this frame's first key or mouse button press, see `pressed_binding`
+    pub(crate) fn draw(
+        &mut self,
+        ui: &mut Ui,
+        state: GameState,
+        settings: &mut Settings,
+        actions: &mut InputActions,
+        pressed: Option<Binding>,
+    ) -> Vec<MenuAction> {
+        if state != GameState::InMenu(Menu::Keybinds) {
+            self.rebinding = None;
+        }
+
+        let mut chosen = vec![];
+        match state {
+            // The console draws itself
+            GameState::Playing | GameState::Console => {}
+            GameState::Loading => ui.panel("Loading", Anchor::Center, glm::vec2(0.0, 0.0), MENU_WIDTH, |ui| {
+                ui.label("Building the world...");
+            }),
+            GameState::Paused => ui.panel("Paused", Anchor::Center, glm::vec2(0.0, 0.0), MENU_WIDTH, |ui| {
+                if ui.button("Resume") {
+                    chosen.push(MenuAction::Transition(GameEvent::Resume));
+                }
+                if ui.button("Settings") {
+                    chosen.push(MenuAction::Transition(GameEvent::Open(Menu::Settings)));
+                }
+                if ui.button("Save and quit") {
+                    chosen.push(MenuAction::SaveAndQuit);
+                }
+            }),
+            GameState::InMenu(Menu::Settings) => {
+                ui.panel("Settings", Anchor::Center, glm::vec2(0.0, 0.0), MENU_WIDTH, |ui| {
+                    let mut changed = ui.slider("Field of view", &mut settings.fov, 30.0..=150.0);
+                    changed |= ui.slider("Mouse sensitivity", &mut settings.mouse_sensitivity, 0.01..=5.0);
+                    changed |= ui.slider("Mouse smoothing", &mut settings.mouse_smoothing, 0.0..=0.9);
+                    changed |= ui.checkbox("Invert mouse Y", &mut settings.invert_mouse_y);
+                    // Whole chunks only
+                    let mut view_distance = settings.view_distance as f32;
+                    if ui.slider("View distance", &mut view_distance, 1.0..=32.0) {
+                        settings.view_distance = view_distance.round() as u32;
+                        changed = true;
+                    }
+                    changed |= ui.checkbox("Shadows", &mut settings.shadows);
+                    if changed {
+                        chosen.push(MenuAction::SettingsChanged);
+                    }
+                    if ui.button("Keybinds") {
+                        chosen.push(MenuAction::Transition(GameEvent::Open(Menu::Keybinds)));
+                    }
+                    if ui.button("Back") {
+                        chosen.push(MenuAction::Transition(GameEvent::Back));
+                    }
+                })
+            }
+            GameState::InMenu(Menu::Keybinds) => self.keybinds(ui, actions, pressed, &mut chosen),
+        }
+        chosen
+    }
+
+    fn keybinds(&mut self, ui: &mut Ui, actions: &mut InputActions, pressed: Option<Binding>, chosen: &mut Vec<MenuAction>) {
+        if let Some(action) = self.rebinding {
+            match pressed {
+                Some(Binding::Key(VirtualKeyCode::Escape)) => self.rebinding = None,
+                Some(binding) => {
+                    actions.rebind(action, binding);
+                    self.rebinding = None;
+                }
+                None => {}
+            }
+        }
+
+        ui.panel("Keybinds", Anchor::Center, glm::vec2(0.0, 0.0), KEYBINDS_WIDTH, |ui| {
+            // Without buttons under the cursor the press that picks a mouse button can't click one
+            if let Some(action) = self.rebinding {
+                ui.label(&format!("Press a key or mouse button for {}", action.name()));
+                ui.label("Escape cancels");
+                return;
+            }
+
+            for action in Action::ALL {
+                let bindings = actions.bindings(action).iter().map(|binding| binding.to_config_string()).collect::<Vec<_>>();
+                if ui.button(&format!("{}: {}", action.name(), bindings.join(", "))) {
+                    self.rebinding = Some(action);
+                }
+            }
+            if ui.button("Reset to defaults") {
+                *actions = InputActions::default();
+            }
+            if ui.button("Back") {
+                chosen.push(MenuAction::Transition(GameEvent::Back));
+            }
+        });
+    }
+}
+
+// Keys win over mouse buttons pressed in the same frame
+pub(crate) fn pressed_binding(input_manager: &InputManager) -> Option<Binding> {
+    let key = input_manager.get_keys_down().first().map(|key| Binding::Key(*key));
+    key.or_else(|| input_manager.get_mouse_buttons_down().first().map(|button| Binding::Mouse(*button)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controlls::gamepad::GamepadButton;
+    use crate::controlls::input_recording::InputRecording;
+    use crate::graphics::text_objects::TextObjects;
+    use crate::graphics::ui::{Rect, UiInput};
+    use crate::graphics::ui_space::UiSpace;
+    use vulkanalia::vk;
+    use winit::event::MouseButton;
+
+    const SPACE: UiSpace = UiSpace {
+        extent: vk::Extent2D { width: 1280, height: 720 },
+        scale_factor: 1.0,
+    };
+
+    struct Harness {
+        menus: Menus,
+        ui: Ui,
+        texts: TextObjects,
+        settings: Settings,
+        actions: InputActions,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            Self {
+                menus: Menus::default(),
+                ui: Ui::default(),
+                texts: TextObjects::default(),
+                settings: Settings::default(),
+                actions: InputActions::default(),
+            }
+        }
+
+        fn frame(&mut self, state: GameState, input: UiInput, pressed: Option<Binding>) -> Vec<MenuAction> {
+            self.ui.begin_frame(input, SPACE);
+            let chosen = self.menus.draw(&mut self.ui, state, &mut self.settings, &mut self.actions, pressed);
+            self.ui.end_frame(&mut self.texts);
+            chosen
+        }
+
+        // Clicks the label's widget on a screen that has settled, panels take a frame to get their size
+        fn click(&mut self, state: GameState, label: &str) -> Vec<MenuAction> {
+            self.frame(state, UiInput::default(), None);
+            self.frame(state, UiInput::default(), None);
+            let rect = self.widget(label);
+            let cursor = Some(rect.position + rect.size / 2.0);
+            self.frame(state, UiInput { cursor, mouse_down: true, mouse_pressed: true, ..Default::default() }, None);
+            self.frame(state, UiInput { cursor, mouse_released: true, ..Default::default() }, None)
+        }
+
+        // Labels are text objects at the top left of their widget, a button is a row tall
+        fn widget(&self, label: &str) -> Rect {
+            let text = self.texts.iter().find(|text| text.get_content().starts_with(label)).unwrap();
+            let position = text.get_position() - glm::vec2(8.0, 4.0);
+            Rect::new(position, glm::vec2(1.0, 28.0))
+        }
+    }
+
+    #[test]
+    fn test_pause_menu_buttons() {
+        let mut harness = Harness::new();
+        assert_eq!(harness.click(GameState::Paused, "Resume"), vec![MenuAction::Transition(GameEvent::Resume)]);
+        assert_eq!(
+            harness.click(GameState::Paused, "Settings"),
+            vec![MenuAction::Transition(GameEvent::Open(Menu::Settings))]
+        );
+        assert_eq!(harness.click(GameState::Paused, "Save and quit"), vec![MenuAction::SaveAndQuit]);
+        assert!(harness.ui.wants_cursor());
+    }
+
+    #[test]
+    fn test_playing_draws_nothing() {
+        let mut harness = Harness::new();
+        harness.frame(GameState::Paused, UiInput::default(), None);
+        assert!(harness.frame(GameState::Playing, UiInput::default(), None).is_empty());
+        assert!(!harness.ui.wants_cursor());
+        assert!(harness.texts.is_empty());
+    }
+
+    #[test]
+    fn test_settings_sliders_change_the_settings() {
+        let mut harness = Harness::new();
+        let state = GameState::InMenu(Menu::Settings);
+
+        // Pressing the middle of the view distance slider sets it halfway
+        harness.frame(state, UiInput::default(), None);
+        harness.frame(state, UiInput::default(), None);
+        let rect = harness.widget("View distance");
+        let slider_middle = glm::vec2(rect.position.x + (MENU_WIDTH - 20.0) / 2.0, rect.position.y + 14.0);
+        let press = UiInput { cursor: Some(slider_middle), mouse_down: true, mouse_pressed: true, ..Default::default() };
+
+        assert_eq!(harness.frame(state, press, None), vec![MenuAction::SettingsChanged]);
+        assert_eq!(harness.settings.view_distance, 17);
+        assert_eq!(harness.settings.fov, Settings::default().fov);
+    }
+
+    #[test]
+    fn test_invert_mouse_checkbox() {
+        let mut harness = Harness::new();
+        let state = GameState::InMenu(Menu::Settings);
+
+        assert_eq!(harness.click(state, "Invert mouse Y"), vec![MenuAction::SettingsChanged]);
+        assert!(harness.settings.invert_mouse_y);
+        assert_eq!(harness.settings.mouse_smoothing, Settings::default().mouse_smoothing);
+    }
+
+    #[test]
+    fn test_rebinding_a_key() {
+        let mut harness = Harness::new();
+        let state = GameState::InMenu(Menu::Keybinds);
+
+        harness.click(state, "jump:");
+        assert!(harness.menus.is_rebinding());
+        // From the next frame only the prompt is shown while waiting
+        harness.frame(state, UiInput::default(), None);
+        assert!(harness.texts.iter().any(|text| text.get_content().ends_with("for jump")));
+
+        harness.frame(state, UiInput::default(), Some(Binding::Key(VirtualKeyCode::J)));
+        assert!(!harness.menus.is_rebinding());
+        assert_eq!(
+            harness.actions.bindings(Action::Jump),
+            &[Binding::GamepadButton(GamepadButton::South), Binding::Key(VirtualKeyCode::J)]
+        );
+
+        // Escape cancels instead of binding
+        harness.click(state, "sneak:");
+        harness.frame(state, UiInput::default(), Some(Binding::Key(VirtualKeyCode::Escape)));
+        assert!(!harness.menus.is_rebinding());
+        assert_eq!(harness.actions.bindings(Action::Sneak), InputActions::default().bindings(Action::Sneak));
+
+        // Leaving the screen forgets the action waiting for a binding
+        harness.click(state, "sneak:");
+        harness.frame(GameState::Paused, UiInput::default(), Some(Binding::Mouse(MouseButton::Middle)));
+        assert!(!harness.menus.is_rebinding());
+    }
+
+    #[test]
+    fn test_rebound_keys_survive_saving() {
+        let mut harness = Harness::new();
+        let state = GameState::InMenu(Menu::Keybinds);
+        for (action, key) in [("jump:", VirtualKeyCode::NumpadAdd), ("sneak:", VirtualKeyCode::LWin), ("pause:", VirtualKeyCode::F13)] {
+            harness.click(state, action);
+            harness.frame(state, UiInput::default(), Some(Binding::Key(key)));
+        }
+
+        // The bindings file and the `bind` lines of recordings read back what the screen bound
+        let loaded = InputActions::from_config_str(&harness.actions.to_config_string().unwrap()).unwrap();
+        assert_eq!(loaded, harness.actions);
+        assert!(loaded.bindings(Action::Sneak).contains(&Binding::Key(VirtualKeyCode::LWin)));
+
+        let mut input_manager = InputManager::new();
+        input_manager.actions = harness.actions.clone();
+        let recording = InputRecording::new(&input_manager, 60, 0.4);
+        assert_eq!(InputRecording::from_text(&recording.to_text().unwrap()).unwrap().actions, harness.actions);
+    }
+
+    #[test]
+    fn test_keys_win_over_mouse_buttons() {
+        let mut input = InputManager::new();
+        assert_eq!(pressed_binding(&input), None);
+
+        input.detect_mouse(unsafe { winit::event::DeviceId::dummy() }, MouseButton::Right, winit::event::ElementState::Pressed, 1);
+        assert_eq!(pressed_binding(&input), Some(Binding::Mouse(MouseButton::Right)));
+
+        #[allow(deprecated)]
+        let key = winit::event::KeyboardInput {
+            scancode: 0,
+            state: winit::event::ElementState::Pressed,
+            virtual_keycode: Some(VirtualKeyCode::Q),
+            modifiers: winit::event::ModifiersState::empty(),
+        };
+        input.detect_keyboard(unsafe { winit::event::DeviceId::dummy() }, key, false, 1).unwrap();
+        assert_eq!(pressed_binding(&input), Some(Binding::Key(VirtualKeyCode::Q)));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::graphics::depth_objects::get_depth_format;
+use crate::graphics::shader_files::{WORLD_FRAG, WORLD_VERT};
 use crate::graphics::vertex::Vertex;
 use anyhow::{anyhow, Result};
 use vulkanalia::prelude::v1_0::*;
@@ -11,6 +12,13 @@ pub(crate) unsafe fn create_render_pass(
 ) -> Result<()> {
     // Attachments
 
+    // Offscreen images are read back after rendering instead of presented
+    let final_layout = if data.is_headless() {
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+    } else {
+        vk::ImageLayout::PRESENT_SRC_KHR
+    };
+
     let color_attachment = vk::AttachmentDescription::builder()
         .format(data.swapchain_format)
         .samples(vk::SampleCountFlags::_1)
@@ -19,7 +27,7 @@ pub(crate) unsafe fn create_render_pass(
         .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
         .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
         .initial_layout(vk::ImageLayout::UNDEFINED)
-        .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+        .final_layout(final_layout);
 
     let depth_stencil_attachment = vk::AttachmentDescription::builder()
         .format(get_depth_format(instance, data)?)
@@ -90,7 +98,7 @@ pub(crate) unsafe fn create_descriptor_set_layout(
         .binding(0)
         .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
         .descriptor_count(1)
-        .stage_flags(vk::ShaderStageFlags::VERTEX);
+        .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
 
     let sampler_binding = vk::DescriptorSetLayoutBinding::builder()
         .binding(1)
@@ -98,7 +106,13 @@ pub(crate) unsafe fn create_descriptor_set_layout(
         .descriptor_count(1)
         .stage_flags(vk::ShaderStageFlags::FRAGMENT);
 
-    let bindings = &[ubo_binding, sampler_binding];
+    let shadow_map_binding = vk::DescriptorSetLayoutBinding::builder()
+        .binding(2)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+    let bindings = &[ubo_binding, sampler_binding, shadow_map_binding];
     let info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(bindings);
 
     data.descriptor_set_layout = device.create_descriptor_set_layout(&info, None)?;
@@ -109,11 +123,11 @@ pub(crate) unsafe fn create_descriptor_set_layout(
 pub(crate) unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()> {
     // Stages
 
-    let vert = include_bytes!("../../shaders/bin/vert.spv");
-    let frag = include_bytes!("../../shaders/bin/frag.spv");
+    let vert = WORLD_VERT.load();
+    let frag = WORLD_FRAG.load();
 
-    let vert_shader_module = create_shader_module(device, &vert[..])?;
-    let frag_shader_module = create_shader_module(device, &frag[..])?;
+    let vert_shader_module = create_shader_module(device, &vert)?;
+    let frag_shader_module = create_shader_module(device, &frag)?;
 
     let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::VERTEX)
@@ -27,14 +27,19 @@ impl QueueFamilyIndices {
             .map(|i| i as u32);
 
         let mut present = None;
-        for (index, properties) in properties.iter().enumerate() {
-            if instance.get_physical_device_surface_support_khr(
-                physical_device,
-                index as u32,
-                data.surface,
-            )? {
-                present = Some(index as u32);
-                break;
+        if data.is_headless() {
+            // Headless rendering never presents, the graphics queue stands in
+            present = graphics;
+        } else {
+            for (index, properties) in properties.iter().enumerate() {
+                if instance.get_physical_device_surface_support_khr(
+                    physical_device,
+                    index as u32,
+                    data.surface,
+                )? {
+                    present = Some(index as u32);
+                    break;
+                }
             }
         }
 
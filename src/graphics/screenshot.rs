@@ -0,0 +1,243 @@
+use anyhow::{anyhow, Result};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::ptr::copy_nonoverlapping as memcpy;
+use vulkanalia::prelude::v1_0::*;
+use crate::core::app_data::AppData;
+use crate::graphics::shared_buffers::create_buffer;
+use crate::graphics::single_time_commands::{begin_single_time_commands, end_single_time_commands};
+
+// Copies a colour image into host memory and returns its raw texels. The image is moved into
+// TRANSFER_SRC_OPTIMAL for the copy and left in `layout` afterwards.
+pub(crate) unsafe fn read_image(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    image: vk::Image,
+    layout: vk::ImageLayout,
+    extent: vk::Extent2D,
+) -> Result<Vec<u8>> {
+    let size = extent.width as u64 * extent.height as u64 * 4;
+    let (buffer, buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_DST,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    let command_buffer = begin_single_time_commands(device, data)?;
+
+    let subresource_range = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let barrier = |old_layout, new_layout, src_access_mask, dst_access_mask| {
+        vk::ImageMemoryBarrier::builder()
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .src_access_mask(src_access_mask)
+            .dst_access_mask(dst_access_mask)
+    };
+
+    if layout != vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier(layout, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::TRANSFER_READ)],
+        );
+    }
+
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let region = vk::BufferImageCopy::builder()
+        .buffer_offset(0)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(subresource)
+        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+        .image_extent(vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        });
+
+    device.cmd_copy_image_to_buffer(
+        command_buffer,
+        image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        buffer,
+        &[region],
+    );
+
+    if layout != vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier(vk::ImageLayout::TRANSFER_SRC_OPTIMAL, layout, vk::AccessFlags::TRANSFER_READ, vk::AccessFlags::empty())],
+        );
+    }
+
+    end_single_time_commands(device, data, command_buffer)?;
+
+    let mut pixels = vec![0u8; size as usize];
+    let memory = device.map_memory(buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
+    memcpy(memory.cast(), pixels.as_mut_ptr(), pixels.len());
+    device.unmap_memory(buffer_memory);
+
+    device.destroy_buffer(buffer, None);
+    device.free_memory(buffer_memory, None);
+
+    Ok(pixels)
+}
+
+// Reorders texels of a 4 byte colour format into opaque RGBA
+pub(crate) fn to_rgba(texels: &[u8], format: vk::Format) -> Result<Vec<u8>> {
+    let is_bgra = match format {
+        vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => true,
+        vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => false,
+        _ => return Err(anyhow!("Can't convert {:?} texels to RGBA", format)),
+    };
+    if !texels.chunks_exact(4).remainder().is_empty() {
+        return Err(anyhow!("{} bytes aren't a whole number of texels", texels.len()));
+    }
+
+    let mut rgba = Vec::with_capacity(texels.len());
+    for texel in texels.chunks_exact(4) {
+        if is_bgra {
+            rgba.extend_from_slice(&[texel[2], texel[1], texel[0], 255]);
+        } else {
+            rgba.extend_from_slice(&[texel[0], texel[1], texel[2], 255]);
+        }
+    }
+    Ok(rgba)
+}
+
+pub(crate) fn save_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<()> {
+    if rgba.len() != width as usize * height as usize * 4 {
+        return Err(anyhow!("Expected {}x{} RGBA pixels, got {} bytes", width, height, rgba.len()));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgba)?;
+    Ok(())
+}
+
+pub(crate) fn load_png(path: &Path) -> Result<(u32, u32, Vec<u8>)> {
+    let decoder = png::Decoder::new(File::open(path)?);
+    let mut reader = decoder.read_info()?;
+    let info = reader.info();
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(anyhow!("{:?} isn't an 8 bit RGBA image", path));
+    }
+
+    let (width, height) = info.size();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels)?;
+    pixels.truncate(width as usize * height as usize * 4);
+    Ok((width, height, pixels))
+}
+
+// Counts pixels where any channel differs by more than `tolerance`, for image regression tests
+pub(crate) fn count_different_pixels(expected: &[u8], actual: &[u8], tolerance: u8) -> Result<usize> {
+    if expected.len() != actual.len() {
+        return Err(anyhow!("Images have different sizes: {} and {} bytes", expected.len(), actual.len()));
+    }
+    Ok(expected
+        .chunks_exact(4)
+        .zip(actual.chunks_exact(4))
+        .filter(|(expected, actual)| {
+            expected.iter().zip(actual.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance)
+        })
+        .count())
+}
+
+// `<directory>/<stamp>.png`, with a counter added when several screenshots share a stamp
+pub(crate) fn unique_screenshot_path(directory: &Path, stamp: &str) -> PathBuf {
+    let mut path = directory.join(format!("{}.png", stamp));
+    let mut counter = 1;
+    while path.exists() {
+        path = directory.join(format!("{}_{}.png", stamp, counter));
+        counter += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_to_rgba_swizzles_and_drops_alpha() {
+        let bgra = [10, 20, 30, 0, 1, 2, 3, 128];
+
+        assert_eq!(to_rgba(&bgra, vk::Format::B8G8R8A8_SRGB).unwrap(), vec![30, 20, 10, 255, 3, 2, 1, 255]);
+        assert_eq!(to_rgba(&bgra, vk::Format::R8G8B8A8_UNORM).unwrap(), vec![10, 20, 30, 255, 1, 2, 3, 255]);
+        assert!(to_rgba(&bgra, vk::Format::R16G16B16A16_SFLOAT).is_err());
+        assert!(to_rgba(&bgra[..6], vk::Format::B8G8R8A8_SRGB).is_err());
+    }
+
+    #[test]
+    fn test_png_round_trip_and_comparison() {
+        let directory = env::temp_dir().join(format!("screenshot_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let pixels = (0..2 * 3 * 4).map(|value| value as u8 * 10).collect::<Vec<_>>();
+        let path = unique_screenshot_path(&directory, "shot");
+        save_png(&path, 2, 3, &pixels).unwrap();
+        assert_eq!(load_png(&path).unwrap(), (2, 3, pixels.clone()));
+        assert!(save_png(&path, 3, 3, &pixels).is_err());
+
+        let mut changed = pixels.clone();
+        changed[0] += 2;
+        changed[9] += 5;
+        assert_eq!(count_different_pixels(&pixels, &changed, 0).unwrap(), 2);
+        assert_eq!(count_different_pixels(&pixels, &changed, 2).unwrap(), 1);
+        assert!(count_different_pixels(&pixels, &changed[4..], 0).is_err());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_unique_screenshot_path() {
+        let directory = env::temp_dir().join(format!("screenshot_path_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        let first = unique_screenshot_path(&directory, "2026-01-01_10-00-00");
+        assert_eq!(first, directory.join("2026-01-01_10-00-00.png"));
+        fs::write(&first, []).unwrap();
+
+        let second = unique_screenshot_path(&directory, "2026-01-01_10-00-00");
+        assert_eq!(second, directory.join("2026-01-01_10-00-00_1.png"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+// Signed distance fields for glyphs. Stored distances stay meaningful when the texture is scaled,
+// so a single rasterization draws crisp text at every size.
+
+// Stands in for infinity in the squared distance transform without producing NaN
+const FAR: f64 = 1e20;
+
+// Distance field of one glyph. A value of 0.5 lies on the outline and it falls linearly to 0 at
+// `spread` pixels outside and rises to 1 at `spread` pixels inside.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct DistanceField {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) spread: u32,
+    pub(crate) values: Vec<u8>,
+}
+
+impl DistanceField {
+    // Signed distance in pixels at a pixel center, negative inside the glyph
+    pub(crate) fn distance(&self, x: u32, y: u32) -> f32 {
+        let value = self.values[(y * self.width + x) as usize] as f32 / 255.0;
+        (0.5 - value) * 2.0 * self.spread as f32
+    }
+}
+
+// Builds the field of a coverage bitmap rasterized `supersampling` times larger than the field.
+// The field gets `spread` pixels of room on every side. Rows and columns rounding the bitmap up to
+// whole field pixels are added at the top and right, so the field's bottom left corner lies
+// `spread` field pixels left of and below the bitmap's.
+pub(crate) fn distance_field(width: u32, height: u32, coverage: &[u8], spread: u32, supersampling: u32) -> DistanceField {
+    let supersampling = supersampling.max(1);
+    let padding = spread * supersampling;
+    let field_width = width.div_ceil(supersampling) + spread * 2;
+    let field_height = height.div_ceil(supersampling) + spread * 2;
+    let (padded_width, padded_height) = (field_width * supersampling, field_height * supersampling);
+    // Rows added at the top to round the height up
+    let top = padded_height - height - padding * 2;
+
+    let mut padded = vec![0; (padded_width * padded_height) as usize];
+    for y in 0..height {
+        let source = (y * width) as usize;
+        let target = ((y + top + padding) * padded_width + padding) as usize;
+        padded[target..target + width as usize].copy_from_slice(&coverage[source..source + width as usize]);
+    }
+    let distances = signed_distances(padded_width, padded_height, &padded);
+
+    // Averaging the supersampled distances smooths out the stair steps of the threshold
+    let mut values = Vec::with_capacity((field_width * field_height) as usize);
+    let samples = (supersampling * supersampling) as f32;
+    for y in 0..field_height {
+        for x in 0..field_width {
+            let mut sum = 0.0;
+            for sample_y in 0..supersampling {
+                let row = ((y * supersampling + sample_y) * padded_width + x * supersampling) as usize;
+                sum += distances[row..row + supersampling as usize].iter().sum::<f32>();
+            }
+            let distance = sum / samples / supersampling as f32;
+            let value = 0.5 - distance / (2.0 * spread.max(1) as f32);
+            values.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    DistanceField {
+        width: field_width,
+        height: field_height,
+        spread,
+        values,
+    }
+}
+
+// Distance in pixels from every pixel center to the outline of the covered area, negative inside.
+// Pixels that are at least half covered count as inside, the outline runs along their edges.
+pub(crate) fn signed_distances(width: u32, height: u32, coverage: &[u8]) -> Vec<f32> {
+    let inside = coverage.iter().map(|value| *value >= 128).collect::<Vec<_>>();
+    let seeds = |of_inside: bool| {
+        inside
+            .iter()
+            .map(|is_inside| if *is_inside == of_inside { 0.0 } else { FAR })
+            .collect::<Vec<_>>()
+    };
+    let to_inside = squared_distance_transform(width, height, seeds(true));
+    let to_outside = squared_distance_transform(width, height, seeds(false));
+
+    // Neighbouring pixel centers are a pixel apart, the outline between them half a pixel from each
+    inside
+        .iter()
+        .enumerate()
+        .map(|(index, is_inside)| {
+            if *is_inside {
+                0.5 - to_outside[index].sqrt() as f32
+            } else {
+                to_inside[index].sqrt() as f32 - 0.5
+            }
+        })
+        .collect()
+}
+
+// Exact squared Euclidean distance to the nearest zero of `grid` (Felzenszwalb and Huttenlocher)
+fn squared_distance_transform(width: u32, height: u32, mut grid: Vec<f64>) -> Vec<f64> {
+    let (width, height) = (width as usize, height as usize);
+    let longest = width.max(height);
+    let mut line = vec![0.0; longest];
+    let mut result = vec![0.0; longest];
+    let mut parabolas = vec![0; longest];
+    let mut boundaries = vec![0.0; longest + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        transform_line(&line[..height], &mut result, &mut parabolas, &mut boundaries);
+        for y in 0..height {
+            grid[y * width + x] = result[y];
+        }
+    }
+    for y in 0..height {
+        line[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        transform_line(&line[..width], &mut result, &mut parabolas, &mut boundaries);
+        grid[y * width..(y + 1) * width].copy_from_slice(&result[..width]);
+    }
+    grid
+}
+
+// One dimensional pass, the lower envelope of the parabolas rooted at every sample
+fn transform_line(samples: &[f64], result: &mut [f64], parabolas: &mut [usize], boundaries: &mut [f64]) {
+    let intersection = |q: usize, p: usize| {
+        let (q_f, p_f) = (q as f64, p as f64);
+        ((samples[q] + q_f * q_f) - (samples[p] + p_f * p_f)) / (2.0 * q_f - 2.0 * p_f)
+    };
+
+    let mut k = 0;
+    parabolas[0] = 0;
+    boundaries[0] = -FAR;
+    boundaries[1] = FAR;
+    for q in 1..samples.len() {
+        let mut s = intersection(q, parabolas[k]);
+        while s <= boundaries[k] {
+            k -= 1;
+            s = intersection(q, parabolas[k]);
+        }
+        k += 1;
+        parabolas[k] = q;
+        boundaries[k] = s;
+        boundaries[k + 1] = FAR;
+    }
+
+    k = 0;
+    for (q, value) in result.iter_mut().enumerate().take(samples.len()) {
+        while boundaries[k + 1] < q as f64 {
+            k += 1;
+        }
+        let offset = q as f64 - parabolas[k] as f64;
+        *value = offset * offset + samples[parabolas[k]];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Coverage of a shape given as a signed distance function, sampled 4x4 per pixel
+    fn rasterize(width: u32, height: u32, shape: impl Fn(f32, f32) -> f32) -> Vec<u8> {
+        let mut coverage = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                let mut covered = 0;
+                for sample in 0..16 {
+                    let sample_x = x as f32 + (sample % 4) as f32 * 0.25 + 0.125;
+                    let sample_y = y as f32 + (sample / 4) as f32 * 0.25 + 0.125;
+                    covered += (shape(sample_x, sample_y) < 0.0) as u32;
+                }
+                coverage.push((covered * 255 / 16) as u8);
+            }
+        }
+        coverage
+    }
+
+    fn disc(center_x: f32, center_y: f32, radius: f32) -> impl Fn(f32, f32) -> f32 {
+        move |x, y| ((x - center_x).powi(2) + (y - center_y).powi(2)).sqrt() - radius
+    }
+
+    // Largest difference to the reference over the pixels within `range` of the outline
+    fn max_error(field: &DistanceField, reference: impl Fn(f32, f32) -> f32, range: f32) -> f32 {
+        let mut max_error = 0.0f32;
+        for y in 0..field.height {
+            for x in 0..field.width {
+                let expected = reference(x as f32 + 0.5, y as f32 + 0.5);
+                if expected.abs() < range {
+                    max_error = max_error.max((field.distance(x, y) - expected).abs());
+                }
+            }
+        }
+        max_error
+    }
+
+    #[test]
+    fn test_square_distances() {
+        // Pixel centers along the border are half a pixel from the outline, the middle ones four and a half
+        let mut bordered = vec![0; 12 * 12];
+        for y in 1..11 {
+            bordered[y * 12 + 1..y * 12 + 11].fill(255);
+        }
+        let distances = signed_distances(12, 12, &bordered);
+        assert_eq!((distances[0], distances[12 + 1], distances[5 * 12 + 5]), (std::f32::consts::SQRT_2 - 0.5, -0.5, -4.5));
+
+        let coverage = vec![255; 10 * 10];
+        let field = distance_field(10, 10, &coverage, 4, 1);
+        assert_eq!((field.width, field.height), (18, 18));
+        // A box from (4, 4) to (14, 14), the exact distance outside corners is round
+        let reference = |x: f32, y: f32| {
+            let (dx, dy) = ((x - 9.0).abs() - 5.0, (y - 9.0).abs() - 5.0);
+            let outside = (dx.max(0.0).powi(2) + dy.max(0.0).powi(2)).sqrt();
+            outside + dx.max(dy).min(0.0)
+        };
+        assert!(max_error(&field, reference, 3.5) < 0.25);
+        assert_eq!(field.distance(9, 9), -4.0);
+    }
+
+    #[test]
+    fn test_disc_matches_reference() {
+        let reference = disc(20.0, 20.0, 12.0);
+        let field = distance_field(40, 40, &rasterize(40, 40, &reference), 6, 1);
+        let shifted = |x: f32, y: f32| reference(x - 6.0, y - 6.0);
+        assert!(max_error(&field, shifted, 5.0) < 0.6, "{}", max_error(&field, shifted, 5.0));
+
+        // Supersampling gets closer to the real outline
+        let reference = disc(40.0, 40.0, 24.0);
+        let field = distance_field(80, 80, &rasterize(80, 80, &reference), 6, 2);
+        assert_eq!((field.width, field.height), (52, 52));
+        let shifted = |x: f32, y: f32| reference((x - 6.0) * 2.0, (y - 6.0) * 2.0) / 2.0;
+        assert!(max_error(&field, shifted, 5.0) < 0.35, "{}", max_error(&field, shifted, 5.0));
+    }
+
+    #[test]
+    fn test_values_saturate_beyond_the_spread() {
+        let field = distance_field(1, 1, &[255], 3, 1);
+        assert_eq!((field.width, field.height), (7, 7));
+
+        // The corners are about 4 pixels out, further than the spread
+        assert_eq!(field.values[0], 0);
+        assert!(field.values[3 * 7 + 3] > 128);
+        assert!(field.values[3 * 7 + 2] < 128);
+
+        let empty = distance_field(0, 0, &[], 2, 2);
+        assert_eq!((empty.width, empty.height), (4, 4));
+        assert!(empty.values.iter().all(|value| *value == 0));
+    }
+
+    #[test]
+    fn test_odd_sizes_are_padded_at_the_top_right() {
+        // A 3x3 bitmap at half resolution rounds up to 2x2 plus the spread
+        let field = distance_field(3, 3, &[255; 9], 1, 2);
+        assert_eq!((field.width, field.height), (4, 4));
+
+        // The row added at the top leaves the bottom left of the glyph where it was
+        let inside = (0..field.height)
+            .flat_map(|y| (0..field.width).map(move |x| (x, y)))
+            .filter(|(x, y)| field.distance(*x, *y) < 0.0)
+            .collect::<Vec<_>>();
+        assert!(inside.contains(&(1, 2)));
+        assert!(!inside.iter().any(|(x, y)| *x == 0 || *y == 0));
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{anyhow, Result};
+use glslang::{Compiler, CompilerOptions, ShaderInput, ShaderSource, ShaderStage, SourceLanguage, SpirvVersion, Target, VulkanVersion};
+use lazy_static::lazy_static;
+use log::{error, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// First word of every SPIR-V module
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+// A GLSL shader in `shaders` and the SPIR-V built from it ahead of time. The source is compiled the
+// first time a pipeline needs it, so edits show up without rebuilding the game. The built copy is
+// used when the source is missing or doesn't compile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ShaderFile {
+    pub(crate) path: &'static str,
+    embedded: &'static [u8],
+}
+
+macro_rules! shader_file {
+    ($source:literal, $binary:literal) => {
+        ShaderFile {
+            path: concat!("shaders/", $source),
+            embedded: include_bytes!(concat!("../../shaders/bin/", $binary)),
+        }
+    };
+}
+
+pub(crate) const WORLD_VERT: ShaderFile = shader_file!("shader.vert", "vert.spv");
+pub(crate) const WORLD_FRAG: ShaderFile = shader_file!("shader.frag", "frag.spv");
+pub(crate) const SKY_VERT: ShaderFile = shader_file!("sky-shader.vert", "sky-vert.spv");
+pub(crate) const SKY_FRAG: ShaderFile = shader_file!("sky-shader.frag", "sky-frag.spv");
+pub(crate) const SHADOW_VERT: ShaderFile = shader_file!("shadow-shader.vert", "shadow-vert.spv");
+pub(crate) const OUTLINE_VERT: ShaderFile = shader_file!("outline-shader.vert", "outline-vert.spv");
+pub(crate) const OUTLINE_FRAG: ShaderFile = shader_file!("outline-shader.frag", "outline-frag.spv");
+pub(crate) const CRACK_VERT: ShaderFile = shader_file!("crack-shader.vert", "crack-vert.spv");
+pub(crate) const CRACK_FRAG: ShaderFile = shader_file!("crack-shader.frag", "crack-frag.spv");
+pub(crate) const TEXT_VERT: ShaderFile = shader_file!("text-shader.vert", "text-vert.spv");
+pub(crate) const TEXT_FRAG: ShaderFile = shader_file!("text-shader.frag", "text-frag.spv");
+pub(crate) const UI_VERT: ShaderFile = shader_file!("ui-shader.vert", "ui-vert.spv");
+pub(crate) const UI_FRAG: ShaderFile = shader_file!("ui-shader.frag", "ui-frag.spv");
+
+lazy_static!(
+    // The last SPIR-V of every shader that was loaded, so recreated pipelines keep what hot reloading
+    // compiled even when the source on disk is broken by then
+    static ref LOADED_SHADERS: Mutex<HashMap<&'static str, Vec<u8>>> = Mutex::new(HashMap::new());
+);
+
+pub(crate) const SHADER_FILES: [ShaderFile; 13] = [
+    WORLD_VERT, WORLD_FRAG, SKY_VERT, SKY_FRAG, SHADOW_VERT, OUTLINE_VERT, OUTLINE_FRAG,
+    CRACK_VERT, CRACK_FRAG, TEXT_VERT, TEXT_FRAG, UI_VERT, UI_FRAG,
+];
+
+// The pipelines built from the shaders, each rebuilt when one of its shaders changes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PipelineKind {
+    World,
+    Sky,
+    Highlight,
+    Shadow,
+    Text,
+    Ui,
+}
+
+impl PipelineKind {
+    pub(crate) const ALL: [PipelineKind; 6] = [Self::World, Self::Sky, Self::Highlight, Self::Shadow, Self::Text, Self::Ui];
+
+    pub(crate) fn shaders(self) -> &'static [ShaderFile] {
+        match self {
+            Self::World => &[WORLD_VERT, WORLD_FRAG],
+            Self::Sky => &[SKY_VERT, SKY_FRAG],
+            Self::Highlight => &[OUTLINE_VERT, OUTLINE_FRAG, CRACK_VERT, CRACK_FRAG],
+            Self::Shadow => &[SHADOW_VERT],
+            Self::Text => &[TEXT_VERT, TEXT_FRAG],
+            Self::Ui => &[UI_VERT, UI_FRAG],
+        }
+    }
+
+    // The SPIR-V of every shader of the pipeline, stopping at the first compile error
+    pub(crate) fn compile(self) -> Result<Vec<Vec<u8>>> {
+        self.shaders().iter().map(|file| file.compile().map(|words| to_bytes(&words))).collect()
+    }
+
+    // What the pipeline is created from, see `ShaderFile::load`
+    pub(crate) fn loaded(self) -> Vec<Vec<u8>> {
+        self.shaders().iter().map(ShaderFile::load).collect()
+    }
+
+    // Replaces what the pipeline is created from, `spirv` is in the order of `shaders`
+    pub(crate) fn store(self, spirv: Vec<Vec<u8>>) {
+        self.shaders().iter().zip(spirv).for_each(|(file, spirv)| file.store(spirv));
+    }
+}
+
+// The pipelines using any of the changed files
+pub(crate) fn affected_pipelines(changed: &[PathBuf]) -> Vec<PipelineKind> {
+    PipelineKind::ALL
+        .into_iter()
+        .filter(|kind| kind.shaders().iter().any(|file| changed.iter().any(|path| path == Path::new(file.path))))
+        .collect()
+}
+
+impl ShaderFile {
+    // The SPIR-V bytes for a shader module. Compiled once, after that only `store` changes them.
+    pub(crate) fn load(&self) -> Vec<u8> {
+        if let Some(spirv) = LOADED_SHADERS.lock().unwrap().get(self.path) {
+            return spirv.clone();
+        }
+
+        let spirv = if Path::new(self.path).exists() {
+            self.compile().map(|words| to_bytes(&words)).unwrap_or_else(|compile_error| {
+                error!("{}", compile_error);
+                warn!("Using the built copy of {}", self.path);
+                self.embedded.to_vec()
+            })
+        } else {
+            self.embedded.to_vec()
+        };
+        self.store(spirv.clone());
+        spirv
+    }
+
+    pub(crate) fn store(&self, spirv: Vec<u8>) {
+        LOADED_SHADERS.lock().unwrap().insert(self.path, spirv);
+    }
+
+    // Compiles the source on disk
+    pub(crate) fn compile(&self) -> Result<Vec<u32>> {
+        let source = fs::read_to_string(self.path).map_err(|error| anyhow!("Couldn't read {}: {}", self.path, error))?;
+        compile_glsl(&source, stage(self.path)?, self.path)
+    }
+}
+
+// The stage comes from the extension, like glslc picks it
+pub(crate) fn stage(path: &str) -> Result<ShaderStage> {
+    match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+        Some("vert") => Ok(ShaderStage::Vertex),
+        Some("frag") => Ok(ShaderStage::Fragment),
+        Some("comp") => Ok(ShaderStage::Compute),
+        _ => Err(anyhow!("Unknown shader stage for {}", path)),
+    }
+}
+
+// `name` prefixes the errors, which name the offending lines
+pub(crate) fn compile_glsl(source: &str, stage: ShaderStage, name: &str) -> Result<Vec<u32>> {
+    // glslang can't take sources with null characters
+    if source.contains('\0') {
+        return Err(anyhow!("Couldn't compile {}: the source contains a null character", name));
+    }
+    let compiler = Compiler::acquire().ok_or_else(|| anyhow!("Couldn't start the shader compiler"))?;
+    let options = CompilerOptions {
+        source_language: SourceLanguage::GLSL,
+        target: Target::Vulkan { version: VulkanVersion::Vulkan1_0, spirv_version: SpirvVersion::SPIRV1_0 },
+        ..Default::default()
+    };
+
+    let source = ShaderSource::from(source);
+    let compile = || {
+        let input = ShaderInput::new(&source, stage, &options, None)?;
+        compiler.create_shader(input)?.compile()
+    };
+    compile().map_err(|error| anyhow!("Couldn't compile {}: {}", name, error.to_string().trim_end()))
+}
+
+// Little endian like the files glslc writes
+pub(crate) fn to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+// Writes the SPIR-V of every shader next to its source, the same files `shaders/compile.bat` builds
+pub(crate) fn compile_all() -> Result<()> {
+    for (file, binary) in SHADER_FILES.iter().zip(binary_paths()) {
+        let words = file.compile()?;
+        fs::write(&binary, to_bytes(&words))?;
+        log::info!("Compiled {} to {}", file.path, binary);
+    }
+    Ok(())
+}
+
+fn binary_paths() -> impl Iterator<Item = String> {
+    SHADER_FILES.iter().map(|file| {
+        let name = Path::new(file.path).file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+        // `shader.vert` is `vert.spv`, `ui-shader.vert` is `ui-vert.spv`
+        let prefix = stem.strip_suffix("shader").unwrap_or(stem);
+        format!("shaders/bin/{}{}.spv", prefix, extension)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const VALID: &str = "#version 450\nlayout(location = 0) out vec4 color;\nvoid main() {\n    color = vec4(1.0);\n}\n";
+
+    #[test]
+    fn test_compiles_glsl() {
+        let words = compile_glsl(VALID, ShaderStage::Fragment, "valid.frag").unwrap();
+        assert_eq!(words[0], SPIRV_MAGIC);
+        assert_eq!(to_bytes(&words)[..4], SPIRV_MAGIC.to_le_bytes());
+    }
+
+    #[test]
+    fn test_reports_compile_errors() {
+        let broken = VALID.replace("vec4(1.0)", "vec3(1.0)");
+        let error = compile_glsl(&broken, ShaderStage::Fragment, "broken.frag").unwrap_err().to_string();
+        assert!(error.starts_with("Couldn't compile broken.frag"), "{}", error);
+        // The line with the mistake is named
+        assert!(error.contains(":4:"), "{}", error);
+
+        assert!(compile_glsl("void main() {}\0", ShaderStage::Vertex, "null.vert").is_err());
+        assert!(stage("shaders/shader.geom").is_err());
+    }
+
+    #[test]
+    fn test_every_shader_compiles() {
+        for file in SHADER_FILES {
+            let words = file.compile().unwrap_or_else(|error| panic!("{}", error));
+            assert_eq!(words[0], SPIRV_MAGIC, "{}", file.path);
+            assert_eq!(file.embedded[..4], SPIRV_MAGIC.to_le_bytes(), "{}", file.path);
+        }
+    }
+
+    #[test]
+    fn test_keeps_the_last_good_spirv() {
+        let path = env::temp_dir().join(format!("shader_files_test_{}.frag", std::process::id()));
+        let file = ShaderFile { path: Box::leak(path.to_string_lossy().into_owned().into_boxed_str()), embedded: &[] };
+        fs::write(file.path, VALID).unwrap();
+        let first = file.load();
+        assert_eq!(first, to_bytes(&compile_glsl(VALID, ShaderStage::Fragment, "valid.frag").unwrap()));
+
+        // Broken sources aren't compiled again, pipelines recreated now get the same shader
+        fs::write(file.path, "#version 450\nvoid main() {").unwrap();
+        assert!(file.compile().is_err());
+        assert_eq!(file.load(), first);
+
+        // Hot reloading stores what compiled
+        let changed = VALID.replace("vec4(1.0)", "vec4(0.5)");
+        fs::write(file.path, &changed).unwrap();
+        let second = to_bytes(&file.compile().unwrap());
+        file.store(second.clone());
+        assert_ne!(second, first);
+        assert_eq!(file.load(), second);
+        fs::remove_file(file.path).unwrap();
+    }
+
+    #[test]
+    fn test_changes_rebuild_their_pipelines() {
+        let changed = vec![PathBuf::from("shaders/crack-shader.frag"), PathBuf::from("resources/blocks.png")];
+        assert_eq!(affected_pipelines(&changed), vec![PipelineKind::Highlight]);
+
+        let changed = vec![PathBuf::from("shaders/shader.frag"), PathBuf::from("shaders/ui-shader.vert")];
+        assert_eq!(affected_pipelines(&changed), vec![PipelineKind::World, PipelineKind::Ui]);
+        assert!(affected_pipelines(&[]).is_empty());
+
+        // Every shader belongs to exactly one pipeline
+        for file in SHADER_FILES {
+            let kinds = affected_pipelines(&[PathBuf::from(file.path)]);
+            assert_eq!(kinds.len(), 1, "{}", file.path);
+            assert!(kinds[0].compile().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_binaries_follow_compile_bat() {
+        let script = fs::read_to_string("shaders/compile.bat").unwrap();
+        for (file, binary) in SHADER_FILES.iter().zip(binary_paths()) {
+            let source = file.path.trim_start_matches("shaders/");
+            let binary = binary.trim_start_matches("shaders/");
+            assert!(script.contains(&format!("{} -o {}", source, binary)), "{}", binary);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::core::camera::Camera;
+use nalgebra_glm as glm;
+
+pub(crate) const CASCADE_COUNT: usize = 3;
+// Width and height of each cascade's depth layer
+pub(crate) const SHADOW_MAP_SIZE: u32 = 2048;
+// 0 splits the view distance evenly, 1 logarithmically, which keeps the near cascades sharp
+const SPLIT_LAMBDA: f32 = 0.75;
+// Terrain this far behind a cascade towards the light still casts shadows into it
+const CASTER_DISTANCE: f32 = 64.0;
+
+// The light's view and projection for each cascade and how far along the view each one reaches
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ShadowCascades {
+    pub(crate) matrices: [glm::Mat4; CASCADE_COUNT],
+    pub(crate) splits: [f32; CASCADE_COUNT],
+}
+
+impl ShadowCascades {
+    // `light_direction` points towards the light
+    pub(crate) fn new(camera: &Camera, aspect_ratio: f32, light_direction: &glm::Vec3) -> Self {
+        let (near, far) = (camera.settings.near, camera.settings.far);
+        let mut splits = [far; CASCADE_COUNT];
+        splits.copy_from_slice(&cascade_splits(near, far, CASCADE_COUNT, SPLIT_LAMBDA));
+
+        let mut matrices = [glm::Mat4::identity(); CASCADE_COUNT];
+        let mut split_near = near;
+        for (matrix, split_far) in matrices.iter_mut().zip(splits) {
+            let corners = frustum_corners(camera, aspect_ratio, split_near, split_far);
+            *matrix = light_matrix(&corners, light_direction, SHADOW_MAP_SIZE);
+            split_near = split_far;
+        }
+        Self { matrices, splits }
+    }
+}
+
+// The far distance of each of `count` slices of the view between `near` and `far`
+pub(crate) fn cascade_splits(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|index| {
+            if index == count {
+                return far;
+            }
+            let fraction = index as f32 / count as f32;
+            let logarithmic = near * (far / near).powf(fraction);
+            let uniform = near + (far - near) * fraction;
+            glm::lerp_scalar(uniform, logarithmic, lambda)
+        })
+        .collect()
+}
+
+// The corners of the part of the camera's view between the `near` and `far` distances, near plane first
+pub(crate) fn frustum_corners(camera: &Camera, aspect_ratio: f32, near: f32, far: f32) -> [glm::Vec3; 8] {
+    let (forward, right, up) = (camera.forward(), camera.right(), camera.up());
+    let tan_half_fov = (camera.settings.fov_degrees.to_radians() / 2.0).tan();
+
+    let mut corners = [glm::vec3(0.0, 0.0, 0.0); 8];
+    for (plane, distance) in [near, far].into_iter().enumerate() {
+        let center = camera.position + forward * distance;
+        let half_height = distance * tan_half_fov;
+        let half_width = half_height * aspect_ratio;
+        for (corner, (x, y)) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].into_iter().enumerate() {
+            corners[plane * 4 + corner] = center + right * (x * half_width) + up * (y * half_height);
+        }
+    }
+    corners
+}
+
+// An orthographic view from the light enclosing the corners. It's sized by the bounding sphere and
+// moved in whole texels, so the shadow edges don't shimmer as the camera turns and moves.
+pub(crate) fn light_matrix(corners: &[glm::Vec3; 8], light_direction: &glm::Vec3, resolution: u32) -> glm::Mat4 {
+    let center = corners.iter().sum::<glm::Vec3>() / corners.len() as f32;
+    let radius = corners
+        .iter()
+        .map(|corner| glm::distance(corner, &center))
+        .fold(0.0, f32::max);
+    // Rounded up, so the size doesn't change with tiny rounding errors
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let light_direction = light_direction.normalize();
+    // Z is up, unless the light is straight above or below
+    let up = if light_direction.z.abs() > 0.99 { glm::vec3(0.0, 1.0, 0.0) } else { glm::vec3(0.0, 0.0, 1.0) };
+    let eye = center + light_direction * (radius + CASTER_DISTANCE);
+    let view = glm::look_at(&eye, &center, &up);
+    let mut projection = glm::ortho_rh_zo(-radius, radius, -radius, radius, 0.0, 2.0 * radius + CASTER_DISTANCE);
+
+    // Snaps the world origin to a texel
+    let half_resolution = resolution as f32 / 2.0;
+    let origin = projection * view * glm::vec4(0.0, 0.0, 0.0, 1.0);
+    let offset = glm::vec2(
+        (origin.x * half_resolution).round() - origin.x * half_resolution,
+        (origin.y * half_resolution).round() - origin.y * half_resolution,
+    ) / half_resolution;
+    projection[(0, 3)] += offset.x;
+    projection[(1, 3)] += offset.y;
+
+    projection * view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraSettings;
+
+    fn camera() -> Camera {
+        Camera::from_angles(glm::vec3(10.0, -5.0, 40.0), 0.3, -0.2, CameraSettings { fov_degrees: 90.0, near: 0.1, far: 128.0 })
+    }
+
+    fn project(matrix: &glm::Mat4, point: &glm::Vec3) -> glm::Vec3 {
+        let clip = matrix * glm::vec4(point.x, point.y, point.z, 1.0);
+        clip.xyz() / clip.w
+    }
+
+    #[test]
+    fn test_splits_cover_the_view() {
+        let splits = cascade_splits(0.1, 128.0, 3, SPLIT_LAMBDA);
+        assert_eq!(splits.len(), 3);
+        assert_eq!(splits.last(), Some(&128.0));
+        assert!(splits[0] > 0.1);
+        assert!(splits.windows(2).all(|pair| pair[0] < pair[1]));
+        // The near cascades are smaller than an even split
+        assert!(splits[0] < 128.0 / 3.0);
+
+        let uniform = cascade_splits(10.0, 100.0, 3, 0.0);
+        for (split, expected) in uniform.iter().zip([40.0, 70.0, 100.0]) {
+            assert!((split - expected).abs() < 1e-4, "{:?}", uniform);
+        }
+    }
+
+    #[test]
+    fn test_frustum_corners() {
+        let camera = Camera::default();
+        let corners = frustum_corners(&camera, 2.0, 1.0, 10.0);
+
+        // Looking along +Y with a 90 degree field of view
+        assert!(corners[..4].iter().all(|corner| (corner.y - 1.0).abs() < 1e-5));
+        assert!(corners[4..].iter().all(|corner| (corner.y - 10.0).abs() < 1e-5));
+        assert!(corners[..4].iter().all(|corner| (corner.z.abs() - 1.0).abs() < 1e-5));
+        assert!(corners[4..].iter().all(|corner| (corner.x.abs() - 20.0).abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_light_matrix_encloses_the_slice() {
+        let camera = camera();
+        for light_direction in [glm::vec3(0.3, 0.1, 1.0), glm::vec3(1.0, 0.0, 0.05), glm::vec3(0.0, 0.0, 1.0)] {
+            let corners = frustum_corners(&camera, 16.0 / 9.0, 5.0, 40.0);
+            let matrix = light_matrix(&corners, &light_direction, SHADOW_MAP_SIZE);
+            for corner in &corners {
+                let projected = project(&matrix, corner);
+                assert!(projected.x.abs() <= 1.0 && projected.y.abs() <= 1.0, "{:?}", projected);
+                assert!((0.0..=1.0).contains(&projected.z), "{:?}", projected);
+            }
+
+            // Closer to the light is smaller depth, so it shadows what's behind it
+            let center = corners.iter().sum::<glm::Vec3>() / 8.0;
+            let towards_light = center + light_direction.normalize() * 10.0;
+            assert!(project(&matrix, &towards_light).z < project(&matrix, &center).z);
+        }
+    }
+
+    #[test]
+    fn test_light_matrix_snaps_to_texels() {
+        let corners = frustum_corners(&camera(), 1.0, 0.1, 20.0);
+        let matrix = light_matrix(&corners, &glm::vec3(0.4, -0.2, 1.0), SHADOW_MAP_SIZE);
+        let origin = project(&matrix, &glm::vec3(0.0, 0.0, 0.0)) * (SHADOW_MAP_SIZE as f32 / 2.0);
+        assert!((origin.x - origin.x.round()).abs() < 1e-2, "{:?}", origin);
+        assert!((origin.y - origin.y.round()).abs() < 1e-2, "{:?}", origin);
+    }
+
+    #[test]
+    fn test_cascades_follow_the_splits() {
+        let cascades = ShadowCascades::new(&camera(), 1.5, &glm::vec3(0.2, 0.2, 1.0));
+        assert_eq!(cascades.splits.to_vec(), cascade_splits(0.1, 128.0, CASCADE_COUNT, SPLIT_LAMBDA));
+        // Farther cascades cover more ground, so the same size in the map is smaller on screen
+        let scale = |matrix: &glm::Mat4| glm::length(&glm::vec3(matrix[(0, 0)], matrix[(0, 1)], matrix[(0, 2)]));
+        assert!(scale(&cascades.matrices[0]) > scale(&cascades.matrices[2]));
+    }
+}
@@ -0,0 +1,379 @@
+use anyhow::{anyhow, Result};
+use nalgebra_glm as glm;
+use vulkanalia::prelude::v1_0::*;
+use crate::core::app_data::AppData;
+use crate::graphics::shader_files::SHADOW_VERT;
+use crate::graphics::shadow_cascades::{CASCADE_COUNT, SHADOW_MAP_SIZE};
+use crate::graphics::shared_images::create_layered_image;
+use crate::graphics::vertex::Vertex;
+
+// Sampled and rendered to by every driver, software ones included
+const SHADOW_MAP_FORMAT: vk::Format = vk::Format::D32_SFLOAT;
+// Keeps lit faces from shadowing themselves
+const DEPTH_BIAS_CONSTANT: f32 = 1.25;
+const DEPTH_BIAS_SLOPE: f32 = 1.75;
+
+// Something drawn into the shadow map
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ShadowCaster {
+    pub(crate) vertex_buffer: vk::Buffer,
+    pub(crate) index_buffer: vk::Buffer,
+    pub(crate) index_count: u32,
+    pub(crate) model: glm::Mat4,
+}
+
+// The shadow map is a depth image with a layer per cascade, drawn from the light before the world
+// render pass. It doesn't depend on the swapchain, so it lives as long as the device.
+pub(crate) unsafe fn create_shadow_objects(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
+    // Image
+
+    let (shadow_image, shadow_image_memory) = create_layered_image(
+        instance,
+        device,
+        data,
+        SHADOW_MAP_SIZE,
+        SHADOW_MAP_SIZE,
+        SHADOW_MAP_FORMAT,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        CASCADE_COUNT as u32,
+    )?;
+    data.shadow_image = shadow_image;
+    data.shadow_image_memory = shadow_image_memory;
+
+    // Views
+
+    // All cascades for sampling, one layer each for drawing
+    data.shadow_image_view = create_layer_view(device, data.shadow_image, vk::ImageViewType::_2D_ARRAY, 0, CASCADE_COUNT as u32)?;
+    data.shadow_layer_views = (0..CASCADE_COUNT as u32)
+        .map(|layer| create_layer_view(device, data.shadow_image, vk::ImageViewType::_2D, layer, 1))
+        .collect::<Result<Vec<_>>>()?;
+
+    create_shadow_render_pass(device, data)?;
+
+    // Framebuffers
+
+    data.shadow_framebuffers = data
+        .shadow_layer_views
+        .iter()
+        .map(|view| {
+            let attachments = &[*view];
+            let info = vk::FramebufferCreateInfo::builder()
+                .render_pass(data.shadow_render_pass)
+                .attachments(attachments)
+                .width(SHADOW_MAP_SIZE)
+                .height(SHADOW_MAP_SIZE)
+                .layers(1);
+            device.create_framebuffer(&info, None)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Sampler
+
+    // Compares against the stored depth, outside the map counts as lit
+    let info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::NEAREST)
+        .min_filter(vk::Filter::NEAREST)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
+        .unnormalized_coordinates(false)
+        .compare_enable(true)
+        .compare_op(vk::CompareOp::LESS_OR_EQUAL)
+        .mipmap_mode(vk::SamplerMipmapMode::NEAREST);
+
+    data.shadow_sampler = device.create_sampler(&info, None)?;
+
+    create_shadow_pipeline(device, data)?;
+
+    Ok(())
+}
+
+unsafe fn create_layer_view(
+    device: &Device,
+    image: vk::Image,
+    view_type: vk::ImageViewType,
+    base_layer: u32,
+    layer_count: u32,
+) -> Result<vk::ImageView> {
+    let subresource_range = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::DEPTH)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(base_layer)
+        .layer_count(layer_count);
+
+    let info = vk::ImageViewCreateInfo::builder()
+        .image(image)
+        .view_type(view_type)
+        .format(SHADOW_MAP_FORMAT)
+        .subresource_range(subresource_range);
+
+    Ok(device.create_image_view(&info, None)?)
+}
+
+unsafe fn create_shadow_render_pass(device: &Device, data: &mut AppData) -> Result<()> {
+    // Attachments
+
+    // Left ready for the world's fragment shader
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(SHADOW_MAP_FORMAT)
+        .samples(vk::SampleCountFlags::_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL);
+
+    // Subpasses
+
+    let depth_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .depth_stencil_attachment(&depth_attachment_ref);
+
+    // Dependencies
+
+    // The previous frame is done reading the map before it's cleared
+    let before = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+        .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+
+    // And this frame's depth is written before the world reads it
+    let after = vk::SubpassDependency::builder()
+        .src_subpass(0)
+        .dst_subpass(vk::SUBPASS_EXTERNAL)
+        .src_stage_mask(vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+        .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .dst_access_mask(vk::AccessFlags::SHADER_READ);
+
+    // Create
+
+    let attachments = &[depth_attachment];
+    let subpasses = &[subpass];
+    let dependencies = &[before, after];
+    let info = vk::RenderPassCreateInfo::builder()
+        .attachments(attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
+
+    data.shadow_render_pass = device.create_render_pass(&info, None)?;
+
+    Ok(())
+}
+
+pub(crate) unsafe fn create_shadow_pipeline(device: &Device, data: &mut AppData) -> Result<()> {
+    // Stages
+
+    // Depth only, there is no fragment shader
+    let vert = SHADOW_VERT.load();
+    let vert_shader_module = create_shader_module(device, &vert)?;
+
+    let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(vert_shader_module)
+        .name(b"main\0");
+
+    // Vertex Input State
+
+    let binding_descriptions = &[Vertex::binding_description()];
+    let attribute_descriptions = Vertex::attribute_descriptions();
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_binding_descriptions(binding_descriptions)
+        .vertex_attribute_descriptions(&attribute_descriptions);
+
+    // Input Assembly State
+
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .primitive_restart_enable(false);
+
+    // Viewport State
+
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(SHADOW_MAP_SIZE as f32)
+        .height(SHADOW_MAP_SIZE as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    let scissor = vk::Rect2D::builder()
+        .offset(vk::Offset2D { x: 0, y: 0 })
+        .extent(vk::Extent2D { width: SHADOW_MAP_SIZE, height: SHADOW_MAP_SIZE });
+
+    let viewports = &[viewport];
+    let scissors = &[scissor];
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewports(viewports)
+        .scissors(scissors);
+
+    // Rasterization State
+
+    // Both sides cast shadows, the light sees the terrain from any side
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(true)
+        .depth_bias_constant_factor(DEPTH_BIAS_CONSTANT)
+        .depth_bias_clamp(0.0)
+        .depth_bias_slope_factor(DEPTH_BIAS_SLOPE);
+
+    // Multisample State
+
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(false)
+        .rasterization_samples(vk::SampleCountFlags::_1);
+
+    //Depth stencil state
+
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(true)
+        .depth_compare_op(vk::CompareOp::LESS)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    // Push Constant Ranges
+
+    let push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::VERTEX)
+        .offset(0)
+        .size(68 /*one mat4 and the cascade index*/);
+
+    // Layout
+
+    // The cascade matrices come from the world uniform buffer
+    let set_layouts = &[data.descriptor_set_layout];
+    let push_constant_ranges = &[push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.shadow_pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+
+    // Create
+
+    let stages = &[vert_stage];
+    let info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .layout(data.shadow_pipeline_layout)
+        .render_pass(data.shadow_render_pass)
+        .subpass(0);
+
+    data.shadow_pipeline = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0;
+
+    // Cleanup
+
+    device.destroy_shader_module(vert_shader_module, None);
+
+    Ok(())
+}
+
+// Draws the casters into every cascade. The passes run even without casters, so the map is always
+// cleared and in the layout the world's fragment shader expects.
+pub(crate) unsafe fn record_shadow_passes(
+    device: &Device,
+    data: &AppData,
+    command_buffer: vk::CommandBuffer,
+    descriptor_set: vk::DescriptorSet,
+    casters: &[ShadowCaster],
+) {
+    let render_area = vk::Rect2D::builder()
+        .offset(vk::Offset2D::default())
+        .extent(vk::Extent2D { width: SHADOW_MAP_SIZE, height: SHADOW_MAP_SIZE });
+
+    let depth_clear_value = vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue {
+            depth: 1.0,
+            stencil: 0,
+        },
+    };
+    let clear_values = &[depth_clear_value];
+
+    for (cascade, framebuffer) in data.shadow_framebuffers.iter().enumerate() {
+        let info = vk::RenderPassBeginInfo::builder()
+            .render_pass(data.shadow_render_pass)
+            .framebuffer(*framebuffer)
+            .render_area(render_area)
+            .clear_values(clear_values);
+
+        device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::INLINE);
+
+        if !casters.is_empty() {
+            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, data.shadow_pipeline);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                data.shadow_pipeline_layout,
+                0,
+                &[descriptor_set],
+                &[],
+            );
+            let cascade_bytes = (cascade as u32).to_ne_bytes();
+            device.cmd_push_constants(command_buffer, data.shadow_pipeline_layout, vk::ShaderStageFlags::VERTEX, 64, &cascade_bytes);
+        }
+        for caster in casters {
+            let (_, model_bytes, _) = caster.model.as_slice().align_to::<u8>();
+            device.cmd_bind_vertex_buffers(command_buffer, 0, &[caster.vertex_buffer], &[0]);
+            device.cmd_bind_index_buffer(command_buffer, caster.index_buffer, 0, vk::IndexType::UINT32);
+            device.cmd_push_constants(command_buffer, data.shadow_pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, model_bytes);
+            device.cmd_draw_indexed(command_buffer, caster.index_count, 1, 0, 0, 0);
+        }
+
+        device.cmd_end_render_pass(command_buffer);
+    }
+}
+
+pub(crate) unsafe fn destroy_shadow_objects(device: &Device, data: &mut AppData) {
+    device.destroy_pipeline(data.shadow_pipeline, None);
+    device.destroy_pipeline_layout(data.shadow_pipeline_layout, None);
+    device.destroy_sampler(data.shadow_sampler, None);
+    data.shadow_framebuffers.drain(..).for_each(|f| device.destroy_framebuffer(f, None));
+    device.destroy_render_pass(data.shadow_render_pass, None);
+    data.shadow_layer_views.drain(..).for_each(|v| device.destroy_image_view(v, None));
+    device.destroy_image_view(data.shadow_image_view, None);
+    device.destroy_image(data.shadow_image, None);
+    device.free_memory(data.shadow_image_memory, None);
+}
+
+unsafe fn create_shader_module(device: &Device, bytecode: &[u8]) -> Result<vk::ShaderModule> {
+    let bytecode = Vec::<u8>::from(bytecode);
+    let (prefix, code, suffix) = bytecode.align_to::<u32>();
+    if !prefix.is_empty() || !suffix.is_empty() {
+        return Err(anyhow!("Shader bytecode is not properly aligned."));
+    }
+
+    let info = vk::ShaderModuleCreateInfo::builder()
+        .code_size(bytecode.len())
+        .code(code);
+
+    Ok(device.create_shader_module(&info, None)?)
+}
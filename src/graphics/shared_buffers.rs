@@ -1,4 +1,6 @@
 use anyhow::Result;
+use std::mem::size_of_val;
+use std::ptr::copy_nonoverlapping as memcpy;
 
 use vulkanalia::prelude::v1_0::*;
 use crate::core::app_data::AppData;
@@ -43,6 +45,18 @@ pub(crate) unsafe fn create_buffer(
     Ok((buffer, buffer_memory))
 }
 
+// Copies `items` to the start of host visible memory
+pub(crate) unsafe fn write_memory<T>(device: &Device, memory: vk::DeviceMemory, items: &[T]) -> Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    let size = size_of_val(items) as u64;
+    let mapped = device.map_memory(memory, 0, size, vk::MemoryMapFlags::empty())?;
+    memcpy(items.as_ptr(), mapped.cast(), items.len());
+    device.unmap_memory(memory);
+    Ok(())
+}
+
 pub(crate) unsafe fn copy_buffer(
     device: &Device,
     data: &AppData,
@@ -77,3 +91,43 @@ pub(crate) unsafe fn copy_buffer_offset(
 
     Ok(())
 }
+
+// Uploads `items` through a staging buffer into a new device local buffer
+pub(crate) unsafe fn create_device_local_buffer<T>(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    items: &[T],
+    usage: vk::BufferUsageFlags,
+) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    let size = size_of_val(items) as u64;
+
+    let (staging_buffer, staging_buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
+    memcpy(items.as_ptr(), memory.cast(), items.len());
+    device.unmap_memory(staging_buffer_memory);
+
+    let (buffer, buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_DST | usage,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    copy_buffer(device, data, staging_buffer, buffer, size)?;
+
+    device.destroy_buffer(staging_buffer, None);
+    device.free_memory(staging_buffer_memory, None);
+
+    Ok((buffer, buffer_memory))
+}
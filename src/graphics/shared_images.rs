@@ -16,6 +16,22 @@ pub(crate) unsafe fn create_image(
     tiling: vk::ImageTiling,
     usage: vk::ImageUsageFlags,
     properties: vk::MemoryPropertyFlags,
+) -> Result<(vk::Image, vk::DeviceMemory)> {
+    create_layered_image(instance, device, data, width, height, format, tiling, usage, properties, 1)
+}
+
+// An image with `array_layers` layers of the same size, like the cascades of the shadow map
+pub(crate) unsafe fn create_layered_image(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    width: u32,
+    height: u32,
+    format: vk::Format,
+    tiling: vk::ImageTiling,
+    usage: vk::ImageUsageFlags,
+    properties: vk::MemoryPropertyFlags,
+    array_layers: u32,
 ) -> Result<(vk::Image, vk::DeviceMemory)> {
     // Image
 
@@ -27,7 +43,7 @@ pub(crate) unsafe fn create_image(
             depth: 1,
         })
         .mip_levels(1)
-        .array_layers(1)
+        .array_layers(array_layers)
         .format(format)
         .tiling(tiling)
         .initial_layout(vk::ImageLayout::UNDEFINED)
@@ -11,11 +11,12 @@ use crate::core::app_data::AppData;
 pub(crate) unsafe fn create_texture_image_view(
     device: &Device,
     image: &vk::Image,
+    format: vk::Format,
 ) -> Result<vk::ImageView> {
     let image_view = create_image_view(
         device,
         *image,
-        vk::Format::R8G8B8A8_SRGB,
+        format,
         vk::ImageAspectFlags::COLOR,
     )?;
 
@@ -46,17 +47,20 @@ pub(crate) unsafe fn create_texture_image_from_path(
         data,
         width,
         height,
+        vk::Format::R8G8B8A8_SRGB,
         pixels.as_slice(),
     )?;
     Ok((image, image_memory))
 }
 
+// Colour textures are sRGB, data that is read as plain numbers like distance fields is UNORM
 pub(crate) unsafe fn create_texture_image_from_byte_buffer(
     instance: &Instance,
     device: &Device,
     data: &mut AppData,
     width: u32,
     height: u32,
+    format: vk::Format,
     buffer: &[u8],
 ) -> Result<(vk::Image, vk::DeviceMemory)> {
     let size = buffer.len() as u64;
@@ -88,7 +92,7 @@ pub(crate) unsafe fn create_texture_image_from_byte_buffer(
         data,
         width,
         height,
-        vk::Format::R8G8B8A8_SRGB,
+        format,
         vk::ImageTiling::OPTIMAL,
         vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
         vk::MemoryPropertyFlags::DEVICE_LOCAL,
@@ -100,7 +104,7 @@ pub(crate) unsafe fn create_texture_image_from_byte_buffer(
         device,
         data,
         texture_image,
-        vk::Format::R8G8B8A8_SRGB,
+        format,
         vk::ImageLayout::UNDEFINED,
         vk::ImageLayout::TRANSFER_DST_OPTIMAL,
     )?;
@@ -111,7 +115,7 @@ pub(crate) unsafe fn create_texture_image_from_byte_buffer(
         device,
         data,
         texture_image,
-        vk::Format::R8G8B8A8_SRGB,
+        format,
         vk::ImageLayout::TRANSFER_DST_OPTIMAL,
         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
     )?;
@@ -23,7 +23,7 @@ pub(crate) unsafe fn create_swapchain(
     let support = SwapchainSupport::get(instance, data, data.physical_device)?;
 
     let surface_format = get_swapchain_surface_format(&support.formats);
-    let present_mode = get_swapchain_present_mode(&support.present_modes);
+    let present_mode = data.present_mode.choose(&support.present_modes);
     let extent = get_swapchain_extent(window, support.capabilities);
 
     data.swapchain_format = surface_format.format;
@@ -45,6 +45,16 @@ pub(crate) unsafe fn create_swapchain(
         vk::SharingMode::EXCLUSIVE
     };
 
+    // Screenshots copy straight out of the swapchain images when the surface allows it
+    data.swapchain_supports_capture = support
+        .capabilities
+        .supported_usage_flags
+        .contains(vk::ImageUsageFlags::TRANSFER_SRC);
+    let mut image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT;
+    if data.swapchain_supports_capture {
+        image_usage |= vk::ImageUsageFlags::TRANSFER_SRC;
+    }
+
     // Create
 
     let info = vk::SwapchainCreateInfoKHR::builder()
@@ -54,7 +64,7 @@ pub(crate) unsafe fn create_swapchain(
         .image_color_space(surface_format.color_space)
         .image_extent(extent)
         .image_array_layers(1)
-        .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+        .image_usage(image_usage)
         .image_sharing_mode(image_sharing_mode)
         .queue_family_indices(&queue_family_indices)
         .pre_transform(support.capabilities.current_transform)
@@ -83,14 +93,6 @@ fn get_swapchain_surface_format(formats: &[vk::SurfaceFormatKHR]) -> vk::Surface
         .unwrap_or_else(|| formats[0])
 }
 
-fn get_swapchain_present_mode(present_modes: &[vk::PresentModeKHR]) -> vk::PresentModeKHR {
-    present_modes
-        .iter()
-        .cloned()
-        .find(|m| *m == vk::PresentModeKHR::MAILBOX)
-        .unwrap_or(vk::PresentModeKHR::FIFO)
-}
-
 fn get_swapchain_extent(window: &Window, capabilities: vk::SurfaceCapabilitiesKHR) -> vk::Extent2D {
     if capabilities.current_extent.width != u32::MAX {
         capabilities.current_extent
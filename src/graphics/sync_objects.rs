@@ -1,7 +1,5 @@
 use anyhow::Result;
 
-use crate::{MAX_FRAMES_IN_FLIGHT};
-
 use vulkanalia::prelude::v1_0::*;
 use crate::core::app_data::AppData;
 
@@ -9,7 +7,7 @@ pub(crate) unsafe fn create_sync_objects(device: &Device, data: &mut AppData) ->
     let semaphore_info = vk::SemaphoreCreateInfo::builder();
     let fence_info = vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED);
 
-    for _ in 0..MAX_FRAMES_IN_FLIGHT {
+    for _ in 0..data.frames_in_flight {
         data.image_available_semaphores
             .push(device.create_semaphore(&semaphore_info, None)?);
         data.render_finished_semaphores
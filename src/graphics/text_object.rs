@@ -1,40 +1,160 @@
+use crate::graphics::font_data::FontData;
+use crate::graphics::fonts::{Character, FontId};
+use crate::graphics::ui_space::{Anchor, UiSpace};
 use nalgebra_glm as glm;
+use std::collections::HashMap;
+
+const SPACE: u32 = 32;
+const NEW_LINE: u32 = 10;
+
+// How lines shorter than the widest one are placed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlignment {
+    fn factor(&self) -> f32 {
+        match self {
+            TextAlignment::Left => 0.0,
+            TextAlignment::Center => 0.5,
+            TextAlignment::Right => 1.0,
+        }
+    }
+}
+
+// Look of a run of characters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TextStyle {
+    pub(crate) color: glm::Vec4,
+    // Draws a dark copy behind the glyphs so the text stays readable on bright backgrounds. Uses
+    // the text's shadow settings if it has them.
+    pub(crate) shadow: bool,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            shadow: false,
+        }
+    }
+}
+
+impl TextStyle {
+    pub(crate) fn new(color: glm::Vec4) -> Self {
+        Self {
+            color,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn set_shadow(&self, shadow: bool) -> Self {
+        let mut clone = *self;
+        clone.shadow = shadow;
+        clone
+    }
+}
+
+// Part of a text drawn in one style
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TextSpan {
+    pub(crate) text: String,
+    pub(crate) style: TextStyle,
+}
+
+impl TextSpan {
+    pub(crate) fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self { text: text.into(), style }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct TextObject {
-    content: &'static str,
+    content: String,
+    spans: Vec<TextSpan>,
+    // Offset in logical pixels from the anchor, Y pointing down
     position: glm::Vec2,
     settings: TextSettings,
     chars: Vec<u32>,
+    // Style of every character in `chars`
+    styles: Vec<TextStyle>,
 }
 
 impl TextObject {
-    pub(crate) fn new(content: &'static str, position: glm::Vec2, settings: TextSettings) -> Self {
-        let mut chars = vec![];
-
-        for char in content.chars() {
-            chars.push(char as u32);
-        }
+    pub(crate) fn new(content: impl Into<String>, position: glm::Vec2, settings: TextSettings) -> Self {
+        Self::from_spans(vec![TextSpan::new(content, TextStyle::default())], position, settings)
+    }
 
-        Self {
-            content,
+    pub(crate) fn from_spans(spans: Vec<TextSpan>, position: glm::Vec2, settings: TextSettings) -> Self {
+        let mut text_object = Self {
+            content: String::new(),
+            spans: vec![],
             position,
             settings,
-            chars,
+            chars: vec![],
+            styles: vec![],
+        };
+        text_object.set_spans(spans);
+        text_object
+    }
+
+    pub(crate) fn get_content(&self) -> &str {
+        &self.content
+    }
+
+    // The new text keeps the style the text started with
+    pub(crate) fn set_content(&mut self, content: impl Into<String>) {
+        let style = self.spans.first().map(|span| span.style).unwrap_or_default();
+        self.set_spans(vec![TextSpan::new(content, style)]);
+    }
+
+    pub(crate) fn get_spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    pub(crate) fn set_spans(&mut self, spans: Vec<TextSpan>) {
+        self.content = spans.iter().map(|span| span.text.as_str()).collect();
+        self.chars.clear();
+        self.styles.clear();
+        for span in &spans {
+            for char in span.text.chars() {
+                self.chars.push(char as u32);
+                self.styles.push(span.style);
+            }
         }
+        self.spans = spans;
     }
 
-    pub(crate) fn get_content(&self) -> &'static str {
-        self.content
+    // Gives the whole text one style
+    pub(crate) fn set_style(&mut self, style: TextStyle) {
+        let content = std::mem::take(&mut self.content);
+        self.set_spans(vec![TextSpan::new(content, style)]);
     }
 
     pub(crate) fn get_position(&self) -> glm::Vec2 {
         self.position
     }
 
+    pub(crate) fn set_position(&mut self, position: glm::Vec2) {
+        self.position = position;
+    }
+
     pub(crate) fn get_chars(&self) -> &Vec<u32> {
         &self.chars
     }
 
+    pub(crate) fn get_settings(&self) -> &TextSettings {
+        &self.settings
+    }
+
+    pub(crate) fn set_settings(&mut self, settings: TextSettings) {
+        self.settings = settings;
+    }
+
     pub(crate) fn get_scale(&self) -> f32 {
         self.settings.scale
     }
@@ -44,29 +164,660 @@ impl TextObject {
     }
 }
 
-#[derive(Clone, Debug)]
+// Line drawn around every glyph, at most as wide as the distance field's spread
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TextOutline {
+    // In logical pixels
+    pub(crate) width: f32,
+    pub(crate) color: glm::Vec4,
+}
+
+impl TextOutline {
+    pub(crate) fn new(width: f32, color: glm::Vec4) -> Self {
+        Self { width, color }
+    }
+}
+
+// Copy of the glyphs drawn behind them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TextShadow {
+    // In logical pixels, Y pointing down
+    pub(crate) offset: glm::Vec2,
+    // Alpha is multiplied with the glyph colour's
+    pub(crate) color: glm::Vec4,
+    // How far in logical pixels the shadow's edge is blurred
+    pub(crate) softness: f32,
+}
+
+impl TextShadow {
+    pub(crate) fn new(offset: glm::Vec2, color: glm::Vec4, softness: f32) -> Self {
+        Self { offset, color, softness }
+    }
+
+    // What styles with a shadow get when the text doesn't set one, a twentieth of the line height
+    // down and right but at least a pixel
+    fn for_line_height(line_height: f32) -> Self {
+        let offset = (line_height / 20.0).max(1.0);
+        Self::new(glm::vec2(offset, offset), glm::vec4(0.0, 0.0, 0.0, 0.75), 0.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) struct TextSettings {
+    // Pixels per font unit
     scale: f32,
     wrap: bool,
+    // Widest a line may get before wrapping, in pixels. Lines never run past the screen edge.
+    max_width: Option<f32>,
+    // Multiplies the font's line height
+    line_spacing: f32,
+    anchor: Anchor,
+    alignment: TextAlignment,
+    font: FontId,
+    outline: Option<TextOutline>,
+    // Given to every character, not only to the styles asking for one
+    shadow: Option<TextShadow>,
 }
 
 impl TextSettings {
     pub(crate) fn new(scale: f32, wrap: bool) -> Self {
-        Self { scale, wrap }
+        Self {
+            scale,
+            wrap,
+            ..Default::default()
+        }
     }
 
     pub(crate) fn set_wrap(&self, wrap: bool) -> Self {
-        let mut clone = self.clone();
+        let mut clone = *self;
         clone.wrap = wrap;
         clone
     }
+
+    pub(crate) fn set_max_width(&self, max_width: Option<f32>) -> Self {
+        let mut clone = *self;
+        clone.max_width = max_width;
+        clone
+    }
+
+    pub(crate) fn set_line_spacing(&self, line_spacing: f32) -> Self {
+        let mut clone = *self;
+        clone.line_spacing = line_spacing;
+        clone
+    }
+
+    pub(crate) fn set_anchor(&self, anchor: Anchor) -> Self {
+        let mut clone = *self;
+        clone.anchor = anchor;
+        clone
+    }
+
+    pub(crate) fn set_alignment(&self, alignment: TextAlignment) -> Self {
+        let mut clone = *self;
+        clone.alignment = alignment;
+        clone
+    }
+
+    pub(crate) fn set_font(&self, font: FontId) -> Self {
+        let mut clone = *self;
+        clone.font = font;
+        clone
+    }
+
+    pub(crate) fn set_outline(&self, outline: Option<TextOutline>) -> Self {
+        let mut clone = *self;
+        clone.outline = outline;
+        clone
+    }
+
+    pub(crate) fn set_shadow(&self, shadow: Option<TextShadow>) -> Self {
+        let mut clone = *self;
+        clone.shadow = shadow;
+        clone
+    }
+
+    pub(crate) fn get_max_width(&self) -> Option<f32> {
+        self.max_width
+    }
+
+    pub(crate) fn get_line_spacing(&self) -> f32 {
+        self.line_spacing
+    }
+
+    pub(crate) fn get_anchor(&self) -> Anchor {
+        self.anchor
+    }
+
+    pub(crate) fn get_alignment(&self) -> TextAlignment {
+        self.alignment
+    }
+
+    pub(crate) fn get_font(&self) -> FontId {
+        self.font
+    }
+
+    pub(crate) fn get_outline(&self) -> Option<TextOutline> {
+        self.outline
+    }
+
+    pub(crate) fn get_shadow(&self) -> Option<TextShadow> {
+        self.shadow
+    }
 }
 
 impl Default for TextSettings {
     fn default() -> Self {
         Self {
-            scale: 0.0001,
+            scale: 0.02,
             wrap: true,
+            max_width: None,
+            line_spacing: 1.0,
+            anchor: Anchor::default(),
+            alignment: TextAlignment::default(),
+            font: FontId::default(),
+            outline: None,
+            shadow: None,
+        }
+    }
+}
+
+// Matches the text pipeline's vertex input, positions are in logical pixels of the UI space.
+// Outline width and softness are in distance field values, where 0.5 is the glyph's edge.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TextVertex {
+    pub(crate) position: glm::Vec2,
+    pub(crate) uv: glm::Vec2,
+    pub(crate) color: glm::Vec4,
+    pub(crate) outline_color: glm::Vec4,
+    pub(crate) outline_width: f32,
+    pub(crate) softness: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct TextMesh {
+    pub(crate) vertices: Vec<TextVertex>,
+    pub(crate) indices: Vec<u32>,
+    // Pixel rectangle the text covers, as top left corner and size
+    pub(crate) origin: glm::Vec2,
+    pub(crate) size: glm::Vec2,
+}
+
+// A glyph placed on screen, in logical pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct GlyphQuad {
+    pub(crate) character: u32,
+    pub(crate) top_left: glm::Vec2,
+    pub(crate) bottom_right: glm::Vec2,
+    // (x_start, x_end, y_start, y_end) in the glyph atlas
+    pub(crate) texture_coordinates: (f32, f32, f32, f32),
+    pub(crate) color: glm::Vec4,
+    // Transparent without an outline
+    pub(crate) outline_color: glm::Vec4,
+    // Distance field values, like TextVertex
+    pub(crate) outline_width: f32,
+    pub(crate) softness: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct TextLayout {
+    // In drawing order, shadows come before the glyphs they belong to
+    pub(crate) quads: Vec<GlyphQuad>,
+    pub(crate) origin: glm::Vec2,
+    pub(crate) size: glm::Vec2,
+}
+
+impl TextLayout {
+    pub(crate) fn to_mesh(&self) -> TextMesh {
+        let mut mesh = TextMesh {
+            origin: self.origin,
+            size: self.size,
+            ..Default::default()
+        };
+        for quad in &self.quads {
+            let (top_left, bottom_right) = (quad.top_left, quad.bottom_right);
+            let (x_start, x_end, y_start, y_end) = quad.texture_coordinates;
+            let vertex = |x: f32, y: f32, u: f32, v: f32| TextVertex {
+                position: glm::vec2(x, y),
+                uv: glm::vec2(u, v),
+                color: quad.color,
+                outline_color: quad.outline_color,
+                outline_width: quad.outline_width,
+                softness: quad.softness,
+            };
+
+            let vertex_index = mesh.vertices.len() as u32;
+            mesh.vertices.push(vertex(top_left.x, bottom_right.y, x_start, y_end));
+            mesh.vertices.push(vertex(bottom_right.x, top_left.y, x_end, y_start));
+            mesh.vertices.push(vertex(top_left.x, top_left.y, x_start, y_start));
+            mesh.vertices.push(vertex(bottom_right.x, bottom_right.y, x_end, y_end));
+            mesh.indices.extend_from_slice(&[
+                vertex_index,
+                vertex_index + 1,
+                vertex_index + 2,
+                vertex_index,
+                vertex_index + 3,
+                vertex_index + 1,
+            ]);
+        }
+        mesh
+    }
+}
+
+struct Line {
+    // Indices into the text's characters with their advance from the start of the line in font units
+    glyphs: Vec<(usize, f32)>,
+    width: f32,
+}
+
+// Splits the text into lines, wrapping whole words once a line gets wider than `max_width` font units
+fn break_lines(chars: &[u32], characters: &HashMap<u32, Character>, font_data: &FontData, max_width: Option<f32>) -> Vec<Line> {
+    let advance_of = |index: usize| characters.get(&chars[index]).map_or(0.0, |character| character.advance as f32);
+    let end_of = |(index, x): &(usize, f32)| x + advance_of(*index);
+    let mut lines = vec![];
+    let mut paragraph_start = 0;
+    for paragraph in chars.split(|char| *char == NEW_LINE) {
+        let offset = paragraph_start;
+        paragraph_start += paragraph.len() + 1;
+
+        let mut line = Line { glyphs: vec![], width: 0.0 };
+        // Index into line.glyphs of the first character after the last space
+        let mut word_start = 0;
+        let mut wrapped = false;
+        for (index, char) in paragraph.iter().enumerate().map(|(index, char)| (offset + index, char)) {
+            if !characters.contains_key(char) {
+                continue;
+            }
+            let advance = advance_of(index);
+            let overflows = max_width.is_some_and(|max_width| line.width + advance > max_width);
+            if overflows && *char != SPACE && !line.glyphs.is_empty() {
+                // Moves the unfinished word to a new line, unless it's the whole line
+                let word = if word_start > 0 { line.glyphs.split_off(word_start) } else { vec![] };
+                while line.glyphs.last().is_some_and(|(index, _)| chars[*index] == SPACE) {
+                    line.glyphs.pop();
+                }
+                line.width = line.glyphs.last().map_or(0.0, end_of);
+                lines.push(line);
+
+                let start = word.first().map_or(0.0, |(_, x)| *x);
+                let glyphs = word.into_iter().map(|(index, x)| (index, x - start)).collect::<Vec<_>>();
+                let width = glyphs.last().map_or(0.0, end_of);
+                line = Line { glyphs, width };
+                word_start = 0;
+                wrapped = true;
+            }
+            if *char == SPACE && line.glyphs.is_empty() && wrapped {
+                // Wrapped lines don't start with a space
+                continue;
+            }
+
+            // Kerning moves the glyph relative to the one before it on the line
+            let kerning = line
+                .glyphs
+                .last()
+                .map_or(0.0, |(previous, _)| font_data.kerning(chars[*previous], *char) as f32);
+            line.glyphs.push((index, line.width + kerning));
+            line.width += kerning + advance;
+            if *char == SPACE {
+                word_start = line.glyphs.len();
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// Places the glyphs in the logical pixels of the UI space
+pub(crate) fn layout_glyphs(
+    text_object: &TextObject,
+    characters: &HashMap<u32, Character>,
+    font_data: &FontData,
+    ui: &UiSpace,
+) -> TextLayout {
+    let settings = &text_object.settings;
+    let scale = settings.scale;
+    let screen = ui.size();
+
+    // Wrapping keeps the text between its anchor and the far edge of the screen
+    let max_width = if settings.wrap && scale > 0.0 {
+        let room = (screen.x - text_object.position.x.abs()).max(0.0);
+        let room = settings.max_width.map_or(room, |max_width| room.min(max_width));
+        Some(room / scale)
+    } else {
+        None
+    };
+    let lines = break_lines(&text_object.chars, characters, font_data, max_width);
+
+    let ascent = font_data.global_bounding_box.y_max as f32;
+    let line_height = (font_data.global_bounding_box.height() + font_data.line_gap) as f32 * scale * settings.line_spacing;
+    let width = lines.iter().map(|line| line.width).fold(0.0, f32::max) * scale;
+    let size = glm::vec2(width, line_height * lines.len() as f32);
+    let origin = ui.place(settings.anchor, text_object.position, size);
+    let default_shadow = settings.shadow.unwrap_or(TextShadow::for_line_height(line_height));
+
+    let mut layout = TextLayout {
+        origin,
+        size,
+        ..Default::default()
+    };
+    let mut shadows = vec![];
+    for (line_index, line) in lines.iter().enumerate() {
+        let line_x = origin.x + (width - line.width * scale) * settings.alignment.factor();
+        let line_y = origin.y + line_height * line_index as f32;
+        for (index, advance) in &line.glyphs {
+            let char = text_object.chars[*index];
+            let character = &characters[&char];
+            let bounding_box = character.bounding_box;
+            if bounding_box.width() == 0 || bounding_box.height() == 0 {
+                continue;
+            }
+            let Some(texture_coordinates) = character.texture_coordinates else {
+                continue;
+            };
+
+            // Logical pixels to distance field values, the field can't reach further than its spread
+            let to_field = |pixels: f32| (pixels / (scale * character.distance_range)).clamp(0.0, 0.5);
+            let (outline_color, outline_width) = settings
+                .outline
+                .map_or((glm::vec4(0.0, 0.0, 0.0, 0.0), 0.0), |outline| (outline.color, to_field(outline.width)));
+
+            let style = text_object.styles[*index];
+            let quad = GlyphQuad {
+                character: char,
+                top_left: glm::vec2(
+                    line_x + (advance + bounding_box.x_min as f32) * scale,
+                    line_y + (ascent - bounding_box.y_max as f32) * scale,
+                ),
+                bottom_right: glm::vec2(
+                    line_x + (advance + bounding_box.x_max as f32) * scale,
+                    line_y + (ascent - bounding_box.y_min as f32) * scale,
+                ),
+                texture_coordinates,
+                color: style.color,
+                outline_color,
+                outline_width,
+                softness: 0.0,
+            };
+            let shadow = settings.shadow.or(style.shadow.then_some(default_shadow));
+            if let Some(shadow) = shadow {
+                let mut color = shadow.color;
+                color.w *= style.color.w;
+                // The shadow covers the outline as well
+                shadows.push(GlyphQuad {
+                    top_left: quad.top_left + shadow.offset,
+                    bottom_right: quad.bottom_right + shadow.offset,
+                    color,
+                    outline_color: color,
+                    softness: to_field(shadow.softness),
+                    ..quad
+                });
+            }
+            layout.quads.push(quad);
+        }
+    }
+    // Shadows are drawn first so they never cover a neighbouring glyph
+    shadows.append(&mut layout.quads);
+    layout.quads = shadows;
+    layout
+}
+
+// The text pipeline projects the logical pixels, so the mesh only changes with the text or screen size
+pub(crate) fn layout_text(
+    text_object: &TextObject,
+    characters: &HashMap<u32, Character>,
+    font_data: &FontData,
+    ui: &UiSpace,
+) -> TextMesh {
+    layout_glyphs(text_object, characters, font_data, ui).to_mesh()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::font_data::Rect;
+
+    use vulkanalia::vk;
+
+    const UI: UiSpace = UiSpace {
+        extent: vk::Extent2D { width: 200, height: 100 },
+        scale_factor: 1.0,
+    };
+
+    // Every glyph is 10 units wide with a 10x10 box, spaces are empty
+    fn characters() -> HashMap<u32, Character> {
+        let mut characters = HashMap::new();
+        for char in "abcdefghij ".chars() {
+            let is_space = char == ' ';
+            characters.insert(char as u32, Character {
+                character: char as u32,
+                texture_coordinates: Some((0.0, 1.0, 0.0, 1.0)),
+                size: glm::vec2(10.0, 10.0),
+                bearing: glm::vec2(0.0, 0.0),
+                bounding_box: Rect {
+                    x_min: 0,
+                    y_min: 0,
+                    x_max: if is_space { 0 } else { 10 },
+                    y_max: if is_space { 0 } else { 10 },
+                },
+                advance: 10,
+                distance_range: 4.0,
+            });
+        }
+        characters
+    }
+
+    // "ea" is kerned 2 units closer
+    fn font_data() -> FontData {
+        FontData {
+            line_gap: 2,
+            global_bounding_box: Rect {
+                x_min: 0,
+                y_min: -2,
+                x_max: 10,
+                y_max: 10,
+            },
+            kerning: HashMap::from([(('e' as u32, 'a' as u32), -2)]),
+        }
+    }
+
+    fn layout(content: &str, position: glm::Vec2, settings: TextSettings) -> TextMesh {
+        layout_text(&TextObject::new(content, position, settings), &characters(), &font_data(), &UI)
+    }
+
+    fn glyphs(text: &TextObject) -> TextLayout {
+        layout_glyphs(text, &characters(), &font_data(), &UI)
+    }
+
+    fn to_pixels(vertex: TextVertex) -> glm::Vec2 {
+        vertex.position
+    }
+
+    fn assert_close(a: glm::Vec2, b: glm::Vec2) {
+        assert!(glm::distance(&a, &b) < 0.001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_glyph_quads() {
+        let mesh = layout("ab c", glm::vec2(10.0, 20.0), TextSettings::new(1.0, false));
+
+        // The space gets no quad but still advances
+        assert_eq!(mesh.vertices.len(), 12);
+        assert_eq!(mesh.indices.len(), 18);
+        assert_eq!(&mesh.indices[6..12], &[4, 5, 6, 4, 7, 5]);
+        assert_close(mesh.size, glm::vec2(40.0, 14.0));
+
+        // Top left corners, glyph tops line up with the font's ascent
+        assert_close(to_pixels(mesh.vertices[2]), glm::vec2(10.0, 20.0));
+        assert_close(to_pixels(mesh.vertices[6]), glm::vec2(20.0, 20.0));
+        assert_close(to_pixels(mesh.vertices[10]), glm::vec2(40.0, 20.0));
+        assert_close(to_pixels(mesh.vertices[3]), glm::vec2(20.0, 30.0));
+    }
+
+    #[test]
+    fn test_anchors() {
+        let settings = TextSettings::new(1.0, false);
+
+        let centered = layout("abcd", glm::vec2(0.0, 0.0), settings.set_anchor(Anchor::Center));
+        assert_close(centered.origin, glm::vec2(80.0, 43.0));
+
+        let corner = layout("abcd", glm::vec2(-5.0, -5.0), settings.set_anchor(Anchor::BottomRight));
+        assert_close(corner.origin + corner.size, glm::vec2(195.0, 95.0));
+        assert_close(to_pixels(corner.vertices[2]), glm::vec2(155.0, 81.0));
+    }
+
+    #[test]
+    fn test_layout_is_in_logical_pixels() {
+        let settings = TextSettings::new(1.0, true).set_anchor(Anchor::BottomRight);
+        let text = TextObject::new("abc defg hij", glm::vec2(-10.0, -10.0), settings);
+        let high_dpi = UiSpace::new(vk::Extent2D { width: 400, height: 200 }, 2.0);
+
+        // Twice the physical pixels at twice the scale factor is the same logical screen
+        assert_eq!(layout_glyphs(&text, &characters(), &font_data(), &high_dpi), glyphs(&text));
+        let resized = UiSpace::new(vk::Extent2D { width: 300, height: 100 }, 1.0);
+        let moved = layout_glyphs(&text, &characters(), &font_data(), &resized);
+        assert_close(moved.origin - glyphs(&text).origin, glm::vec2(100.0, 0.0));
+    }
+
+    #[test]
+    fn test_new_lines_and_alignment() {
+        let settings = TextSettings::new(1.0, false).set_alignment(TextAlignment::Right);
+        let mesh = layout("abcd\nab", glm::vec2(0.0, 0.0), settings);
+
+        assert_close(mesh.size, glm::vec2(40.0, 28.0));
+        // The short second line is pushed right, one line height down
+        assert_close(to_pixels(mesh.vertices[16 + 2]), glm::vec2(20.0, 14.0));
+        assert_close(to_pixels(mesh.vertices[20 + 2]), glm::vec2(30.0, 14.0));
+    }
+
+    #[test]
+    fn test_wrapping_moves_whole_words() {
+        let settings = TextSettings::new(1.0, true);
+        // 100 pixels of room to the right edge, so at most 10 characters per line
+        let mesh = layout("abc defg hij", glm::vec2(100.0, 0.0), settings);
+
+        assert_close(mesh.size, glm::vec2(80.0, 28.0));
+        assert_eq!(mesh.vertices.len(), 10 * 4);
+        // "hij" starts the second line instead of being split
+        assert_close(to_pixels(mesh.vertices[7 * 4 + 2]), glm::vec2(100.0, 14.0));
+
+        // A word longer than the line is split where it overflows
+        let mesh = layout("abcdefghijabc", glm::vec2(100.0, 0.0), settings);
+        assert_close(mesh.size, glm::vec2(100.0, 28.0));
+        assert_close(to_pixels(mesh.vertices[10 * 4 + 2]), glm::vec2(100.0, 14.0));
+    }
+
+    #[test]
+    fn test_updating_content() {
+        let mut text = TextObject::new(String::from("ab"), glm::vec2(0.0, 0.0), TextSettings::default());
+        text.set_content(format!("{} xyz", 60));
+
+        assert_eq!(text.get_content(), "60 xyz");
+        assert_eq!(text.get_chars().len(), 6);
+        // Characters missing from the font are skipped
+        let mesh = layout_text(&text, &characters(), &font_data(), &UI);
+        assert!(mesh.vertices.is_empty());
+    }
+
+    #[test]
+    fn test_kerning_pulls_pairs_together() {
+        let text = TextObject::new("eab ae", glm::vec2(0.0, 0.0), TextSettings::new(1.0, false));
+        let layout = glyphs(&text);
+
+        let lefts = layout.quads.iter().map(|quad| quad.top_left.x).collect::<Vec<_>>();
+        // Only the "ea" pair moves, everything after it follows
+        assert_eq!(lefts, vec![0.0, 8.0, 18.0, 38.0, 48.0]);
+        assert_close(layout.size, glm::vec2(58.0, 14.0));
+    }
+
+    #[test]
+    fn test_max_width_and_line_spacing() {
+        let settings = TextSettings::new(1.0, true).set_max_width(Some(35.0)).set_line_spacing(2.0);
+        let layout = glyphs(&TextObject::new("ab cd ef", glm::vec2(0.0, 0.0), settings));
+
+        // Three lines of two characters, 28 pixels apart
+        assert_close(layout.size, glm::vec2(20.0, 84.0));
+        let tops = layout.quads.iter().map(|quad| quad.top_left.y).collect::<Vec<_>>();
+        assert_eq!(tops, vec![0.0, 0.0, 28.0, 28.0, 56.0, 56.0]);
+
+        // The screen edge still wins over a larger max width
+        let settings = TextSettings::new(1.0, true).set_max_width(Some(1000.0));
+        let layout = glyphs(&TextObject::new("abcdefghij abcdefghij", glm::vec2(0.0, 0.0), settings));
+        assert_eq!(layout.size.y, 14.0 * 2.0);
+    }
+
+    #[test]
+    fn test_span_colors_and_shadows() {
+        let red = glm::vec4(1.0, 0.0, 0.0, 1.0);
+        let spans = vec![
+            TextSpan::new("ab", TextStyle::default()),
+            TextSpan::new(" c", TextStyle::new(red).set_shadow(true)),
+        ];
+        let text = TextObject::from_spans(spans.clone(), glm::vec2(0.0, 0.0), TextSettings::new(1.0, false));
+        assert_eq!(text.get_content(), "ab c");
+
+        let layout = glyphs(&text);
+        assert_eq!(layout.quads.len(), 4);
+        // The shadow comes first, offset down and right, then the glyphs in order
+        let (shadow, glyph) = (layout.quads[0], layout.quads[3]);
+        assert_eq!(shadow.character, 'c' as u32);
+        assert_close(shadow.top_left - glyph.top_left, glm::vec2(1.0, 1.0));
+        assert_eq!(shadow.color, glm::vec4(0.0, 0.0, 0.0, 0.75));
+        assert_eq!(layout.quads[1].color, TextStyle::default().color);
+        assert_eq!(glyph.color, red);
+
+        // Every vertex of a quad carries its colour into the mesh
+        let mesh = layout.to_mesh();
+        assert!(mesh.vertices[12..16].iter().all(|vertex| vertex.color == red));
+
+        // Replacing the content keeps the first span's style
+        let mut text = TextObject::from_spans(spans[1..].to_vec(), glm::vec2(0.0, 0.0), TextSettings::new(1.0, false));
+        text.set_content("ab");
+        assert_eq!(text.get_spans(), &[TextSpan::new("ab", TextStyle::new(red).set_shadow(true))]);
+        text.set_style(TextStyle::default());
+        assert_eq!(glyphs(&text).quads.len(), 2);
+    }
+
+    #[test]
+    fn test_outlines_and_shadows_in_field_values() {
+        let black = glm::vec4(0.0, 0.0, 0.0, 1.0);
+        let settings = TextSettings::new(0.5, false)
+            .set_outline(Some(TextOutline::new(0.5, black)))
+            .set_shadow(Some(TextShadow::new(glm::vec2(3.0, -1.0), glm::vec4(0.0, 0.0, 1.0, 0.5), 0.5)));
+        let mut text = TextObject::new("ab", glm::vec2(0.0, 0.0), settings);
+        text.set_style(TextStyle::new(glm::vec4(1.0, 1.0, 1.0, 0.5)));
+        let layout = glyphs(&text);
+
+        // Every character gets the text's shadow, not only styles asking for one
+        assert_eq!(layout.quads.len(), 4);
+        let (shadow, glyph) = (layout.quads[0], layout.quads[2]);
+        assert_close(shadow.top_left - glyph.top_left, glm::vec2(3.0, -1.0));
+        assert_eq!(shadow.color, glm::vec4(0.0, 0.0, 1.0, 0.25));
+        // Half a pixel is a font unit at this scale, a quarter of the field's range
+        assert_eq!((glyph.outline_color, glyph.outline_width, glyph.softness), (black, 0.25, 0.0));
+        assert_eq!((shadow.outline_width, shadow.softness), (0.25, 0.25));
+
+        // Outlines wider than the field can reach stop at its end
+        let wide = settings.set_outline(Some(TextOutline::new(10.0, black))).set_shadow(None);
+        let layout = glyphs(&TextObject::new("a", glm::vec2(0.0, 0.0), wide));
+        assert_eq!(layout.quads.len(), 1);
+        assert_eq!(layout.quads[0].outline_width, 0.5);
+
+        let mesh = layout.to_mesh();
+        assert!(mesh.vertices.iter().all(|vertex| vertex.outline_width == 0.5 && vertex.outline_color == black));
+    }
+
+    #[test]
+    fn test_quads_match_the_mesh() {
+        let text = TextObject::new("ab\nc", glm::vec2(20.0, 10.0), TextSettings::new(1.0, false));
+        let layout = glyphs(&text);
+        let mesh = layout.to_mesh();
+
+        assert_eq!(mesh.vertices.len(), layout.quads.len() * 4);
+        for (quad, vertices) in layout.quads.iter().zip(mesh.vertices.chunks(4)) {
+            assert_close(to_pixels(vertices[2]), quad.top_left);
+            assert_close(to_pixels(vertices[3]), quad.bottom_right);
+            assert_eq!(vertices[2].uv, glm::vec2(quad.texture_coordinates.0, quad.texture_coordinates.2));
         }
+        assert_close(layout.quads[2].top_left, glm::vec2(20.0, 24.0));
     }
 }
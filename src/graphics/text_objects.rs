@@ -0,0 +1,474 @@
+use crate::core::app_data::AppData;
+use crate::core::stats::BufferStats;
+use crate::graphics::font_data::FontData;
+use crate::graphics::fonts::Fonts;
+use crate::graphics::shared_buffers::{create_buffer, write_memory};
+use crate::graphics::text_object::{layout_text, TextMesh, TextObject, TextSpan, TextVertex};
+use crate::graphics::ui_space::UiSpace;
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::collections::HashMap;
+use std::mem::size_of;
+use vulkanalia::prelude::v1_0::*;
+
+// Buffers are sized in whole glyphs and grow by doubling, so small edits reuse them
+const MIN_GLYPH_CAPACITY: usize = 16;
+
+// Refers to a text object, stays invalid once the object is removed even if its slot is reused
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct TextHandle {
+    index: u32,
+    generation: u32,
+}
+
+// Host visible so changed text is written straight into it
+#[derive(Clone, Copy, Debug, Default)]
+struct TextBuffers {
+    vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+    glyph_capacity: usize,
+}
+
+// A text object's copy of its mesh for one frame in flight
+#[derive(Clone, Copy, Debug)]
+struct FrameText {
+    buffers: Option<TextBuffers>,
+    // Laid out since the mesh was last written into these buffers
+    stale: bool,
+}
+
+impl Default for FrameText {
+    fn default() -> Self {
+        Self { buffers: None, stale: true }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct TextSlot {
+    generation: u32,
+    object: Option<TextObject>,
+    // Needs laying out and uploading again
+    dirty: bool,
+    mesh: TextMesh,
+    // Indexed by frame in flight. A frame only draws from its own buffers, so they can be rewritten once
+    // its fence was waited for while the other frames are still drawing.
+    frames: Vec<FrameText>,
+}
+
+// What a draw call needs from one text object
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TextDraw {
+    pub(crate) vertex_buffer: vk::Buffer,
+    pub(crate) index_buffer: vk::Buffer,
+    pub(crate) index_count: u32,
+}
+
+// Owns every text object on screen, only the ones that changed are laid out and uploaded again
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TextObjects {
+    slots: Vec<TextSlot>,
+    free_indices: Vec<u32>,
+    // Buffers of removed text by the frame in flight that used them, freed when that frame comes around again
+    retired_buffers: Vec<Vec<TextBuffers>>,
+}
+
+impl TextObjects {
+    pub(crate) fn add(&mut self, object: TextObject) -> TextHandle {
+        let index = match self.free_indices.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(TextSlot::default());
+                self.slots.len() as u32 - 1
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.object = Some(object);
+        slot.dirty = true;
+        TextHandle {
+            index,
+            generation: slot.generation,
+        }
+    }
+
+    pub(crate) fn remove(&mut self, handle: TextHandle) -> bool {
+        let Some(slot) = self.slot_mut(handle) else {
+            return false;
+        };
+        slot.object = None;
+        slot.mesh = TextMesh::default();
+        slot.generation += 1;
+        let frames = std::mem::take(&mut slot.frames);
+        for (frame, frame_text) in frames.into_iter().enumerate() {
+            if let Some(buffers) = frame_text.buffers {
+                retired_buffers(&mut self.retired_buffers, frame).push(buffers);
+            }
+        }
+        self.free_indices.push(handle.index);
+        true
+    }
+
+    pub(crate) fn contains(&self, handle: TextHandle) -> bool {
+        self.slot(handle).is_some()
+    }
+
+    pub(crate) fn get(&self, handle: TextHandle) -> Option<&TextObject> {
+        self.slot(handle)?.object.as_ref()
+    }
+
+    // Marks the object as changed, it's rebuilt before the next frame is drawn
+    pub(crate) fn get_mut(&mut self, handle: TextHandle) -> Option<&mut TextObject> {
+        let slot = self.slot_mut(handle)?;
+        slot.dirty = true;
+        slot.object.as_mut()
+    }
+
+    // Skips the rebuild when the text didn't change, handy for values refreshed every frame
+    pub(crate) fn set_content(&mut self, handle: TextHandle, content: &str) -> bool {
+        match self.get(handle) {
+            Some(object) if object.get_content() == content => true,
+            Some(_) => {
+                self.get_mut(handle).unwrap().set_content(content);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn set_spans(&mut self, handle: TextHandle, spans: Vec<TextSpan>) -> bool {
+        match self.get(handle) {
+            Some(object) if object.get_spans() == spans.as_slice() => true,
+            Some(_) => {
+                self.get_mut(handle).unwrap().set_spans(spans);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn set_position(&mut self, handle: TextHandle, position: glm::Vec2) -> bool {
+        match self.get(handle) {
+            Some(object) if object.get_position() == position => true,
+            Some(_) => {
+                self.get_mut(handle).unwrap().set_position(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.object.is_some()).count()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Every live object, in slot order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &TextObject> + '_ {
+        self.slots.iter().filter_map(|slot| slot.object.as_ref())
+    }
+
+    // Anchored text moves with the screen size and the scale factor changes the logical screen, so a new
+    // swapchain relayouts everything
+    pub(crate) fn mark_all_dirty(&mut self) {
+        self.slots.iter_mut().filter(|slot| slot.object.is_some()).for_each(|slot| slot.dirty = true);
+    }
+
+    pub(crate) fn needs_update(&self, frame: usize) -> bool {
+        self.retired_buffers.get(frame).is_some_and(|retired| !retired.is_empty())
+            || self.slots.iter().any(|slot| {
+                slot.dirty || (slot.object.is_some() && slot.frames.get(frame).is_none_or(|frame_text| frame_text.stale))
+            })
+    }
+
+    // Rasterizes missing glyphs and lays out every changed object, returns the handles that were rebuilt
+    pub(crate) fn layout(&mut self, fonts: &mut Fonts, ui: &UiSpace) -> Result<Vec<TextHandle>> {
+        let mut atlas_grew = false;
+        for slot in self.slots.iter().filter(|slot| slot.dirty) {
+            if let Some(object) = &slot.object {
+                atlas_grew |= fonts.prepare(object.get_settings().get_font(), object.get_chars())?;
+            }
+        }
+        // Texture coordinates of glyphs already on screen moved
+        if atlas_grew {
+            self.mark_all_dirty();
+        }
+
+        let no_characters = HashMap::new();
+        let no_font_data = FontData::default();
+        let mut rebuilt = vec![];
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let (Some(object), true) = (&slot.object, slot.dirty) else {
+                continue;
+            };
+            let settings = object.get_settings();
+            let characters = fonts.characters(settings.get_font()).unwrap_or(&no_characters);
+            let font_data = fonts.font_data(settings.get_font()).unwrap_or(&no_font_data);
+            slot.mesh = layout_text(object, characters, font_data, ui);
+            slot.dirty = false;
+            slot.frames.iter_mut().for_each(|frame_text| frame_text.stale = true);
+            rebuilt.push(TextHandle {
+                index: index as u32,
+                generation: slot.generation,
+            });
+        }
+        Ok(rebuilt)
+    }
+
+    pub(crate) fn mesh(&self, handle: TextHandle) -> Option<&TextMesh> {
+        self.slot(handle).map(|slot| &slot.mesh)
+    }
+
+    // Uploads the objects laid out since `frame` was last drawn into that frame's buffers. The caller has to
+    // wait for the frame's fence first, the buffers of the other frames aren't touched.
+    pub(crate) unsafe fn update_buffers(&mut self, instance: &Instance, device: &Device, data: &AppData, frame: usize) -> Result<()> {
+        for buffers in retired_buffers(&mut self.retired_buffers, frame).drain(..) {
+            destroy_text_buffers(device, &buffers);
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.object.is_some()) {
+            if slot.frames.len() <= frame {
+                slot.frames.resize_with(frame + 1, FrameText::default);
+            }
+            let frame_text = &mut slot.frames[frame];
+            if !frame_text.stale {
+                continue;
+            }
+            frame_text.stale = false;
+            let glyph_count = slot.mesh.vertices.len() / 4;
+
+            let buffers = match frame_text.buffers {
+                Some(buffers) if buffers.glyph_capacity >= glyph_count => buffers,
+                _ if glyph_count == 0 => continue,
+                _ => {
+                    if let Some(old_buffers) = frame_text.buffers.take() {
+                        destroy_text_buffers(device, &old_buffers);
+                    }
+                    let glyph_capacity = glyph_count.next_power_of_two().max(MIN_GLYPH_CAPACITY);
+                    let buffers = create_text_buffers(instance, device, data, glyph_capacity)?;
+                    frame_text.buffers = Some(buffers);
+                    buffers
+                }
+            };
+            write_memory(device, buffers.vertex_buffer_memory, &slot.mesh.vertices)?;
+            write_memory(device, buffers.index_buffer_memory, &slot.mesh.indices)?;
+        }
+        Ok(())
+    }
+
+    // Memory of the text buffers, used counts the meshes in them
+    pub(crate) fn buffer_stats(&self) -> BufferStats {
+        let glyph_size = size_of::<TextVertex>() * 4 + size_of::<u32>() * 6;
+        let mut stats = BufferStats::new("Text", 0, 0);
+        for slot in &self.slots {
+            for buffers in slot.frames.iter().filter_map(|frame_text| frame_text.buffers) {
+                stats.used += slot.mesh.vertices.len() / 4 * glyph_size;
+                stats.capacity += buffers.glyph_capacity * glyph_size;
+            }
+        }
+        stats.capacity += self.retired_buffers.iter().flatten().map(|buffers| buffers.glyph_capacity * glyph_size).sum::<usize>();
+        stats
+    }
+
+    // What `frame` draws, its buffers have to be updated first
+    pub(crate) fn draws(&self, frame: usize) -> Vec<TextDraw> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.object.is_some() && !slot.mesh.indices.is_empty())
+            .filter_map(|slot| {
+                let buffers = slot.frames.get(frame)?.buffers?;
+                Some(TextDraw {
+                    vertex_buffer: buffers.vertex_buffer,
+                    index_buffer: buffers.index_buffer,
+                    index_count: slot.mesh.indices.len() as u32,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, device: &Device) {
+        for buffers in self.retired_buffers.drain(..).flatten() {
+            destroy_text_buffers(device, &buffers);
+        }
+        for slot in &mut self.slots {
+            for buffers in slot.frames.drain(..).filter_map(|frame_text| frame_text.buffers) {
+                destroy_text_buffers(device, &buffers);
+            }
+        }
+    }
+
+    fn slot(&self, handle: TextHandle) -> Option<&TextSlot> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation && slot.object.is_some())
+    }
+
+    fn slot_mut(&mut self, handle: TextHandle) -> Option<&mut TextSlot> {
+        self.slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation && slot.object.is_some())
+    }
+}
+
+fn retired_buffers(retired_buffers: &mut Vec<Vec<TextBuffers>>, frame: usize) -> &mut Vec<TextBuffers> {
+    if retired_buffers.len() <= frame {
+        retired_buffers.resize_with(frame + 1, Vec::new);
+    }
+    &mut retired_buffers[frame]
+}
+
+unsafe fn create_text_buffers(instance: &Instance, device: &Device, data: &AppData, glyph_capacity: usize) -> Result<TextBuffers> {
+    let (vertex_buffer, vertex_buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        (size_of::<TextVertex>() * 4 * glyph_capacity) as u64,
+        vk::BufferUsageFlags::VERTEX_BUFFER,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+    let (index_buffer, index_buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        (size_of::<u32>() * 6 * glyph_capacity) as u64,
+        vk::BufferUsageFlags::INDEX_BUFFER,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+    Ok(TextBuffers {
+        vertex_buffer,
+        vertex_buffer_memory,
+        index_buffer,
+        index_buffer_memory,
+        glyph_capacity,
+    })
+}
+
+unsafe fn destroy_text_buffers(device: &Device, buffers: &TextBuffers) {
+    device.destroy_buffer(buffers.vertex_buffer, None);
+    device.free_memory(buffers.vertex_buffer_memory, None);
+    device.destroy_buffer(buffers.index_buffer, None);
+    device.free_memory(buffers.index_buffer_memory, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::fonts::Font;
+    use crate::graphics::text_object::TextSettings;
+
+    fn text(content: &str) -> TextObject {
+        TextObject::new(content, glm::vec2(0.0, 0.0), TextSettings::default())
+    }
+
+    #[test]
+    fn test_handles_outlive_removal() {
+        let mut texts = TextObjects::default();
+        let first = texts.add(text("first"));
+        let second = texts.add(text("second"));
+        assert_eq!(texts.len(), 2);
+
+        assert!(texts.remove(first));
+        assert!(!texts.remove(first));
+        assert!(texts.get(first).is_none());
+
+        // The freed slot is reused without reviving the old handle
+        let third = texts.add(text("third"));
+        assert_eq!(third.index, first.index);
+        assert!(!texts.contains(first));
+        assert_eq!(texts.get(third).unwrap().get_content(), "third");
+        assert_eq!(texts.get(second).unwrap().get_content(), "second");
+        assert!(!texts.set_content(first, "stale"));
+    }
+
+    #[test]
+    fn test_only_changed_text_is_rebuilt() {
+        let mut texts = TextObjects::default();
+        let fps = texts.add(text("60"));
+        let position = texts.add(text("0 0 0"));
+        let mut fonts = Fonts::default();
+        let ui = UiSpace::new(vk::Extent2D { width: 100, height: 100 }, 1.0);
+
+        assert_eq!(texts.layout(&mut fonts, &ui).unwrap().len(), 2);
+        texts.slots.iter_mut().for_each(|slot| slot.frames = vec![FrameText { buffers: None, stale: false }]);
+        assert!(!texts.needs_update(0));
+        // Every frame in flight gets its own copy
+        assert!(texts.needs_update(1));
+
+        // Setting the same content again doesn't count as a change
+        texts.set_content(fps, "60");
+        assert!(!texts.needs_update(0));
+
+        texts.set_content(position, "1 0 0");
+        assert_eq!(texts.layout(&mut fonts, &ui).unwrap(), vec![position]);
+        assert!(texts.layout(&mut fonts, &ui).unwrap().is_empty());
+
+        texts.mark_all_dirty();
+        assert_eq!(texts.layout(&mut fonts, &ui).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_buffer_stats() {
+        let mut texts = TextObjects::default();
+        let handle = texts.add(text("abc"));
+        let mut fonts = Fonts::default();
+        fonts.add_font(Font::embedded());
+        texts.layout(&mut fonts, &UiSpace::new(vk::Extent2D { width: 100, height: 100 }, 1.0)).unwrap();
+        // Nothing is on the GPU before the first upload
+        assert_eq!(texts.buffer_stats(), BufferStats::new("Text", 0, 0));
+
+        let frame_text = FrameText {
+            buffers: Some(TextBuffers {
+                glyph_capacity: MIN_GLYPH_CAPACITY,
+                ..Default::default()
+            }),
+            stale: false,
+        };
+        texts.slots[handle.index as usize].frames = vec![frame_text, frame_text];
+        let glyph_size = size_of::<TextVertex>() * 4 + size_of::<u32>() * 6;
+        let stats = texts.buffer_stats();
+        assert_eq!((stats.used, stats.capacity), (2 * 3 * glyph_size, 2 * MIN_GLYPH_CAPACITY * glyph_size));
+
+        // Removed text keeps its buffers until each frame in flight comes around again
+        texts.remove(handle);
+        assert_eq!(texts.buffer_stats().capacity, 2 * MIN_GLYPH_CAPACITY * glyph_size);
+        assert!(texts.needs_update(0) && texts.needs_update(1));
+    }
+
+    #[test]
+    fn test_sizes_share_glyphs() {
+        let mut texts = TextObjects::default();
+        let mut fonts = Fonts::default();
+        let id = fonts.add_font(Font::embedded());
+        texts.add(TextObject::new("abc", glm::vec2(0.0, 0.0), TextSettings::new(0.01, false)));
+        texts.layout(&mut fonts, &UiSpace::new(vk::Extent2D { width: 800, height: 600 }, 2.0)).unwrap();
+        let height = fonts.atlas().height();
+
+        // The distance fields scale to any size, so bigger text adds nothing to the atlas
+        let large = texts.add(TextObject::new("cab", glm::vec2(0.0, 0.0), TextSettings::new(0.1, false)));
+        texts.layout(&mut fonts, &UiSpace::new(vk::Extent2D { width: 800, height: 600 }, 1.0)).unwrap();
+        assert_eq!(fonts.characters(id).unwrap().len(), 3);
+        assert_eq!(fonts.atlas().height(), height);
+        assert_eq!(texts.mesh(large).unwrap().vertices.len(), 12);
+    }
+
+    #[test]
+    fn test_growing_atlas_relayouts_everything() {
+        let mut texts = TextObjects::default();
+        let mut fonts = Fonts::default();
+        fonts.add_font(Font::embedded());
+        let ui = UiSpace::new(vk::Extent2D { width: 800, height: 600 }, 1.0);
+        let settings = TextSettings::new(0.05, false);
+
+        let first = texts.add(TextObject::new("abc", glm::vec2(0.0, 0.0), settings));
+        texts.layout(&mut fonts, &ui).unwrap();
+        assert_eq!(texts.mesh(first).unwrap().vertices.len(), 12);
+
+        // Enough new glyphs to outgrow the atlas
+        let alphabet = ('A'..='Z').chain('А'..='Я').collect::<String>();
+        let second = texts.add(TextObject::new(alphabet.repeat(2), glm::vec2(0.0, 100.0), settings));
+        assert_eq!(texts.layout(&mut fonts, &ui).unwrap(), vec![first, second]);
+    }
+}
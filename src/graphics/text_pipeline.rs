@@ -1,8 +1,10 @@
 use crate::graphics::depth_objects::get_depth_format;
+use crate::graphics::shader_files::{TEXT_FRAG, TEXT_VERT};
+use crate::graphics::text_object::TextVertex;
 use anyhow::{anyhow, Result};
 use std::mem::size_of;
 use vulkanalia::prelude::v1_0::*;
-use vulkanalia::vk::{PushConstantRangeBuilder, VertexInputAttributeDescription};
+use vulkanalia::vk::VertexInputAttributeDescription;
 use crate::core::app_data::AppData;
 
 pub(crate) unsafe fn create_text_render_pass(
@@ -104,29 +106,54 @@ pub(crate) unsafe fn create_text_descriptor_set_layout(
 fn binding_description() -> vk::VertexInputBindingDescription {
     vk::VertexInputBindingDescription::builder()
         .binding(0)
-        .stride(size_of::<nalgebra_glm::Vec4>() as u32)
+        .stride(size_of::<TextVertex>() as u32)
         .input_rate(vk::VertexInputRate::VERTEX)
         .build()
 }
 
-fn attribute_descriptions() -> [VertexInputAttributeDescription; 1] {
-    let pos_uv = vk::VertexInputAttributeDescription::builder()
+fn attribute_descriptions() -> [VertexInputAttributeDescription; 5] {
+    let position = vk::VertexInputAttributeDescription::builder()
         .binding(0)
         .location(0)
-        .format(vk::Format::R32G32B32A32_SFLOAT)
+        .format(vk::Format::R32G32_SFLOAT)
         .offset(0)
         .build();
-    [pos_uv]
+    let uv = vk::VertexInputAttributeDescription::builder()
+        .binding(0)
+        .location(1)
+        .format(vk::Format::R32G32_SFLOAT)
+        .offset(size_of::<nalgebra_glm::Vec2>() as u32)
+        .build();
+    let color = vk::VertexInputAttributeDescription::builder()
+        .binding(0)
+        .location(2)
+        .format(vk::Format::R32G32B32A32_SFLOAT)
+        .offset((size_of::<nalgebra_glm::Vec2>() * 2) as u32)
+        .build();
+    let outline_color = vk::VertexInputAttributeDescription::builder()
+        .binding(0)
+        .location(3)
+        .format(vk::Format::R32G32B32A32_SFLOAT)
+        .offset((size_of::<nalgebra_glm::Vec2>() * 2 + size_of::<nalgebra_glm::Vec4>()) as u32)
+        .build();
+    // Outline width and softness
+    let edges = vk::VertexInputAttributeDescription::builder()
+        .binding(0)
+        .location(4)
+        .format(vk::Format::R32G32_SFLOAT)
+        .offset((size_of::<nalgebra_glm::Vec2>() * 2 + size_of::<nalgebra_glm::Vec4>() * 2) as u32)
+        .build();
+    [position, uv, color, outline_color, edges]
 }
 
 pub(crate) unsafe fn create_text_pipeline(device: &Device, data: &mut AppData) -> Result<()> {
     // Stages
 
-    let vert = include_bytes!("../../shaders/bin/text-vert.spv");
-    let frag = include_bytes!("../../shaders/bin/text-frag.spv");
+    let vert = TEXT_VERT.load();
+    let frag = TEXT_FRAG.load();
 
-    let vert_shader_module = create_shader_module(device, &vert[..])?;
-    let frag_shader_module = create_shader_module(device, &frag[..])?;
+    let vert_shader_module = create_shader_module(device, &vert)?;
+    let frag_shader_module = create_shader_module(device, &frag)?;
 
     let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::VERTEX)
@@ -222,21 +249,16 @@ pub(crate) unsafe fn create_text_pipeline(device: &Device, data: &mut AppData) -
 
     // Push Constant Ranges
 
-    /*// TODO: Possible ERROR
+    // The UI projection
     let vert_push_constant_range = vk::PushConstantRange::builder()
         .stage_flags(vk::ShaderStageFlags::VERTEX)
         .offset(0)
         .size(64 /*16 x 4 byte floats (one mat4)*/);
 
-    let frag_push_constant_range = vk::PushConstantRange::builder()
-        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
-        .offset(64)
-        .size(4);*/
-
     // Layout
 
     let set_layouts = &[data.text_descriptor_set_layout];
-    let push_constant_ranges: &[PushConstantRangeBuilder] = &[]; //&[vert_push_constant_range, frag_push_constant_range];
+    let push_constant_ranges = &[vert_push_constant_range];
     let layout_info = vk::PipelineLayoutCreateInfo::builder()
         .set_layouts(set_layouts)
         .push_constant_ranges(push_constant_ranges);
@@ -0,0 +1,735 @@
+use crate::controlls::input_manager::InputManager;
+use crate::graphics::text_object::{TextObject, TextSettings, TextSpan, TextStyle};
+use crate::graphics::text_objects::{TextHandle, TextObjects};
+use crate::graphics::ui_space::{Anchor, UiSpace};
+use nalgebra_glm as glm;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+use winit::event::{MouseButton, VirtualKeyCode};
+
+// Pixels per font unit of widget labels, about 16 pixel text
+const TEXT_SCALE: f32 = 0.008;
+// Labels sit this far from the top left of their widget
+const TEXT_INSET: (f32, f32) = (8.0, 4.0);
+const ROW_HEIGHT: f32 = 28.0;
+const SPACING: f32 = 6.0;
+const PADDING: f32 = 10.0;
+const CHECKBOX_SIZE: f32 = 16.0;
+const SLIDER_HANDLE_WIDTH: f32 = 8.0;
+const CROSSHAIR_SIZE: f32 = 16.0;
+const CROSSHAIR_THICKNESS: f32 = 2.0;
+// Widgets drawn outside of a panel are stacked down the left edge of the screen this wide
+const ROOT_WIDTH: f32 = 240.0;
+
+// Sent as characters by winit, so holding them repeats like typing does
+const BACKSPACE: char = '\u{8}';
+const RETURN: char = '\r';
+
+// Keys text fields react to, the rest of the keyboard types characters
+const EDITING_KEYS: [VirtualKeyCode; 6] = [
+    VirtualKeyCode::Left,
+    VirtualKeyCode::Right,
+    VirtualKeyCode::Home,
+    VirtualKeyCode::End,
+    VirtualKeyCode::Delete,
+    VirtualKeyCode::Escape,
+];
+
+// What the UI reacts to in one frame, in logical pixels of the UI space
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct UiInput {
+    // None while the cursor is outside the window
+    pub(crate) cursor: Option<glm::Vec2>,
+    pub(crate) mouse_down: bool,
+    pub(crate) mouse_pressed: bool,
+    pub(crate) mouse_released: bool,
+    pub(crate) characters: Vec<char>,
+    // Editing keys pressed this frame
+    pub(crate) keys: Vec<VirtualKeyCode>,
+}
+
+impl UiInput {
+    pub(crate) fn from_input_manager(input_manager: &InputManager, space: &UiSpace) -> Self {
+        Self {
+            cursor: input_manager.get_cursor_position().map(|(x, y)| space.to_logical(glm::vec2(x, y))),
+            mouse_down: input_manager.get_key_mouse(MouseButton::Left),
+            mouse_pressed: input_manager.get_key_down_mouse(MouseButton::Left),
+            mouse_released: input_manager.get_key_up_mouse(MouseButton::Left),
+            characters: input_manager.get_typed_characters().to_vec(),
+            keys: EDITING_KEYS.into_iter().filter(|key| input_manager.get_key_down(*key)).collect(),
+        }
+    }
+}
+
+// Screen rectangle in logical pixels, as top left corner and size
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Rect {
+    pub(crate) position: glm::Vec2,
+    pub(crate) size: glm::Vec2,
+}
+
+impl Rect {
+    pub(crate) fn new(position: glm::Vec2, size: glm::Vec2) -> Self {
+        Self { position, size }
+    }
+
+    pub(crate) fn contains(&self, point: glm::Vec2) -> bool {
+        let end = self.position + self.size;
+        point.x >= self.position.x && point.y >= self.position.y && point.x < end.x && point.y < end.y
+    }
+
+    // Shrunk by `amount` on every side
+    pub(crate) fn inset(&self, amount: f32) -> Self {
+        Self::new(
+            self.position + glm::vec2(amount, amount),
+            (self.size - glm::vec2(amount, amount) * 2.0).sup(&glm::vec2(0.0, 0.0)),
+        )
+    }
+}
+
+// Matches the UI pipeline's vertex input
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct UiVertex {
+    pub(crate) position: glm::Vec2,
+    pub(crate) color: glm::Vec4,
+}
+
+// Solid coloured rectangles in drawing order, the labels on them are text objects
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct UiMesh {
+    pub(crate) vertices: Vec<UiVertex>,
+    pub(crate) indices: Vec<u32>,
+}
+
+impl UiMesh {
+    // Returns the index of the rectangle for changing it later
+    fn push_rect(&mut self, rect: Rect, color: glm::Vec4) -> usize {
+        let first = self.vertices.len() as u32;
+        self.vertices.extend([UiVertex { position: glm::vec2(0.0, 0.0), color }; 4]);
+        self.indices.extend([first, first + 1, first + 2, first + 2, first + 3, first]);
+        let index = self.vertices.len() / 4 - 1;
+        self.set_rect(index, rect);
+        index
+    }
+
+    fn set_rect(&mut self, index: usize, rect: Rect) {
+        let (start, end) = (rect.position, rect.position + rect.size);
+        let corners = [start, glm::vec2(end.x, start.y), end, glm::vec2(start.x, end.y)];
+        for (vertex, corner) in self.vertices[index * 4..index * 4 + 4].iter_mut().zip(corners) {
+            vertex.position = corner;
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct UiTheme {
+    pub(crate) panel: glm::Vec4,
+    pub(crate) title_bar: glm::Vec4,
+    pub(crate) widget: glm::Vec4,
+    pub(crate) widget_hovered: glm::Vec4,
+    pub(crate) widget_active: glm::Vec4,
+    // Check marks, slider handles and the caret
+    pub(crate) accent: glm::Vec4,
+    pub(crate) text: glm::Vec4,
+    // Placeholder text of empty text fields
+    pub(crate) hint: glm::Vec4,
+    pub(crate) error: glm::Vec4,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        Self {
+            panel: glm::vec4(0.08, 0.08, 0.1, 0.85),
+            title_bar: glm::vec4(0.16, 0.18, 0.24, 0.95),
+            widget: glm::vec4(0.22, 0.23, 0.28, 1.0),
+            widget_hovered: glm::vec4(0.3, 0.32, 0.4, 1.0),
+            widget_active: glm::vec4(0.38, 0.42, 0.55, 1.0),
+            accent: glm::vec4(0.45, 0.7, 1.0, 1.0),
+            text: glm::vec4(0.95, 0.95, 0.95, 1.0),
+            hint: glm::vec4(0.6, 0.6, 0.65, 1.0),
+            error: glm::vec4(1.0, 0.45, 0.4, 1.0),
+        }
+    }
+}
+
+// Identifies a widget between frames, hashed from its label and the panel it's in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct WidgetId(u64);
+
+#[derive(Clone, Debug, PartialEq)]
+struct UiLabel {
+    text: String,
+    position: glm::Vec2,
+    color: glm::Vec4,
+}
+
+// Where the next widget goes
+#[derive(Clone, Copy, Debug)]
+struct Layout {
+    id: WidgetId,
+    position: glm::Vec2,
+    width: f32,
+    // Below the last widget
+    next_y: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Interaction {
+    hovered: bool,
+    held: bool,
+    clicked: bool,
+}
+
+// Immediate mode UI: widgets are declared again every frame between begin_frame and end_frame, and
+// report clicks and changes right away. Only which widget is hovered, pressed or typed into is kept
+// between frames. Needs no GPU, end_frame leaves a mesh for the UI pipeline and text objects.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Ui {
+    pub(crate) theme: UiTheme,
+    space: UiSpace,
+    input: UiInput,
+    hovered: Option<WidgetId>,
+    active: Option<WidgetId>,
+    focused: Option<WidgetId>,
+    // Of the focused text field, in characters
+    caret: usize,
+    focus_claimed: bool,
+    layout: Option<Layout>,
+    // Panels are placed with the size they had last frame, their contents decide it
+    panel_sizes: HashMap<WidgetId, glm::Vec2>,
+    is_interactive: bool,
+    last_rect: Rect,
+    mesh: UiMesh,
+    uploaded_mesh: UiMesh,
+    labels: Vec<UiLabel>,
+    texts: Vec<TextHandle>,
+}
+
+impl Ui {
+    pub(crate) fn begin_frame(&mut self, input: UiInput, space: UiSpace) {
+        self.input = input;
+        self.space = space;
+        self.hovered = None;
+        self.focus_claimed = false;
+        self.layout = None;
+        self.is_interactive = false;
+        self.mesh = UiMesh::default();
+        self.labels.clear();
+    }
+
+    // Hands the labels to the text objects, the mesh waits in `mesh` for the renderer
+    pub(crate) fn end_frame(&mut self, texts: &mut TextObjects) {
+        if !self.input.mouse_down {
+            self.active = None;
+        }
+        // Clicking anywhere else leaves the text field
+        if self.input.mouse_pressed && !self.focus_claimed {
+            self.focused = None;
+        }
+
+        for (index, label) in self.labels.iter().enumerate() {
+            let spans = vec![TextSpan::new(label.text.as_str(), TextStyle::new(label.color))];
+            match self.texts.get(index) {
+                Some(handle) if texts.contains(*handle) => {
+                    texts.set_spans(*handle, spans);
+                    texts.set_position(*handle, label.position);
+                }
+                _ => {
+                    let settings = TextSettings::new(TEXT_SCALE, false);
+                    let handle = texts.add(TextObject::from_spans(spans, label.position, settings));
+                    match self.texts.get_mut(index) {
+                        Some(old) => *old = handle,
+                        None => self.texts.push(handle),
+                    }
+                }
+            }
+        }
+        for handle in self.texts.drain(self.labels.len()..) {
+            texts.remove(handle);
+        }
+    }
+
+    // A screen is open, so the cursor should be free to point at it
+    pub(crate) fn wants_cursor(&self) -> bool {
+        self.is_interactive
+    }
+
+    // A text field has focus, typing shouldn't trigger key bindings
+    pub(crate) fn wants_keyboard(&self) -> bool {
+        self.focused.is_some()
+    }
+
+    // Space taken by the last widget
+    pub(crate) fn last_rect(&self) -> Rect {
+        self.last_rect
+    }
+
+    pub(crate) fn mesh(&self) -> &UiMesh {
+        &self.mesh
+    }
+
+    pub(crate) fn needs_upload(&self) -> bool {
+        self.mesh != self.uploaded_mesh
+    }
+
+    pub(crate) fn mark_uploaded(&mut self) {
+        self.uploaded_mesh = self.mesh.clone();
+    }
+
+    // A background for the widgets added by `contents`, the panel is as tall as they need
+    pub(crate) fn panel(&mut self, title: &str, anchor: Anchor, offset: glm::Vec2, width: f32, contents: impl FnOnce(&mut Self)) {
+        let id = self.id(title);
+        let size = self.panel_sizes.get(&id).copied().unwrap_or(glm::vec2(width, 0.0));
+        let position = self.space.place(anchor, offset, glm::vec2(width, size.y));
+        self.is_interactive = true;
+
+        let background = self.mesh.push_rect(Rect::new(position, size), self.theme.panel);
+        let mut next_y = position.y + PADDING;
+        if !title.is_empty() {
+            let title_bar = Rect::new(position, glm::vec2(width, ROW_HEIGHT));
+            self.mesh.push_rect(title_bar, self.theme.title_bar);
+            self.push_label(title, title_bar, self.theme.text);
+            next_y += ROW_HEIGHT;
+        }
+
+        let outer = self.layout.replace(Layout {
+            id,
+            position: glm::vec2(position.x + PADDING, position.y),
+            width: width - PADDING * 2.0,
+            next_y,
+        });
+        contents(self);
+        let layout = std::mem::replace(&mut self.layout, outer).unwrap();
+
+        // The last widget left spacing below it, the padding takes its place
+        let size = glm::vec2(width, (layout.next_y - SPACING + PADDING - position.y).max(0.0));
+        self.mesh.set_rect(background, Rect::new(position, size));
+        self.panel_sizes.insert(id, size);
+        self.last_rect = Rect::new(position, size);
+    }
+
+    // Two bars crossing at the centre of the screen. Not a widget, it leaves the cursor alone.
+    pub(crate) fn crosshair(&mut self) {
+        let center = self.space.size() / 2.0;
+        let (half_size, half_thickness) = (CROSSHAIR_SIZE / 2.0, CROSSHAIR_THICKNESS / 2.0);
+        let horizontal = Rect::new(center - glm::vec2(half_size, half_thickness), glm::vec2(CROSSHAIR_SIZE, CROSSHAIR_THICKNESS));
+        let vertical = Rect::new(center - glm::vec2(half_thickness, half_size), glm::vec2(CROSSHAIR_THICKNESS, CROSSHAIR_SIZE));
+        self.mesh.push_rect(horizontal, self.theme.text);
+        self.mesh.push_rect(vertical, self.theme.text);
+    }
+
+    pub(crate) fn label(&mut self, text: &str) {
+        self.colored_label(text, self.theme.text);
+    }
+
+    pub(crate) fn colored_label(&mut self, text: &str, color: glm::Vec4) {
+        let rect = self.allocate(ROW_HEIGHT);
+        self.push_label(text, rect, color);
+    }
+
+    // True in the frame it's clicked
+    pub(crate) fn button(&mut self, text: &str) -> bool {
+        let id = self.id(text);
+        let rect = self.allocate(ROW_HEIGHT);
+        let interaction = self.interact(id, rect);
+        self.mesh.push_rect(rect, self.widget_color(interaction));
+        self.push_label(text, rect, self.theme.text);
+        interaction.clicked
+    }
+
+    // Returns whether the value changed
+    pub(crate) fn checkbox(&mut self, text: &str, value: &mut bool) -> bool {
+        let id = self.id(text);
+        let rect = self.allocate(ROW_HEIGHT);
+        let interaction = self.interact(id, rect);
+        if interaction.clicked {
+            *value = !*value;
+        }
+
+        let offset = (ROW_HEIGHT - CHECKBOX_SIZE) / 2.0;
+        let check_box = Rect::new(rect.position + glm::vec2(0.0, offset), glm::vec2(CHECKBOX_SIZE, CHECKBOX_SIZE));
+        self.mesh.push_rect(check_box, self.widget_color(interaction));
+        if *value {
+            self.mesh.push_rect(check_box.inset(4.0), self.theme.accent);
+        }
+        let label = Rect::new(rect.position + glm::vec2(CHECKBOX_SIZE, 0.0), rect.size);
+        self.push_label(text, label, self.theme.text);
+        interaction.clicked
+    }
+
+    // Dragging anywhere along the track sets the value, returns whether it changed
+    pub(crate) fn slider(&mut self, text: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        let id = self.id(text);
+        let rect = self.allocate(ROW_HEIGHT);
+        let interaction = self.interact(id, rect);
+        let (min, max) = (*range.start(), *range.end());
+
+        let previous = *value;
+        if let (true, Some(cursor)) = (interaction.held, self.input.cursor) {
+            let travel = (rect.size.x - SLIDER_HANDLE_WIDTH).max(1.0);
+            let fraction = ((cursor.x - rect.position.x - SLIDER_HANDLE_WIDTH / 2.0) / travel).clamp(0.0, 1.0);
+            *value = min + (max - min) * fraction;
+        }
+        *value = value.clamp(min.min(max), max.max(min));
+
+        self.mesh.push_rect(rect, self.widget_color(interaction));
+        let fraction = if max > min { (*value - min) / (max - min) } else { 0.0 };
+        let handle_x = rect.position.x + (rect.size.x - SLIDER_HANDLE_WIDTH) * fraction;
+        let handle = Rect::new(glm::vec2(handle_x, rect.position.y), glm::vec2(SLIDER_HANDLE_WIDTH, rect.size.y));
+        self.mesh.push_rect(handle, self.theme.accent);
+        self.push_label(&format!("{}: {:.2}", text, value), rect, self.theme.text);
+        *value != previous
+    }
+
+    // Single line text field, `hint` shows while it's empty and names the field. Returns whether the
+    // text changed.
+    pub(crate) fn text_input(&mut self, hint: &str, text: &mut String) -> bool {
+        let id = self.id(hint);
+        let rect = self.allocate(ROW_HEIGHT);
+        let interaction = self.interact(id, rect);
+        if interaction.held && self.input.mouse_pressed {
+            if self.focused != Some(id) {
+                self.caret = text.chars().count();
+            }
+            self.focused = Some(id);
+            self.focus_claimed = true;
+        } else if self.input.mouse_pressed && self.focused == Some(id) {
+            self.focused = None;
+        }
+
+        let is_focused = self.focused == Some(id);
+        let changed = is_focused && self.edit_text(text);
+        let is_focused = self.focused == Some(id);
+
+        let color = if is_focused { self.theme.widget_active } else { self.widget_color(interaction) };
+        self.mesh.push_rect(rect, color);
+        if is_focused {
+            let mut shown = text.clone();
+            let caret = shown.char_indices().nth(self.caret).map_or(shown.len(), |(index, _)| index);
+            shown.insert(caret, '|');
+            self.push_label(&shown, rect, self.theme.text);
+        } else if text.is_empty() {
+            self.push_label(hint, rect, self.theme.hint);
+        } else {
+            self.push_label(text, rect, self.theme.text);
+        }
+        changed
+    }
+
+    // Applies this frame's typing to the focused field
+    fn edit_text(&mut self, text: &mut String) -> bool {
+        let mut chars = text.chars().collect::<Vec<_>>();
+        self.caret = self.caret.min(chars.len());
+        let before = chars.clone();
+
+        for character in self.input.characters.clone() {
+            match character {
+                BACKSPACE if self.caret > 0 => {
+                    self.caret -= 1;
+                    chars.remove(self.caret);
+                }
+                RETURN | '\n' => self.focused = None,
+                character if !character.is_control() => {
+                    chars.insert(self.caret, character);
+                    self.caret += 1;
+                }
+                _ => {}
+            }
+        }
+        for key in self.input.keys.clone() {
+            match key {
+                VirtualKeyCode::Left => self.caret = self.caret.saturating_sub(1),
+                VirtualKeyCode::Right => self.caret = (self.caret + 1).min(chars.len()),
+                VirtualKeyCode::Home => self.caret = 0,
+                VirtualKeyCode::End => self.caret = chars.len(),
+                VirtualKeyCode::Delete if self.caret < chars.len() => {
+                    chars.remove(self.caret);
+                }
+                VirtualKeyCode::Escape => self.focused = None,
+                _ => {}
+            }
+        }
+
+        if chars == before {
+            return false;
+        }
+        *text = chars.into_iter().collect();
+        true
+    }
+
+    fn id(&self, label: &str) -> WidgetId {
+        let mut hasher = DefaultHasher::new();
+        self.layout.map(|layout| layout.id).hash(&mut hasher);
+        label.hash(&mut hasher);
+        WidgetId(hasher.finish())
+    }
+
+    // Takes a full width row below the previous widget
+    fn allocate(&mut self, height: f32) -> Rect {
+        let layout = self.layout.get_or_insert(Layout {
+            id: WidgetId(0),
+            position: glm::vec2(PADDING, PADDING),
+            width: ROOT_WIDTH,
+            next_y: PADDING,
+        });
+        let rect = Rect::new(glm::vec2(layout.position.x, layout.next_y), glm::vec2(layout.width, height));
+        layout.next_y += height + SPACING;
+        self.last_rect = rect;
+        rect
+    }
+
+    // A press on the widget makes it active until the mouse is released, it's clicked when that
+    // happens over it
+    fn interact(&mut self, id: WidgetId, rect: Rect) -> Interaction {
+        self.is_interactive = true;
+        let is_over = self.input.cursor.is_some_and(|cursor| rect.contains(cursor));
+        let hovered = is_over && self.active.is_none_or(|active| active == id);
+        if hovered {
+            self.hovered = Some(id);
+            if self.input.mouse_pressed {
+                self.active = Some(id);
+            }
+        }
+        let is_active = self.active == Some(id);
+        Interaction {
+            hovered,
+            held: is_active && (self.input.mouse_down || self.input.mouse_pressed),
+            clicked: is_active && is_over && self.input.mouse_released,
+        }
+    }
+
+    fn widget_color(&self, interaction: Interaction) -> glm::Vec4 {
+        if interaction.held {
+            self.theme.widget_active
+        } else if interaction.hovered {
+            self.theme.widget_hovered
+        } else {
+            self.theme.widget
+        }
+    }
+
+    fn push_label(&mut self, text: &str, rect: Rect, color: glm::Vec4) {
+        self.labels.push(UiLabel {
+            text: text.to_string(),
+            position: rect.position + glm::vec2(TEXT_INSET.0, TEXT_INSET.1),
+            color,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vulkanalia::vk;
+
+    const SPACE: UiSpace = UiSpace {
+        extent: vk::Extent2D { width: 800, height: 600 },
+        scale_factor: 1.0,
+    };
+
+    fn hover(x: f32, y: f32) -> UiInput {
+        UiInput {
+            cursor: Some(glm::vec2(x, y)),
+            ..Default::default()
+        }
+    }
+
+    // Pressed and released between two frames, like a quick click between simulation ticks
+    fn click(x: f32, y: f32) -> UiInput {
+        UiInput {
+            mouse_pressed: true,
+            mouse_released: true,
+            ..hover(x, y)
+        }
+    }
+
+    fn center(rect: Rect) -> glm::Vec2 {
+        rect.position + rect.size / 2.0
+    }
+
+    // Runs one frame of a centered panel, returns what `contents` returned and the rect of its last widget
+    fn frame<T>(ui: &mut Ui, texts: &mut TextObjects, input: UiInput, contents: impl FnOnce(&mut Ui) -> T) -> (T, Rect) {
+        ui.begin_frame(input, SPACE);
+        let mut result = None;
+        let mut rect = Rect::default();
+        ui.panel("Test", Anchor::Center, glm::vec2(0.0, 0.0), 200.0, |ui| {
+            result = Some(contents(ui));
+            rect = ui.last_rect();
+        });
+        ui.end_frame(texts);
+        (result.unwrap(), rect)
+    }
+
+    // Panels are placed with last frame's size, so it takes a frame for widgets to settle
+    fn open<T>(ui: &mut Ui, texts: &mut TextObjects, mut contents: impl FnMut(&mut Ui) -> T) -> Rect {
+        frame(ui, texts, UiInput::default(), &mut contents);
+        frame(ui, texts, UiInput::default(), &mut contents).1
+    }
+
+    #[test]
+    fn test_panel_fits_its_widgets() {
+        let (mut ui, mut texts) = (Ui::default(), TextObjects::default());
+        let contents = |ui: &mut Ui| {
+            ui.label("first");
+            ui.label("second");
+        };
+        open(&mut ui, &mut texts, contents);
+
+        // A title row and two label rows between the padding
+        let height = PADDING + ROW_HEIGHT + (ROW_HEIGHT + SPACING) * 2.0 - SPACING + PADDING;
+        assert_eq!(ui.last_rect(), Rect::new(glm::vec2(300.0, 300.0 - height / 2.0), glm::vec2(200.0, height)));
+        // Background, title bar and the labels on top
+        assert_eq!(ui.mesh().vertices.len(), 8);
+        assert_eq!(ui.mesh().vertices[2].position, glm::vec2(500.0, 300.0 + height / 2.0));
+        assert_eq!(texts.len(), 3);
+        assert!(ui.wants_cursor());
+    }
+
+    #[test]
+    fn test_button_clicks_on_release_over_it() {
+        let (mut ui, mut texts) = (Ui::default(), TextObjects::default());
+        let rect = open(&mut ui, &mut texts, |ui| ui.button("Resume"));
+        let (x, y) = (center(rect).x, center(rect).y);
+
+        assert!(!frame(&mut ui, &mut texts, hover(x, y), |ui| ui.button("Resume")).0);
+        assert!(ui.hovered.is_some());
+        assert!(frame(&mut ui, &mut texts, click(x, y), |ui| ui.button("Resume")).0);
+
+        // Pressed over the button but released elsewhere
+        let press = UiInput {
+            mouse_pressed: true,
+            mouse_down: true,
+            ..hover(x, y)
+        };
+        let release = UiInput {
+            mouse_released: true,
+            ..hover(x, y + 100.0)
+        };
+        assert!(!frame(&mut ui, &mut texts, press, |ui| ui.button("Resume")).0);
+        assert!(!frame(&mut ui, &mut texts, release, |ui| ui.button("Resume")).0);
+        assert_eq!(ui.active, None);
+        assert!(!frame(&mut ui, &mut texts, click(x, rect.position.y - 1.0), |ui| ui.button("Resume")).0);
+    }
+
+    #[test]
+    fn test_checkbox_toggles() {
+        let (mut ui, mut texts) = (Ui::default(), TextObjects::default());
+        let mut value = false;
+        let rect = open(&mut ui, &mut texts, |ui| ui.checkbox("Fullscreen", &mut value));
+        let vertices = ui.mesh().vertices.len();
+
+        let (x, y) = (center(rect).x, center(rect).y);
+        assert!(frame(&mut ui, &mut texts, click(x, y), |ui| ui.checkbox("Fullscreen", &mut value)).0);
+        assert!(value);
+        // The check mark is drawn
+        assert_eq!(ui.mesh().vertices.len(), vertices + 4);
+        assert!(!frame(&mut ui, &mut texts, hover(x, y), |ui| ui.checkbox("Fullscreen", &mut value)).0);
+        assert!(value);
+    }
+
+    #[test]
+    fn test_slider_follows_the_drag() {
+        let (mut ui, mut texts) = (Ui::default(), TextObjects::default());
+        let mut value = 0.5;
+        let rect = open(&mut ui, &mut texts, |ui| ui.slider("Volume", &mut value, 0.0..=1.0));
+        let y = center(rect).y;
+        let track_x = |fraction: f32| rect.position.x + SLIDER_HANDLE_WIDTH / 2.0 + (rect.size.x - SLIDER_HANDLE_WIDTH) * fraction;
+
+        let drag = |x: f32| UiInput {
+            mouse_down: true,
+            ..hover(x, y)
+        };
+        let press = UiInput {
+            mouse_pressed: true,
+            ..drag(track_x(0.25))
+        };
+        assert!(frame(&mut ui, &mut texts, press, |ui| ui.slider("Volume", &mut value, 0.0..=1.0)).0);
+        assert!((value - 0.25).abs() < 0.001);
+
+        // Keeps following the cursor outside the slider, clamped to the range
+        frame(&mut ui, &mut texts, drag(track_x(0.75)), |ui| ui.slider("Volume", &mut value, 0.0..=1.0));
+        assert!((value - 0.75).abs() < 0.001);
+        let outside = UiInput {
+            cursor: Some(glm::vec2(1000.0, 0.0)),
+            ..drag(0.0)
+        };
+        frame(&mut ui, &mut texts, outside, |ui| ui.slider("Volume", &mut value, 0.0..=1.0));
+        assert_eq!(value, 1.0);
+
+        // Hovering alone doesn't move it
+        assert!(!frame(&mut ui, &mut texts, hover(track_x(0.1), y), |ui| ui.slider("Volume", &mut value, 0.0..=1.0)).0);
+        assert_eq!(value, 1.0);
+        assert_eq!(texts.get(ui.texts[1]).unwrap().get_content(), "Volume: 1.00");
+    }
+
+    #[test]
+    fn test_text_input_editing() {
+        let (mut ui, mut texts) = (Ui::default(), TextObjects::default());
+        let mut name = String::from("ab");
+        let rect = open(&mut ui, &mut texts, |ui| ui.text_input("Name", &mut name));
+        let (x, y) = (center(rect).x, center(rect).y);
+
+        // Typing does nothing until the field is clicked
+        let typing = |characters: &str, keys: Vec<VirtualKeyCode>| UiInput {
+            characters: characters.chars().collect(),
+            keys,
+            ..hover(x, y)
+        };
+        assert!(!frame(&mut ui, &mut texts, typing("x", vec![]), |ui| ui.text_input("Name", &mut name)).0);
+        frame(&mut ui, &mut texts, click(x, y), |ui| ui.text_input("Name", &mut name));
+        assert!(ui.wants_keyboard());
+        assert_eq!(texts.get(ui.texts[1]).unwrap().get_content(), "ab|");
+
+        assert!(frame(&mut ui, &mut texts, typing("cd\u{8}", vec![]), |ui| ui.text_input("Name", &mut name)).0);
+        assert_eq!(name, "abc");
+        frame(&mut ui, &mut texts, typing("", vec![VirtualKeyCode::Home, VirtualKeyCode::Right]), |ui| ui.text_input("Name", &mut name));
+        frame(&mut ui, &mut texts, typing("é", vec![VirtualKeyCode::Delete]), |ui| ui.text_input("Name", &mut name));
+        assert_eq!(name, "aéc");
+        assert_eq!(texts.get(ui.texts[1]).unwrap().get_content(), "aé|c");
+
+        // Clicking elsewhere leaves the field
+        frame(&mut ui, &mut texts, click(0.0, 0.0), |ui| ui.text_input("Name", &mut name));
+        assert!(!ui.wants_keyboard());
+        assert_eq!(texts.get(ui.texts[1]).unwrap().get_content(), "aéc");
+        frame(&mut ui, &mut texts, click(x, y), |ui| ui.text_input("Name", &mut name));
+        frame(&mut ui, &mut texts, typing("\r", vec![]), |ui| ui.text_input("Name", &mut name));
+        assert!(!ui.wants_keyboard());
+    }
+
+    #[test]
+    fn test_crosshair_is_centred_and_passive() {
+        let mut ui = Ui::default();
+        ui.begin_frame(UiInput::default(), SPACE);
+        ui.crosshair();
+        ui.end_frame(&mut TextObjects::default());
+
+        let vertices = &ui.mesh().vertices;
+        assert_eq!(vertices.len(), 8);
+        let center = vertices.iter().fold(glm::vec2(0.0, 0.0), |sum, vertex| sum + vertex.position) / 8.0;
+        assert_eq!(center, glm::vec2(400.0, 300.0));
+        assert!(!ui.wants_cursor());
+    }
+
+    #[test]
+    fn test_closed_ui_removes_its_text() {
+        let (mut ui, mut texts) = (Ui::default(), TextObjects::default());
+        frame(&mut ui, &mut texts, UiInput::default(), |ui| ui.button("Quit"));
+        assert_eq!(texts.len(), 2);
+        assert!(!ui.mesh().is_empty());
+        assert!(ui.needs_upload());
+        ui.mark_uploaded();
+
+        ui.begin_frame(UiInput::default(), SPACE);
+        ui.end_frame(&mut texts);
+        assert!(texts.is_empty());
+        assert!(!ui.wants_cursor());
+        assert!(ui.needs_upload());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::core::app_data::AppData;
+use crate::graphics::shared_buffers::{create_buffer, write_memory};
+use crate::graphics::ui::{UiMesh, UiVertex};
+use anyhow::Result;
+use std::mem::size_of;
+use vulkanalia::prelude::v1_0::*;
+
+// Sized in whole rectangles and grown by doubling, like the text buffers
+const MIN_RECT_CAPACITY: usize = 64;
+
+// Host visible buffers holding the UI mesh, rewritten whenever it changes
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct UiBuffers {
+    pub(crate) vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    pub(crate) index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+    rect_capacity: usize,
+    pub(crate) index_count: u32,
+}
+
+impl UiBuffers {
+    // The caller has to make sure no submitted frame still reads the buffers
+    pub(crate) unsafe fn upload(&mut self, instance: &Instance, device: &Device, data: &AppData, mesh: &UiMesh) -> Result<()> {
+        let rect_count = mesh.vertices.len() / 4;
+        if rect_count > self.rect_capacity {
+            self.destroy(device);
+            let rect_capacity = rect_count.next_power_of_two().max(MIN_RECT_CAPACITY);
+            (self.vertex_buffer, self.vertex_buffer_memory) = create_buffer(
+                instance,
+                device,
+                data,
+                (size_of::<UiVertex>() * 4 * rect_capacity) as u64,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+                vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+            )?;
+            (self.index_buffer, self.index_buffer_memory) = create_buffer(
+                instance,
+                device,
+                data,
+                (size_of::<u32>() * 6 * rect_capacity) as u64,
+                vk::BufferUsageFlags::INDEX_BUFFER,
+                vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+            )?;
+            self.rect_capacity = rect_capacity;
+        }
+
+        write_memory(device, self.vertex_buffer_memory, &mesh.vertices)?;
+        write_memory(device, self.index_buffer_memory, &mesh.indices)?;
+        self.index_count = mesh.indices.len() as u32;
+        Ok(())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.index_count == 0
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, device: &Device) {
+        if self.rect_capacity == 0 {
+            return;
+        }
+        device.destroy_buffer(self.vertex_buffer, None);
+        device.free_memory(self.vertex_buffer_memory, None);
+        device.destroy_buffer(self.index_buffer, None);
+        device.free_memory(self.index_buffer_memory, None);
+        *self = Self::default();
+    }
+}
+
+// One set of UI buffers per frame in flight. A changed mesh is written into the set of the frame being
+// recorded, whose fence was already waited for, while the other frames may still draw from theirs.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UiFrameBuffers {
+    frames: Vec<UiBuffers>,
+    // Sets that don't hold the latest mesh yet
+    stale: Vec<bool>,
+}
+
+impl UiFrameBuffers {
+    // The mesh changed, every frame has to upload it again
+    pub(crate) fn mark_stale(&mut self) {
+        self.stale.iter_mut().for_each(|stale| *stale = true);
+    }
+
+    pub(crate) fn needs_upload(&self, frame: usize) -> bool {
+        self.stale.get(frame).copied().unwrap_or(true)
+    }
+
+    pub(crate) unsafe fn upload(&mut self, instance: &Instance, device: &Device, data: &AppData, frame: usize, mesh: &UiMesh) -> Result<()> {
+        if self.frames.len() <= frame {
+            self.frames.resize_with(frame + 1, UiBuffers::default);
+            self.stale.resize(frame + 1, true);
+        }
+        self.frames[frame].upload(instance, device, data, mesh)?;
+        self.stale[frame] = false;
+        Ok(())
+    }
+
+    // What `frame` draws, uploaded first
+    pub(crate) fn frame(&self, frame: usize) -> UiBuffers {
+        self.frames.get(frame).copied().unwrap_or_default()
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, device: &Device) {
+        self.frames.iter_mut().for_each(|buffers| buffers.destroy(device));
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_frame_uploads_a_changed_mesh() {
+        let mut buffers = UiFrameBuffers {
+            frames: vec![UiBuffers::default(); 2],
+            stale: vec![false, false],
+        };
+        assert!(!buffers.needs_upload(0));
+        // Frames in flight added later start without the mesh
+        assert!(buffers.needs_upload(2));
+
+        buffers.mark_stale();
+        assert!(buffers.needs_upload(0) && buffers.needs_upload(1));
+        assert!(buffers.frame(3).is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+use nalgebra_glm as glm;
+use vulkanalia::vk;
+
+// Which point of the screen a position is measured from, the same point of the element sits there
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // Fraction of the width and height the anchor sits at
+    pub(crate) fn factors(&self) -> glm::Vec2 {
+        match self {
+            Anchor::TopLeft => glm::vec2(0.0, 0.0),
+            Anchor::Top => glm::vec2(0.5, 0.0),
+            Anchor::TopRight => glm::vec2(1.0, 0.0),
+            Anchor::Left => glm::vec2(0.0, 0.5),
+            Anchor::Center => glm::vec2(0.5, 0.5),
+            Anchor::Right => glm::vec2(1.0, 0.5),
+            Anchor::BottomLeft => glm::vec2(0.0, 1.0),
+            Anchor::Bottom => glm::vec2(0.5, 1.0),
+            Anchor::BottomRight => glm::vec2(1.0, 1.0),
+        }
+    }
+}
+
+// Coordinates for text and HUD: logical pixels from the top left corner with Y pointing down.
+// A logical pixel covers `scale_factor` physical pixels, so the UI keeps its size on high DPI screens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct UiSpace {
+    // Size of the swapchain images in physical pixels
+    pub(crate) extent: vk::Extent2D,
+    pub(crate) scale_factor: f32,
+}
+
+impl Default for UiSpace {
+    fn default() -> Self {
+        Self {
+            extent: vk::Extent2D::default(),
+            scale_factor: 1.0,
+        }
+    }
+}
+
+impl UiSpace {
+    pub(crate) fn new(extent: vk::Extent2D, scale_factor: f32) -> Self {
+        Self {
+            extent,
+            scale_factor: if scale_factor > 0.0 { scale_factor } else { 1.0 },
+        }
+    }
+
+    // Screen size in logical pixels
+    pub(crate) fn size(&self) -> glm::Vec2 {
+        glm::vec2(self.extent.width as f32, self.extent.height as f32) / self.scale_factor
+    }
+
+    pub(crate) fn anchor_point(&self, anchor: Anchor) -> glm::Vec2 {
+        self.size().component_mul(&anchor.factors())
+    }
+
+    // Top left corner of an element of `size` whose anchor point sits `offset` away from the screen's
+    pub(crate) fn place(&self, anchor: Anchor, offset: glm::Vec2, size: glm::Vec2) -> glm::Vec2 {
+        self.anchor_point(anchor) + offset - size.component_mul(&anchor.factors())
+    }
+
+    pub(crate) fn to_physical(self, point: glm::Vec2) -> glm::Vec2 {
+        point * self.scale_factor
+    }
+
+    // For cursor positions, which winit reports in physical pixels
+    pub(crate) fn to_logical(self, point: glm::Vec2) -> glm::Vec2 {
+        point / self.scale_factor
+    }
+
+    // Maps logical pixels to Vulkan's normalized device coordinates, Y already points down in both
+    pub(crate) fn projection(&self) -> glm::Mat4 {
+        let size = self.size();
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return glm::identity();
+        }
+        glm::translation(&glm::vec3(-1.0, -1.0, 0.0)) * glm::scaling(&glm::vec3(2.0 / size.x, 2.0 / size.y, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: vk::Extent2D = vk::Extent2D { width: 1920, height: 1080 };
+
+    fn project(ui: &UiSpace, point: glm::Vec2) -> glm::Vec2 {
+        let projected = ui.projection() * glm::vec4(point.x, point.y, 0.0, 1.0);
+        glm::vec2(projected.x, projected.y)
+    }
+
+    #[test]
+    fn test_projection_covers_the_screen() {
+        for scale_factor in [1.0, 1.5, 2.0] {
+            let ui = UiSpace::new(EXTENT, scale_factor);
+            let size = ui.size();
+
+            assert_eq!(project(&ui, glm::vec2(0.0, 0.0)), glm::vec2(-1.0, -1.0));
+            assert_eq!(project(&ui, size), glm::vec2(1.0, 1.0));
+            assert_eq!(project(&ui, size / 2.0), glm::vec2(0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_scale_factor_keeps_physical_size() {
+        let ui = UiSpace::new(EXTENT, 2.0);
+
+        assert_eq!(ui.size(), glm::vec2(960.0, 540.0));
+        assert_eq!(ui.to_physical(glm::vec2(100.0, 10.0)), glm::vec2(200.0, 20.0));
+        assert_eq!(ui.to_logical(glm::vec2(200.0, 20.0)), glm::vec2(100.0, 10.0));
+        assert_eq!(UiSpace::new(EXTENT, 0.0).scale_factor, 1.0);
+        assert_eq!(UiSpace::default().projection(), glm::Mat4::identity());
+    }
+
+    #[test]
+    fn test_anchored_placement_follows_the_window() {
+        let size = glm::vec2(100.0, 20.0);
+        let offset = glm::vec2(-10.0, -10.0);
+
+        for extent in [EXTENT, vk::Extent2D { width: 800, height: 600 }] {
+            let ui = UiSpace::new(extent, 1.0);
+            let origin = ui.place(Anchor::BottomRight, offset, size);
+            // The element keeps its distance to the corner whatever the window size
+            assert_eq!(ui.size() - (origin + size), glm::vec2(10.0, 10.0));
+        }
+
+        let ui = UiSpace::new(EXTENT, 1.0);
+        assert_eq!(ui.place(Anchor::Center, glm::vec2(0.0, 0.0), size), glm::vec2(910.0, 530.0));
+        assert_eq!(ui.place(Anchor::TopLeft, glm::vec2(5.0, 5.0), size), glm::vec2(5.0, 5.0));
+    }
+}
@@ -1,8 +1,70 @@
+use crate::core::camera::Camera;
+use crate::core::time_of_day::{fog_range, sample_sky, WorldClock, SKY_KEYFRAMES};
+use crate::graphics::shadow_cascades::{ShadowCascades, CASCADE_COUNT};
 use nalgebra_glm as glm;
 
+// Vec4s only, so the std140 layout of the shaders matches without padding
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct UniformBufferObject {
     pub(crate) view: glm::Mat4,
     pub(crate) proj: glm::Mat4,
+    // w is unused
+    pub(crate) sky_zenith: glm::Vec4,
+    // Also the fog colour, w is unused
+    pub(crate) sky_horizon: glm::Vec4,
+    // Towards the sun, w is unused
+    pub(crate) sun_direction: glm::Vec4,
+    // Fog start and end distance, the light multiplier and an unused w
+    pub(crate) fog: glm::Vec4,
+    // From the world into each cascade of the shadow map
+    pub(crate) light_matrices: [glm::Mat4; CASCADE_COUNT],
+    // How far along the view each cascade reaches, w is 1 when shadows are on
+    pub(crate) cascade_splits: glm::Vec4,
+}
+
+impl UniformBufferObject {
+    pub(crate) fn new(camera: &Camera, aspect_ratio: f32, clock: &WorldClock, shadows: bool) -> Self {
+        let sky = sample_sky(&SKY_KEYFRAMES, clock.time());
+        let (fog_start, fog_end) = fog_range(camera.settings.far);
+        let cascades = ShadowCascades::new(camera, aspect_ratio, &clock.light_direction());
+        let [near_split, middle_split, far_split] = cascades.splits;
+        Self {
+            view: camera.view_matrix(),
+            proj: camera.projection_matrix(aspect_ratio),
+            sky_zenith: glm::vec3_to_vec4(&sky.zenith),
+            sky_horizon: glm::vec3_to_vec4(&sky.horizon),
+            sun_direction: glm::vec3_to_vec4(&clock.sun_direction()),
+            fog: glm::vec4(fog_start, fog_end, sky.light, 0.0),
+            light_matrices: cascades.matrices,
+            cascade_splits: glm::vec4(near_split, middle_split, far_split, if shadows { 1.0 } else { 0.0 }),
+        }
+    }
+
+    // The colour behind everything, matching the sky at the horizon
+    pub(crate) fn clear_color(&self) -> [f32; 4] {
+        [self.sky_horizon.x, self.sky_horizon.y, self.sky_horizon.z, 1.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_matches_the_shader_layout() {
+        // Two matrices, four vectors, the cascade matrices and their splits
+        assert_eq!(size_of::<UniformBufferObject>(), 2 * 64 + 4 * 16 + CASCADE_COUNT * 64 + 16);
+
+        let mut clock = WorldClock::default();
+        clock.set_time(0.5);
+        let ubo = UniformBufferObject::new(&Camera::default(), 1.0, &clock, true);
+        assert_eq!(ubo.fog.y, Camera::default().settings.far);
+        assert_eq!(ubo.fog.z, 1.0);
+        assert_eq!(ubo.clear_color()[3], 1.0);
+        assert_eq!(ubo.cascade_splits.z, ubo.fog.y);
+        assert_eq!(ubo.cascade_splits.w, 1.0);
+        assert_eq!(UniformBufferObject::new(&Camera::default(), 1.0, &clock, false).cascade_splits.w, 0.0);
+    }
 }
@@ -14,45 +14,50 @@ mod player;
 mod terrain;
 
 use crate::controlls::input_manager::InputManager;
-use crate::core::collider::Collider;
-use crate::core::collision::intersects;
-use crate::core::game_object::GameObject;
-
-use anyhow::{anyhow, Result};
-use nalgebra_glm as glm;
-use std::collections::HashMap;
-use std::mem::size_of;
-use std::ptr::copy_nonoverlapping as memcpy;
-use std::time::Instant;
-use vulkanalia::loader::{LibloadingLoader, LIBRARY};
+
+use anyhow::Result;
+use log::error;
+use std::env;
+use std::path::{Path, PathBuf};
 use vulkanalia::prelude::v1_0::*;
-use vulkanalia::vk::{DeviceV1_2, KhrSwapchainExtension};
-use vulkanalia::vk::{ExtDebugUtilsExtension, KhrSurfaceExtension};
-use vulkanalia::window as vk_window;
 use winit::dpi::LogicalSize;
-use winit::event::{Event, MouseButton, VirtualKeyCode, WindowEvent};
+use winit::event::{DeviceEvent, Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::{Fullscreen, Window, WindowBuilder};
+use winit::window::WindowBuilder;
 use crate::core::app::App;
-use crate::terrain::chunk_coord::ChunkCoord;
-use crate::terrain::world::World;
+use crate::core::headless_renderer;
+use crate::core::replay;
+use crate::core::settings::{self, Settings};
+use crate::graphics::shader_files;
 
-//Whether the validation layers should be enabled.
-const VALIDATION_ENABLED: bool = false; //
-                                        //The name of the validation layers.
+//The name of the validation layers, enabled through the settings file.
 const VALIDATION_LAYER: vk::ExtensionName =
     vk::ExtensionName::from_bytes(b"VK_LAYER_KHRONOS_validation");
 
 //The required device extensions.
 const DEVICE_EXTENSIONS: &[vk::ExtensionName] = &[vk::KHR_SWAPCHAIN_EXTENSION.name];
 
-//The maximum number of frames that can be processed concurrently.
-const MAX_FRAMES_IN_FLIGHT: usize = 2;
-
-const LOW_DELTA_TIME_LIMIT: f64 = 0.0005;
+//How many times per second the simulation is advanced.
+const TICK_RATE: u32 = 60;
 
+//Longest frame time fed into the simulation, longer frames are slowed down instead.
 const HIGH_DELTA_TIME_LIMIT: f64 = 0.4;
 
+//Font used for text unless the settings name another one, the embedded copy is used if it's missing.
+const DEFAULT_FONT_PATH: &str = "resources/fonts/DejaVuSans.ttf";
+
+//Folder inside the user's config directory that holds the settings file.
+const SETTINGS_DIRECTORY_NAME: &str = "kub4e";
+
+//Name of the graphics and gameplay settings file.
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+//Name of the key, mouse and gamepad bindings file, kept next to the settings file and created with the defaults if missing.
+const INPUT_FILE_NAME: &str = "input.toml";
+
+//Where screenshots taken in game are saved.
+const SCREENSHOT_DIRECTORY: &str = "screenshots";
+
 #[derive(Clone)]
 pub(crate) struct FrameData<'a> {
     pub(crate) delta_time: f32,
@@ -64,22 +69,57 @@ pub(crate) struct FrameData<'a> {
 fn main() -> Result<()> {
     pretty_env_logger::init();
 
+    // `--replay <path>` checks a recording without opening a window, `--record <path>` records this session
+    // and `--render <path>` saves a frame of the starting world without a window. `--compile-shaders` rebuilds
+    // the SPIR-V in `shaders/bin` on any platform.
+    let arguments = env::args().collect::<Vec<_>>();
+    let record_path = match arguments.as_slice() {
+        [_, flag, path] if flag == "--replay" => {
+            let report = replay::run_headless(Path::new(path))?;
+            println!("Replay matched after {} ticks", report.ticks);
+            return Ok(());
+        }
+        [_, flag, path] if flag == "--render" => {
+            let settings = Settings::load_or_default(&settings::default_settings_path());
+            unsafe { headless_renderer::render_start_world(Path::new(path), &settings)? };
+            println!("Rendered the starting world to {}", path);
+            return Ok(());
+        }
+        [_, flag, path] if flag == "--record" => Some(PathBuf::from(path)),
+        [_, flag] if flag == "--compile-shaders" => {
+            shader_files::compile_all()?;
+            println!("Compiled {} shaders", shader_files::SHADER_FILES.len());
+            return Ok(());
+        }
+        _ => None,
+    };
+
+    let settings_path = settings::default_settings_path();
+    let settings = Settings::load_or_default(&settings_path);
+
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
         .with_title("Kub4e")
-        .with_inner_size(LogicalSize::new(1024, 576))
+        .with_inner_size(LogicalSize::new(settings.resolution.0, settings.resolution.1))
         .build(&event_loop)?;
 
-    let mut game_objects: Vec<Box<dyn GameObject>> = vec![];
-    let mut new_objects: Vec<usize> = vec![];
-
-    let mut app = unsafe { App::create(&window, &mut game_objects, &mut new_objects)? };
+    let mut app = unsafe { App::create(&window, settings, settings_path)? };
+    if let Some(path) = record_path {
+        app.start_recording(path);
+    }
     let mut destroying = false;
     let mut minimized = false;
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
         match event {
-            Event::MainEventsCleared if !destroying && !minimized => unsafe { app.render(&window, &mut game_objects, &mut new_objects) }.unwrap(),
+            Event::MainEventsCleared if !destroying && !minimized => {
+                unsafe { app.render(&window) }.unwrap();
+                if app.quit_requested {
+                    destroying = true;
+                    *control_flow = ControlFlow::Exit;
+                    shut_down(&mut app);
+                }
+            }
             Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
                 if size.width == 0 || size.height == 0 {
                     minimized = true;
@@ -88,25 +128,36 @@ fn main() -> Result<()> {
                     app.resized = true;
                 }
             }
+            // Text and HUD are laid out again for the new DPI with the next swapchain
+            Event::WindowEvent { event: WindowEvent::ScaleFactorChanged { .. }, .. } => app.resized = true,
             Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
                 destroying = true;
                 *control_flow = ControlFlow::Exit;
-                unsafe { app.destroy(); }
+                shut_down(&mut app);
             }
             Event::WindowEvent { event: WindowEvent::KeyboardInput {device_id, input, is_synthetic}, .. } => {
-                if app.input_manager.detect_keyboard(device_id, input, is_synthetic, app.frame_count).is_err() {
-                    app.unlock_cursor();
-                    window.set_cursor_visible(true);
+                if app.input_manager.detect_keyboard(device_id, input, is_synthetic, app.simulation.tick_count()).is_err() {
+                    app.unlock_cursor(&window);
                 }
             },
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion {delta}, ..} => {
+                app.input_manager.detect_mouse_motion(delta);
+            },
             Event::WindowEvent { event: WindowEvent::MouseInput {state, button, device_id, ..}, ..} => {
-                app.input_manager.detect_mouse(device_id, button, state, app.frame_count);
+                app.input_manager.detect_mouse(device_id, button, state, app.simulation.tick_count());
             },
             Event::WindowEvent { event: WindowEvent::MouseWheel {device_id, delta, phase, ..}, ..} => {
-                app.input_manager.detect_wheel(device_id, delta, phase, app.frame_count);
+                app.input_manager.detect_wheel(device_id, delta, phase, app.simulation.tick_count());
             },
+            Event::WindowEvent { event: WindowEvent::CursorMoved {position, ..}, ..} => {
+                app.input_manager.detect_cursor_position(Some((position.x, position.y)));
+            }
+            Event::WindowEvent { event: WindowEvent::ReceivedCharacter(character), ..} => {
+                app.input_manager.detect_character(character, app.simulation.tick_count());
+            }
             Event::WindowEvent { event: WindowEvent::CursorLeft {device_id}, ..} => {
                 app.is_hovered_by_cursor = false;
+                app.input_manager.detect_cursor_position(None);
             }
             Event::WindowEvent { event: WindowEvent::CursorEntered {device_id}, ..} => {
                 app.is_hovered_by_cursor = true;
@@ -115,3 +166,11 @@ fn main() -> Result<()> {
         }
     });
 }
+
+// Closing the window and quitting from the pause menu end the session the same way
+fn shut_down(app: &mut App) {
+    if let Err(error) = app.finish_recording() {
+        error!("Couldn't save the input recording: {}", error);
+    }
+    unsafe { app.destroy(); }
+}
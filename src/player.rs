@@ -1 +1,5 @@
+pub mod block_breaking;
+pub mod camera_controller;
+pub mod character_controller;
 pub mod player_data;
+pub mod player_systems;
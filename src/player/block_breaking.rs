@@ -0,0 +1,120 @@
+use nalgebra_glm as glm;
+
+// Crack overlays shown while breaking, the last one just before the voxel goes
+pub(crate) const BREAK_STAGES: u32 = 10;
+
+// How long the break button has been held on the voxel under the crosshair. Ticked by the
+// simulation, the renderer only reads the stage.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct BlockBreaking {
+    target: Option<glm::IVec3>,
+    // Seconds
+    elapsed: f32,
+    hardness: f32,
+}
+
+impl BlockBreaking {
+    // Looking at another voxel or letting go starts over. `hardness` is the target's time to break
+    // in seconds, infinite for voxels that can't be broken. Returns the voxel in the tick it breaks.
+    pub(crate) fn update(&mut self, target: Option<glm::IVec3>, is_holding: bool, hardness: f32, delta_time: f32) -> Option<glm::IVec3> {
+        let Some(position) = target.filter(|_| is_holding) else {
+            *self = Self::default();
+            return None;
+        };
+        if self.target != Some(position) {
+            *self = Self { target: Some(position), elapsed: 0.0, hardness };
+        }
+
+        self.elapsed += delta_time;
+        if self.elapsed < self.hardness {
+            return None;
+        }
+        *self = Self::default();
+        Some(position)
+    }
+
+    pub(crate) fn target(&self) -> Option<glm::IVec3> {
+        self.target
+    }
+
+    // From 0 when the button went down to 1 when the voxel breaks
+    pub(crate) fn progress(&self) -> f32 {
+        if self.target.is_none() || !self.hardness.is_finite() {
+            return 0.0;
+        }
+        if self.hardness <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.hardness).min(1.0)
+    }
+
+    // The crack overlay to draw, None while nothing is being broken
+    pub(crate) fn stage(&self) -> Option<u32> {
+        self.target?;
+        Some(((self.progress() * BREAK_STAGES as f32) as u32).min(BREAK_STAGES - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Exact in binary, so the elapsed time adds up without rounding
+    const TICK: f32 = 0.125;
+
+    fn ticks_to_break(breaking: &mut BlockBreaking, target: glm::IVec3, hardness: f32) -> Option<u32> {
+        (1..1000).find(|_| breaking.update(Some(target), true, hardness, TICK).is_some())
+    }
+
+    #[test]
+    fn test_breaks_after_the_hardness_time() {
+        let mut breaking = BlockBreaking::default();
+        let target = glm::vec3(1, 2, 3);
+
+        assert_eq!(ticks_to_break(&mut breaking, target, 0.5), Some(4));
+        // Starts over for the next voxel
+        assert_eq!(breaking, BlockBreaking::default());
+        assert_eq!(ticks_to_break(&mut breaking, target, 0.0), Some(1));
+        assert_eq!(ticks_to_break(&mut breaking, target, f32::INFINITY), None);
+        assert_eq!(breaking.progress(), 0.0);
+    }
+
+    #[test]
+    fn test_letting_go_or_looking_away_resets() {
+        let mut breaking = BlockBreaking::default();
+        let first = glm::vec3(0, 0, 0);
+        let second = glm::vec3(0, 0, 1);
+
+        for _ in 0..4 {
+            breaking.update(Some(first), true, 1.0, TICK);
+        }
+        assert_eq!(breaking.progress(), 0.5);
+        assert_eq!(breaking.target(), Some(first));
+
+        breaking.update(Some(second), true, 1.0, TICK);
+        assert_eq!(breaking.progress(), 0.125);
+        assert_eq!(breaking.target(), Some(second));
+
+        assert_eq!(breaking.update(Some(second), false, 1.0, TICK), None);
+        assert_eq!(breaking.stage(), None);
+        breaking.update(Some(second), true, 1.0, TICK);
+        breaking.update(None, true, 1.0, TICK);
+        assert_eq!(breaking.target(), None);
+    }
+
+    #[test]
+    fn test_stages_cover_the_progress() {
+        let mut breaking = BlockBreaking::default();
+        let target = glm::vec3(0, 0, 0);
+        let mut stages = vec![];
+        // 16 ticks to break, the last one breaks it
+        for _ in 0..15 {
+            breaking.update(Some(target), true, 2.0, TICK);
+            stages.push(breaking.stage().unwrap());
+        }
+        assert_eq!(stages.first(), Some(&0));
+        assert_eq!(stages.last(), Some(&(BREAK_STAGES - 1)));
+        assert!(stages.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(breaking.update(Some(target), true, 2.0, TICK), Some(target));
+    }
+}
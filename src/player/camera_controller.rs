@@ -0,0 +1,245 @@
+use crate::core::camera::{direction_from_angles, Camera, CameraSettings};
+use nalgebra_glm as glm;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CameraMode {
+    // Looks out of the player's eyes while walking
+    FirstPerson,
+    // Follows behind the player's head
+    ThirdPerson,
+    // Noclip spectator camera, the player stays where it was left
+    FreeFly,
+    // Circles the point the player was looking from when the mode was entered
+    Orbit,
+}
+
+impl CameraMode {
+    pub(crate) const ALL: [CameraMode; 4] = [
+        CameraMode::FirstPerson,
+        CameraMode::ThirdPerson,
+        CameraMode::FreeFly,
+        CameraMode::Orbit,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            CameraMode::FirstPerson => "first_person",
+            CameraMode::ThirdPerson => "third_person",
+            CameraMode::FreeFly => "free_fly",
+            CameraMode::Orbit => "orbit",
+        }
+    }
+
+    pub(crate) fn next(&self) -> CameraMode {
+        let index = CameraMode::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        CameraMode::ALL[(index + 1) % CameraMode::ALL.len()]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Transition {
+    from: Camera,
+    elapsed: f32,
+}
+
+// Turns the player's eye and look angles into the camera that gets rendered
+#[derive(Clone, Debug)]
+pub(crate) struct CameraController {
+    pub(crate) mode: CameraMode,
+    pub(crate) settings: CameraSettings,
+    // Distance behind the eye in third person and from the target while orbiting
+    pub(crate) follow_distance: f32,
+    pub(crate) free_fly_speed: f32,
+    // Seconds spent blending from the old view when the mode changes
+    pub(crate) transition_time: f32,
+    free_fly_position: glm::Vec3,
+    orbit_target: glm::Vec3,
+    transition: Option<Transition>,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::FirstPerson,
+            settings: CameraSettings::default(),
+            follow_distance: 4.0,
+            free_fly_speed: 20.0,
+            transition_time: 0.25,
+            free_fly_position: glm::vec3(0.0, 0.0, 0.0),
+            orbit_target: glm::vec3(0.0, 0.0, 0.0),
+            transition: None,
+        }
+    }
+}
+
+impl CameraController {
+    pub(crate) fn is_free_flying(&self) -> bool {
+        self.mode == CameraMode::FreeFly
+    }
+
+    pub(crate) fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: CameraMode, eye: glm::Vec3, yaw: f32, pitch: f32) {
+        if mode == self.mode {
+            return;
+        }
+
+        let from = self.camera(eye, yaw, pitch);
+        match mode {
+            CameraMode::FreeFly => self.free_fly_position = from.position,
+            CameraMode::Orbit => self.orbit_target = eye,
+            _ => {}
+        }
+        self.mode = mode;
+        self.transition = (self.transition_time > 0.0).then_some(Transition { from, elapsed: 0.0 });
+    }
+
+    pub(crate) fn cycle_mode(&mut self, eye: glm::Vec3, yaw: f32, pitch: f32) {
+        self.set_mode(self.mode.next(), eye, yaw, pitch);
+    }
+
+    pub(crate) fn update(&mut self, delta_time: f32) {
+        if let Some(transition) = &mut self.transition {
+            transition.elapsed += delta_time;
+            if transition.elapsed >= self.transition_time {
+                self.transition = None;
+            }
+        }
+    }
+
+    // Moves the free fly camera, a direction longer than 1 is shortened so diagonals aren't faster
+    pub(crate) fn fly(&mut self, direction: glm::Vec3, delta_time: f32) {
+        let length = glm::length(&direction);
+        if length == 0.0 {
+            return;
+        }
+        self.free_fly_position += direction / length.max(1.0) * self.free_fly_speed * delta_time;
+    }
+
+    // Where the current mode wants the camera, ignoring any transition
+    pub(crate) fn target_camera(&self, eye: glm::Vec3, yaw: f32, pitch: f32) -> Camera {
+        let forward = direction_from_angles(yaw, pitch);
+        let position = match self.mode {
+            CameraMode::FirstPerson => eye,
+            CameraMode::ThirdPerson => eye - forward * self.follow_distance,
+            CameraMode::FreeFly => self.free_fly_position,
+            CameraMode::Orbit => self.orbit_target - forward * self.follow_distance,
+        };
+        Camera::from_angles(position, yaw, pitch, self.settings)
+    }
+
+    pub(crate) fn camera(&self, eye: glm::Vec3, yaw: f32, pitch: f32) -> Camera {
+        let target = self.target_camera(eye, yaw, pitch);
+        match &self.transition {
+            Some(transition) => {
+                let t = (transition.elapsed / self.transition_time).clamp(0.0, 1.0);
+                // Smoothstep so the blend eases in and out
+                transition.from.lerp(&target, t * t * (3.0 - 2.0 * t))
+            }
+            None => target,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: glm::Vec3, b: glm::Vec3) {
+        assert!(glm::distance(&a, &b) < 0.0001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_mode_positions() {
+        let eye = glm::vec3(1.0, 2.0, 3.0);
+        let mut controller = CameraController {
+            transition_time: 0.0,
+            ..Default::default()
+        };
+
+        assert_close(controller.camera(eye, 0.0, 0.0).position, eye);
+
+        controller.set_mode(CameraMode::ThirdPerson, eye, 0.0, 0.0);
+        let camera = controller.camera(eye, 0.0, 0.0);
+        assert_close(camera.position, glm::vec3(1.0, -2.0, 3.0));
+        assert_close(camera.forward(), glm::vec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_orbit_keeps_looking_at_target() {
+        let eye = glm::vec3(5.0, 5.0, 10.0);
+        let mut controller = CameraController {
+            transition_time: 0.0,
+            ..Default::default()
+        };
+        controller.set_mode(CameraMode::Orbit, eye, 0.0, 0.0);
+
+        // The target stays put even when the player walks away
+        let walked_eye = glm::vec3(20.0, 0.0, 10.0);
+        for (yaw, pitch) in [(0.0, 0.0), (1.2, -0.5), (3.0, 0.8)] {
+            let camera = controller.camera(walked_eye, yaw, pitch);
+            assert!((glm::distance(&camera.position, &eye) - controller.follow_distance).abs() < 0.0001);
+            assert_close(camera.position + camera.forward() * controller.follow_distance, eye);
+        }
+    }
+
+    #[test]
+    fn test_free_fly_leaves_the_player_behind() {
+        let eye = glm::vec3(0.0, 0.0, 2.0);
+        let mut controller = CameraController {
+            transition_time: 0.0,
+            free_fly_speed: 10.0,
+            ..Default::default()
+        };
+        controller.set_mode(CameraMode::FreeFly, eye, 0.0, 0.0);
+        assert!(controller.is_free_flying());
+
+        controller.fly(glm::vec3(0.0, 1.0, 0.0), 0.5);
+        // Diagonals are normalized, half a stick is half speed
+        controller.fly(glm::vec3(3.0, 0.0, 4.0), 0.1);
+        controller.fly(glm::vec3(0.0, 0.0, -0.5), 0.2);
+
+        let camera = controller.camera(glm::vec3(100.0, 0.0, 0.0), 0.0, 0.0);
+        assert_close(camera.position, glm::vec3(0.6, 5.0, 1.8));
+    }
+
+    #[test]
+    fn test_transition_blends_smoothly() {
+        let eye = glm::vec3(0.0, 0.0, 0.0);
+        let mut controller = CameraController {
+            transition_time: 1.0,
+            follow_distance: 4.0,
+            ..Default::default()
+        };
+
+        controller.set_mode(CameraMode::ThirdPerson, eye, 0.0, 0.0);
+        assert!(controller.is_transitioning());
+        assert_close(controller.camera(eye, 0.0, 0.0).position, eye);
+
+        controller.update(0.5);
+        assert_close(controller.camera(eye, 0.0, 0.0).position, glm::vec3(0.0, -2.0, 0.0));
+
+        controller.update(0.25);
+        let position = controller.camera(eye, 0.0, 0.0).position;
+        assert!(position.y < -2.0 && position.y > -4.0);
+
+        controller.update(0.25);
+        assert!(!controller.is_transitioning());
+        assert_close(controller.camera(eye, 0.0, 0.0).position, glm::vec3(0.0, -4.0, 0.0));
+    }
+
+    #[test]
+    fn test_cycle_visits_every_mode() {
+        let mut controller = CameraController::default();
+        let mut visited = vec![controller.mode];
+        for _ in 0..CameraMode::ALL.len() {
+            controller.cycle_mode(glm::vec3(0.0, 0.0, 0.0), 0.0, 0.0);
+            visited.push(controller.mode);
+        }
+
+        assert_eq!(&visited[..4], &CameraMode::ALL);
+        assert_eq!(visited[4], CameraMode::FirstPerson);
+    }
+}
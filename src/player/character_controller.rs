@@ -0,0 +1,404 @@
+use crate::core::aabb::Aabb;
+use crate::core::collider::Collider;
+use crate::terrain::world::World;
+use nalgebra_glm as glm;
+
+// How far below the feet sneaking looks for ground before refusing to move
+const SNEAK_EDGE_PROBE_DEPTH: f32 = 0.5;
+// Amount the sneak edge check backs off per iteration
+const SNEAK_EDGE_STEP: f32 = 0.05;
+
+#[derive(Clone, Debug)]
+pub(crate) struct CharacterController {
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) eye_height: f32,
+    pub(crate) step_height: f32,
+    pub(crate) gravity: f32,
+    pub(crate) jump_speed: f32,
+    pub(crate) terminal_velocity: f32,
+    pub(crate) sneak_speed_multiplier: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MoveResult {
+    pub(crate) motion: glm::Vec3,
+    pub(crate) is_grounded: bool,
+    pub(crate) hit_ceiling: bool,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            width: 0.6,
+            height: 1.8,
+            eye_height: 1.62,
+            step_height: 1.0,
+            gravity: 9.81 * 2.5,
+            jump_speed: 8.0,
+            terminal_velocity: 60.0,
+            sneak_speed_multiplier: 0.3,
+        }
+    }
+}
+
+impl CharacterController {
+    // `position` is the center of the bottom face of the box
+    pub(crate) fn get_bounds(&self, position: glm::Vec3) -> Aabb {
+        Aabb::from_bottom_center(position, self.width, self.height)
+    }
+
+    // The box `get_bounds` moves as a collider relative to the feet, so contacts test the same volume
+    pub(crate) fn collider(&self) -> Collider {
+        let bounds = self.get_bounds(glm::vec3(0.0, 0.0, 0.0));
+        Collider::cuboid(bounds.center(), bounds.size() * 0.5)
+    }
+
+    pub(crate) fn move_and_collide(
+        &self,
+        world: &World,
+        position: glm::Vec3,
+        motion: glm::Vec3,
+        was_grounded: bool,
+        is_sneaking: bool,
+    ) -> MoveResult {
+        let bounds = self.get_bounds(position);
+
+        let mut motion = motion;
+        if is_sneaking && was_grounded && motion.z <= 0.0 {
+            motion = Self::stop_at_edges(world, &bounds, motion);
+        }
+
+        let resolved = Self::sweep(world, &bounds, motion);
+        let landed = motion.z < 0.0 && resolved.z > motion.z;
+        let hit_ceiling = motion.z > 0.0 && resolved.z < motion.z;
+        let is_horizontally_blocked = resolved.x != motion.x || resolved.y != motion.y;
+
+        if self.step_height > 0.0 && (was_grounded || landed) && is_horizontally_blocked {
+            if let Some(stepped) = self.try_step_up(world, &bounds, motion) {
+                let stepped_distance = glm::length2(&glm::vec2(stepped.x, stepped.y));
+                let resolved_distance = glm::length2(&glm::vec2(resolved.x, resolved.y));
+                if stepped_distance > resolved_distance {
+                    return MoveResult {
+                        motion: stepped,
+                        is_grounded: true,
+                        hit_ceiling: false,
+                    };
+                }
+            }
+        }
+
+        MoveResult {
+            motion: resolved,
+            is_grounded: landed,
+            hit_ceiling,
+        }
+    }
+
+    // Moves the box axis by axis (vertical first) and returns how far it actually got
+    fn sweep(world: &World, bounds: &Aabb, motion: glm::Vec3) -> glm::Vec3 {
+        let obstacles = world.get_collision_boxes(&bounds.expand_towards(motion));
+
+        let mut bounds = *bounds;
+        let mut resolved = glm::vec3(0.0, 0.0, 0.0);
+        for axis in [2, 0, 1] {
+            let mut axis_motion = motion[axis];
+            for obstacle in &obstacles {
+                axis_motion = bounds.clip_motion(obstacle, axis, axis_motion);
+            }
+
+            let mut offset = glm::vec3(0.0, 0.0, 0.0);
+            offset[axis] = axis_motion;
+            bounds = bounds.offset(offset);
+            resolved[axis] = axis_motion;
+        }
+        resolved
+    }
+
+    // Lift the box by the step height, move horizontally, then put it back down.
+    // Returns None if the box didn't end up standing on anything.
+    fn try_step_up(&self, world: &World, bounds: &Aabb, motion: glm::Vec3) -> Option<glm::Vec3> {
+        let up = Self::sweep(world, bounds, glm::vec3(0.0, 0.0, self.step_height)).z;
+        let raised = bounds.offset(glm::vec3(0.0, 0.0, up));
+
+        let horizontal = Self::sweep(world, &raised, glm::vec3(motion.x, motion.y, 0.0));
+        let moved = raised.offset(glm::vec3(horizontal.x, horizontal.y, 0.0));
+
+        let down_motion = -up + motion.z.min(0.0);
+        let down = Self::sweep(world, &moved, glm::vec3(0.0, 0.0, down_motion)).z;
+        if down <= down_motion {
+            return None;
+        }
+
+        Some(glm::vec3(horizontal.x, horizontal.y, up + down))
+    }
+
+    // Shrinks horizontal motion until there is still ground under the box after moving
+    fn stop_at_edges(world: &World, bounds: &Aabb, motion: glm::Vec3) -> glm::Vec3 {
+        let has_ground = |dx: f32, dy: f32| {
+            let probe = bounds.offset(glm::vec3(dx, dy, -SNEAK_EDGE_PROBE_DEPTH));
+            world
+                .get_collision_boxes(&probe)
+                .iter()
+                .any(|obstacle| probe.intersects(obstacle))
+        };
+        let back_off = |value: f32| {
+            if value.abs() < SNEAK_EDGE_STEP {
+                0.0
+            } else {
+                value - SNEAK_EDGE_STEP * value.signum()
+            }
+        };
+
+        let (mut dx, mut dy) = (motion.x, motion.y);
+        while dx != 0.0 && !has_ground(dx, 0.0) {
+            dx = back_off(dx);
+        }
+        while dy != 0.0 && !has_ground(0.0, dy) {
+            dy = back_off(dy);
+        }
+        while dx != 0.0 && dy != 0.0 && !has_ground(dx, dy) {
+            dx = back_off(dx);
+            dy = back_off(dy);
+        }
+
+        glm::vec3(dx, dy, motion.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ecs::components::{PlayerInput, Velocity};
+    use crate::core::transform::Transform;
+    use crate::player::player_data::PlayerData;
+
+    const DELTA_TIME: f32 = 1.0 / 60.0;
+
+    // 9x9 stone floor whose top face is at z = 1
+    fn flat_world() -> World {
+        let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        for x in -4..=4 {
+            for y in -4..=4 {
+                world.set_voxel_id(x, y, 0, 2);
+            }
+        }
+        world
+    }
+
+    // The components of a player entity that physics_step works on
+    #[derive(Default)]
+    struct TestPlayer {
+        data: PlayerData,
+        transform: Transform,
+        velocity: Velocity,
+        input: PlayerInput,
+    }
+
+    fn player_at(position: glm::Vec3) -> TestPlayer {
+        let mut player = TestPlayer::default();
+        player.data.controller = CharacterController::default();
+        player.data.move_speed = 4.0;
+        player.transform.position = position;
+        player
+    }
+
+    fn run(player: &mut TestPlayer, world: &World, ticks: usize) {
+        for _ in 0..ticks {
+            player.data.physics_step(
+                &mut player.transform,
+                &mut player.velocity,
+                &mut player.input,
+                world,
+                DELTA_TIME,
+            );
+        }
+    }
+
+    #[test]
+    fn test_falls_onto_ground() {
+        let world = flat_world();
+        let mut player = player_at(glm::vec3(0.5, 0.5, 6.0));
+
+        run(&mut player, &world, 120);
+
+        assert!(player.data.is_grounded);
+        assert!((player.transform.position.z - 1.0).abs() < 0.0001);
+        assert_eq!(player.velocity.0.z, 0.0);
+    }
+
+    #[test]
+    fn test_unloaded_world_is_air() {
+        let world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        let mut player = player_at(glm::vec3(0.5, 0.5, 6.0));
+
+        run(&mut player, &world, 10);
+
+        assert!(!player.data.is_grounded);
+        assert!(player.transform.position.z < 6.0);
+    }
+
+    #[test]
+    fn test_wall_blocks_movement() {
+        let mut world = flat_world();
+        for y in -4..=4 {
+            world.set_voxel_id(2, y, 1, 2);
+            world.set_voxel_id(2, y, 2, 2);
+        }
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        player.input.wish_direction = glm::vec3(1.0, 0.0, 0.0);
+
+        run(&mut player, &world, 120);
+
+        let bounds = player.data.controller.get_bounds(player.transform.position);
+        assert!((bounds.max.x - 2.0).abs() < 0.0001);
+        assert!((player.transform.position.z - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_sliding_along_wall() {
+        let mut world = flat_world();
+        for y in -4..=4 {
+            world.set_voxel_id(2, y, 1, 2);
+            world.set_voxel_id(2, y, 2, 2);
+        }
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        player.input.wish_direction = glm::vec3(1.0, 1.0, 0.0);
+
+        run(&mut player, &world, 30);
+
+        assert!(player.transform.position.x < 1.7 + 0.0001);
+        assert!(player.transform.position.y > 1.5);
+    }
+
+    #[test]
+    fn test_steps_up_one_block_ledge() {
+        let mut world = flat_world();
+        for y in -4..=4 {
+            for x in 2..=4 {
+                world.set_voxel_id(x, y, 1, 2);
+            }
+        }
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        player.data.is_grounded = true;
+        player.input.wish_direction = glm::vec3(1.0, 0.0, 0.0);
+
+        run(&mut player, &world, 30);
+
+        assert!(player.transform.position.x > 2.0);
+        assert!((player.transform.position.z - 2.0).abs() < 0.0001);
+        assert!(player.data.is_grounded);
+    }
+
+    #[test]
+    fn test_does_not_step_up_two_block_wall() {
+        let mut world = flat_world();
+        for y in -4..=4 {
+            world.set_voxel_id(2, y, 1, 2);
+            world.set_voxel_id(2, y, 2, 2);
+        }
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        player.data.is_grounded = true;
+        player.input.wish_direction = glm::vec3(1.0, 0.0, 0.0);
+
+        run(&mut player, &world, 30);
+
+        assert!(player.transform.position.x < 2.0);
+        assert!((player.transform.position.z - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_jump() {
+        let world = flat_world();
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        run(&mut player, &world, 1);
+        assert!(player.data.is_grounded);
+
+        player.input.wants_jump = true;
+        run(&mut player, &world, 10);
+        assert!(!player.data.is_grounded);
+        assert!(player.transform.position.z > 1.5);
+
+        run(&mut player, &world, 120);
+        assert!(player.data.is_grounded);
+        assert!((player.transform.position.z - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_cannot_jump_in_air() {
+        let world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        let mut player = player_at(glm::vec3(0.5, 0.5, 10.0));
+
+        player.input.wants_jump = true;
+        run(&mut player, &world, 1);
+
+        assert!(player.velocity.0.z < 0.0);
+    }
+
+    #[test]
+    fn test_ceiling_stops_jump() {
+        let mut world = flat_world();
+        for x in -4..=4 {
+            for y in -4..=4 {
+                world.set_voxel_id(x, y, 3, 2);
+            }
+        }
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        run(&mut player, &world, 1);
+
+        player.input.wants_jump = true;
+        run(&mut player, &world, 5);
+
+        let bounds = player.data.controller.get_bounds(player.transform.position);
+        assert!(bounds.max.z <= 3.0 + 0.0001);
+        assert!(player.velocity.0.z <= 0.0);
+    }
+
+    #[test]
+    fn test_sneaking_stops_at_edge() {
+        let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        for x in -4..=0 {
+            for y in -4..=4 {
+                world.set_voxel_id(x, y, 0, 2);
+            }
+        }
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        run(&mut player, &world, 1);
+        player.input.is_sneaking = true;
+        player.input.wish_direction = glm::vec3(1.0, 0.0, 0.0);
+
+        run(&mut player, &world, 240);
+
+        let bounds = player.data.controller.get_bounds(player.transform.position);
+        assert!(player.data.is_grounded);
+        assert!(bounds.min.x < 1.0);
+        assert!((player.transform.position.z - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_walking_off_edge_falls() {
+        let mut world = World::load(glm::vec3(0.0, 0.0, 0.0));
+        for x in -4..=0 {
+            for y in -4..=4 {
+                world.set_voxel_id(x, y, 0, 2);
+            }
+        }
+        let mut player = player_at(glm::vec3(0.5, 0.5, 1.0));
+        run(&mut player, &world, 1);
+        player.input.wish_direction = glm::vec3(1.0, 0.0, 0.0);
+
+        run(&mut player, &world, 60);
+
+        assert!(!player.data.is_grounded);
+        assert!(player.transform.position.z < 1.0);
+    }
+
+    #[test]
+    fn test_collider_matches_bounds() {
+        let controller = CharacterController::default();
+        let bounds = controller.collider().get_bounds().unwrap();
+
+        assert_eq!(bounds, controller.get_bounds(glm::vec3(0.0, 0.0, 0.0)));
+        assert_eq!(bounds.min, glm::vec3(-0.3, -0.3, 0.0));
+    }
+}
@@ -1,148 +1,209 @@
-use crate::core::collider::Collider;
-use crate::core::game_object::GameObject;
+use crate::controlls::input_actions::{Action, Axis};
+use crate::core::ecs::components::{PlayerInput, Velocity};
 use crate::core::transform::Transform;
 use crate::terrain::chunk_coord::ChunkCoord;
 use crate::{FrameData};
 use image::imageops::horizontal_gradient;
 use nalgebra_glm as glm;
-use std::any::Any;
 use vulkanalia::{Device, Instance};
 use winit::event::{MouseButton, VirtualKeyCode};
-use crate::terrain::mesh_data::MeshData;
-
+use crate::player::character_controller::CharacterController;
+use crate::terrain::world::{VoxelId, World};
+use crate::core::aabb::Aabb;
+use crate::core::camera::{self, MAX_PITCH};
+use crate::player::block_breaking::BlockBreaking;
+use crate::player::camera_controller::CameraController;
+
+// Player specific state, position and velocity live in their own components
 #[derive(Clone, Debug, Default)]
 pub(crate) struct PlayerData {
-    pub(crate) transform: Transform,
-    pub(crate) collider: Collider,
-    // Camera
-    pub(crate) horizontal_angle: f32,
-    pub(crate) vertical_angle: f32,
+    // Camera, see core::camera for how yaw and pitch are measured
+    pub(crate) yaw: f32,
+    pub(crate) pitch: f32,
     pub(crate) mouse_speed: f32,
+    // Radians per second at full stick deflection
+    pub(crate) stick_look_speed: f32,
+    pub(crate) camera: CameraController,
 
     // Movement
     pub(crate) move_speed: f32,
-    pub(crate) velocity: glm::Vec3,
     pub(crate) is_grounded: bool,
+    // No gravity, jump and sneak move straight up and down. Still collides, unlike the free fly camera.
+    pub(crate) is_flying: bool,
+    pub(crate) controller: CharacterController,
 
     // Voxel Manipulation
     pub(crate) reach: f32,
     pub(crate) reach_step: f32,
+    pub(crate) selected_voxel: VoxelId,
+    pub(crate) breaking: BlockBreaking,
+}
+
+// The solid voxel being looked at and the empty one just in front of it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct VoxelTarget {
+    pub(crate) position: glm::IVec3,
+    pub(crate) previous: Option<glm::IVec3>,
 }
 
-impl GameObject for PlayerData {
-    fn as_any(&self) -> &dyn Any {
-        self
+impl PlayerData {
+    pub(crate) fn eye_position(&self, position: glm::Vec3) -> glm::Vec3 {
+        position + glm::vec3(0.0, 0.0, self.controller.eye_height)
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
+    // Applies gravity, jumping and the wished walking direction, then resolves collisions with the world
+    pub(crate) fn physics_step(
+        &mut self,
+        transform: &mut Transform,
+        velocity: &mut Velocity,
+        input: &mut PlayerInput,
+        world: &World,
+        delta_time: f32,
+    ) {
+        let mut speed = self.move_speed;
+        if input.is_sneaking && !self.is_flying {
+            speed *= self.controller.sneak_speed_multiplier;
+        }
+
+        let horizontal = glm::vec3(input.wish_direction.x, input.wish_direction.y, 0.0);
+        // Keys combine to diagonals longer than 1, a half tilted stick walks at half speed
+        let horizontal = if glm::length2(&horizontal) > 0.0 {
+            horizontal.normalize() * speed * glm::length(&horizontal).min(1.0)
+        } else {
+            glm::vec3(0.0, 0.0, 0.0)
+        };
+        let velocity = &mut velocity.0;
+        velocity.x = horizontal.x;
+        velocity.y = horizontal.y;
+
+        if self.is_flying {
+            velocity.z = (input.wants_jump as i32 - input.is_sneaking as i32) as f32 * speed;
+        } else {
+            if input.wants_jump && self.is_grounded {
+                velocity.z = self.controller.jump_speed;
+            }
+            velocity.z = (velocity.z - self.controller.gravity * delta_time)
+                .max(-self.controller.terminal_velocity);
+        }
+        input.wants_jump = false;
+
+        // Sneaking keeps walkers from falling off edges, flyers use it to descend
+        let result = self.controller.move_and_collide(
+            world,
+            transform.position,
+            *velocity * delta_time,
+            self.is_grounded,
+            input.is_sneaking && !self.is_flying,
+        );
+
+        transform.position += result.motion;
+        self.is_grounded = result.is_grounded;
+        if result.is_grounded || result.hit_ceiling {
+            velocity.z = 0.0;
+        }
     }
 
-    fn get_mesh(&self) -> MeshData {
-        MeshData::default()
+    pub(crate) fn forward(&self) -> glm::Vec3 {
+        camera::direction_from_angles(self.yaw, self.pitch)
     }
 
-    fn get_collider(&self) -> Collider {
-        self.collider.compensate_position(self.transform.position)
+    pub(crate) fn right(&self) -> glm::Vec3 {
+        camera::right_from_yaw(self.yaw)
     }
 
-    fn start(&mut self, data: FrameData) {}
+    pub(crate) fn handle_camera(&mut self, data: &FrameData, position: glm::Vec3) {
+        let (x_offset, y_offset) = data.input_manager.get_mouse_delta();
 
-    fn update(&mut self, data: FrameData) {
-        self.handle_camera(&data);
-        self.handle_movement(&data);
-    }
-}
+        // Moving the mouse down looks down
+        self.yaw += data.delta_time * self.mouse_speed * x_offset;
+        self.pitch -= data.delta_time * self.mouse_speed * y_offset;
 
-impl PlayerData {
-    pub(crate) fn add_horizontal_angle(&mut self, add: f32) {
-        self.horizontal_angle += add;
-    }
+        // Sticks give a turn rate rather than a distance, pushing up looks up
+        self.yaw += data.delta_time * self.stick_look_speed * data.input_manager.get_axis(Axis::LookRight);
+        self.pitch += data.delta_time * self.stick_look_speed * data.input_manager.get_axis(Axis::LookUp);
 
-    pub(crate) fn add_vertical_angle(&mut self, add: f32) {
-        self.vertical_angle += add;
-    }
+        self.yaw %= std::f32::consts::TAU;
+        self.pitch = glm::clamp_scalar(self.pitch, -MAX_PITCH, MAX_PITCH);
 
-    pub(crate) fn walk(&mut self, direction: glm::Vec3, delta_time: f32) {
-        let new_pos = direction * self.move_speed * delta_time;
-        self.transform.position += new_pos;
-        // println!("{:?}", self.transform.position);
+        if data.input_manager.get_action_down(Action::CycleCamera) {
+            self.camera.cycle_mode(self.eye_position(position), self.yaw, self.pitch);
+        }
+        self.camera.update(data.delta_time);
     }
 
-    pub(crate) fn forward(&mut self) -> glm::Vec3 {
-        glm::vec3(
-            (self.vertical_angle.cos() * self.horizontal_angle.sin()) as f32,
-            (self.vertical_angle.cos() * self.horizontal_angle.cos()) as f32,
-            self.vertical_angle.sin() as f32,
-        )
-        .normalize()
-    }
+    // Only records what the player wants to do, physics_step does the actual moving
+    pub(crate) fn handle_movement(&mut self, data: &FrameData, input: &mut PlayerInput) {
+        let forward_amount = data.input_manager.get_axis(Axis::MoveForward);
+        let right_amount = data.input_manager.get_axis(Axis::MoveRight);
 
-    pub(crate) fn right(&mut self) -> glm::Vec3 {
-        glm::vec3(
-            (self.horizontal_angle - 3.14 * 0.5).sin() as f32,
-            (self.horizontal_angle - 3.14 * 0.5).cos() as f32,
-            0.0,
-        )
-    }
+        // While spectating the body stays put and the controls fly the camera instead
+        if self.camera.is_free_flying() {
+            let mut vertical = 0.0;
+            if data.input_manager.get_action(Action::Jump) {
+                vertical += 1.0;
+            }
+            if data.input_manager.get_action(Action::Sneak) {
+                vertical -= 1.0;
+            }
+            let direction = self.forward() * forward_amount
+                + self.right() * right_amount
+                + camera::world_up() * vertical;
+            self.camera.fly(direction, data.delta_time);
 
-    fn handle_camera(&mut self, data: &FrameData) {
-        let (x_offset, y_offset) = data.input_manager.get_mouse_delta();
+            *input = PlayerInput::default();
+            return;
+        }
 
-        self.horizontal_angle += data.delta_time * self.mouse_speed * x_offset as f32;
-        self.vertical_angle += data.delta_time * self.mouse_speed * y_offset as f32;
+        let mut forward = self.forward();
+        forward.z = 0.0;
+        let forward = if glm::length2(&forward) > 0.0 { forward.normalize() } else { forward };
 
-        self.vertical_angle = glm::clamp_scalar(self.vertical_angle, 0.0 + 1.57, 4.71);
+        input.wish_direction = forward * forward_amount + self.right() * right_amount;
+        input.wants_jump = data.input_manager.get_action(Action::Jump);
+        input.is_sneaking = data.input_manager.get_action(Action::Sneak);
     }
 
-    fn handle_movement(&mut self, data: &FrameData) {
-        if data.input_manager.get_key(VirtualKeyCode::W) {
-            let forward = self.forward();
-            //forward.z = 0.0;
-            self.walk(forward, data.delta_time);
-        }
-        if data.input_manager.get_key(VirtualKeyCode::S) {
-            let backward = -self.forward();
-            //backward.z = 0.0;
-            self.walk(backward, data.delta_time);
+    // Breaks the voxel under the crosshair once the break button was held for its hardness, or
+    // places one against the face that was looked at
+    pub(crate) fn handle_voxel_manipulation(&mut self, data: &FrameData, world: &mut World, position: glm::Vec3) {
+        let target = self.find_target_voxel(world, position);
+        let is_breaking = data.input_manager.get_action(Action::BreakBlock);
+        let hardness = target.map_or(0.0, |target| world.voxel_hardness(target.position));
+        if let Some(broken) = self.breaking.update(target.map(|target| target.position), is_breaking, hardness, data.delta_time) {
+            world.edit_voxel(broken, 0);
         }
-        if data.input_manager.get_key(VirtualKeyCode::D) {
-            let right = self.right();
-            self.walk(right, data.delta_time);
+
+        if !data.input_manager.get_action_down(Action::PlaceBlock) {
+            return;
         }
-        if data.input_manager.get_key(VirtualKeyCode::A) {
-            let left = -self.right();
-            self.walk(left, data.delta_time);
+        if let Some(place_position) = target.and_then(|target| target.previous) {
+            let voxel_bounds = Aabb::from_voxel(place_position.x, place_position.y, place_position.z);
+            if !voxel_bounds.intersects(&self.controller.get_bounds(position)) {
+                world.edit_voxel(place_position, self.selected_voxel);
+            }
         }
     }
 
-    /*pub(crate) fn handle_voxel_manipulation(&mut self, data: &FrameData, world: &mut World, instance: &Instance, appdata: &mut AppData, device: &Device) {
-        let is_left_clicked = data.input_manager.get_key_down_mouse(MouseButton::Left);
-        let is_right_clicked = data.input_manager.get_key_down_mouse(MouseButton::Right);
-
-        if is_left_clicked || is_right_clicked {
-            let look_at = glm::vec3(
-                (self.vertical_angle.cos() * self.horizontal_angle.sin()) as f32,
-                (self.vertical_angle.cos() * self.horizontal_angle.cos()) as f32,
-                self.vertical_angle.sin() as f32,
-            );
-
-            let mut length = 0.0;
-            while length < self.reach {
-                let world_pos_vec = (look_at * length) + self.transform.position;
-                let voxel_world_position = VoxelWorldPosition::new(world_pos_vec.x as i32, world_pos_vec.y as i32, world_pos_vec.z as i32);
-                let chunk_coord = voxel_world_position.get_chunk_coord();
-
-                let voxel_chunk_position = voxel_world_position.to_chunk_position();
-                let voxel_id = world.get_voxel_id(
-                    chunk_coord,
-                    voxel_chunk_position,
-                );
-                if voxel_id != 0 {
-                    //TODO: Modify
-                    break;
-                }
-                length += self.reach_step
+    // Marches along the view ray in reach_step increments up to reach
+    pub(crate) fn find_target_voxel(&self, world: &World, position: glm::Vec3) -> Option<VoxelTarget> {
+        let origin = self.eye_position(position);
+        let direction = self.forward();
+        let mut previous = None;
+
+        let mut length = 0.0;
+        while length < self.reach {
+            let point = origin + direction * length;
+            let voxel = glm::vec3(point.x.floor() as i32, point.y.floor() as i32, point.z.floor() as i32);
+            if world.get_voxel_id(voxel.x, voxel.y, voxel.z) != 0 {
+                return Some(VoxelTarget {
+                    position: voxel,
+                    previous: previous.filter(|previous| *previous != voxel),
+                });
             }
-        }*/
+            previous = Some(voxel);
+            length += self.reach_step;
+        }
+        None
+    }
+
 }
@@ -0,0 +1,95 @@
+use crate::core::ecs::components::{PlayerInput, Velocity};
+use crate::core::ecs::registry::Registry;
+use crate::core::ecs::schedule::{System, SystemContext};
+use crate::core::transform::Transform;
+use crate::player::player_data::PlayerData;
+use nalgebra_glm as glm;
+
+// Turns the camera and records movement intent from the input manager
+pub(crate) struct PlayerInputSystem;
+
+impl System for PlayerInputSystem {
+    fn name(&self) -> &str {
+        "player_input"
+    }
+
+    fn run(&mut self, registry: &mut Registry, context: &mut SystemContext) {
+        let (Some(mut players), Some(mut inputs), Some(transforms)) = (
+            registry.storage_mut::<PlayerData>(),
+            registry.storage_mut::<PlayerInput>(),
+            registry.storage::<Transform>(),
+        ) else {
+            return;
+        };
+
+        for (entity, player) in players.iter_mut() {
+            let position = transforms.get(entity).map_or(glm::vec3(0.0, 0.0, 0.0), |transform| transform.position);
+            player.handle_camera(&context.frame_data, position);
+            if let Some(input) = inputs.get_mut(entity) {
+                player.handle_movement(&context.frame_data, input);
+            }
+        }
+    }
+}
+
+// Breaks and places voxels where players are looking
+pub(crate) struct PlayerBlockEditSystem;
+
+impl System for PlayerBlockEditSystem {
+    fn name(&self) -> &str {
+        "player_block_edit"
+    }
+
+    fn run(&mut self, registry: &mut Registry, context: &mut SystemContext) {
+        let (Some(mut players), Some(transforms)) = (
+            registry.storage_mut::<PlayerData>(),
+            registry.storage::<Transform>(),
+        ) else {
+            return;
+        };
+
+        for (entity, player) in players.iter_mut() {
+            if let Some(transform) = transforms.get(entity) {
+                player.handle_voxel_manipulation(&context.frame_data, context.world, transform.position);
+            }
+        }
+    }
+}
+
+// Moves players through the voxel world with their character controller
+pub(crate) struct PlayerMovementSystem;
+
+impl System for PlayerMovementSystem {
+    fn name(&self) -> &str {
+        "player_movement"
+    }
+
+    fn run(&mut self, registry: &mut Registry, context: &mut SystemContext) {
+        let (Some(mut players), Some(mut transforms), Some(mut velocities), Some(mut inputs)) = (
+            registry.storage_mut::<PlayerData>(),
+            registry.storage_mut::<Transform>(),
+            registry.storage_mut::<Velocity>(),
+            registry.storage_mut::<PlayerInput>(),
+        ) else {
+            return;
+        };
+
+        for (entity, player) in players.iter_mut() {
+            let (Some(transform), Some(velocity), Some(input)) = (
+                transforms.get_mut(entity),
+                velocities.get_mut(entity),
+                inputs.get_mut(entity),
+            ) else {
+                continue;
+            };
+
+            player.physics_step(
+                transform,
+                velocity,
+                input,
+                context.world,
+                context.frame_data.delta_time,
+            );
+        }
+    }
+}
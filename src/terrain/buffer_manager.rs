@@ -1,6 +1,7 @@
 extern crate core;
 
 use anyhow::anyhow;
+use crate::core::stats::BufferStats;
 
 #[derive(Clone, Debug)]
 pub(crate) struct BufferManager {
@@ -37,6 +38,20 @@ impl BufferManager {
         self.used_regions = vec![];
     }
 
+    // Bytes currently handed out
+    pub(crate) fn used_size(&self) -> usize {
+        self.used_regions.iter().map(|region| region.size).sum()
+    }
+
+    // Bytes the manager knows about, used or free
+    pub(crate) fn capacity(&self) -> usize {
+        self.used_size() + self.free_regions.iter().map(|region| region.size).sum::<usize>()
+    }
+
+    pub(crate) fn stats(&self, name: &str) -> BufferStats {
+        BufferStats::new(name, self.used_size(), self.capacity())
+    }
+
     pub(crate) fn use_free_region(&mut self, size: usize) -> anyhow::Result<usize> {
         let mut best_fit: Option<usize> = None;
         let mut best_fit_size: Option<usize> = None;
@@ -366,4 +381,17 @@ mod tests {
         assert_eq!(buffer_manager.free_regions.len(), 1);
         assert_eq!(buffer_manager.free_regions[0], BufferRegion { offset: 5, size: 30 });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_stats_count_used_and_free_bytes() {
+        let mut buffer_manager = BufferManager::new();
+        buffer_manager.add_free_region(0, 100);
+        buffer_manager.use_free_region(30).unwrap();
+        buffer_manager.use_free_region(20).unwrap();
+
+        let stats = buffer_manager.stats("chunks");
+        assert_eq!(stats, BufferStats::new("chunks", 50, 100));
+        buffer_manager.clear();
+        assert_eq!(buffer_manager.capacity(), 0);
+    }
+}
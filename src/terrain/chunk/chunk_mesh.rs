@@ -26,4 +26,12 @@ impl ChunkMesh {
     pub(crate) fn get_vertex_index(&self) -> u32 {
         self.vertex_index
     }
+
+    pub(crate) fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub(crate) fn indices(&self) -> &[u32] {
+        &self.indices
+    }
 }
\ No newline at end of file
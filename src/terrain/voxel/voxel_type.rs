@@ -8,14 +8,17 @@ pub(crate) struct VoxelType {
     pub(crate) collidable: bool,
     // Front Back Left Right Top Bottom
     pub(crate) draw_neighbours: DirectionMap<bool>,
+    // Seconds the break button has to be held, infinite for voxels that can't be broken
+    pub(crate) hardness: f32,
 }
 
 impl VoxelType {
-    pub(crate) fn new(faces: Vec<VoxelFace>, collidable: bool, draw_neighbours: DirectionMap<bool>) -> Self {
+    pub(crate) fn new(faces: Vec<VoxelFace>, collidable: bool, draw_neighbours: DirectionMap<bool>, hardness: f32) -> Self {
         Self {
             faces,
             collidable,
             draw_neighbours,
+            hardness,
         }
     }
 
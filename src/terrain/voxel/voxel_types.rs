@@ -7,7 +7,7 @@ lazy_static!(
     pub(crate) static ref VOXEL_TYPES : Vec<VoxelType> = {
         let mut types = Vec::new();
         // 0
-        let air = VoxelType::new(vec![], false, DirectionMap::from_slice(&[true, true, true, true, true, true]));
+        let air = VoxelType::new(vec![], false, DirectionMap::from_slice(&[true, true, true, true, true, true]), 0.0);
         types.push(air);
         // 1
         let grass = VoxelType::new(
@@ -20,7 +20,8 @@ lazy_static!(
                 VoxelFace::bottom(1),
             ],
             true,
-            DirectionMap::from_slice(&[false, false, false, false, false, false])
+            DirectionMap::from_slice(&[false, false, false, false, false, false]),
+            0.6,
         );
         types.push(grass);
         // 2
@@ -34,7 +35,8 @@ lazy_static!(
                 VoxelFace::bottom(0),
             ],
             true,
-            DirectionMap::from_slice(&[false, false, false, false, false, false])
+            DirectionMap::from_slice(&[false, false, false, false, false, false]),
+            1.5,
         );
         types.push(stone);
 
@@ -49,7 +51,8 @@ lazy_static!(
                 VoxelFace::bottom(1),
             ],
             true,
-            DirectionMap::from_slice(&[false, false, false, false, false, false])
+            DirectionMap::from_slice(&[false, false, false, false, false, false]),
+            0.5,
         );
         types.push(dirt);
 
@@ -64,7 +67,8 @@ lazy_static!(
                 VoxelFace::bottom(9),
             ],
             true,
-            DirectionMap::from_slice(&[false, false, false, false, false, false])
+            DirectionMap::from_slice(&[false, false, false, false, false, false]),
+            f32::INFINITY,
         );
         types.push(bedrock);
 
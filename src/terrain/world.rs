@@ -5,8 +5,10 @@ use std::thread::{spawn, Thread};
 use anyhow::anyhow;
 use vulkanalia::{Device, Instance, vk};
 use nalgebra_glm as glm;
+use crate::core::aabb::Aabb;
 use crate::core::app_data::AppData;
 use crate::core::math_functions::{remap, translate};
+use crate::core::stats::WorldStats;
 use crate::graphics::texturing_shared::calculate_uv;
 use crate::graphics::vertex::Vertex;
 use crate::terrain::chunk::chunk_mesh::ChunkMesh;
@@ -20,7 +22,7 @@ use crate::terrain::voxel::voxel_position::{VoxelChunkPosition, VoxelPositionAdd
 use crate::terrain::voxel::voxel_type::VoxelType;
 use crate::terrain::voxel::voxel_types::VOXEL_TYPES;
 
-type VoxelId = u8;
+pub(crate) type VoxelId = u8;
 type ChunkVoxelMap = [u8; CHUNK_SIZE as usize * CHUNK_SIZE as usize * CHUNK_SIZE as usize];
 
 const CHUNK_SIZE: u8 = 32;
@@ -29,19 +31,47 @@ pub(crate) static TEXTURE_ATLAS_SIZE_IN_BLOCKS: u8 = 4;
 pub(crate) static NORMALIZED_BLOCK_TEXTURE_SIZE: f32 = 1.0 / TEXTURE_ATLAS_SIZE_IN_BLOCKS as f32;
 
 
+// A voxel changed by gameplay rather than by generation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct VoxelEdit {
+    pub(crate) position: glm::IVec3,
+    pub(crate) voxel_id: VoxelId,
+}
+
 #[derive(Debug)]
 pub(crate) struct World {
-    pub(crate) chunks: HashMap<ChunkCoord, ThreadedChunk>
+    pub(crate) chunks: HashMap<ChunkCoord, ThreadedChunk>,
+    edits: Vec<VoxelEdit>,
+    // Only affects chunks generated after it's set, 0 is the original terrain
+    seed: u32,
 }
 
 impl World {
     pub(crate) fn load(start_position: glm::Vec3) -> Self {
         Self {
             chunks: HashMap::new(),
+            edits: vec![],
+            seed: 0,
         }
     }
 
+    pub(crate) fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub(crate) fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+
+    // Where generation samples the noise, every seed picks another spot inside its 256 unit period
+    fn noise_offset(&self) -> (f64, f64) {
+        let offset = |hash: u32| (hash >> 24) as f64 + ((hash >> 16) & 0xff) as f64 / 256.0;
+        (offset(self.seed.wrapping_mul(0x9E37_79B9)), offset(self.seed.wrapping_mul(0x85EB_CA6B)))
+    }
+
+    // Replaces the chunk if it exists, dropping every change made to it
     pub(crate) fn generate_chunk_voxel_map(&mut self, coord: &ChunkCoord) {
+        let (offset_x, offset_y) = self.noise_offset();
         let mut voxel_map: ChunkVoxelMap = [0u8; VOXELS_COUNT_IN_CHUNK];
         for x in 0..CHUNK_SIZE as u8 {
             for y in 0..CHUNK_SIZE as u8 {
@@ -49,7 +79,7 @@ impl World {
                 let world_y = coord.y * CHUNK_SIZE as i32;
 
                 let noise_value = remap(
-                    perlin_noise2d(world_x as f64 * 0.1, world_y as f64 * 0.1),
+                    perlin_noise2d(world_x as f64 * 0.1 + offset_x, world_y as f64 * 0.1 + offset_y),
                     -1.0,
                     1.0,
                     0.0,
@@ -94,9 +124,18 @@ impl World {
     }
 
     pub(crate) fn mesh_chunk_sync(&mut self, coord: &ChunkCoord, instance: &Instance, device: &Device, data: &mut AppData) {
-        let mut threaded_chunk = self.chunks.get_mut(coord).expect("Couldn't get chunk"); // fix: generate chunk instead of panic
+        let chunk_mesh = self.build_chunk_mesh(coord).expect("Couldn't get chunk"); // fix: generate chunk instead of panic
+        create_chunk_vertex_buffer(instance, device, data, self)?;
+        create_chunk_index_buffer(instance, device, data, self)?;
+        if let Some(threaded_chunk) = self.chunks.get_mut(coord) {
+            threaded_chunk.mesh_size = Some((chunk_mesh.vertices().len(), chunk_mesh.indices().len()));
+        }
+        println!("{:?}", chunk_mesh);
+    }
 
-        let own_voxel_map = threaded_chunk.chunk.voxel_map;
+    // Builds the mesh of a generated chunk on the CPU, None when the chunk doesn't exist
+    pub(crate) fn build_chunk_mesh(&self, coord: &ChunkCoord) -> Option<ChunkMesh> {
+        let own_voxel_map = self.chunks.get(coord)?.chunk.voxel_map;
         let neighbour_voxel_maps = self.get_neighbour_voxel_maps(coord);
 
         let mut chunk_mesh = ChunkMesh::new();
@@ -108,9 +147,7 @@ impl World {
             let voxel_mesh = Self::mesh_voxel(voxel_index, voxel_id, Self::should_draw(voxel_index, &own_voxel_map, &neighbour_voxel_maps), chunk_mesh.get_vertex_index());
             chunk_mesh.add_voxel_mesh(voxel_mesh);
         }
-        create_chunk_vertex_buffer(instance, device, data, self)?;
-        create_chunk_index_buffer(instance, device, data, self)?;
-        println!("{:?}", chunk_mesh);
+        Some(chunk_mesh)
     }
 
 /*    pub(crate) fn mesh_chunk(&mut self, coord: &ChunkCoord) {
@@ -242,6 +279,77 @@ impl World {
         }
     }
 
+    pub(crate) fn get_voxel_id(&self, x: i32, y: i32, z: i32) -> VoxelId {
+        let (coord, position) = Self::split_world_position(x, y, z);
+        match self.chunks.get(&coord) {
+            None => 0,
+            Some(threaded_chunk) => threaded_chunk.get_voxel(position.to_index()),
+        }
+    }
+
+    // Creates an empty chunk if the position isn't loaded yet
+    pub(crate) fn set_voxel_id(&mut self, x: i32, y: i32, z: i32, voxel_id: VoxelId) {
+        let (coord, position) = Self::split_world_position(x, y, z);
+        let threaded_chunk = self
+            .chunks
+            .entry(coord)
+            .or_insert_with(|| ThreadedChunk::new(&coord));
+        threaded_chunk.chunk.voxel_map[position.to_index()] = voxel_id;
+    }
+
+    // Gameplay changes go through here so sessions can be compared against each other
+    pub(crate) fn edit_voxel(&mut self, position: glm::IVec3, voxel_id: VoxelId) {
+        self.set_voxel_id(position.x, position.y, position.z, voxel_id);
+        self.edits.push(VoxelEdit { position, voxel_id });
+    }
+
+    pub(crate) fn edits(&self) -> &[VoxelEdit] {
+        &self.edits
+    }
+
+    // Seconds to break the voxel, unknown voxels break right away
+    pub(crate) fn voxel_hardness(&self, position: glm::IVec3) -> f32 {
+        let voxel_id = self.get_voxel_id(position.x, position.y, position.z);
+        VOXEL_TYPES.get(voxel_id as usize).map_or(0.0, |voxel_type| voxel_type.hardness)
+    }
+
+    pub(crate) fn is_voxel_collidable(&self, x: i32, y: i32, z: i32) -> bool {
+        match VOXEL_TYPES.get(self.get_voxel_id(x, y, z) as usize) {
+            None => false,
+            Some(voxel_type) => voxel_type.collidable,
+        }
+    }
+
+    // Boxes of every collidable voxel overlapping `region`
+    pub(crate) fn get_collision_boxes(&self, region: &Aabb) -> Vec<Aabb> {
+        let mut boxes = vec![];
+        for x in region.min.x.floor() as i32..region.max.x.ceil() as i32 {
+            for y in region.min.y.floor() as i32..region.max.y.ceil() as i32 {
+                for z in region.min.z.floor() as i32..region.max.z.ceil() as i32 {
+                    if self.is_voxel_collidable(x, y, z) {
+                        boxes.push(Aabb::from_voxel(x, y, z));
+                    }
+                }
+            }
+        }
+        boxes
+    }
+
+    fn split_world_position(x: i32, y: i32, z: i32) -> (ChunkCoord, VoxelChunkPosition) {
+        let size = CHUNK_SIZE as i32;
+        let coord = ChunkCoord {
+            x: x.div_euclid(size),
+            y: y.div_euclid(size),
+            z: z.div_euclid(size),
+        };
+        let position = VoxelChunkPosition::new(
+            x.rem_euclid(size) as u8,
+            y.rem_euclid(size) as u8,
+            z.rem_euclid(size) as u8,
+        );
+        (coord, position)
+    }
+
     pub(crate) fn get_chunk_by_index(&self, index: usize) -> anyhow::Result<&ThreadedChunk> {
         let mut counter = 0_i32;
         for (coord, chunk) in &self.chunks {
@@ -254,6 +362,19 @@ impl World {
         Err(anyhow!("No chunk at index: {}", index))
     }
 
+    pub(crate) fn stats(&self) -> WorldStats {
+        let mut stats = WorldStats {
+            loaded_chunks: self.chunks.len(),
+            ..Default::default()
+        };
+        for (vertices, indices) in self.chunks.values().filter_map(|threaded_chunk| threaded_chunk.mesh_size) {
+            stats.meshed_chunks += 1;
+            stats.vertices += vertices;
+            stats.indices += indices;
+        }
+        stats
+    }
+
     pub(crate) fn destroy(&self, device: &Device) {
 
     }
@@ -267,6 +388,8 @@ pub struct ThreadedChunk {
     stop_sender: Option<crossbeam::channel::Sender<()>>,
     has_stopped_receiver: Option<crossbeam::channel::Receiver<()>>,
     model_matrix: glm::Mat4,
+    // Vertices and indices of the uploaded mesh, None until the chunk is meshed
+    mesh_size: Option<(usize, usize)>,
 
     //Buffers
     vertex_buffer: vk::Buffer,
@@ -289,6 +412,7 @@ impl ThreadedChunk {
             stop_sender: None,
             has_stopped_receiver: None,
             model_matrix: modle_matrix,
+            mesh_size: None,
             vertex_buffer: Default::default(),
             vertex_buffer_memory: Default::default(),
             index: Default::default(),